@@ -0,0 +1,125 @@
+const COMMANDS: &[&str] = &[
+    "probe_deepgram",
+    "validate_api_key",
+    "get_bandwidth_stats",
+    "start_stream_transcription",
+    "stop_stream_transcription",
+    "transcribe_url",
+    "benchmark_providers",
+    "evaluate_accuracy",
+    "get_acceleration_info",
+    "list_available_models",
+    "download_model",
+    "delete_model",
+    "estimate_model_requirements",
+    "list_model_options",
+    "get_active_engine",
+    "speak_text",
+    "speak_session",
+    "control_readback",
+    "train_wake_word",
+    "list_wake_words",
+    "delete_wake_word",
+    "feed_wake_word",
+    "reset_wake_word",
+    "recognize_command",
+    "set_command_grammar",
+    "inject_text",
+    "scratch_that",
+    "reset_injection",
+    "paste_last_transcript",
+    "set_spelling_mode",
+    "get_spelling_mode",
+    "finalize_dictation",
+    "set_snippets",
+    "get_foreground_app",
+    "get_recent_utterances",
+    "copy_utterance",
+    "list_templates",
+    "start_template",
+    "select_template_slot",
+    "get_template_document",
+    "finish_template",
+    "list_sessions",
+    "get_session",
+    "delete_session",
+    "set_session_starred",
+    "search_sessions",
+    "find_in_session",
+    "get_segment_at",
+    "get_settings",
+    "update_settings",
+    "list_jobs",
+    "add_job",
+    "remove_job",
+    "set_job_enabled",
+    "begin_dictation",
+    "end_dictation",
+    "start_caption_server",
+    "stop_caption_server",
+    "get_caption_server_status",
+    "publish_caption",
+    "show_caption_overlay",
+    "hide_caption_overlay",
+    "set_caption_overlay_position",
+    "export_session",
+    "export_bundle",
+    "archive_recording",
+    "get_storage_usage",
+    "apply_retention",
+    "publish_session",
+    "import_transcript",
+    "merge_sessions",
+    "edit_segment",
+    "retranscribe_session",
+    "undo_edit",
+    "redo_edit",
+    "get_session_history",
+    "get_session_version",
+    "diff_session_versions",
+    "generate_chapters",
+    "add_chapter",
+    "remove_chapter",
+    "set_watch_folders",
+    "set_hotkeys",
+    "list_hid_devices",
+    "get_encryption_status",
+    "enable_encryption",
+    "disable_encryption",
+    "unlock_history",
+    "lock_history",
+    "get_app_lock_status",
+    "enable_app_lock",
+    "disable_app_lock",
+    "unlock",
+    "lock",
+    "erase_all_data",
+    "backup_data",
+    "restore_data",
+    "sync_now",
+    "list_pending_deliveries",
+    "purge_pending_deliveries",
+    "start_lan_receiver",
+    "stop_lan_receiver",
+    "discover_lan_peers",
+    "send_to_lan_peer",
+    "set_telemetry",
+    "get_telemetry_report",
+    "list_crash_reports",
+    "delete_crash_reports",
+    "create_filter_chain",
+    "filter_audio",
+    "close_filter_chain",
+    "calibrate_device",
+    "list_device_calibrations",
+    "start_rest_api",
+    "stop_rest_api",
+    "get_rest_api_status",
+    "start_soak_test",
+    "stop_soak_test",
+    "get_soak_report",
+];
+
+fn main() {
+    tauri_plugin::Builder::new(COMMANDS).build();
+}
@@ -0,0 +1,12 @@
+//! Microphone-side processing: filters, calibration, hotkeys and pedals, the wake word, and
+//! the recordings archive.
+
+pub mod audio_filters;
+pub mod calibration;
+pub mod flac;
+pub mod hid;
+pub mod hotkeys;
+pub mod recordings;
+pub mod silence;
+pub mod wake_word;
+pub mod wav;
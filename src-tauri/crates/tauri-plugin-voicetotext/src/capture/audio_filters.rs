@@ -0,0 +1,414 @@
+//! The configurable preprocessing chain applied before recognition.
+//!
+//! `settings.audio_filters` lists stages in the order they run, each with its own enable
+//! flag and parameters. The same [`Pipeline`] processes every PCM source: microphone
+//! audio arrives from the frontend through `filter_audio` in chunks, and 16-bit WAV files
+//! are filtered before upload. Encoded sources (compressed files, URL streams) can't be
+//! filtered without a decoder and are sent as they are. Live chains are tuned to the
+//! microphone's measured noise floor when it was calibrated (see `calibration.rs`).
+
+use crate::settings::{
+    self, AgcParams, AudioFilter, FilterStage, HighPassParams, NoiseSuppressionParams, VadParams,
+};
+use crate::{calibration, wav};
+use std::collections::HashMap;
+use std::sync::Mutex;
+use tauri::{AppHandle, State};
+
+/// Stages analyse audio in 10 ms frames.
+pub const FRAMES_PER_SECOND: u32 = 100;
+/// Frames quieter than this are treated as digital silence.
+pub const SILENCE_DB: f32 = -80.0;
+
+pub fn to_db(level: f32) -> f32 {
+    20.0 * level.max(1e-9).log10()
+}
+
+fn from_db(db: f32) -> f32 {
+    10f32.powf(db / 20.0)
+}
+
+pub fn rms(frame: &[f32]) -> f32 {
+    if frame.is_empty() {
+        return 0.0;
+    }
+    (frame.iter().map(|s| s * s).sum::<f32>() / frame.len() as f32).sqrt()
+}
+
+/// RBJ cookbook biquad high-pass, direct form I.
+struct HighPass {
+    b0: f32,
+    b1: f32,
+    b2: f32,
+    a1: f32,
+    a2: f32,
+    x1: f32,
+    x2: f32,
+    y1: f32,
+    y2: f32,
+}
+
+impl HighPass {
+    fn new(params: &HighPassParams, sample_rate: u32) -> Self {
+        // Keep the cutoff below Nyquist and Q positive so the filter stays stable
+        let nyquist = sample_rate as f32 / 2.0;
+        let cutoff = params.cutoff_hz.clamp(1.0, nyquist * 0.95);
+        let q = params.q.max(0.1);
+        let omega = std::f32::consts::TAU * cutoff / sample_rate as f32;
+        let alpha = omega.sin() / (2.0 * q);
+        let cos = omega.cos();
+        let a0 = 1.0 + alpha;
+        HighPass {
+            b0: (1.0 + cos) / 2.0 / a0,
+            b1: -(1.0 + cos) / a0,
+            b2: (1.0 + cos) / 2.0 / a0,
+            a1: -2.0 * cos / a0,
+            a2: (1.0 - alpha) / a0,
+            x1: 0.0,
+            x2: 0.0,
+            y1: 0.0,
+            y2: 0.0,
+        }
+    }
+
+    fn process(&mut self, frame: &mut [f32]) {
+        for sample in frame.iter_mut() {
+            let x = *sample;
+            let y = self.b0 * x + self.b1 * self.x1 + self.b2 * self.x2
+                - self.a1 * self.y1
+                - self.a2 * self.y2;
+            self.x2 = self.x1;
+            self.x1 = x;
+            self.y2 = self.y1;
+            self.y1 = y;
+            *sample = y;
+        }
+    }
+}
+
+struct NoiseSuppression {
+    threshold: f32,
+    reduction: f32,
+    /// Tracked noise floor (linear RMS): follows drops immediately, rises slowly.
+    floor: Option<f32>,
+    rise: f32,
+    gain: f32,
+}
+
+impl NoiseSuppression {
+    fn new(params: &NoiseSuppressionParams) -> Self {
+        NoiseSuppression {
+            threshold: from_db(params.threshold_db),
+            reduction: from_db(-params.reduction_db.abs()),
+            floor: None,
+            // About 3 dB per second
+            rise: from_db(3.0 / FRAMES_PER_SECOND as f32),
+            gain: 1.0,
+        }
+    }
+
+    fn process(&mut self, frame: &mut [f32]) {
+        let level = rms(frame);
+        if to_db(level) < SILENCE_DB {
+            return;
+        }
+        let floor = match self.floor {
+            Some(floor) if level >= floor => floor * self.rise,
+            _ => level,
+        };
+        self.floor = Some(floor);
+        let target = if level < floor * self.threshold {
+            self.reduction
+        } else {
+            1.0
+        };
+        self.gain += (target - self.gain) * 0.3;
+        frame.iter_mut().for_each(|s| *s *= self.gain);
+    }
+}
+
+struct Agc {
+    target: f32,
+    max_gain: f32,
+    gain: f32,
+}
+
+impl Agc {
+    fn new(params: &AgcParams) -> Self {
+        Agc {
+            target: from_db(params.target_db),
+            max_gain: from_db(params.max_gain_db.abs()),
+            gain: 1.0,
+        }
+    }
+
+    fn process(&mut self, frame: &mut [f32]) {
+        let level = rms(frame);
+        // Don't pump up silence between words
+        if to_db(level) > SILENCE_DB + 20.0 {
+            let desired = (self.target / level).clamp(1.0 / self.max_gain, self.max_gain);
+            // Attack fast so peaks don't clip, release slowly so speech doesn't pump
+            let rate = if desired < self.gain { 0.5 } else { 0.05 };
+            self.gain += (desired - self.gain) * rate;
+        }
+        frame
+            .iter_mut()
+            .for_each(|s| *s = (*s * self.gain).clamp(-1.0, 1.0));
+    }
+}
+
+struct Vad {
+    threshold_db: f32,
+    hang_frames: u32,
+    remaining: u32,
+}
+
+impl Vad {
+    fn new(params: &VadParams) -> Self {
+        Vad {
+            threshold_db: params.threshold_db,
+            hang_frames: params.hang_ms * FRAMES_PER_SECOND / 1000,
+            remaining: 0,
+        }
+    }
+
+    fn process(&mut self, frame: &mut [f32]) {
+        if to_db(rms(frame)) > self.threshold_db {
+            self.remaining = self.hang_frames;
+        } else if self.remaining > 0 {
+            self.remaining -= 1;
+        } else {
+            frame.fill(0.0);
+        }
+    }
+}
+
+enum Stage {
+    HighPass(HighPass),
+    NoiseSuppression(NoiseSuppression),
+    Agc(Agc),
+    Vad(Vad),
+}
+
+impl Stage {
+    fn new(filter: &AudioFilter, sample_rate: u32) -> Self {
+        match filter {
+            AudioFilter::HighPass(params) => Stage::HighPass(HighPass::new(params, sample_rate)),
+            AudioFilter::NoiseSuppression(params) => {
+                Stage::NoiseSuppression(NoiseSuppression::new(params))
+            }
+            AudioFilter::Agc(params) => Stage::Agc(Agc::new(params)),
+            AudioFilter::Vad(params) => Stage::Vad(Vad::new(params)),
+        }
+    }
+
+    fn process(&mut self, frame: &mut [f32]) {
+        match self {
+            Stage::HighPass(stage) => stage.process(frame),
+            Stage::NoiseSuppression(stage) => stage.process(frame),
+            Stage::Agc(stage) => stage.process(frame),
+            Stage::Vad(stage) => stage.process(frame),
+        }
+    }
+}
+
+/// A stateful chain for one mono signal; keep one per stream so filter state carries
+/// across chunks.
+pub struct Pipeline {
+    stages: Vec<Stage>,
+    frame_len: usize,
+}
+
+impl Pipeline {
+    pub fn new(config: &[FilterStage], sample_rate: u32) -> Self {
+        Pipeline {
+            stages: config
+                .iter()
+                .filter(|stage| stage.enabled)
+                .map(|stage| Stage::new(&stage.filter, sample_rate))
+                .collect(),
+            frame_len: (sample_rate / FRAMES_PER_SECOND).max(1) as usize,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.stages.is_empty()
+    }
+
+    pub fn process(&mut self, samples: &mut [f32]) {
+        for frame in samples.chunks_mut(self.frame_len) {
+            for stage in &mut self.stages {
+                stage.process(frame);
+            }
+        }
+    }
+}
+
+/// Run every channel of a 16-bit WAV file through the configured filters. Returns `None`
+/// when nothing is enabled or the file can't be decoded, so the original is sent.
+pub fn filter_wav(app: &AppHandle, bytes: &[u8]) -> Option<Vec<u8>> {
+    let config = settings::load(app).audio_filters;
+    if !config.iter().any(|stage| stage.enabled) || !wav::is_wav(bytes) {
+        return None;
+    }
+    let mut decoded = match wav::decode(bytes) {
+        Ok(decoded) => decoded,
+        Err(e) => {
+            println!("[audio_filters] Sending file unfiltered: {}", e);
+            return None;
+        }
+    };
+    let channels = decoded.channels as usize;
+    for channel in 0..channels {
+        let mut pipeline = Pipeline::new(&config, decoded.sample_rate);
+        let mut mono: Vec<f32> = decoded
+            .samples
+            .iter()
+            .skip(channel)
+            .step_by(channels)
+            .copied()
+            .collect();
+        pipeline.process(&mut mono);
+        for (i, sample) in mono.into_iter().enumerate() {
+            decoded.samples[i * channels + channel] = sample;
+        }
+    }
+    Some(wav::encode(&decoded))
+}
+
+/// Filter chains opened by the frontend for live microphone audio.
+#[derive(Default)]
+pub struct FilterChains(Mutex<HashMap<String, Pipeline>>);
+
+/// Open a chain built from the current settings, tuned to the input device when it was
+/// calibrated. Returns `None` when no stage is enabled, in which case the caller should
+/// send audio untouched.
+#[tauri::command]
+#[specta::specta]
+pub fn create_filter_chain(
+    app: AppHandle,
+    chains: State<'_, FilterChains>,
+    sample_rate: u32,
+    device_id: Option<String>,
+) -> Option<String> {
+    let mut config = settings::load(&app).audio_filters;
+    if let Some(calibration) = device_id.and_then(|id| calibration::get(&app, &id)) {
+        config = calibration::tune(&config, &calibration);
+    }
+    let pipeline = Pipeline::new(&config, sample_rate);
+    if pipeline.is_empty() {
+        return None;
+    }
+    let id = uuid::Uuid::new_v4().to_string();
+    chains.0.lock().unwrap().insert(id.clone(), pipeline);
+    Some(id)
+}
+
+/// Filter the next chunk of mono samples for a chain. Chunks must be sent in order.
+#[tauri::command]
+#[specta::specta]
+pub fn filter_audio(
+    chains: State<'_, FilterChains>,
+    chain_id: String,
+    mut samples: Vec<f32>,
+) -> Result<Vec<f32>, String> {
+    let mut chains = chains.0.lock().unwrap();
+    let pipeline = chains
+        .get_mut(&chain_id)
+        .ok_or_else(|| format!("No filter chain: {}", chain_id))?;
+    pipeline.process(&mut samples);
+    Ok(samples)
+}
+
+#[tauri::command]
+#[specta::specta]
+pub fn close_filter_chain(chains: State<'_, FilterChains>, chain_id: String) {
+    chains.0.lock().unwrap().remove(&chain_id);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SAMPLE_RATE: u32 = 16_000;
+
+    fn pipeline(filter: AudioFilter) -> Pipeline {
+        let stage = FilterStage {
+            enabled: true,
+            filter,
+        };
+        Pipeline::new(&[stage], SAMPLE_RATE)
+    }
+
+    fn tone(amplitude: f32, hz: f32, seconds: f32) -> Vec<f32> {
+        (0..(seconds * SAMPLE_RATE as f32) as usize)
+            .map(|i| amplitude * (std::f32::consts::TAU * hz * i as f32 / SAMPLE_RATE as f32).sin())
+            .collect()
+    }
+
+    fn last_second(samples: &[f32]) -> &[f32] {
+        &samples[samples.len() - SAMPLE_RATE as usize..]
+    }
+
+    #[test]
+    fn high_pass_removes_dc_and_keeps_speech() {
+        let mut samples: Vec<f32> = tone(0.1, 1000.0, 2.0)
+            .into_iter()
+            .map(|s| s + 0.3)
+            .collect();
+        pipeline(AudioFilter::HighPass(HighPassParams::default())).process(&mut samples);
+
+        let settled = last_second(&samples);
+        let mean = settled.iter().sum::<f32>() / settled.len() as f32;
+        assert!(mean.abs() < 1e-3, "DC left: {}", mean);
+        let level = rms(settled);
+        assert!(
+            (level - 0.1 / 2f32.sqrt()).abs() < 0.005,
+            "tone RMS {}",
+            level
+        );
+    }
+
+    #[test]
+    fn agc_converges_on_the_target_level() {
+        // Quiet and loud constant tones both end up at the target
+        for amplitude in [0.02, 0.1, 0.5, 0.9] {
+            let mut samples = tone(amplitude, 440.0, 5.0);
+            pipeline(AudioFilter::Agc(AgcParams::default())).process(&mut samples);
+            let level = to_db(rms(last_second(&samples)));
+            assert!((level + 20.0).abs() < 0.5, "{} → {} dB", amplitude, level);
+        }
+    }
+
+    #[test]
+    fn agc_stops_at_the_maximum_gain() {
+        // -57 dBFS needs 37 dB to reach the target; only 20 dB are allowed
+        let mut samples = tone(0.002, 440.0, 5.0);
+        pipeline(AudioFilter::Agc(AgcParams::default())).process(&mut samples);
+        let level = to_db(rms(last_second(&samples)));
+        assert!((level - (to_db(0.002 / 2f32.sqrt()) + 20.0)).abs() < 0.5);
+    }
+
+    #[test]
+    fn vad_silences_quiet_frames_after_the_hang() {
+        let params = VadParams::default();
+        let hang = (params.hang_ms * SAMPLE_RATE / 1000) as usize;
+        let mut samples = tone(0.1, 440.0, 1.0);
+        samples.extend(tone(0.001, 440.0, 1.0));
+        pipeline(AudioFilter::Vad(params)).process(&mut samples);
+
+        let second = SAMPLE_RATE as usize;
+        let (speech, quiet) = samples.split_at(second);
+        assert!((rms(speech) - 0.1 / 2f32.sqrt()).abs() < 1e-4);
+        // Quiet audio passes during the hang, then is replaced by silence
+        assert!(rms(&quiet[..hang]) > 0.0);
+        assert!(quiet[hang..].iter().all(|s| *s == 0.0));
+    }
+
+    #[test]
+    fn vad_silences_a_quiet_recording_from_the_start() {
+        let mut samples = tone(0.001, 440.0, 1.0);
+        pipeline(AudioFilter::Vad(VadParams::default())).process(&mut samples);
+        assert!(samples.iter().all(|s| *s == 0.0));
+    }
+}
@@ -0,0 +1,158 @@
+//! Per-device noise-floor calibration.
+//!
+//! The frontend records a few seconds of room tone from a microphone and hands it to
+//! `calibrate_device`, which measures the noise floor and keeps it per device in
+//! `<app data dir>/calibration.json`. Filter chains opened for a calibrated device are
+//! tuned to it: the VAD threshold sits a fixed margin above the floor, and AGC gain is
+//! capped so the floor isn't amplified into audible hiss.
+
+use crate::audio_filters::{rms, to_db, FRAMES_PER_SECOND, SILENCE_DB};
+use crate::history;
+use crate::schema::{self, Store};
+use crate::settings::{AudioFilter, FilterStage};
+use serde::{Deserialize, Serialize};
+use specta::Type;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use tauri::{AppHandle, Manager, State};
+
+/// Room tone shorter than this gives no stable estimate.
+const MIN_SECONDS: f32 = 1.0;
+/// Speech must be this far above the floor to pass the VAD.
+const VAD_MARGIN_DB: f32 = 10.0;
+/// Bounds on the tuned VAD threshold, in dBFS.
+const VAD_RANGE_DB: (f32, f32) = (-70.0, -20.0);
+/// AGC gain is capped so the floor stays below this, in dBFS.
+const AGC_FLOOR_CEILING_DB: f32 = -50.0;
+/// A spread this wide between quiet and loud frames means the take wasn't room tone.
+const UNSTEADY_SPREAD_DB: f32 = 20.0;
+
+/// Serializes updates of the calibration file.
+#[derive(Default)]
+pub struct CalibrationStore(Mutex<()>);
+
+#[derive(Serialize, Deserialize, Clone, Debug, Type)]
+pub struct DeviceCalibration {
+    /// The browser's id of the input device.
+    pub device_id: String,
+    pub label: String,
+    /// Median level of the room tone, in dBFS.
+    pub noise_floor_db: f32,
+    /// Gap between the loudest 5% and the median, in dB.
+    pub spread_db: f32,
+    /// Unix time in milliseconds.
+    pub measured_at: u64,
+}
+
+pub fn store_path(app: &AppHandle) -> Result<PathBuf, String> {
+    let dir = app
+        .path()
+        .app_data_dir()
+        .map_err(|e| format!("Failed to resolve app data directory: {}", e))?;
+    fs::create_dir_all(&dir).map_err(|e| format!("Failed to create app data directory: {}", e))?;
+    Ok(dir.join("calibration.json"))
+}
+
+const STORE: Store = Store {
+    name: "calibration",
+    wrapped: true,
+    migrations: &[schema::wrap_records],
+};
+
+fn read_store(app: &AppHandle) -> BTreeMap<String, DeviceCalibration> {
+    store_path(app)
+        .map(|path| STORE.read_or_default(&path))
+        .unwrap_or_default()
+}
+
+pub fn get(app: &AppHandle, device_id: &str) -> Option<DeviceCalibration> {
+    read_store(app).remove(device_id)
+}
+
+/// Median and 95th percentile frame level of the room tone, ignoring digital silence.
+fn measure(samples: &[f32], sample_rate: u32) -> Result<(f32, f32), String> {
+    let frame_len = (sample_rate / FRAMES_PER_SECOND).max(1) as usize;
+    let mut levels: Vec<f32> = samples
+        .chunks(frame_len)
+        .map(|frame| to_db(rms(frame)))
+        .filter(|db| *db > SILENCE_DB)
+        .collect();
+    if (levels.len() as f32) < MIN_SECONDS * FRAMES_PER_SECOND as f32 {
+        return Err(format!(
+            "Need at least {} second(s) of room tone from the microphone",
+            MIN_SECONDS
+        ));
+    }
+    levels.sort_by(|a, b| a.total_cmp(b));
+    let at = |fraction: f32| levels[((levels.len() - 1) as f32 * fraction) as usize];
+    Ok((at(0.5), at(0.95)))
+}
+
+/// Filter stages tuned to a device's noise floor.
+pub fn tune(stages: &[FilterStage], calibration: &DeviceCalibration) -> Vec<FilterStage> {
+    let floor = calibration.noise_floor_db;
+    stages
+        .iter()
+        .cloned()
+        .map(|mut stage| {
+            match &mut stage.filter {
+                AudioFilter::Vad(params) => {
+                    params.threshold_db =
+                        (floor + VAD_MARGIN_DB).clamp(VAD_RANGE_DB.0, VAD_RANGE_DB.1);
+                }
+                AudioFilter::Agc(params) => {
+                    let headroom = (AGC_FLOOR_CEILING_DB - floor).max(0.0);
+                    params.max_gain_db = params.max_gain_db.abs().min(headroom);
+                }
+                _ => {}
+            }
+            stage
+        })
+        .collect()
+}
+
+/// Measure the noise floor of a recording of room tone and store it for the device.
+#[tauri::command]
+#[specta::specta]
+pub fn calibrate_device(
+    app: AppHandle,
+    store: State<'_, CalibrationStore>,
+    device_id: String,
+    label: String,
+    samples: Vec<f32>,
+    sample_rate: u32,
+) -> Result<DeviceCalibration, String> {
+    let (median, loud) = measure(&samples, sample_rate)?;
+    let calibration = DeviceCalibration {
+        device_id: device_id.clone(),
+        label,
+        noise_floor_db: median,
+        spread_db: loud - median,
+        measured_at: history::now_ms(),
+    };
+    if calibration.spread_db > UNSTEADY_SPREAD_DB {
+        println!(
+            "[calibration] {} varied by {:.1} dB; was someone talking?",
+            calibration.label, calibration.spread_db
+        );
+    }
+
+    let _guard = store.0.lock().unwrap();
+    let mut calibrations = read_store(&app);
+    calibrations.insert(device_id, calibration.clone());
+    STORE.write(&store_path(&app)?, &calibrations)?;
+    println!(
+        "[calibration] {}: noise floor {:.1} dBFS",
+        calibration.label, calibration.noise_floor_db
+    );
+    Ok(calibration)
+}
+
+/// Stored calibrations, by device id.
+#[tauri::command]
+#[specta::specta]
+pub fn list_device_calibrations(app: AppHandle) -> Vec<DeviceCalibration> {
+    read_store(&app).into_values().collect()
+}
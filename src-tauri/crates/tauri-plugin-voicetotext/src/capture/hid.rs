@@ -0,0 +1,165 @@
+//! HID buttons, such as transcription foot pedals, bound to hotkey actions.
+//!
+//! Devices named in `settings.hid_buttons` are opened by vendor and product id and their
+//! input reports read on a dedicated thread. A bound bit going up or down triggers its
+//! action exactly like the hotkey's press or release, so a pedal can push-to-talk.
+//! Devices are looked for again every few seconds, which picks up pedals plugged in later
+//! and changed bindings. Clickers that present themselves as keyboards can't be opened
+//! on every platform; bind their keys as hotkeys instead.
+
+use crate::hotkeys;
+use crate::settings::{self, HidButton};
+use hidapi::{HidApi, HidDevice};
+use serde::Serialize;
+use specta::Type;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Manager, State};
+
+const RESCAN_INTERVAL: Duration = Duration::from_secs(3);
+/// Time spent waiting for a report per pass over the open devices.
+const READ_TIMEOUT_MS: i32 = 50;
+
+/// Devices seen at the last scan, for `list_hid_devices`.
+#[derive(Default)]
+pub struct HidDevices(Mutex<Vec<HidDeviceInfo>>);
+
+#[derive(Serialize, Clone, Debug, Type)]
+pub struct HidDeviceInfo {
+    pub vendor_id: u16,
+    pub product_id: u16,
+    pub manufacturer: Option<String>,
+    pub product: Option<String>,
+}
+
+struct OpenDevice {
+    device: HidDevice,
+    /// The last report read, so only changed bits trigger.
+    report: Vec<u8>,
+}
+
+fn bit(report: &[u8], index: u32) -> bool {
+    report
+        .get(index as usize / 8)
+        .is_some_and(|byte| byte & (1 << (index % 8)) != 0)
+}
+
+/// Open bound devices that aren't open yet and forget unbound ones.
+fn scan(
+    app: &AppHandle,
+    api: &mut HidApi,
+    open: &mut HashMap<(u16, u16), OpenDevice>,
+    bindings: &[HidButton],
+) {
+    open.retain(|key, _| {
+        bindings
+            .iter()
+            .any(|binding| (binding.vendor_id, binding.product_id) == *key)
+    });
+    if let Err(e) = api.refresh_devices() {
+        println!("[hid] Failed to list devices: {}", e);
+        return;
+    }
+    *app.state::<HidDevices>().0.lock().unwrap() = api
+        .device_list()
+        .map(|info| HidDeviceInfo {
+            vendor_id: info.vendor_id(),
+            product_id: info.product_id(),
+            manufacturer: info.manufacturer_string().map(str::to_string),
+            product: info.product_string().map(str::to_string),
+        })
+        .collect();
+
+    for binding in bindings {
+        let key = (binding.vendor_id, binding.product_id);
+        if open.contains_key(&key) {
+            continue;
+        }
+        let Some(info) = api
+            .device_list()
+            .find(|info| (info.vendor_id(), info.product_id()) == key)
+        else {
+            continue;
+        };
+        match info.open_device(api) {
+            Ok(device) => {
+                println!(
+                    "[hid] Opened {:04x}:{:04x} ({})",
+                    key.0,
+                    key.1,
+                    info.product_string().unwrap_or("unnamed")
+                );
+                open.insert(
+                    key,
+                    OpenDevice {
+                        device,
+                        report: Vec::new(),
+                    },
+                );
+            }
+            Err(e) => println!("[hid] Failed to open {:04x}:{:04x}: {}", key.0, key.1, e),
+        }
+    }
+}
+
+/// Read one report, if any arrives in time, and trigger the bindings whose bit changed.
+/// Returns false when the device is gone.
+fn poll(app: &AppHandle, key: (u16, u16), open: &mut OpenDevice, bindings: &[HidButton]) -> bool {
+    let mut buf = [0u8; 64];
+    let read = match open.device.read_timeout(&mut buf, READ_TIMEOUT_MS) {
+        Ok(0) => return true,
+        Ok(read) => read,
+        Err(e) => {
+            println!("[hid] Lost {:04x}:{:04x}: {}", key.0, key.1, e);
+            return false;
+        }
+    };
+    let report = &buf[..read];
+    for binding in bindings
+        .iter()
+        .filter(|binding| (binding.vendor_id, binding.product_id) == key)
+    {
+        let pressed = bit(report, binding.bit);
+        if pressed != bit(&open.report, binding.bit) {
+            hotkeys::trigger(app, binding.action, pressed);
+        }
+    }
+    open.report = report.to_vec();
+    true
+}
+
+/// Start the thread that reads bound HID devices. Called once from `setup`.
+pub fn spawn(app: AppHandle) {
+    std::thread::spawn(move || {
+        let mut api = match HidApi::new() {
+            Ok(api) => api,
+            Err(e) => {
+                println!("[hid] HID access unavailable: {}", e);
+                return;
+            }
+        };
+        let mut open = HashMap::new();
+        let mut bindings = Vec::new();
+        let mut last_scan: Option<Instant> = None;
+        loop {
+            if last_scan.is_none_or(|scanned| scanned.elapsed() >= RESCAN_INTERVAL) {
+                bindings = settings::load(&app).hid_buttons;
+                scan(&app, &mut api, &mut open, &bindings);
+                last_scan = Some(Instant::now());
+            }
+            if open.is_empty() {
+                std::thread::sleep(RESCAN_INTERVAL);
+                continue;
+            }
+            open.retain(|key, device| poll(&app, *key, device, &bindings));
+        }
+    });
+}
+
+/// HID devices connected at the last scan, to pick vendor and product ids from.
+#[tauri::command]
+#[specta::specta]
+pub fn list_hid_devices(devices: State<'_, HidDevices>) -> Vec<HidDeviceInfo> {
+    devices.0.lock().unwrap().clone()
+}
@@ -0,0 +1,142 @@
+//! Global keyboard shortcuts.
+//!
+//! Chords are stored in settings as accelerator strings (`"CommandOrControl+Shift+Space"`)
+//! and registered system-wide. Presses are forwarded to the frontend as `hotkey:triggered`,
+//! which owns the microphone the recording actions act on. Spelling mode and inserting
+//! the last transcript are carried out here, so they work while the window is hidden.
+//! HID buttons such as foot pedals trigger the same actions through [`trigger`] (see
+//! `hid.rs`).
+
+use crate::schema;
+use crate::settings::{self, Hotkey};
+use crate::{inject, spelling};
+use serde::{Deserialize, Serialize};
+use specta::Type;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use tauri::plugin::TauriPlugin;
+use tauri::{AppHandle, Manager, Wry};
+use tauri_plugin_global_shortcut::{GlobalShortcutExt, Shortcut, ShortcutState};
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, Type)]
+#[serde(rename_all = "snake_case")]
+pub enum HotkeyAction {
+    /// Record while the chord is held.
+    PushToTalk,
+    /// Start or stop recording.
+    Toggle,
+    /// Copy the last transcript to the clipboard.
+    CopyLast,
+    /// Put the last transcript into the focused app; handled in the backend.
+    InsertLast,
+    /// Switch spelling mode on or off; handled in the backend.
+    ToggleSpelling,
+}
+
+/// Payload of the `hotkey:triggered` event.
+#[derive(Serialize, Clone, Debug, Type)]
+pub struct HotkeyEvent {
+    pub action: HotkeyAction,
+    /// False when the chord is released, which ends push-to-talk.
+    pub pressed: bool,
+}
+
+/// Registered shortcut ids and the action each one triggers.
+#[derive(Default)]
+pub struct Hotkeys(Mutex<HashMap<u32, HotkeyAction>>);
+
+pub fn plugin() -> TauriPlugin<Wry> {
+    tauri_plugin_global_shortcut::Builder::new()
+        .with_handler(|app, shortcut, event| {
+            let Some(action) = app
+                .state::<Hotkeys>()
+                .0
+                .lock()
+                .unwrap()
+                .get(&shortcut.id())
+                .copied()
+            else {
+                return;
+            };
+            trigger(app, action, event.state == ShortcutState::Pressed);
+        })
+        .build()
+}
+
+/// Carry out the backend's part of an action and forward it to the frontend.
+pub fn trigger(app: &AppHandle, action: HotkeyAction, pressed: bool) {
+    if pressed {
+        match action {
+            HotkeyAction::ToggleSpelling => spelling::toggle(app),
+            HotkeyAction::InsertLast => {
+                let app = app.clone();
+                tauri::async_runtime::spawn(async move {
+                    if let Err(e) = inject::paste_last(&app).await {
+                        println!("[hotkeys] {}", e);
+                    }
+                });
+            }
+            _ => {}
+        }
+    }
+    let _ = schema::emit(app, "hotkey:triggered", HotkeyEvent { action, pressed });
+}
+
+/// Parse every chord, rejecting unparseable ones and chords bound to more than one action.
+fn parse(hotkeys: &[Hotkey]) -> Result<Vec<(Shortcut, HotkeyAction)>, String> {
+    let mut parsed: Vec<(Shortcut, HotkeyAction)> = Vec::new();
+    for hotkey in hotkeys {
+        let shortcut: Shortcut = hotkey
+            .chord
+            .parse()
+            .map_err(|e| format!("Invalid shortcut {}: {}", hotkey.chord, e))?;
+        if let Some((_, other)) = parsed.iter().find(|(s, _)| s.id() == shortcut.id()) {
+            return Err(format!(
+                "{} is assigned to both {:?} and {:?}",
+                hotkey.chord, other, hotkey.action
+            ));
+        }
+        parsed.push((shortcut, hotkey.action));
+    }
+    Ok(parsed)
+}
+
+/// Replace all registered shortcuts with the ones in settings.
+pub fn register(app: &AppHandle) -> Result<(), String> {
+    let parsed = parse(&settings::load(app).hotkeys)?;
+    let manager = app.global_shortcut();
+    manager
+        .unregister_all()
+        .map_err(|e| format!("Failed to clear shortcuts: {}", e))?;
+
+    // The handler locks the map, so it is only touched once registration is done
+    let mut registered = HashMap::new();
+    let result = parsed.into_iter().try_for_each(|(shortcut, action)| {
+        // Fails when another application already owns the chord
+        manager
+            .register(shortcut)
+            .map_err(|e| format!("Failed to register {}: {}", shortcut, e))?;
+        registered.insert(shortcut.id(), action);
+        Ok(())
+    });
+    *app.state::<Hotkeys>().0.lock().unwrap() = registered;
+    result
+}
+
+/// Validate, save and re-register the shortcut map.
+#[tauri::command]
+#[specta::specta]
+pub fn set_hotkeys(app: AppHandle, hotkeys: Vec<Hotkey>) -> Result<(), String> {
+    parse(&hotkeys)?;
+    let mut current = settings::load(&app);
+    let previous = std::mem::replace(&mut current.hotkeys, hotkeys);
+    settings::save(&app, &current)?;
+    if let Err(e) = register(&app) {
+        // Restore the last working map so a taken chord doesn't leave every shortcut dead
+        current.hotkeys = previous;
+        settings::save(&app, &current)?;
+        let _ = register(&app);
+        return Err(e);
+    }
+    Ok(())
+}
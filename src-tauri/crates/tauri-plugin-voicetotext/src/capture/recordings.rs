@@ -0,0 +1,332 @@
+//! The audio archive: one recording per session under `<app data dir>/recordings/`.
+//!
+//! Microphone audio is captured by the frontend, which hands the finished recording to
+//! `archive_recording` once a session ends. It is written as WAV and, when
+//! `recording_compression` is set, re-encoded in the background to FLAC (built in) or
+//! Opus (through `opusenc` or `ffmpeg`). Recordings left as WAV by an interrupted run are
+//! picked up again on the next launch.
+//!
+//! With at-rest encryption on, a recording is compressed in memory and only ever written
+//! sealed; see [`encryption`].
+
+use crate::history::{self, Session};
+use crate::settings::{self, RecordingCodec, RecordingCompression};
+use crate::{app_lock, encryption, flac, quota, wav};
+use std::fs;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::time::SystemTime;
+use tauri::{AppHandle, Manager};
+use tokio::io::AsyncWriteExt;
+use tokio::process::Command;
+
+/// Archive formats, in the order a session's recording is looked up.
+const EXTENSIONS: [&str; 3] = ["flac", "opus", "wav"];
+
+pub fn recordings_dir(app: &AppHandle) -> Result<PathBuf, String> {
+    let dir = app
+        .path()
+        .app_data_dir()
+        .map_err(|e| format!("Failed to resolve app data directory: {}", e))?
+        .join("recordings");
+    fs::create_dir_all(&dir)
+        .map_err(|e| format!("Failed to create recordings directory: {}", e))?;
+    Ok(dir)
+}
+
+fn recording_path(app: &AppHandle, session_id: &str, extension: &str) -> Result<PathBuf, String> {
+    if session_id.is_empty() || session_id.contains(['/', '\\', '.']) {
+        return Err(format!("Invalid session id: {}", session_id));
+    }
+    Ok(recordings_dir(app)?.join(format!("{}.{}", session_id, extension)))
+}
+
+/// The archived recording of a session, if there is one.
+pub fn find(app: &AppHandle, session_id: &str) -> Option<PathBuf> {
+    EXTENSIONS
+        .iter()
+        .filter_map(|extension| recording_path(app, session_id, extension).ok())
+        .find(|path| path.is_file())
+}
+
+/// An archived recording as found on disk.
+pub struct Recording {
+    pub session_id: String,
+    pub path: PathBuf,
+    pub bytes: u64,
+    /// Last time it was written or read, for least-recently-used eviction.
+    pub last_used: SystemTime,
+}
+
+/// Every archived recording, including WAV files still waiting to be compressed.
+pub fn list(app: &AppHandle) -> Result<Vec<Recording>, String> {
+    let entries = fs::read_dir(recordings_dir(app)?)
+        .map_err(|e| format!("Failed to read recordings directory: {}", e))?;
+    Ok(entries
+        .filter_map(|entry| entry.ok())
+        .filter_map(|entry| {
+            let path = entry.path();
+            let extension = path.extension()?.to_str()?;
+            if !EXTENSIONS.contains(&extension) {
+                return None;
+            }
+            let metadata = entry.metadata().ok()?;
+            Some(Recording {
+                session_id: path.file_stem()?.to_str()?.to_string(),
+                bytes: metadata.len(),
+                last_used: metadata.modified().unwrap_or(SystemTime::UNIX_EPOCH),
+                path,
+            })
+        })
+        .collect())
+}
+
+/// Where a session's audio can be read: its archived recording, or the file it was
+/// transcribed from. Streams and URL sessions keep none locally.
+pub fn audio_path(app: &AppHandle, session: &Session) -> Option<PathBuf> {
+    if let Some(path) = find(app, &session.id) {
+        // The modification time doubles as the last use, so read recordings are evicted last
+        let _ = fs::File::options()
+            .append(true)
+            .open(&path)
+            .and_then(|file| file.set_modified(SystemTime::now()));
+        return Some(path);
+    }
+    session
+        .source
+        .strip_prefix("file:")
+        .map(PathBuf::from)
+        .filter(|path| path.is_file())
+}
+
+/// The audio of a session as in [`audio_path`], decrypted when it is sealed.
+pub fn read(app: &AppHandle, session: &Session) -> Result<Option<(PathBuf, Vec<u8>)>, String> {
+    let Some(path) = audio_path(app, session) else {
+        return Ok(None);
+    };
+    let bytes = fs::read(&path).map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    Ok(Some((path, encryption::open(app, bytes)?)))
+}
+
+/// Whether a stored file is sealed, judged by its first bytes.
+fn is_sealed(path: &Path) -> bool {
+    let mut head = [0u8; 8];
+    fs::File::open(path)
+        .and_then(|mut file| file.read_exact(&mut head))
+        .is_ok_and(|_| encryption::is_encrypted(&head))
+}
+
+/// Remove a session's recording; nothing to do when it has none.
+pub fn delete(app: &AppHandle, session_id: &str) -> Result<(), String> {
+    match find(app, session_id) {
+        Some(path) => fs::remove_file(&path)
+            .map_err(|e| format!("Failed to delete {}: {}", path.display(), e)),
+        None => Ok(()),
+    }
+}
+
+/// Run `program` with `input` on stdin and return its stdout, so audio never touches
+/// the disk unsealed.
+async fn pipe(program: &str, args: &[&str], input: Vec<u8>) -> Result<Vec<u8>, String> {
+    let mut child = Command::new(program)
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()
+        .map_err(|e| format!("{}: {}", program, e))?;
+    let mut stdin = child.stdin.take().ok_or("stdin unavailable")?;
+    // Written concurrently, or a full stdout pipe would stall the encoder
+    let writer = tauri::async_runtime::spawn(async move {
+        let _ = stdin.write_all(&input).await;
+    });
+    let output = child
+        .wait_with_output()
+        .await
+        .map_err(|e| format!("{}: {}", program, e))?;
+    let _ = writer.await;
+    if output.status.success() && !output.stdout.is_empty() {
+        Ok(output.stdout)
+    } else {
+        Err(format!("{} exited with {}", program, output.status))
+    }
+}
+
+async fn encode_opus(wav: Vec<u8>, bitrate_kbps: u32) -> Result<Vec<u8>, String> {
+    let bitrate = bitrate_kbps.clamp(6, 256).to_string();
+    let opusenc = pipe(
+        "opusenc",
+        &["--quiet", "--bitrate", &bitrate, "-", "-"],
+        wav.clone(),
+    )
+    .await;
+    if let Ok(encoded) = opusenc {
+        return Ok(encoded);
+    }
+    let bitrate = format!("{}k", bitrate);
+    pipe(
+        "ffmpeg",
+        &[
+            "-v", "error", "-i", "pipe:0", "-c:a", "libopus", "-b:a", &bitrate, "-f", "ogg",
+            "pipe:1",
+        ],
+        wav,
+    )
+    .await
+    .map_err(|e| format!("Opus needs opusenc or ffmpeg: {}", e))
+}
+
+fn codec_extension(config: &RecordingCompression) -> &'static str {
+    match config.codec {
+        RecordingCodec::Flac => "flac",
+        RecordingCodec::Opus => "opus",
+    }
+}
+
+/// Re-encode a WAV recording held in memory.
+async fn encode(wav: Vec<u8>, config: &RecordingCompression) -> Result<Vec<u8>, String> {
+    match config.codec {
+        RecordingCodec::Flac => {
+            tokio::task::spawn_blocking(move || flac::encode(&wav::decode(&wav)?))
+                .await
+                .map_err(|e| format!("FLAC encoder failed: {}", e))?
+        }
+        RecordingCodec::Opus => encode_opus(wav, config.bitrate_kbps).await,
+    }
+}
+
+/// Re-encode one archived WAV recording and remove the original.
+async fn compress(
+    app: &AppHandle,
+    path: &Path,
+    config: &RecordingCompression,
+) -> Result<PathBuf, String> {
+    let target = path.with_extension(codec_extension(config));
+    // Written under a temporary name so a crash never leaves a truncated recording
+    let partial = path.with_extension(format!("{}.part", codec_extension(config)));
+
+    let bytes = tokio::fs::read(path)
+        .await
+        .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    let encoded = encryption::seal(app, encode(bytes, config).await?)?;
+    tokio::fs::write(&partial, encoded)
+        .await
+        .map_err(|e| format!("Failed to write {}: {}", partial.display(), e))?;
+    tokio::fs::rename(&partial, &target)
+        .await
+        .map_err(|e| format!("Failed to store {}: {}", target.display(), e))?;
+    tokio::fs::remove_file(path)
+        .await
+        .map_err(|e| format!("Failed to remove {}: {}", path.display(), e))?;
+    Ok(target)
+}
+
+/// Compress `paths` one after another in the background, if compression is configured,
+/// then enforce the storage quota on what is left; see [`quota::enforce_quota`].
+fn compress_later(app: &AppHandle, paths: Vec<PathBuf>, keep: Option<String>) {
+    let Some(config) = settings::load(app).recording_compression else {
+        quota::enforce_quota(app, keep.as_deref());
+        return;
+    };
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        for path in paths {
+            let before = fs::metadata(&path).map(|m| m.len()).unwrap_or(0);
+            match compress(&app, &path, &config).await {
+                Ok(target) => {
+                    let after = fs::metadata(&target).map(|m| m.len()).unwrap_or(0);
+                    println!(
+                        "[recordings] Compressed {} ({} -> {} bytes)",
+                        target.display(),
+                        before,
+                        after
+                    );
+                }
+                Err(e) => println!("[recordings] Keeping {} as WAV: {}", path.display(), e),
+            }
+        }
+        quota::enforce_quota(&app, keep.as_deref());
+    });
+}
+
+/// Compress recordings still stored as WAV, e.g. after compression was turned on, and
+/// apply the storage quota.
+pub fn spawn(app: AppHandle) {
+    let pending: Vec<PathBuf> = recordings_dir(&app)
+        .and_then(|dir| {
+            fs::read_dir(dir).map_err(|e| format!("Failed to read recordings directory: {}", e))
+        })
+        .map(|entries| {
+            entries
+                .filter_map(|entry| entry.ok())
+                .map(|entry| entry.path())
+                .filter(|path| path.extension().is_some_and(|ext| ext == "wav"))
+                // Sealed WAVs were archived while compression was off
+                .filter(|path| !is_sealed(path))
+                .collect()
+        })
+        .unwrap_or_default();
+    compress_later(&app, pending, None);
+}
+
+/// Store the finished recording of a session. `samples` are interleaved and scaled to
+/// [-1, 1]; any earlier recording of the session is replaced.
+#[tauri::command]
+#[specta::specta]
+pub fn archive_recording(
+    app: AppHandle,
+    session_id: String,
+    samples: Vec<f32>,
+    sample_rate: u32,
+    channels: Option<u16>,
+) -> Result<(), String> {
+    app_lock::ensure_unlocked(&app)?;
+    history::load(&app, &session_id)?;
+    let channels = channels.unwrap_or(1).max(1);
+    if sample_rate == 0 {
+        return Err("Invalid sample rate".to_string());
+    }
+    delete(&app, &session_id)?;
+
+    let bytes = wav::encode(&wav::Wav {
+        sample_rate,
+        channels,
+        samples,
+    });
+    if encryption::is_enabled(&app) {
+        archive_sealed(&app, session_id, bytes);
+        return Ok(());
+    }
+    let path = recording_path(&app, &session_id, "wav")?;
+    fs::write(&path, bytes).map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+    compress_later(&app, vec![path], Some(session_id));
+    Ok(())
+}
+
+/// Compress a recording in memory if configured, then write it sealed.
+fn archive_sealed(app: &AppHandle, session_id: String, wav: Vec<u8>) {
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        let config = settings::load(&app).recording_compression;
+        let (extension, bytes) = match &config {
+            Some(config) => match encode(wav.clone(), config).await {
+                Ok(encoded) => (codec_extension(config), encoded),
+                Err(e) => {
+                    println!("[recordings] Keeping {} as WAV: {}", session_id, e);
+                    ("wav", wav)
+                }
+            },
+            None => ("wav", wav),
+        };
+        let stored = recording_path(&app, &session_id, extension).and_then(|path| {
+            let sealed = encryption::seal(&app, bytes)?;
+            fs::write(&path, sealed)
+                .map_err(|e| format!("Failed to write {}: {}", path.display(), e))
+        });
+        if let Err(e) = stored {
+            println!("[recordings] {}", e);
+        }
+        quota::enforce_quota(&app, Some(&session_id));
+    });
+}
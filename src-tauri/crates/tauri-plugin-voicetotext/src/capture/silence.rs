@@ -0,0 +1,235 @@
+//! Cutting long silences out of WAV files before they are sent to a paid API.
+//!
+//! Each silence longer than `min_silence_ms` is shortened to `keep_ms`. The cuts are
+//! recorded in a [`TimeMap`] so the returned segment times are mapped back onto the
+//! original file.
+
+use crate::history::Segment;
+use crate::settings::{self, SilenceTrimSettings};
+use crate::wav::{self, Wav};
+use tauri::AppHandle;
+
+const FRAME_MS: usize = 10;
+
+/// A stretch of the original audio kept in the trimmed file, in seconds.
+struct Region {
+    original_start: f64,
+    trimmed_start: f64,
+}
+
+/// Maps times in the trimmed audio back to the original.
+pub struct TimeMap {
+    regions: Vec<Region>,
+    pub removed_seconds: f64,
+}
+
+impl TimeMap {
+    pub fn to_original(&self, t: f64) -> f64 {
+        let index = self
+            .regions
+            .partition_point(|region| region.trimmed_start <= t)
+            .saturating_sub(1);
+        match self.regions.get(index) {
+            Some(region) => region.original_start + (t - region.trimmed_start),
+            None => t,
+        }
+    }
+
+    pub fn remap(&self, segments: &mut [Segment]) {
+        for segment in segments {
+            segment.start = self.to_original(segment.start);
+            segment.end = self.to_original(segment.end);
+        }
+    }
+}
+
+/// Frame-level silence flags, measured across all channels.
+fn silent_frames(wav: &Wav, frame_len: usize, threshold_db: f32) -> Vec<bool> {
+    let threshold = 10f32.powf(threshold_db / 20.0);
+    wav.samples
+        .chunks(frame_len * wav.channels as usize)
+        .map(|frame| {
+            let power = frame.iter().map(|s| s * s).sum::<f32>() / frame.len().max(1) as f32;
+            power.sqrt() < threshold
+        })
+        .collect()
+}
+
+pub fn trim(wav: &Wav, config: &SilenceTrimSettings) -> Option<(Wav, TimeMap)> {
+    let channels = wav.channels as usize;
+    let frame_len = (wav.sample_rate as usize * FRAME_MS / 1000).max(1);
+    let min_frames = (config.min_silence_ms as usize / FRAME_MS).max(1);
+    // Half of the kept silence stays on each side of the cut
+    let pad_frames = config.keep_ms as usize / FRAME_MS / 2;
+    let silent = silent_frames(wav, frame_len, config.threshold_db);
+
+    // Frame ranges to drop: the inside of every long enough silent run
+    let mut cuts: Vec<(usize, usize)> = Vec::new();
+    let mut run_start = None;
+    for (i, &is_silent) in silent.iter().chain(std::iter::once(&false)).enumerate() {
+        match (is_silent, run_start) {
+            (true, None) => run_start = Some(i),
+            (false, Some(start)) => {
+                if i - start >= min_frames && i - start > 2 * pad_frames {
+                    cuts.push((start + pad_frames, i - pad_frames));
+                }
+                run_start = None;
+            }
+            _ => {}
+        }
+    }
+    if cuts.is_empty() {
+        return None;
+    }
+
+    let sample_rate = wav.sample_rate as f64;
+    let total_frames = wav.samples.len() / channels;
+    let mut samples = Vec::with_capacity(wav.samples.len());
+    let mut regions = Vec::new();
+    let mut kept_from = 0;
+    for (cut_start, cut_end) in cuts
+        .iter()
+        .copied()
+        .chain(std::iter::once((silent.len(), silent.len())))
+    {
+        // Frames are a whole number of samples, so not always exactly FRAME_MS long
+        regions.push(Region {
+            original_start: (kept_from * frame_len).min(total_frames) as f64 / sample_rate,
+            trimmed_start: samples.len() as f64 / (sample_rate * channels as f64),
+        });
+        let from = (kept_from * frame_len * channels).min(wav.samples.len());
+        let to = (cut_start * frame_len * channels).min(wav.samples.len());
+        samples.extend_from_slice(&wav.samples[from..to]);
+        kept_from = cut_end;
+    }
+    let removed = wav.samples.len() - samples.len();
+    let map = TimeMap {
+        regions,
+        removed_seconds: removed as f64 / (sample_rate * channels as f64),
+    };
+    Some((
+        Wav {
+            sample_rate: wav.sample_rate,
+            channels: wav.channels,
+            samples,
+        },
+        map,
+    ))
+}
+
+/// Trim a 16-bit WAV file when silence trimming is enabled. Returns `None` when it's off,
+/// nothing was cut, or the file isn't a format that can be edited here.
+pub fn trim_wav(app: &AppHandle, bytes: &[u8]) -> Option<(Vec<u8>, TimeMap)> {
+    let config = settings::load(app).silence_trimming?;
+    if !wav::is_wav(bytes) {
+        return None;
+    }
+    let decoded = match wav::decode(bytes) {
+        Ok(decoded) => decoded,
+        Err(e) => {
+            println!("[silence] Sending file untrimmed: {}", e);
+            return None;
+        }
+    };
+    let (trimmed, map) = trim(&decoded, &config)?;
+    println!("[silence] Removed {:.1}s of silence", map.removed_seconds);
+    Some((wav::encode(&trimmed), map))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// `parts` of (seconds, level) played one after another on every channel.
+    fn audio(sample_rate: u32, channels: u16, parts: &[(f64, f32)]) -> Wav {
+        let mut samples = Vec::new();
+        for &(seconds, level) in parts {
+            let frames = (seconds * sample_rate as f64).round() as usize;
+            samples.extend(std::iter::repeat_n(level, frames * channels as usize));
+        }
+        Wav {
+            sample_rate,
+            channels,
+            samples,
+        }
+    }
+
+    /// Where the first sample at `level` is in `wav`, in seconds.
+    fn first_at(wav: &Wav, level: f32) -> f64 {
+        let index = wav.samples.iter().position(|&s| s == level).unwrap();
+        (index / wav.channels as usize) as f64 / wav.sample_rate as f64
+    }
+
+    #[test]
+    fn maps_times_back_at_any_sample_rate() {
+        for (sample_rate, channels) in [(16000, 1), (22050, 1), (11025, 2), (44100, 2)] {
+            let original = audio(
+                sample_rate,
+                channels,
+                &[(1.0, 0.5), (3.0, 0.0), (1.0, 0.25), (2.5, 0.0), (0.5, 0.75)],
+            );
+            let (trimmed, map) = trim(&original, &SilenceTrimSettings::default()).unwrap();
+            assert!(trimmed.samples.len() < original.samples.len());
+            for level in [0.5, 0.25, 0.75] {
+                let mapped = map.to_original(first_at(&trimmed, level));
+                assert!(
+                    (mapped - first_at(&original, level)).abs() < 1e-9,
+                    "{} Hz: {} mapped to {}",
+                    sample_rate,
+                    level,
+                    mapped
+                );
+            }
+            let removed = (original.samples.len() - trimmed.samples.len()) as f64
+                / (sample_rate as f64 * channels as f64);
+            assert!((map.removed_seconds - removed).abs() < 1e-9);
+        }
+    }
+
+    #[test]
+    fn keeps_part_of_each_silence() {
+        let original = audio(22050, 1, &[(1.0, 0.5), (3.0, 0.0), (1.0, 0.5)]);
+        let config = SilenceTrimSettings::default();
+        let (trimmed, map) = trim(&original, &config).unwrap();
+        let silence = trimmed.samples.iter().filter(|&&s| s == 0.0).count();
+        let kept = silence as f64 / 22050.0;
+        // Frames half sound, half silence at either edge count as sound
+        assert!(
+            (kept - config.keep_ms as f64 / 1000.0).abs() < 0.021,
+            "{}",
+            kept
+        );
+        assert!((map.removed_seconds - (3.0 - kept)).abs() < 1e-9);
+    }
+
+    #[test]
+    fn leaves_short_silences_alone() {
+        let original = audio(22050, 1, &[(1.0, 0.5), (1.5, 0.0), (1.0, 0.5)]);
+        assert!(trim(&original, &SilenceTrimSettings::default()).is_none());
+    }
+
+    #[test]
+    fn trims_trailing_silence() {
+        let original = audio(22050, 1, &[(1.0, 0.5), (4.0, 0.0)]);
+        let (trimmed, map) = trim(&original, &SilenceTrimSettings::default()).unwrap();
+        assert!(trimmed.samples.len() < 22050 * 2);
+        assert_eq!(map.to_original(0.5), 0.5);
+    }
+
+    #[test]
+    fn remaps_segments() {
+        let original = audio(22050, 1, &[(1.0, 0.5), (3.0, 0.0), (1.0, 0.25)]);
+        let (trimmed, map) = trim(&original, &SilenceTrimSettings::default()).unwrap();
+        let start = first_at(&trimmed, 0.25);
+        let mut segments = vec![Segment {
+            start,
+            end: start + 0.5,
+            text: "later".to_string(),
+            speaker: None,
+            confidence: 1.0,
+        }];
+        map.remap(&mut segments);
+        assert!((segments[0].start - 4.0).abs() < 1e-9);
+        assert!((segments[0].end - 4.5).abs() < 1e-9);
+    }
+}
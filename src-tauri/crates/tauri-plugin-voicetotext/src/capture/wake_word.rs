@@ -0,0 +1,498 @@
+//! On-device wake-word detection, so dictation can start hands-free.
+//!
+//! Wake words are trained by example: the user records the phrase a few times and each
+//! recording is kept as a sequence of MFCC frames in `<app data dir>/wake_words.json`.
+//! While listening, the frontend feeds microphone audio through `feed_wake_word`; the
+//! recent audio is compared with every template using dynamic time warping, and a close
+//! enough match emits `wake_word:detected` with the action bound to that word, which the
+//! frontend carries out. No audio leaves the machine for this.
+
+use crate::history;
+use crate::schema::{self, Store};
+use crate::settings;
+use serde::{Deserialize, Serialize};
+use specta::Type;
+use std::collections::VecDeque;
+use std::f32::consts::PI;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use tauri::{AppHandle, Manager, State};
+
+const SAMPLE_RATE: u32 = 16000;
+const FRAME_LEN: usize = 400; // 25 ms
+const HOP: usize = 160; // 10 ms
+const FFT_LEN: usize = 512;
+const MEL_BANDS: usize = 26;
+/// Cepstral coefficients kept per frame; c0 (overall loudness) is dropped.
+const COEFFICIENTS: usize = 12;
+const MIN_RECORDINGS: usize = 3;
+/// Frames quieter than this, relative to the loudest frame, are trimmed from recordings.
+const TRIM_DB: f32 = 35.0;
+/// Compare the recent audio with the templates every this many frames.
+const CHECK_EVERY: usize = 5;
+/// Frames to ignore after a detection, so one utterance triggers once.
+const REFRACTORY_FRAMES: usize = 150;
+
+type Frame = [f32; COEFFICIENTS];
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct WakeWord {
+    pub name: String,
+    pub created_at: u64,
+    templates: Vec<Vec<Frame>>,
+    /// Mean distance between the training recordings, the scale for the threshold.
+    spread: f32,
+}
+
+#[derive(Serialize, Clone, Debug, Type)]
+pub struct WakeWordInfo {
+    pub name: String,
+    pub recordings: usize,
+    pub created_at: u64,
+}
+
+/// What hearing a wake word does. Words without a configured action start dictation.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, Type)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum WakeWordAction {
+    StartDictation,
+    /// Start capturing a meeting recording.
+    StartMeetingCapture,
+    /// Insert the named template into the focused app.
+    InsertTemplate {
+        template: String,
+    },
+    /// End dictation and copy the transcript to the clipboard.
+    StopAndCopy,
+}
+
+/// Payload of the `wake_word:detected` event.
+#[derive(Serialize, Clone, Debug, Type)]
+pub struct WakeWordDetected {
+    pub name: String,
+    /// 0 for a borderline match, approaching 1 for a close one.
+    pub confidence: f32,
+    pub action: WakeWordAction,
+}
+
+impl WakeWord {
+    fn info(&self) -> WakeWordInfo {
+        WakeWordInfo {
+            name: self.name.clone(),
+            recordings: self.templates.len(),
+            created_at: self.created_at,
+        }
+    }
+}
+
+/// Streaming feature extraction and matching state for the listening microphone.
+#[derive(Default)]
+struct Listener {
+    pending: Vec<f32>,
+    frames: VecDeque<Frame>,
+    since_check: usize,
+    refractory: usize,
+}
+
+#[derive(Default)]
+pub struct WakeWordDetector(Mutex<Listener>);
+
+pub fn wake_words_path(app: &AppHandle) -> Result<PathBuf, String> {
+    let dir = app
+        .path()
+        .app_data_dir()
+        .map_err(|e| format!("Failed to resolve app data directory: {}", e))?;
+    fs::create_dir_all(&dir).map_err(|e| format!("Failed to create app data directory: {}", e))?;
+    Ok(dir.join("wake_words.json"))
+}
+
+const STORE: Store = Store {
+    name: "wake words",
+    wrapped: true,
+    migrations: &[schema::wrap_records],
+};
+
+pub fn load(app: &AppHandle) -> Vec<WakeWord> {
+    wake_words_path(app)
+        .map(|path| STORE.read_or_default(&path))
+        .unwrap_or_default()
+}
+
+fn save(app: &AppHandle, words: &[WakeWord]) -> Result<(), String> {
+    STORE.write(&wake_words_path(app)?, &words)
+}
+
+fn resample(samples: &[f32], from: u32) -> Vec<f32> {
+    if from == SAMPLE_RATE || from == 0 {
+        return samples.to_vec();
+    }
+    let ratio = from as f64 / SAMPLE_RATE as f64;
+    let len = (samples.len() as f64 / ratio) as usize;
+    (0..len)
+        .map(|i| {
+            let position = i as f64 * ratio;
+            let index = position as usize;
+            let fraction = (position - index as f64) as f32;
+            let a = samples[index.min(samples.len() - 1)];
+            let b = samples[(index + 1).min(samples.len() - 1)];
+            a + (b - a) * fraction
+        })
+        .collect()
+}
+
+/// In-place iterative radix-2 FFT; `re` and `im` must be `FFT_LEN` long.
+fn fft(re: &mut [f32], im: &mut [f32]) {
+    let n = re.len();
+    let mut j = 0;
+    for i in 1..n {
+        let mut bit = n >> 1;
+        while j & bit != 0 {
+            j ^= bit;
+            bit >>= 1;
+        }
+        j |= bit;
+        if i < j {
+            re.swap(i, j);
+            im.swap(i, j);
+        }
+    }
+    let mut len = 2;
+    while len <= n {
+        let angle = -2.0 * PI / len as f32;
+        for start in (0..n).step_by(len) {
+            for k in 0..len / 2 {
+                let (sin, cos) = (angle * k as f32).sin_cos();
+                let (a, b) = (start + k, start + k + len / 2);
+                let tr = re[b] * cos - im[b] * sin;
+                let ti = re[b] * sin + im[b] * cos;
+                re[b] = re[a] - tr;
+                im[b] = im[a] - ti;
+                re[a] += tr;
+                im[a] += ti;
+            }
+        }
+        len <<= 1;
+    }
+}
+
+fn mel(hz: f32) -> f32 {
+    2595.0 * (1.0 + hz / 700.0).log10()
+}
+
+fn hz(mel: f32) -> f32 {
+    700.0 * (10f32.powf(mel / 2595.0) - 1.0)
+}
+
+/// MFCCs of one frame, plus its log energy for trimming.
+fn frame_features(frame: &[f32]) -> (Frame, f32) {
+    let mut re = vec![0.0f32; FFT_LEN];
+    let mut im = vec![0.0f32; FFT_LEN];
+    let mut energy = 0.0;
+    for (i, sample) in frame.iter().enumerate() {
+        // Pre-emphasis and a Hamming window
+        let previous = if i > 0 { frame[i - 1] } else { 0.0 };
+        let window = 0.54 - 0.46 * (2.0 * PI * i as f32 / (FRAME_LEN - 1) as f32).cos();
+        re[i] = (sample - 0.97 * previous) * window;
+        energy += sample * sample;
+    }
+    fft(&mut re, &mut im);
+    let power: Vec<f32> = (0..=FFT_LEN / 2)
+        .map(|k| re[k] * re[k] + im[k] * im[k])
+        .collect();
+
+    let (low, high) = (mel(20.0), mel(SAMPLE_RATE as f32 / 2.0));
+    let edges: Vec<f32> = (0..MEL_BANDS + 2)
+        .map(|i| hz(low + (high - low) * i as f32 / (MEL_BANDS + 1) as f32))
+        .map(|f| f * FFT_LEN as f32 / SAMPLE_RATE as f32)
+        .collect();
+    let bands: Vec<f32> = (0..MEL_BANDS)
+        .map(|band| {
+            let (left, center, right) = (edges[band], edges[band + 1], edges[band + 2]);
+            let sum: f32 = power
+                .iter()
+                .enumerate()
+                .map(|(k, p)| {
+                    let k = k as f32;
+                    let weight = if k > left && k <= center {
+                        (k - left) / (center - left)
+                    } else if k > center && k < right {
+                        (right - k) / (right - center)
+                    } else {
+                        0.0
+                    };
+                    weight * p
+                })
+                .sum();
+            sum.max(1e-10).ln()
+        })
+        .collect();
+
+    let mut coefficients = [0.0; COEFFICIENTS];
+    for (c, coefficient) in coefficients.iter_mut().enumerate() {
+        let n = (c + 1) as f32;
+        *coefficient = bands
+            .iter()
+            .enumerate()
+            .map(|(m, b)| b * (PI * n * (m as f32 + 0.5) / MEL_BANDS as f32).cos())
+            .sum();
+    }
+    (
+        coefficients,
+        10.0 * (energy / frame.len() as f32).max(1e-10).log10(),
+    )
+}
+
+fn features(samples: &[f32]) -> Vec<(Frame, f32)> {
+    if samples.len() < FRAME_LEN {
+        return Vec::new();
+    }
+    (0..=(samples.len() - FRAME_LEN) / HOP)
+        .map(|i| frame_features(&samples[i * HOP..i * HOP + FRAME_LEN]))
+        .collect()
+}
+
+/// Subtract the mean of each coefficient, which removes the microphone's coloration.
+fn normalize(frames: &[Frame]) -> Vec<Frame> {
+    let mut mean = [0.0; COEFFICIENTS];
+    for frame in frames {
+        for (m, v) in mean.iter_mut().zip(frame) {
+            *m += v / frames.len() as f32;
+        }
+    }
+    frames
+        .iter()
+        .map(|frame| {
+            let mut out = *frame;
+            out.iter_mut().zip(&mean).for_each(|(v, m)| *v -= m);
+            out
+        })
+        .collect()
+}
+
+/// Normalized DTW distance between two feature sequences.
+fn dtw(a: &[Frame], b: &[Frame]) -> f32 {
+    if a.is_empty() || b.is_empty() {
+        return f32::MAX;
+    }
+    let distance = |x: &Frame, y: &Frame| {
+        x.iter()
+            .zip(y)
+            .map(|(p, q)| (p - q) * (p - q))
+            .sum::<f32>()
+            .sqrt()
+    };
+    let mut previous = vec![f32::MAX; b.len() + 1];
+    previous[0] = 0.0;
+    for x in a {
+        let mut current = vec![f32::MAX; b.len() + 1];
+        for (j, y) in b.iter().enumerate() {
+            let best = previous[j].min(previous[j + 1]).min(current[j]);
+            current[j + 1] = distance(x, y) + best;
+        }
+        previous = current;
+    }
+    previous[b.len()] / (a.len() + b.len()) as f32
+}
+
+/// Features of a training recording with leading and trailing silence removed.
+fn template(recording: &[f32], sample_rate: u32) -> Result<Vec<Frame>, String> {
+    let frames = features(&resample(recording, sample_rate));
+    let loudest = frames
+        .iter()
+        .map(|(_, energy)| *energy)
+        .fold(f32::MIN, f32::max);
+    let voiced = |(_, energy): &&(Frame, f32)| *energy > loudest - TRIM_DB;
+    let first = frames.iter().position(|f| voiced(&f));
+    let last = frames.iter().rposition(|f| voiced(&f));
+    match (first, last) {
+        (Some(first), Some(last)) if last - first >= 20 => Ok(normalize(
+            &frames[first..=last]
+                .iter()
+                .map(|(frame, _)| *frame)
+                .collect::<Vec<_>>(),
+        )),
+        _ => Err("A recording is too short or silent; say the phrase clearly".to_string()),
+    }
+}
+
+/// Train (or retrain) a wake word from at least three recordings of the phrase.
+#[tauri::command]
+#[specta::specta]
+pub fn train_wake_word(
+    app: AppHandle,
+    name: String,
+    recordings: Vec<Vec<f32>>,
+    sample_rate: u32,
+) -> Result<WakeWordInfo, String> {
+    let name = name.trim().to_string();
+    if name.is_empty() {
+        return Err("Wake word name is empty".to_string());
+    }
+    if recordings.len() < MIN_RECORDINGS {
+        return Err(format!(
+            "Record the phrase at least {} times",
+            MIN_RECORDINGS
+        ));
+    }
+    let templates = recordings
+        .iter()
+        .map(|recording| template(recording, sample_rate))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let mut distances = Vec::new();
+    for (i, a) in templates.iter().enumerate() {
+        for b in &templates[i + 1..] {
+            distances.push(dtw(a, b));
+        }
+    }
+    let spread = distances.iter().sum::<f32>() / distances.len() as f32;
+
+    let word = WakeWord {
+        name: name.clone(),
+        created_at: history::now_ms(),
+        templates,
+        spread,
+    };
+    let info = word.info();
+    let mut words = load(&app);
+    words.retain(|existing| existing.name != name);
+    words.push(word);
+    save(&app, &words)?;
+    println!(
+        "[wake_word] Trained \"{}\" (spread {:.2})",
+        info.name, spread
+    );
+    Ok(info)
+}
+
+#[tauri::command]
+#[specta::specta]
+pub fn list_wake_words(app: AppHandle) -> Vec<WakeWordInfo> {
+    load(&app).iter().map(WakeWord::info).collect()
+}
+
+#[tauri::command]
+#[specta::specta]
+pub fn delete_wake_word(app: AppHandle, name: String) -> Result<(), String> {
+    let mut words = load(&app);
+    let before = words.len();
+    words.retain(|word| word.name != name);
+    if words.len() == before {
+        return Err(format!("No such wake word: {}", name));
+    }
+    save(&app, &words)
+}
+
+/// The best match among `words` for the most recent frames, as (name, confidence).
+fn best_match(words: &[WakeWord], recent: &[Frame], sensitivity: f32) -> Option<(String, f32)> {
+    // Higher sensitivity accepts matches further from the training recordings
+    let tolerance = 1.0 + sensitivity.clamp(0.0, 1.0);
+    words
+        .iter()
+        .filter_map(|word| {
+            let threshold = word.spread * tolerance;
+            word.templates
+                .iter()
+                .filter(|template| template.len() <= recent.len())
+                .map(|template| {
+                    let window = normalize(&recent[recent.len() - template.len()..]);
+                    dtw(template, &window)
+                })
+                .fold(None, |best: Option<f32>, d| {
+                    Some(best.map_or(d, |b| b.min(d)))
+                })
+                .filter(|distance| *distance < threshold)
+                .map(|distance| (word.name.clone(), 1.0 - distance / threshold))
+        })
+        .max_by(|a, b| a.1.total_cmp(&b.1))
+}
+
+/// Feed the next chunk of microphone audio while listening for wake words. Returns the
+/// detection, if any; it is also emitted as `wake_word:detected`.
+///
+/// Keep feeding while dictating so phrases bound to actions like `stop_and_copy` are heard;
+/// matching happens here, before the frontend decides what audio to stream.
+#[tauri::command]
+#[specta::specta]
+pub fn feed_wake_word(
+    app: AppHandle,
+    detector: State<'_, WakeWordDetector>,
+    samples: Vec<f32>,
+    sample_rate: u32,
+) -> Option<WakeWordDetected> {
+    let config = settings::load(&app).wake_word;
+    if !config.enabled {
+        return None;
+    }
+    // The active word plus every word with an action; all words when no word is active
+    let words: Vec<WakeWord> = load(&app)
+        .into_iter()
+        .filter(|word| {
+            config
+                .active
+                .as_ref()
+                .is_none_or(|active| *active == word.name)
+                || config
+                    .actions
+                    .iter()
+                    .any(|binding| binding.word == word.name)
+        })
+        .collect();
+    let longest = words
+        .iter()
+        .flat_map(|word| word.templates.iter().map(Vec::len))
+        .max()?;
+
+    let mut listener = detector.0.lock().unwrap();
+    listener.pending.extend(resample(&samples, sample_rate));
+    let mut detected = None;
+    while listener.pending.len() >= FRAME_LEN {
+        let (frame, _) = frame_features(&listener.pending[..FRAME_LEN]);
+        listener.pending.drain(..HOP);
+        listener.frames.push_back(frame);
+        if listener.frames.len() > longest {
+            listener.frames.pop_front();
+        }
+        if listener.refractory > 0 {
+            listener.refractory -= 1;
+            continue;
+        }
+        listener.since_check += 1;
+        if listener.since_check < CHECK_EVERY || detected.is_some() {
+            continue;
+        }
+        listener.since_check = 0;
+        let recent: Vec<Frame> = listener.frames.iter().copied().collect();
+        if let Some((name, confidence)) = best_match(&words, &recent, config.sensitivity) {
+            let action = config
+                .actions
+                .iter()
+                .find(|binding| binding.word == name)
+                .map_or(WakeWordAction::StartDictation, |binding| {
+                    binding.action.clone()
+                });
+            println!(
+                "[wake_word] Detected \"{}\" ({:.2}): {:?}",
+                name, confidence, action
+            );
+            listener.refractory = REFRACTORY_FRAMES;
+            listener.frames.clear();
+            let event = WakeWordDetected {
+                name,
+                confidence,
+                action,
+            };
+            let _ = schema::emit(&app, "wake_word:detected", event.clone());
+            detected = Some(event);
+        }
+    }
+    detected
+}
+
+/// Forget buffered audio, e.g. when the microphone stops listening.
+#[tauri::command]
+#[specta::specta]
+pub fn reset_wake_word(detector: State<'_, WakeWordDetector>) {
+    *detector.0.lock().unwrap() = Listener::default();
+}
@@ -0,0 +1,94 @@
+//! Just enough RIFF/WAVE handling to run PCM through the audio filters: 16-bit integer
+//! PCM in, 16-bit integer PCM out. Other formats are left for Deepgram to decode.
+
+const WAVE_FORMAT_PCM: u16 = 1;
+const WAVE_FORMAT_EXTENSIBLE: u16 = 0xFFFE;
+
+pub struct Wav {
+    pub sample_rate: u32,
+    pub channels: u16,
+    /// Interleaved samples scaled to [-1, 1].
+    pub samples: Vec<f32>,
+}
+
+fn u16_at(bytes: &[u8], at: usize) -> Option<u16> {
+    Some(u16::from_le_bytes(bytes.get(at..at + 2)?.try_into().ok()?))
+}
+
+fn u32_at(bytes: &[u8], at: usize) -> Option<u32> {
+    Some(u32::from_le_bytes(bytes.get(at..at + 4)?.try_into().ok()?))
+}
+
+pub fn is_wav(bytes: &[u8]) -> bool {
+    bytes.len() >= 12 && &bytes[0..4] == b"RIFF" && &bytes[8..12] == b"WAVE"
+}
+
+/// Decode 16-bit PCM WAV. Errors name what is unsupported so callers can fall back.
+pub fn decode(bytes: &[u8]) -> Result<Wav, String> {
+    if !is_wav(bytes) {
+        return Err("Not a WAV file".to_string());
+    }
+    let mut format: Option<(u16, u16, u32, u16)> = None;
+    let mut at = 12;
+    while at + 8 <= bytes.len() {
+        let id = &bytes[at..at + 4];
+        let len = u32_at(bytes, at + 4).unwrap_or(0) as usize;
+        let body = at + 8;
+        let end = body.saturating_add(len).min(bytes.len());
+        if id == b"fmt " {
+            format = Some((
+                u16_at(bytes, body).ok_or("Truncated fmt chunk")?,
+                u16_at(bytes, body + 2).ok_or("Truncated fmt chunk")?,
+                u32_at(bytes, body + 4).ok_or("Truncated fmt chunk")?,
+                u16_at(bytes, body + 14).ok_or("Truncated fmt chunk")?,
+            ));
+        } else if id == b"data" {
+            let (tag, channels, sample_rate, bits) = format.ok_or("WAV data before fmt chunk")?;
+            if (tag != WAVE_FORMAT_PCM && tag != WAVE_FORMAT_EXTENSIBLE) || bits != 16 {
+                return Err(format!(
+                    "Unsupported WAV encoding (format {}, {} bits)",
+                    tag, bits
+                ));
+            }
+            if channels == 0 {
+                return Err("WAV file has no channels".to_string());
+            }
+            let samples = bytes[body..end]
+                .chunks_exact(2)
+                .map(|pair| i16::from_le_bytes([pair[0], pair[1]]) as f32 / 32768.0)
+                .collect();
+            return Ok(Wav {
+                sample_rate,
+                channels,
+                samples,
+            });
+        }
+        // Chunks are padded to an even length
+        at = body + len + (len & 1);
+    }
+    Err("WAV file has no data chunk".to_string())
+}
+
+/// Encode as a canonical 16-bit PCM WAV.
+pub fn encode(wav: &Wav) -> Vec<u8> {
+    let data_len = (wav.samples.len() * 2) as u32;
+    let block_align = wav.channels * 2;
+    let mut out = Vec::with_capacity(44 + data_len as usize);
+    out.extend_from_slice(b"RIFF");
+    out.extend_from_slice(&(36 + data_len).to_le_bytes());
+    out.extend_from_slice(b"WAVEfmt ");
+    out.extend_from_slice(&16u32.to_le_bytes());
+    out.extend_from_slice(&WAVE_FORMAT_PCM.to_le_bytes());
+    out.extend_from_slice(&wav.channels.to_le_bytes());
+    out.extend_from_slice(&wav.sample_rate.to_le_bytes());
+    out.extend_from_slice(&(wav.sample_rate * block_align as u32).to_le_bytes());
+    out.extend_from_slice(&block_align.to_le_bytes());
+    out.extend_from_slice(&16u16.to_le_bytes());
+    out.extend_from_slice(b"data");
+    out.extend_from_slice(&data_len.to_le_bytes());
+    for sample in &wav.samples {
+        let value = (sample.clamp(-1.0, 1.0) * 32767.0).round() as i16;
+        out.extend_from_slice(&value.to_le_bytes());
+    }
+    out
+}
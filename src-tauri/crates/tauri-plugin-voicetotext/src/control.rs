@@ -0,0 +1,354 @@
+//! Headless mode control socket.
+//!
+//! With `--headless` the main window is never created and the engine is driven over a
+//! localhost TCP socket speaking line-delimited JSON, e.g.
+//! `{"cmd":"start_stream","url":"http://radio.example/live.mp3"}`. Every request gets one
+//! response line `{"ok":true,"result":...}` or `{"ok":false,"error":"..."}`; after
+//! `{"cmd":"subscribe"}` transcript and session events are streamed as
+//! `{"event":"transcript:update","payload":{...}}` lines as well, except while the app
+//! lock is engaged. A subscriber that falls [`EVENT_BUFFER`] events behind is
+//! disconnected rather than buffered without bound.
+//!
+//! A connection's first request must be `{"cmd":"auth","token":"..."}` with the token in
+//! settings (`control.token`), generated the first time the socket starts without one.
+//! Any other first request or a wrong token is answered with an error and the connection
+//! closed. A line that isn't JSON at all closes the connection unanswered, which keeps a
+//! web page from driving the socket through a browser's cross-origin HTTP request.
+
+use crate::scheduler::Priority;
+use crate::soak::{SoakConfig, SoakProvider};
+use crate::{app_lock, history, prerecorded, settings, stream};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::sync::Arc;
+use tauri::{AppHandle, EventId, Listener, Manager};
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::{mpsc, Notify};
+
+pub const DEFAULT_PORT: u16 = 7891;
+
+/// Events forwarded to subscribed clients.
+const FORWARDED_EVENTS: [&str; 2] = ["transcript:update", "session:ended"];
+/// Event lines queued for a subscriber before it counts as too slow.
+const EVENT_BUFFER: usize = 256;
+
+#[derive(Deserialize)]
+#[serde(tag = "cmd", rename_all = "snake_case")]
+enum ControlRequest {
+    Auth {
+        token: String,
+    },
+    StartStream {
+        url: String,
+        api_key: Option<String>,
+        interim_results: Option<bool>,
+    },
+    Stop {
+        session_id: String,
+    },
+    TranscribeUrl {
+        url: String,
+        api_key: Option<String>,
+    },
+    ListSessions,
+    Subscribe,
+    Quit,
+}
+
+#[derive(Serialize)]
+struct ControlResponse {
+    ok: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    result: Option<Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
+/// Command-line options understood by the app binary.
+pub struct CliOptions {
+    pub headless: bool,
+    pub control_port: u16,
+    /// A soak test to run at startup, from `--soak=<sessions>`, `--soak-minutes=<minutes>`
+    /// and `--soak-provider=mock|deepgram`.
+    pub soak: Option<SoakConfig>,
+}
+
+impl CliOptions {
+    pub fn from_args() -> Self {
+        let mut options = CliOptions {
+            headless: false,
+            control_port: DEFAULT_PORT,
+            soak: None,
+        };
+        for arg in std::env::args().skip(1) {
+            if arg == "--headless" {
+                options.headless = true;
+            } else if let Some(port) = arg.strip_prefix("--control-port=") {
+                match port.parse() {
+                    Ok(port) => options.control_port = port,
+                    Err(_) => println!("[control] Ignoring invalid --control-port: {}", port),
+                }
+            } else if let Some(sessions) = arg.strip_prefix("--soak=") {
+                match sessions.parse() {
+                    Ok(sessions) => options.soak_config().sessions = sessions,
+                    Err(_) => println!("[control] Ignoring invalid --soak: {}", sessions),
+                }
+            } else if let Some(minutes) = arg.strip_prefix("--soak-minutes=") {
+                match minutes.parse() {
+                    Ok(minutes) => options.soak_config().duration_minutes = minutes,
+                    Err(_) => println!("[control] Ignoring invalid --soak-minutes: {}", minutes),
+                }
+            } else if let Some(provider) = arg.strip_prefix("--soak-provider=") {
+                let provider = match provider {
+                    "mock" => SoakProvider::Mock,
+                    "deepgram" => SoakProvider::Deepgram,
+                    _ => {
+                        println!("[control] Ignoring invalid --soak-provider: {}", provider);
+                        continue;
+                    }
+                };
+                options.soak_config().provider = provider;
+            }
+        }
+        options
+    }
+
+    /// The startup soak test, set up as soon as any `--soak` option is given.
+    fn soak_config(&mut self) -> &mut SoakConfig {
+        self.soak.get_or_insert_with(SoakConfig::default)
+    }
+}
+
+/// The configured token, generated and saved when there is none yet.
+fn token(app: &AppHandle) -> Result<String, String> {
+    let mut current = settings::try_load(app)?;
+    if let Some(token) = current.control.token.clone().filter(|t| !t.is_empty()) {
+        return Ok(token);
+    }
+    let token = uuid::Uuid::new_v4().simple().to_string();
+    current.control.token = Some(token.clone());
+    settings::save(app, &current)?;
+    Ok(token)
+}
+
+/// Bind the control socket on 127.0.0.1 and serve clients in the background.
+pub fn spawn(app: AppHandle, port: u16) {
+    let token = match token(&app) {
+        Ok(token) => token,
+        Err(e) => {
+            println!("[control] Not starting the control socket: {}", e);
+            return;
+        }
+    };
+    tauri::async_runtime::spawn(async move {
+        let listener = match TcpListener::bind(("127.0.0.1", port)).await {
+            Ok(listener) => listener,
+            Err(e) => {
+                println!(
+                    "[control] Failed to bind control socket on port {}: {}",
+                    port, e
+                );
+                return;
+            }
+        };
+        println!(
+            "[control] Listening on 127.0.0.1:{}; authenticate with control.token from settings",
+            port
+        );
+        loop {
+            match listener.accept().await {
+                Ok((stream, _)) => {
+                    tauri::async_runtime::spawn(serve_client(app.clone(), stream, token.clone()));
+                }
+                Err(e) => println!("[control] Accept failed: {}", e),
+            }
+        }
+    });
+}
+
+fn api_key_or_settings(app: &AppHandle, api_key: Option<String>) -> Result<String, String> {
+    match api_key {
+        Some(key) if !key.is_empty() => Ok(key),
+        _ => settings::api_key(app),
+    }
+}
+
+fn to_value<T: Serialize>(value: T) -> Result<Option<Value>, String> {
+    serde_json::to_value(value)
+        .map(Some)
+        .map_err(|e| format!("Failed to serialize result: {}", e))
+}
+
+async fn handle_request(app: &AppHandle, request: ControlRequest) -> Result<Option<Value>, String> {
+    match request {
+        ControlRequest::StartStream {
+            url,
+            api_key,
+            interim_results,
+        } => {
+            let api_key = api_key_or_settings(app, api_key)?;
+            let interim_results = interim_results.unwrap_or(true);
+            to_value(
+                stream::start(app, url, &api_key, interim_results, Priority::Interactive).await?,
+            )
+        }
+        ControlRequest::Stop { session_id } => {
+            stream::stop(&app.state::<stream::StreamSessions>(), &session_id)?;
+            Ok(None)
+        }
+        ControlRequest::TranscribeUrl { url, api_key } => {
+            let api_key = api_key_or_settings(app, api_key)?;
+            to_value(prerecorded::transcribe_url(app.clone(), url, api_key).await?)
+        }
+        ControlRequest::ListSessions => {
+            app_lock::ensure_unlocked(app)?;
+            to_value(
+                history::load_all(app)?
+                    .iter()
+                    .map(history::Session::summary)
+                    .collect::<Vec<_>>(),
+            )
+        }
+        // Handled in `respond`, which owns the connection's state
+        ControlRequest::Auth { .. } | ControlRequest::Subscribe => Ok(None),
+        ControlRequest::Quit => {
+            app.exit(0);
+            Ok(None)
+        }
+    }
+}
+
+async fn serve_client(app: AppHandle, stream: TcpStream, token: String) {
+    let (reader, mut writer) = stream.into_split();
+    let mut lines = BufReader::new(reader).lines();
+    let (event_tx, mut event_rx) = mpsc::channel::<String>(EVENT_BUFFER);
+    let lagged = Arc::new(Notify::new());
+    let mut client = Client {
+        token,
+        authenticated: false,
+        event_tx,
+        lagged: lagged.clone(),
+        listeners: Vec::new(),
+    };
+
+    loop {
+        let (reply, close) = tokio::select! {
+            line = lines.next_line() => match line {
+                Ok(Some(line)) => match serde_json::from_str::<Value>(&line) {
+                    Ok(request) => respond(&app, request, &mut client).await,
+                    // Most likely a browser's HTTP request, which gets no answer
+                    Err(_) => break,
+                },
+                _ => break,
+            },
+            Some(event) = event_rx.recv() => (event, false),
+            _ = lagged.notified() => {
+                println!("[control] Disconnecting a subscriber that fell behind");
+                break;
+            }
+        };
+
+        if writer
+            .write_all(format!("{}\n", reply).as_bytes())
+            .await
+            .is_err()
+            || close
+        {
+            break;
+        }
+    }
+
+    for id in client.listeners {
+        app.unlisten(id);
+    }
+}
+
+/// What a connection has set up so far.
+struct Client {
+    token: String,
+    authenticated: bool,
+    event_tx: mpsc::Sender<String>,
+    /// Notified when `event_tx` is full.
+    lagged: Arc<Notify>,
+    listeners: Vec<EventId>,
+}
+
+/// Queue an event line for a subscriber, flagging it as lagging when its queue is full.
+fn forward(tx: &mpsc::Sender<String>, lagged: &Notify, line: String) {
+    if let Err(mpsc::error::TrySendError::Full(_)) = tx.try_send(line) {
+        lagged.notify_one();
+    }
+}
+
+/// Handle one request and serialize its response line. Returns whether the connection
+/// should be closed after it.
+async fn respond(app: &AppHandle, request: Value, client: &mut Client) -> (String, bool) {
+    let request = serde_json::from_value::<ControlRequest>(request);
+    let mut close = false;
+    let response = match request {
+        Ok(ControlRequest::Auth { token }) if token == client.token => {
+            client.authenticated = true;
+            Ok(None)
+        }
+        _ if !client.authenticated => {
+            close = true;
+            Err("Missing or invalid token".to_string())
+        }
+        Ok(ControlRequest::Subscribe) if client.listeners.is_empty() => {
+            for name in FORWARDED_EVENTS {
+                let tx = client.event_tx.clone();
+                let lagged = client.lagged.clone();
+                let handle = app.clone();
+                client.listeners.push(app.listen_any(name, move |event| {
+                    // Checked per event, so locking the app stops a running subscription
+                    if app_lock::ensure_unlocked(&handle).is_err() {
+                        return;
+                    }
+                    let payload: Value =
+                        serde_json::from_str(event.payload()).unwrap_or(Value::Null);
+                    let line = serde_json::json!({ "event": name, "payload": payload });
+                    forward(&tx, &lagged, line.to_string());
+                }));
+            }
+            Ok(None)
+        }
+        Ok(request) => handle_request(app, request).await,
+        Err(e) => Err(format!("Invalid request: {}", e)),
+    };
+    let response = match response {
+        Ok(result) => ControlResponse {
+            ok: true,
+            result,
+            error: None,
+        },
+        Err(error) => ControlResponse {
+            ok: false,
+            result: None,
+            error: Some(error),
+        },
+    };
+    (serde_json::to_string(&response).unwrap_or_default(), close)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[tokio::test]
+    async fn flags_a_subscriber_that_falls_behind() {
+        let (tx, mut rx) = mpsc::channel::<String>(EVENT_BUFFER);
+        let lagged = Notify::new();
+        for i in 0..EVENT_BUFFER {
+            forward(&tx, &lagged, i.to_string());
+        }
+        let notified = tokio::time::timeout(Duration::from_millis(50), lagged.notified());
+        assert!(notified.await.is_err(), "flagged before the queue was full");
+
+        forward(&tx, &lagged, "one too many".to_string());
+        let notified = tokio::time::timeout(Duration::from_millis(50), lagged.notified());
+        assert!(notified.await.is_ok());
+        assert_eq!(rx.recv().await.as_deref(), Some("0"));
+    }
+}
@@ -0,0 +1,154 @@
+//! Watched-folder auto-transcription.
+//!
+//! New audio files dropped into a configured folder are transcribed once they stop
+//! growing, and the chosen export format is written next to them (`memo.m4a` → `memo.txt`).
+
+use crate::export::{self, ExportFormat};
+use crate::scheduler::Priority;
+use crate::settings::{self, WatchFolder};
+use crate::{history, prerecorded};
+use notify::event::{AccessKind, AccessMode, ModifyKind, RenameMode};
+use notify::{Event, EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::Duration;
+use tauri::{AppHandle, Manager};
+use tokio::sync::mpsc;
+
+/// How long a file's size must stay unchanged before it is considered fully written.
+const SETTLE_TIME: Duration = Duration::from_secs(2);
+
+#[derive(Default)]
+pub struct FolderWatcher {
+    watcher: Mutex<Option<RecommendedWatcher>>,
+}
+
+fn is_new_file(kind: &EventKind) -> bool {
+    matches!(
+        kind,
+        EventKind::Create(_)
+            | EventKind::Modify(ModifyKind::Name(RenameMode::To))
+            | EventKind::Access(AccessKind::Close(AccessMode::Write))
+    )
+}
+
+/// (Re)start watching every folder in settings, replacing any previous watcher.
+pub fn restart(app: &AppHandle) -> Result<(), String> {
+    let folders = settings::load(app).watch_folders;
+    let state = app.state::<FolderWatcher>();
+    let mut current = state.watcher.lock().unwrap();
+    // Dropping the old watcher closes its channel, which ends its worker task
+    *current = None;
+    if folders.is_empty() {
+        return Ok(());
+    }
+
+    let (tx, rx) = mpsc::unbounded_channel();
+    let mut watcher =
+        notify::recommended_watcher(move |result: notify::Result<Event>| match result {
+            Ok(event) if is_new_file(&event.kind) => {
+                for path in event.paths {
+                    let _ = tx.send(path);
+                }
+            }
+            Ok(_) => {}
+            Err(e) => println!("[folder_watch] Watch error: {}", e),
+        })
+        .map_err(|e| format!("Failed to create folder watcher: {}", e))?;
+
+    for folder in &folders {
+        watcher
+            .watch(Path::new(&folder.path), RecursiveMode::NonRecursive)
+            .map_err(|e| format!("Failed to watch {}: {}", folder.path, e))?;
+        println!("[folder_watch] Watching {}", folder.path);
+    }
+    *current = Some(watcher);
+
+    tauri::async_runtime::spawn(process_events(app.clone(), folders, rx));
+    Ok(())
+}
+
+async fn process_events(
+    app: AppHandle,
+    folders: Vec<WatchFolder>,
+    mut rx: mpsc::UnboundedReceiver<PathBuf>,
+) {
+    let mut handled = HashSet::new();
+    while let Some(path) = rx.recv().await {
+        if !path.is_file() || !prerecorded::is_audio_file(&path) || handled.contains(&path) {
+            continue;
+        }
+        let Some(format) = path
+            .parent()
+            .and_then(|dir| export_format_for(&folders, dir))
+        else {
+            continue;
+        };
+        handled.insert(path.clone());
+
+        if let Err(e) = transcribe(&app, &path, format).await {
+            println!("[folder_watch] {}", e);
+            // Allow another attempt if the file is written again
+            handled.remove(&path);
+        }
+    }
+}
+
+fn export_format_for(folders: &[WatchFolder], dir: &Path) -> Option<ExportFormat> {
+    folders
+        .iter()
+        .find(|folder| Path::new(&folder.path) == dir)
+        .map(|folder| folder.export_format)
+}
+
+/// Wait until the file stops growing, so half-copied files aren't uploaded.
+async fn wait_until_settled(path: &Path) -> Result<(), String> {
+    let mut last = None;
+    loop {
+        let size = fs::metadata(path)
+            .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?
+            .len();
+        if last == Some(size) {
+            return Ok(());
+        }
+        last = Some(size);
+        tokio::time::sleep(SETTLE_TIME).await;
+    }
+}
+
+async fn transcribe(app: &AppHandle, path: &Path, format: ExportFormat) -> Result<(), String> {
+    let source = format!("file:{}", path.display());
+    if history::load_all(app)?
+        .iter()
+        .any(|session| session.source == source)
+    {
+        return Ok(());
+    }
+
+    wait_until_settled(path).await?;
+    let api_key = settings::api_key(app)?;
+    let session = prerecorded::transcribe_file(app, path, &api_key, Priority::Normal).await?;
+
+    let output = path.with_extension(format.extension());
+    fs::write(&output, export::render(&session, format)?)
+        .map_err(|e| format!("Failed to write {}: {}", output.display(), e))?;
+    println!("[folder_watch] Wrote {}", output.display());
+    Ok(())
+}
+
+/// Replace the watched folders and restart watching.
+#[tauri::command]
+#[specta::specta]
+pub fn set_watch_folders(app: AppHandle, folders: Vec<WatchFolder>) -> Result<(), String> {
+    for folder in &folders {
+        if !Path::new(&folder.path).is_dir() {
+            return Err(format!("Not a directory: {}", folder.path));
+        }
+    }
+    let mut current = settings::load(&app);
+    current.watch_folders = folders;
+    settings::save(&app, &current)?;
+    restart(&app)
+}
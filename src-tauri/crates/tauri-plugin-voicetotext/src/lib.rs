@@ -0,0 +1,425 @@
+//! The dictation engine of Voice to Text as a Tauri plugin: audio capture, transcription
+//! providers, sessions and storage, with every command the app's frontend calls.
+//!
+//! Register it with `tauri::Builder::plugin(tauri_plugin_voicetotext::init(options))`.
+//! Commands are invoked as `plugin:voicetotext|<command>` and allowed by the
+//! `voicetotext:default` permission. The plugin opens no windows; that is the app's job.
+
+mod capture;
+mod control;
+mod folder_watch;
+mod providers;
+mod rest_api;
+mod sessions;
+mod soak;
+mod storage;
+
+// Modules are grouped by area on disk but addressed by name throughout the crate
+use capture::{
+    audio_filters, calibration, flac, hid, hotkeys, recordings, silence, wake_word, wav,
+};
+use providers::{
+    acceleration, accuracy, api_keys, bandwidth, benchmark, chunked, deflate, dns, local_engine,
+    models, network, network_watch, prerecorded, scheduler, stream,
+};
+use sessions::{
+    capitalization, caption_server, chapters, clipboard, commands, dates, dictation, edit, emoji,
+    export, history, import, inject, numbers, overlay, profiles, publish, recent, snippets,
+    spelling, templates, tts, versions,
+};
+use storage::{
+    app_lock, backup, bundle, crash, encryption, lan_sync, outbox, quota, retention, schema,
+    settings, sync, telemetry, webhook, wipe, zip,
+};
+
+pub use control::CliOptions;
+
+use futures_util::{SinkExt, StreamExt};
+use serde::{Deserialize, Serialize};
+use specta::Type;
+use specta_typescript::{BigIntExportBehavior, Typescript};
+use std::path::Path;
+use tauri::plugin::{Builder, TauriPlugin};
+use tauri::{Manager, Wry};
+use tokio_tungstenite::tungstenite::{self, Message};
+
+#[derive(Serialize, Deserialize, Debug, Type)]
+pub struct ProbeResult {
+    pub success: bool,
+    pub message: String,
+    pub code: Option<u16>,
+    pub reason: Option<String>,
+    /// Connection stage that failed.
+    pub stage: Option<network::Stage>,
+}
+
+/// Probe Deepgram WebSocket endpoint to validate API key and network connectivity.
+/// Runs outside the browser context, avoiding browser-level TLS/proxy restrictions.
+/// Each stage of the connection has its own time limit, from `timeouts` or else
+/// `network.timeouts`; a failure reports the stage it stopped at.
+#[tauri::command]
+#[specta::specta]
+async fn probe_deepgram(
+    app: tauri::AppHandle,
+    api_key: String,
+    timeouts: Option<settings::ConnectTimeouts>,
+) -> ProbeResult {
+    let config = settings::load(&app);
+    let mut network = config.network;
+    if let Some(timeouts) = timeouts {
+        network.timeouts = timeouts;
+    }
+    // The key goes in the Authorization header unless the query-param fallback is set
+    let url = "wss://api.deepgram.com/v1/listen?model=nova-2&language=en-US&encoding=linear16&sample_rate=16000";
+    let request = match api_keys::websocket_request(url, &api_key, config.deepgram_key_in_url) {
+        Ok(request) => request,
+        Err(message) => {
+            return ProbeResult {
+                success: false,
+                message,
+                code: None,
+                reason: None,
+                stage: None,
+            }
+        }
+    };
+
+    println!("[probe_deepgram] Attempting connection to: {}", url);
+    println!("[probe_deepgram] Timeouts: {:?}", network.timeouts);
+
+    let result = match network::connect(request, &network, &bandwidth::Meter::default()).await {
+        // Asking Deepgram to close makes it answer, which proves the socket carries data
+        Ok(mut ws_stream) => {
+            network::stage(
+                network::Stage::FirstMessage,
+                network.timeouts.first_message_ms,
+                async {
+                    ws_stream
+                        .send(Message::Text(r#"{"type":"CloseStream"}"#.to_string()))
+                        .await?;
+                    match ws_stream.next().await {
+                        Some(message) => message.map(|_| ()),
+                        None => Err(tungstenite::Error::ConnectionClosed),
+                    }
+                },
+            )
+            .await
+        }
+        Err(e) => Err(e),
+    };
+
+    match result {
+        Ok(()) => {
+            println!("[probe_deepgram] WebSocket opened successfully");
+            ProbeResult {
+                success: true,
+                message: "WebSocket connection established successfully".to_string(),
+                code: None,
+                reason: None,
+                stage: None,
+            }
+        }
+        Err(e) => {
+            println!("[probe_deepgram] {} failed: {}", e.stage, e.message);
+            let message = match e.status {
+                Some(401) => "Authentication failed — check your Deepgram API key".to_string(),
+                Some(403) => "Permission denied — check your Deepgram account and plan".to_string(),
+                _ => e.message,
+            };
+            ProbeResult {
+                success: false,
+                message,
+                code: e.timed_out.then_some(1006),
+                reason: e.timed_out.then(|| "Timeout".to_string()),
+                stage: Some(e.stage),
+            }
+        }
+    }
+}
+
+/// Commands, and the payloads of events the backend emits, as tauri-specta sees them.
+fn specta_builder() -> tauri_specta::Builder<Wry> {
+    tauri_specta::Builder::<Wry>::new()
+        .plugin_name("voicetotext")
+        .commands(tauri_specta::collect_commands![
+            probe_deepgram,
+            api_keys::validate_api_key,
+            bandwidth::get_bandwidth_stats,
+            stream::start_stream_transcription,
+            stream::stop_stream_transcription,
+            prerecorded::transcribe_url,
+            benchmark::benchmark_providers,
+            accuracy::evaluate_accuracy,
+            acceleration::get_acceleration_info,
+            models::list_available_models,
+            models::download_model,
+            models::delete_model,
+            models::estimate_model_requirements,
+            models::list_model_options,
+            local_engine::get_active_engine,
+            tts::speak_text,
+            tts::speak_session,
+            tts::control_readback,
+            wake_word::train_wake_word,
+            wake_word::list_wake_words,
+            wake_word::delete_wake_word,
+            wake_word::feed_wake_word,
+            wake_word::reset_wake_word,
+            commands::recognize_command,
+            commands::set_command_grammar,
+            inject::inject_text,
+            inject::scratch_that,
+            inject::reset_injection,
+            inject::paste_last_transcript,
+            spelling::set_spelling_mode,
+            spelling::get_spelling_mode,
+            dictation::finalize_dictation,
+            snippets::set_snippets,
+            profiles::get_foreground_app,
+            recent::get_recent_utterances,
+            recent::copy_utterance,
+            templates::list_templates,
+            templates::start_template,
+            templates::select_template_slot,
+            templates::get_template_document,
+            templates::finish_template,
+            history::list_sessions,
+            history::get_session,
+            history::delete_session,
+            history::set_session_starred,
+            history::search_sessions,
+            history::find_in_session,
+            history::get_segment_at,
+            settings::get_settings,
+            settings::update_settings,
+            scheduler::list_jobs,
+            scheduler::add_job,
+            scheduler::remove_job,
+            scheduler::set_job_enabled,
+            scheduler::begin_dictation,
+            scheduler::end_dictation,
+            caption_server::start_caption_server,
+            caption_server::stop_caption_server,
+            caption_server::get_caption_server_status,
+            caption_server::publish_caption,
+            overlay::show_caption_overlay,
+            overlay::hide_caption_overlay,
+            overlay::set_caption_overlay_position,
+            export::export_session,
+            bundle::export_bundle,
+            recordings::archive_recording,
+            quota::get_storage_usage,
+            retention::apply_retention,
+            publish::publish_session,
+            import::import_transcript,
+            edit::merge_sessions,
+            edit::edit_segment,
+            edit::retranscribe_session,
+            edit::undo_edit,
+            edit::redo_edit,
+            versions::get_session_history,
+            versions::get_session_version,
+            versions::diff_session_versions,
+            chapters::generate_chapters,
+            chapters::add_chapter,
+            chapters::remove_chapter,
+            folder_watch::set_watch_folders,
+            hotkeys::set_hotkeys,
+            hid::list_hid_devices,
+            encryption::get_encryption_status,
+            encryption::enable_encryption,
+            encryption::disable_encryption,
+            encryption::unlock_history,
+            encryption::lock_history,
+            app_lock::get_app_lock_status,
+            app_lock::enable_app_lock,
+            app_lock::disable_app_lock,
+            app_lock::unlock,
+            app_lock::lock,
+            wipe::erase_all_data,
+            backup::backup_data,
+            backup::restore_data,
+            sync::sync_now,
+            outbox::list_pending_deliveries,
+            outbox::purge_pending_deliveries,
+            lan_sync::start_lan_receiver,
+            lan_sync::stop_lan_receiver,
+            lan_sync::discover_lan_peers,
+            lan_sync::send_to_lan_peer,
+            telemetry::set_telemetry,
+            telemetry::get_telemetry_report,
+            crash::list_crash_reports,
+            crash::delete_crash_reports,
+            audio_filters::create_filter_chain,
+            audio_filters::filter_audio,
+            audio_filters::close_filter_chain,
+            calibration::calibrate_device,
+            calibration::list_device_calibrations,
+            rest_api::start_rest_api,
+            rest_api::stop_rest_api,
+            rest_api::get_rest_api_status,
+            soak::start_soak_test,
+            soak::stop_soak_test,
+            soak::get_soak_report
+        ])
+        // Events are emitted by name, with `schema_version` added; each payload's doc comment
+        // names its event
+        .typ::<local_engine::EngineChanged>()
+        .typ::<models::DownloadProgress>()
+        .typ::<api_keys::KeyExhausted>()
+        .typ::<stream::ConnectionEvent>()
+        .typ::<stream::TranscriptEvent>()
+        .typ::<stream::SessionEnded>()
+        .typ::<stream::SaveFailed>()
+        .typ::<stream::RolledOver>()
+        .typ::<network_watch::NetworkChanged>()
+        .typ::<scheduler::CaptureRequest>()
+        .typ::<scheduler::JobFinished>()
+        .typ::<commands::RecognizedCommand>()
+        .typ::<templates::DocumentState>()
+        .typ::<tts::ReadbackEvent>()
+        .typ::<spelling::SpellingChanged>()
+        .typ::<quota::QuotaWarning>()
+        .typ::<quota::Evicting>()
+        .typ::<lan_sync::LanReceived>()
+        .typ::<hotkeys::HotkeyEvent>()
+        .typ::<wake_word::WakeWordDetected>()
+        .typ::<soak::SoakSample>()
+}
+
+/// Write TypeScript types and wrappers for every command and event payload to `path`.
+/// `BINDINGS_VERSION` in the output is the plugin version they were generated from.
+pub fn export_bindings(path: impl AsRef<Path>) -> Result<(), String> {
+    // Written here rather than as builder constants, which come out in random order
+    let header = format!(
+        "// @ts-nocheck\n// Generated by tauri-plugin-voicetotext; do not edit.\n\n\
+         export const BINDINGS_VERSION = {:?} as const;\n\
+         export const EVENT_SCHEMA_VERSION = {} as const;\n",
+        env!("CARGO_PKG_VERSION"),
+        schema::EVENT_SCHEMA_VERSION
+    );
+    let typescript = Typescript::default()
+        // The generated helpers the app doesn't use would trip `noUnusedLocals`
+        .header(header)
+        // Byte counts and timestamps are u64 but stay well inside a JS number
+        .bigint(BigIntExportBehavior::Number);
+    specta_builder()
+        .export(typescript, path)
+        .map_err(|e| format!("Failed to export bindings: {}", e))
+}
+
+/// The plugin, started according to the command line the app was launched with.
+pub fn init(options: CliOptions) -> TauriPlugin<Wry> {
+    let specta = specta_builder();
+    Builder::new("voicetotext")
+        .setup(move |app, _api| {
+            app.manage(stream::StreamSessions::default());
+            app.manage(scheduler::Scheduler::default());
+            app.manage(scheduler::RequestLimiter::default());
+            app.manage(outbox::Outbox::default());
+            app.manage(caption_server::CaptionServer::default());
+            app.manage(folder_watch::FolderWatcher::default());
+            app.manage(rest_api::RestApi::default());
+            app.manage(hotkeys::Hotkeys::default());
+            app.manage(hid::HidDevices::default());
+            app.manage(encryption::Vault::default());
+            app.manage(app_lock::AppLock::default());
+            app.manage(api_keys::KeyRotation::default());
+            app.manage(bandwidth::BandwidthLog::default());
+            app.manage(sync::SyncLock::default());
+            app.manage(lan_sync::LanReceiver::default());
+            app.manage(telemetry::Telemetry::default());
+            app.manage(audio_filters::FilterChains::default());
+            app.manage(calibration::CalibrationStore::default());
+            app.manage(models::ModelDownloads::default());
+            app.manage(wake_word::WakeWordDetector::default());
+            app.manage(inject::Injector::default());
+            app.manage(spelling::SpellingMode::default());
+            app.manage(templates::TemplateDocument::default());
+            app.manage(recent::RecentUtterances::default());
+            app.manage(local_engine::ActiveEngine::default());
+            app.manage(tts::Readback::default());
+            app.manage(soak::SoakTest::default());
+            app.plugin(hotkeys::plugin())?;
+            encryption::unlock_from_keychain(app);
+            if let Err(e) = crash::install(app) {
+                println!("[crash] {}", e);
+            }
+            crash::spawn_upload(app.clone());
+            if options.headless {
+                control::spawn(app.clone(), options.control_port);
+            }
+            scheduler::spawn(app.clone());
+            outbox::spawn(app.clone());
+            telemetry::spawn(app.clone());
+            recordings::spawn(app.clone());
+            retention::spawn(app.clone());
+            network_watch::spawn(app.clone());
+            hid::spawn(app.clone());
+            if let Err(e) = folder_watch::restart(app) {
+                println!("[folder_watch] {}", e);
+            }
+            if !options.headless {
+                if let Err(e) = hotkeys::register(app) {
+                    println!("[hotkeys] {}", e);
+                }
+            }
+            if let Some(config) = options.soak.clone() {
+                let handle = app.clone();
+                let exit_when_done = options.headless;
+                tauri::async_runtime::spawn(async move {
+                    if let Err(e) = soak::start(&handle, config, exit_when_done).await {
+                        println!("[soak] {}", e);
+                        if exit_when_done {
+                            handle.exit(1);
+                        }
+                    }
+                });
+            }
+            let startup = settings::load(app);
+            if startup.caption_server.enabled {
+                let handle = app.clone();
+                tauri::async_runtime::spawn(async move {
+                    if let Err(e) = caption_server::start(&handle).await {
+                        println!("[caption_server] {}", e);
+                    }
+                });
+            }
+            if startup.rest_api.enabled {
+                let handle = app.clone();
+                tauri::async_runtime::spawn(async move {
+                    if let Err(e) = rest_api::start(&handle).await {
+                        println!("[rest_api] {}", e);
+                    }
+                });
+            }
+            Ok(())
+        })
+        .invoke_handler(specta.invoke_handler())
+        .build()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    /// The frontend's copy, which the webview imports.
+    const BINDINGS: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/../../../src/bindings.ts");
+
+    #[test]
+    fn committed_bindings_are_up_to_date() {
+        let dir = std::env::temp_dir().join(format!("bindings-{}", uuid::Uuid::new_v4()));
+        fs::create_dir_all(&dir).unwrap();
+        export_bindings(dir.join("bindings.ts")).unwrap();
+        let generated = fs::read_to_string(dir.join("bindings.ts")).unwrap();
+        if std::env::var_os("UPDATE_BINDINGS").is_some() {
+            fs::write(BINDINGS, &generated).unwrap();
+        }
+        let committed = fs::read_to_string(BINDINGS).unwrap_or_default();
+        assert!(
+            committed == generated,
+            "src/bindings.ts is out of date; regenerate it with `UPDATE_BINDINGS=1 cargo test -p tauri-plugin-voicetotext bindings`"
+        );
+    }
+}
@@ -0,0 +1,18 @@
+//! Speech-to-text providers: Deepgram streaming and prerecorded requests, the local engine
+//! and its models, and the networking, scheduling and accounting around them.
+
+pub mod acceleration;
+pub mod accuracy;
+pub mod api_keys;
+pub mod bandwidth;
+pub mod benchmark;
+pub mod chunked;
+pub mod deflate;
+pub mod dns;
+pub mod local_engine;
+pub mod models;
+pub mod network;
+pub mod network_watch;
+pub mod prerecorded;
+pub mod scheduler;
+pub mod stream;
@@ -0,0 +1,132 @@
+//! GPU runtimes usable for local transcription.
+//!
+//! Recognition runs on Deepgram, or on an external whisper.cpp program when offline (see
+//! `local_engine`). whisper.cpp uses the GPU backend it was compiled with; builds with
+//! shared libraries ship each backend as a `ggml-<backend>` library beside the program,
+//! and macOS builds include Metal. The command reports which accelerators the machine
+//! offers, which the installed whisper.cpp can use, and which one local transcription
+//! will run on given `offline_fallback.gpu`.
+
+use crate::local_engine;
+use crate::settings;
+use serde::Serialize;
+use specta::Type;
+use std::path::Path;
+use tauri::AppHandle;
+
+#[derive(Serialize, Clone, Copy, Debug, PartialEq, Eq, Type)]
+#[serde(rename_all = "snake_case")]
+pub enum AcceleratorKind {
+    Cuda,
+    Metal,
+    Vulkan,
+}
+
+#[derive(Serialize, Clone, Debug, Type)]
+pub struct Accelerator {
+    pub kind: AcceleratorKind,
+    pub detected: bool,
+    /// What the detection was based on, e.g. the driver library that was found.
+    pub detail: Option<String>,
+    /// Whether the installed whisper.cpp program was built with this backend.
+    pub supported: bool,
+}
+
+#[derive(Serialize, Clone, Debug, Type)]
+pub struct AccelerationInfo {
+    pub accelerators: Vec<Accelerator>,
+    /// Whether local transcription is set up: whisper.cpp is installed and a model is
+    /// downloaded.
+    pub local_backend: bool,
+    /// The accelerator local transcription runs on; the CPU when unset.
+    pub active: Option<AcceleratorKind>,
+}
+
+const CUDA_LIBRARIES: &[&str] = &[
+    "/usr/lib/x86_64-linux-gnu/libcuda.so.1",
+    "/usr/lib/aarch64-linux-gnu/libcuda.so.1",
+    "/usr/lib64/libcuda.so.1",
+    "/usr/lib/libcuda.so.1",
+    "/usr/lib/wsl/lib/libcuda.so.1",
+    "C:\\Windows\\System32\\nvcuda.dll",
+];
+
+const VULKAN_LIBRARIES: &[&str] = &[
+    "/usr/lib/x86_64-linux-gnu/libvulkan.so.1",
+    "/usr/lib/aarch64-linux-gnu/libvulkan.so.1",
+    "/usr/lib64/libvulkan.so.1",
+    "/usr/lib/libvulkan.so.1",
+    "C:\\Windows\\System32\\vulkan-1.dll",
+];
+
+fn first_existing(paths: &[&str]) -> Option<String> {
+    paths
+        .iter()
+        .find(|path| Path::new(path).exists())
+        .map(|path| path.to_string())
+}
+
+/// Whether a `ggml-<backend>` library sits beside the whisper.cpp program or in the
+/// `lib` directory next to its own.
+fn has_backend_library(binary: &Path, backend: &str) -> bool {
+    let Some(dir) = binary.parent() else {
+        return false;
+    };
+    let names = [
+        format!("libggml-{}.so", backend),
+        format!("libggml-{}.dylib", backend),
+        format!("ggml-{}.dll", backend),
+    ];
+    [dir.to_path_buf(), dir.join("..").join("lib")]
+        .iter()
+        .any(|dir| names.iter().any(|name| dir.join(name).is_file()))
+}
+
+fn supported(binary: Option<&Path>, kind: AcceleratorKind) -> bool {
+    let Some(binary) = binary else {
+        return false;
+    };
+    match kind {
+        AcceleratorKind::Cuda => has_backend_library(binary, "cuda"),
+        AcceleratorKind::Vulkan => has_backend_library(binary, "vulkan"),
+        AcceleratorKind::Metal => cfg!(target_os = "macos") || has_backend_library(binary, "metal"),
+    }
+}
+
+/// Report the GPU runtimes found on this machine and the one local transcription uses.
+#[tauri::command]
+#[specta::specta]
+pub fn get_acceleration_info(app: AppHandle) -> AccelerationInfo {
+    let config = settings::load(&app).offline_fallback;
+    let binary = local_engine::find_binary(&config);
+    // Every Mac that runs Tauri 2 supports Metal
+    let metal = cfg!(target_os = "macos").then(|| {
+        if cfg!(target_arch = "aarch64") {
+            "Apple silicon".to_string()
+        } else {
+            "macOS".to_string()
+        }
+    });
+    let accelerators: Vec<Accelerator> = [
+        (AcceleratorKind::Cuda, first_existing(CUDA_LIBRARIES)),
+        (AcceleratorKind::Metal, metal),
+        (AcceleratorKind::Vulkan, first_existing(VULKAN_LIBRARIES)),
+    ]
+    .into_iter()
+    .map(|(kind, detail)| Accelerator {
+        kind,
+        detected: detail.is_some(),
+        detail,
+        supported: supported(binary.as_deref(), kind),
+    })
+    .collect();
+    let active = accelerators
+        .iter()
+        .find(|accelerator| config.gpu && accelerator.detected && accelerator.supported)
+        .map(|accelerator| accelerator.kind);
+    AccelerationInfo {
+        accelerators,
+        local_backend: local_engine::available(&app).is_some(),
+        active,
+    }
+}
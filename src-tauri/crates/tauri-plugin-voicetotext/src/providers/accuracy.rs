@@ -0,0 +1,200 @@
+//! Word and character error rates of a stored session against a reference transcript.
+//!
+//! Both texts are normalized the same way before comparing — lowercased, punctuation
+//! dropped (apostrophes kept) and whitespace collapsed — so formatting differences such as
+//! smart formatting's capitalization don't count as errors.
+
+use crate::{app_lock, history};
+use serde::Serialize;
+use specta::Type;
+use tauri::AppHandle;
+
+/// Largest character alignment to attempt, in reference × hypothesis characters: about
+/// an hour of speech against its reference. Alignment time grows with the product.
+const MAX_ALIGN_CELLS: usize = 2_500_000_000;
+
+#[derive(Serialize, Clone, Debug, Default, Type)]
+pub struct EditCounts {
+    pub substitutions: usize,
+    pub deletions: usize,
+    pub insertions: usize,
+    /// Length of the reference, in words or characters.
+    pub reference_len: usize,
+}
+
+impl EditCounts {
+    /// Errors divided by reference length; can exceed 1 when the hypothesis is much longer.
+    pub fn rate(&self) -> f64 {
+        if self.reference_len == 0 {
+            return 0.0;
+        }
+        (self.substitutions + self.deletions + self.insertions) as f64 / self.reference_len as f64
+    }
+}
+
+#[derive(Serialize, Clone, Debug, Type)]
+pub struct AccuracyReport {
+    pub wer: f64,
+    pub cer: f64,
+    pub words: EditCounts,
+    pub chars: EditCounts,
+}
+
+fn normalize(text: &str) -> Vec<String> {
+    text.to_lowercase()
+        .chars()
+        .map(|c| {
+            if c.is_alphanumeric() || c == '\'' {
+                c
+            } else {
+                ' '
+            }
+        })
+        .collect::<String>()
+        .split_whitespace()
+        .map(str::to_string)
+        .collect()
+}
+
+/// Levenshtein alignment of `hypothesis` against `reference`, counting each edit kind.
+fn align<T: PartialEq>(reference: &[T], hypothesis: &[T]) -> EditCounts {
+    // Each cell holds (substitutions, deletions, insertions); cost is their sum
+    type Cell = (usize, usize, usize);
+    let cost = |c: &Cell| c.0 + c.1 + c.2;
+
+    let mut previous: Vec<Cell> = (0..=hypothesis.len()).map(|j| (0, 0, j)).collect();
+    for (i, r) in reference.iter().enumerate() {
+        let mut current: Vec<Cell> = Vec::with_capacity(hypothesis.len() + 1);
+        current.push((0, i + 1, 0));
+        for (j, h) in hypothesis.iter().enumerate() {
+            let diagonal = previous[j];
+            let candidates = [
+                if r == h {
+                    diagonal
+                } else {
+                    (diagonal.0 + 1, diagonal.1, diagonal.2)
+                },
+                (previous[j + 1].0, previous[j + 1].1 + 1, previous[j + 1].2),
+                (current[j].0, current[j].1, current[j].2 + 1),
+            ];
+            current.push(candidates.into_iter().min_by_key(cost).unwrap());
+        }
+        previous = current;
+    }
+    let (substitutions, deletions, insertions) = previous[hypothesis.len()];
+    EditCounts {
+        substitutions,
+        deletions,
+        insertions,
+        reference_len: reference.len(),
+    }
+}
+
+pub fn evaluate(hypothesis: &str, reference: &str) -> AccuracyReport {
+    let reference_words = normalize(reference);
+    let hypothesis_words = normalize(hypothesis);
+    let words = align(&reference_words, &hypothesis_words);
+
+    let reference_chars: Vec<char> = reference_words.join(" ").chars().collect();
+    let hypothesis_chars: Vec<char> = hypothesis_words.join(" ").chars().collect();
+    let chars = align(&reference_chars, &hypothesis_chars);
+
+    AccuracyReport {
+        wer: words.rate(),
+        cer: chars.rate(),
+        words,
+        chars,
+    }
+}
+
+/// Compare a stored session's transcript with `reference_text`.
+#[tauri::command]
+#[specta::specta]
+pub async fn evaluate_accuracy(
+    app: AppHandle,
+    session_id: String,
+    reference_text: String,
+) -> Result<AccuracyReport, String> {
+    app_lock::ensure_unlocked(&app)?;
+    if normalize(&reference_text).is_empty() {
+        return Err("Reference text is empty".to_string());
+    }
+    let session = history::load(&app, &session_id)?;
+    let hypothesis = session
+        .segments
+        .iter()
+        .map(|segment| segment.text.as_str())
+        .collect::<Vec<_>>()
+        .join(" ");
+    if hypothesis
+        .chars()
+        .count()
+        .saturating_mul(reference_text.chars().count())
+        > MAX_ALIGN_CELLS
+    {
+        return Err("Transcript is too long to compare".to_string());
+    }
+    let report =
+        tauri::async_runtime::spawn_blocking(move || evaluate(&hypothesis, &reference_text))
+            .await
+            .map_err(|e| format!("Accuracy task failed: {}", e))?;
+    println!(
+        "[accuracy] Session {}: WER {:.3}, CER {:.3}",
+        session_id, report.wer, report.cer
+    );
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn normalizes_case_punctuation_and_spacing() {
+        let cases = [
+            ("Hello, World!", vec!["hello", "world"]),
+            ("  it's   fine\n", vec!["it's", "fine"]),
+            ("e-mail — now.", vec!["e", "mail", "now"]),
+            ("Über 42%", vec!["über", "42"]),
+            ("?!", vec![]),
+        ];
+        for (text, expected) in cases {
+            assert_eq!(normalize(text), expected, "{:?}", text);
+        }
+    }
+
+    #[test]
+    fn counts_each_edit_kind() {
+        // (reference, hypothesis, substitutions, deletions, insertions)
+        let cases = [
+            ("the cat sat", "the cat sat", 0, 0, 0),
+            ("the cat sat", "the dog sat", 1, 0, 0),
+            ("the cat sat", "the sat", 0, 1, 0),
+            ("the cat sat", "the black cat sat", 0, 0, 1),
+            ("the cat sat", "", 0, 3, 0),
+            ("", "the cat", 0, 0, 2),
+            ("a b c d", "b c d e", 0, 1, 1),
+            ("a b c", "x b y z", 2, 0, 1),
+        ];
+        for (reference, hypothesis, substitutions, deletions, insertions) in cases {
+            let counts = align(&normalize(reference), &normalize(hypothesis));
+            assert_eq!(
+                (counts.substitutions, counts.deletions, counts.insertions),
+                (substitutions, deletions, insertions),
+                "{:?} vs {:?}",
+                reference,
+                hypothesis
+            );
+            assert_eq!(counts.reference_len, normalize(reference).len());
+        }
+    }
+
+    #[test]
+    fn rates_words_and_characters() {
+        let report = evaluate("The dog sat.", "the cat sat");
+        assert!((report.wer - 1.0 / 3.0).abs() < 1e-9);
+        assert_eq!(report.chars.substitutions, 3);
+        assert_eq!(report.chars.reference_len, 11);
+        assert_eq!(evaluate("anything", "").wer, 0.0);
+    }
+}
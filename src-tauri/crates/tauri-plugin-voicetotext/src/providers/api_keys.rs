@@ -0,0 +1,310 @@
+//! Checking and rotating provider API keys.
+//!
+//! `validate_api_key` catches malformed keys locally and then makes one cheap
+//! authenticated REST call, which tells a revoked key apart from a network problem far
+//! better than a failed WebSocket upgrade does. Deepgram is the only provider.
+//!
+//! Several keys can be configured (`deepgram_api_key` plus `deepgram_api_keys`). When
+//! one is rate-limited or out of quota, requests move on to the next and
+//! `api_key:exhausted` is emitted; an exhausted key is tried again after a cooldown.
+
+use crate::schema;
+use crate::settings::{self, Settings};
+use serde::{Deserialize, Serialize};
+use specta::Type;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Manager};
+use tokio_tungstenite::tungstenite::client::IntoClientRequest;
+use tokio_tungstenite::tungstenite::handshake::client::Request;
+use tokio_tungstenite::tungstenite::http::HeaderValue;
+
+const PROJECTS_ENDPOINT: &str = "https://api.deepgram.com/v1/projects";
+const TOKEN_ENDPOINT: &str = "https://api.deepgram.com/v1/auth/token";
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+/// How long a rate-limited key is skipped before it is tried again.
+const EXHAUSTED_COOLDOWN: Duration = Duration::from_secs(10 * 60);
+
+/// Start of the error returned for a rate-limited or out-of-quota key.
+pub const EXHAUSTED: &str = "Deepgram rate limit or quota reached";
+
+/// The key currently in use and the keys that recently hit a limit.
+#[derive(Default)]
+pub struct KeyRotation(Mutex<RotationState>);
+
+#[derive(Default)]
+struct RotationState {
+    active: Option<String>,
+    exhausted: HashMap<String, Instant>,
+}
+
+/// Payload of `api_key:exhausted`. Keys are identified by their last characters only.
+#[derive(Serialize, Clone, Debug, Type)]
+pub struct KeyExhausted {
+    pub provider: String,
+    pub key_hint: String,
+    /// The key used from now on; `None` when every configured key is exhausted.
+    pub next_key_hint: Option<String>,
+}
+
+#[derive(Serialize, Clone, Debug, Default, Type)]
+pub struct KeyValidation {
+    pub valid: bool,
+    pub message: String,
+    /// Projects the key can access.
+    pub projects: Vec<KeyProject>,
+    /// Permissions of the key, when the provider reports them.
+    pub scopes: Vec<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Type)]
+pub struct KeyProject {
+    pub project_id: String,
+    pub name: String,
+}
+
+#[derive(Deserialize)]
+struct ProjectsResponse {
+    #[serde(default)]
+    projects: Vec<KeyProject>,
+}
+
+#[derive(Deserialize)]
+struct TokenResponse {
+    #[serde(default)]
+    scopes: Vec<String>,
+}
+
+fn invalid(message: impl Into<String>) -> KeyValidation {
+    KeyValidation {
+        valid: false,
+        message: message.into(),
+        ..KeyValidation::default()
+    }
+}
+
+/// Deepgram keys are 40 hexadecimal characters.
+fn check_deepgram_format(key: &str) -> Result<(), String> {
+    if key.trim() != key {
+        return Err("The key has leading or trailing whitespace".to_string());
+    }
+    if key.len() != 40 {
+        return Err(format!(
+            "Deepgram keys are 40 characters long; this one has {}",
+            key.chars().count()
+        ));
+    }
+    if !key.chars().all(|c| c.is_ascii_hexdigit()) {
+        return Err("Deepgram keys contain only the characters 0-9 and a-f".to_string());
+    }
+    Ok(())
+}
+
+async fn validate_deepgram(key: &str) -> KeyValidation {
+    if let Err(e) = check_deepgram_format(key) {
+        return invalid(e);
+    }
+    let client = reqwest::Client::new();
+    let response = match client
+        .get(PROJECTS_ENDPOINT)
+        .header("Authorization", format!("Token {}", key))
+        .timeout(REQUEST_TIMEOUT)
+        .send()
+        .await
+    {
+        Ok(response) => response,
+        Err(e) if e.is_timeout() => return invalid("Deepgram did not answer in time"),
+        Err(e) => return invalid(format!("Could not reach Deepgram: {}", e)),
+    };
+
+    let status = response.status();
+    if status == reqwest::StatusCode::UNAUTHORIZED {
+        return invalid("Deepgram rejected the key — it may be mistyped, expired or revoked");
+    }
+    if status == reqwest::StatusCode::FORBIDDEN {
+        // Keys limited to transcription may not list projects, but they do authenticate
+        return KeyValidation {
+            valid: true,
+            message: "Key accepted; it has no permission to list projects".to_string(),
+            ..KeyValidation::default()
+        };
+    }
+    if !status.is_success() {
+        return invalid(format!("Deepgram returned HTTP {}", status));
+    }
+    let projects = match response.json::<ProjectsResponse>().await {
+        Ok(parsed) => parsed.projects,
+        Err(e) => return invalid(format!("Unexpected Deepgram response: {}", e)),
+    };
+
+    // Best effort: the key still works when its details can't be read
+    let scopes = match client
+        .get(TOKEN_ENDPOINT)
+        .header("Authorization", format!("Token {}", key))
+        .timeout(REQUEST_TIMEOUT)
+        .send()
+        .await
+    {
+        Ok(response) if response.status().is_success() => response
+            .json::<TokenResponse>()
+            .await
+            .map(|parsed| parsed.scopes)
+            .unwrap_or_default(),
+        _ => Vec::new(),
+    };
+
+    let message = match projects.as_slice() {
+        [] => "Key accepted".to_string(),
+        [project] => format!("Key accepted for project {}", project.name),
+        _ => format!("Key accepted for {} projects", projects.len()),
+    };
+    KeyValidation {
+        valid: true,
+        message,
+        projects,
+        scopes,
+    }
+}
+
+/// Check that `key` is well-formed and accepted by `provider` without opening a stream.
+#[tauri::command]
+#[specta::specta]
+pub async fn validate_api_key(provider: String, key: String) -> Result<KeyValidation, String> {
+    match provider.to_ascii_lowercase().as_str() {
+        "deepgram" => Ok(validate_deepgram(&key).await),
+        other => Err(format!("Unknown provider: {}", other)),
+    }
+}
+
+/// Every configured Deepgram key, primary first, without blanks or duplicates.
+pub fn configured_keys(settings: &Settings) -> Vec<String> {
+    let mut keys: Vec<String> = Vec::new();
+    for key in settings
+        .deepgram_api_key
+        .iter()
+        .chain(&settings.deepgram_api_keys)
+    {
+        if !key.is_empty() && !keys.contains(key) {
+            keys.push(key.clone());
+        }
+    }
+    keys
+}
+
+fn hint(key: &str) -> String {
+    let tail: String = key
+        .chars()
+        .rev()
+        .take(4)
+        .collect::<Vec<_>>()
+        .into_iter()
+        .rev()
+        .collect();
+    format!("…{}", tail)
+}
+
+/// Whether an HTTP `status` means the key hit a rate limit (429) or ran out of credit
+/// (402).
+pub fn is_exhausted_status(status: u16) -> bool {
+    status == 429 || status == 402
+}
+
+pub fn is_exhausted(error: &str) -> bool {
+    error.starts_with(EXHAUSTED)
+}
+
+/// The configured key to use right now: the one rotated to last, or the primary.
+pub fn current(app: &AppHandle) -> Option<String> {
+    let keys = configured_keys(&settings::load(app));
+    let state = app.state::<KeyRotation>();
+    let state = state.0.lock().unwrap();
+    match &state.active {
+        Some(active) if keys.contains(active) => Some(active.clone()),
+        _ => keys.into_iter().next(),
+    }
+}
+
+/// The key a request should start with: `key` itself, unless it recently hit a limit
+/// and another configured key has taken over.
+pub fn resolve(app: &AppHandle, key: &str) -> String {
+    let state = app.state::<KeyRotation>();
+    let state = state.0.lock().unwrap();
+    let recently_exhausted = state
+        .exhausted
+        .get(key)
+        .is_some_and(|at| at.elapsed() < EXHAUSTED_COOLDOWN);
+    match &state.active {
+        Some(active) if recently_exhausted && active != key => active.clone(),
+        _ => key.to_string(),
+    }
+}
+
+/// Mark `exhausted` as rate-limited and switch to the next configured key that isn't.
+/// Returns that key, or `None` when there is nothing left to try.
+pub fn rotate(app: &AppHandle, exhausted: &str) -> Option<String> {
+    let keys = configured_keys(&settings::load(app));
+    let next = {
+        let state = app.state::<KeyRotation>();
+        let mut state = state.0.lock().unwrap();
+        state
+            .exhausted
+            .retain(|_, at| at.elapsed() < EXHAUSTED_COOLDOWN);
+        state
+            .exhausted
+            .insert(exhausted.to_string(), Instant::now());
+
+        // Continue after the exhausted key, wrapping around; an unknown key starts at the top
+        let start = keys
+            .iter()
+            .position(|key| key == exhausted)
+            .map_or(0, |index| index + 1);
+        let next = (0..keys.len())
+            .map(|offset| &keys[(start + offset) % keys.len()])
+            .find(|key| !state.exhausted.contains_key(*key))
+            .cloned();
+        if next.is_some() {
+            state.active = next.clone();
+        }
+        next
+    };
+
+    println!(
+        "[api_keys] Key {} exhausted; {}",
+        hint(exhausted),
+        next.as_deref()
+            .map_or("no other key left".to_string(), |key| format!(
+                "switching to {}",
+                hint(key)
+            ))
+    );
+    let _ = schema::emit(
+        app,
+        "api_key:exhausted",
+        KeyExhausted {
+            provider: "deepgram".to_string(),
+            key_hint: hint(exhausted),
+            next_key_hint: next.as_deref().map(hint),
+        },
+    );
+    next
+}
+
+/// The handshake request for a Deepgram WebSocket `url` given without credentials. The
+/// key is sent as an `Authorization` header, so it stays out of logs and proxies; with
+/// `key_in_url` it is appended as a `token` query parameter instead.
+pub fn websocket_request(url: &str, api_key: &str, key_in_url: bool) -> Result<Request, String> {
+    if key_in_url {
+        let separator = if url.contains('?') { '&' } else { '?' };
+        return format!("{}{}token={}", url, separator, api_key)
+            .into_client_request()
+            .map_err(|e| format!("Invalid WebSocket URL: {}", e));
+    }
+    let mut request = url
+        .into_client_request()
+        .map_err(|e| format!("Invalid WebSocket URL: {}", e))?;
+    let header = HeaderValue::from_str(&format!("Token {}", api_key))
+        .map_err(|_| "The API key contains characters not allowed in a header".to_string())?;
+    request.headers_mut().insert("Authorization", header);
+    Ok(request)
+}
@@ -0,0 +1,212 @@
+//! Bytes exchanged with the provider, per session and per day, for metered connections.
+//!
+//! Streams are measured on the TCP socket through [`Metered`], so TLS and WebSocket
+//! framing are included. Prerecorded requests count request and response bodies only.
+//! Sessions keep their own usage in `bandwidth`; daily totals go to `bandwidth.json`
+//! and are read with `get_bandwidth_stats`.
+
+use crate::schema::{self, Store};
+use chrono::{Days, Local};
+use serde::{Deserialize, Serialize};
+use specta::Type;
+use std::collections::BTreeMap;
+use std::fs;
+use std::io;
+use std::ops::AddAssign;
+use std::path::PathBuf;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll};
+use tauri::{AppHandle, Manager};
+use tokio::io::{AsyncRead, AsyncWrite, ReadBuf};
+
+/// Daily totals older than this are dropped.
+const DAYS_KEPT: u64 = 90;
+
+/// Serializes updates of the bandwidth file.
+#[derive(Default)]
+pub struct BandwidthLog(Mutex<()>);
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, Type)]
+#[serde(default)]
+pub struct Usage {
+    pub bytes_sent: u64,
+    pub bytes_received: u64,
+}
+
+impl AddAssign for Usage {
+    fn add_assign(&mut self, other: Usage) {
+        self.bytes_sent += other.bytes_sent;
+        self.bytes_received += other.bytes_received;
+    }
+}
+
+#[derive(Serialize, Clone, Debug, Type)]
+pub struct DailyUsage {
+    /// Local date, `YYYY-MM-DD`.
+    pub date: String,
+    #[serde(flatten)]
+    pub usage: Usage,
+}
+
+#[derive(Serialize, Clone, Debug, Type)]
+pub struct BandwidthStats {
+    pub today: Usage,
+    /// Sum of `days`.
+    pub total: Usage,
+    /// Days with traffic, oldest first.
+    pub days: Vec<DailyUsage>,
+}
+
+/// Running byte counts shared between a socket and whoever reports on it.
+#[derive(Clone, Default)]
+pub struct Meter(Arc<Counts>);
+
+#[derive(Default)]
+struct Counts {
+    sent: AtomicU64,
+    received: AtomicU64,
+}
+
+impl Usage {
+    /// Traffic since `earlier` was taken from the same meter.
+    pub fn since(&self, earlier: Usage) -> Usage {
+        Usage {
+            bytes_sent: self.bytes_sent.saturating_sub(earlier.bytes_sent),
+            bytes_received: self.bytes_received.saturating_sub(earlier.bytes_received),
+        }
+    }
+}
+
+impl Meter {
+    pub fn usage(&self) -> Usage {
+        Usage {
+            bytes_sent: self.0.sent.load(Ordering::Relaxed),
+            bytes_received: self.0.received.load(Ordering::Relaxed),
+        }
+    }
+}
+
+/// A stream that adds everything read and written to a [`Meter`].
+pub struct Metered<S> {
+    inner: S,
+    meter: Meter,
+}
+
+impl<S> Metered<S> {
+    pub fn new(inner: S, meter: Meter) -> Self {
+        Metered { inner, meter }
+    }
+}
+
+impl<S: AsyncRead + Unpin> AsyncRead for Metered<S> {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        let before = buf.filled().len();
+        let result = Pin::new(&mut self.inner).poll_read(cx, buf);
+        let read = (buf.filled().len() - before) as u64;
+        self.meter.0.received.fetch_add(read, Ordering::Relaxed);
+        result
+    }
+}
+
+impl<S: AsyncWrite + Unpin> AsyncWrite for Metered<S> {
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        let result = Pin::new(&mut self.inner).poll_write(cx, buf);
+        if let Poll::Ready(Ok(written)) = &result {
+            self.meter
+                .0
+                .sent
+                .fetch_add(*written as u64, Ordering::Relaxed);
+        }
+        result
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.inner).poll_flush(cx)
+    }
+
+    fn poll_shutdown(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.inner).poll_shutdown(cx)
+    }
+}
+
+pub fn log_path(app: &AppHandle) -> Result<PathBuf, String> {
+    let dir = app
+        .path()
+        .app_data_dir()
+        .map_err(|e| format!("Failed to resolve app data directory: {}", e))?;
+    fs::create_dir_all(&dir).map_err(|e| format!("Failed to create app data directory: {}", e))?;
+    Ok(dir.join("bandwidth.json"))
+}
+
+const STORE: Store = Store {
+    name: "bandwidth log",
+    wrapped: true,
+    migrations: &[schema::wrap_records],
+};
+
+fn read_log(app: &AppHandle) -> BTreeMap<String, Usage> {
+    log_path(app)
+        .map(|path| STORE.read_or_default(&path))
+        .unwrap_or_default()
+}
+
+fn date_key(days_ago: u64) -> String {
+    Local::now()
+        .date_naive()
+        .checked_sub_days(Days::new(days_ago))
+        .unwrap_or_default()
+        .format("%Y-%m-%d")
+        .to_string()
+}
+
+/// Add `usage` to today's total.
+pub fn record(app: &AppHandle, usage: Usage) {
+    if usage.bytes_sent == 0 && usage.bytes_received == 0 {
+        return;
+    }
+    let state = app.state::<BandwidthLog>();
+    let _guard = state.0.lock().unwrap();
+    let mut log = read_log(app);
+    *log.entry(date_key(0)).or_default() += usage;
+    let cutoff = date_key(DAYS_KEPT);
+    log.retain(|date, _| *date >= cutoff);
+
+    if let Err(e) = log_path(app).and_then(|path| STORE.write(&path, &log)) {
+        println!("[bandwidth] Failed to write usage: {}", e);
+    }
+}
+
+/// Daily traffic of the last `days` days (default 30), today included.
+#[tauri::command]
+#[specta::specta]
+pub fn get_bandwidth_stats(app: AppHandle, days: Option<u32>) -> BandwidthStats {
+    let days = days.unwrap_or(30).max(1) as u64;
+    let cutoff = date_key(days - 1);
+    let today_key = date_key(0);
+    let log = read_log(&app);
+
+    let mut total = Usage::default();
+    let mut today = Usage::default();
+    let days: Vec<DailyUsage> = log
+        .into_iter()
+        .filter(|(date, _)| *date >= cutoff)
+        .map(|(date, usage)| {
+            total += usage;
+            if date == today_key {
+                today = usage;
+            }
+            DailyUsage { date, usage }
+        })
+        .collect();
+    BandwidthStats { today, total, days }
+}
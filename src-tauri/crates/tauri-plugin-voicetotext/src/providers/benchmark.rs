@@ -0,0 +1,121 @@
+//! Side-by-side comparison of transcription options on the same recording.
+//!
+//! Deepgram is the only provider the app talks to, so "providers" here are its models.
+//! Each candidate transcribes the file in turn (not concurrently, so uploads don't skew
+//! each other's latency) and nothing is stored in history.
+
+use crate::prerecorded::{self, LISTEN_ENDPOINT};
+use serde::Serialize;
+use specta::Type;
+use std::path::Path;
+use std::time::Instant;
+
+/// Models compared by default, with their pay-as-you-go prerecorded rate in USD per
+/// audio minute. Rates change; results label the cost as an estimate.
+const CANDIDATES: [(&str, f64); 4] = [
+    ("nova-3", 0.0043),
+    ("nova-2", 0.0043),
+    ("enhanced", 0.0145),
+    ("base", 0.0125),
+];
+
+#[derive(Serialize, Clone, Debug, Type)]
+pub struct BenchmarkResult {
+    pub provider: String,
+    pub model: String,
+    /// Wall-clock time from starting the upload to the parsed response.
+    pub latency_ms: Option<u64>,
+    /// Audio length as reported by the provider.
+    pub audio_seconds: Option<f64>,
+    /// Estimated price of this request in USD, when the model's rate is known.
+    pub cost_estimate_usd: Option<f64>,
+    pub text: String,
+    pub error: Option<String>,
+}
+
+fn rate_per_minute(model: &str) -> Option<f64> {
+    CANDIDATES
+        .iter()
+        .find(|(name, _)| *name == model)
+        .map(|(_, rate)| *rate)
+}
+
+/// Run `file` through every candidate model (or just `models`) and report each result.
+/// A failing model is reported with `error` rather than failing the whole benchmark.
+#[tauri::command]
+#[specta::specta]
+pub async fn benchmark_providers(
+    file: String,
+    api_key: String,
+    models: Option<Vec<String>>,
+) -> Result<Vec<BenchmarkResult>, String> {
+    let path = Path::new(&file);
+    let bytes = tokio::fs::read(path)
+        .await
+        .map_err(|e| format!("Failed to read {}: {}", file, e))?;
+    let models = models.unwrap_or_else(|| {
+        CANDIDATES
+            .iter()
+            .map(|(name, _)| name.to_string())
+            .collect()
+    });
+    println!(
+        "[benchmark] Comparing {} models on {} ({} bytes)",
+        models.len(),
+        file,
+        bytes.len()
+    );
+
+    let client = reqwest::Client::new();
+    let mut results = Vec::with_capacity(models.len());
+    for model in models {
+        let request = client
+            .post(LISTEN_ENDPOINT)
+            .header("Content-Type", prerecorded::content_type(path))
+            .body(bytes.clone());
+        let started = Instant::now();
+        let outcome = prerecorded::listen_with_model(&api_key, &model, request).await;
+        let latency_ms = started.elapsed().as_millis() as u64;
+
+        let result = match outcome {
+            Ok(listened) => BenchmarkResult {
+                provider: "deepgram".to_string(),
+                cost_estimate_usd: listened
+                    .duration
+                    .zip(rate_per_minute(&model))
+                    .map(|(seconds, rate)| seconds / 60.0 * rate),
+                model,
+                latency_ms: Some(latency_ms),
+                audio_seconds: listened.duration,
+                text: listened
+                    .segments
+                    .iter()
+                    .map(|segment| segment.text.as_str())
+                    .collect::<Vec<_>>()
+                    .join(" "),
+                error: None,
+            },
+            Err(e) => BenchmarkResult {
+                provider: "deepgram".to_string(),
+                model,
+                latency_ms: None,
+                audio_seconds: None,
+                cost_estimate_usd: None,
+                text: String::new(),
+                error: Some(e),
+            },
+        };
+        println!(
+            "[benchmark] {}: {:?} ms{}",
+            result.model,
+            result.latency_ms,
+            result
+                .error
+                .as_deref()
+                .map(|e| format!(" (error: {})", e))
+                .unwrap_or_default()
+        );
+        results.push(result);
+    }
+    Ok(results)
+}
@@ -0,0 +1,316 @@
+//! Transcribing long WAV recordings in overlapping chunks.
+//!
+//! A multi-hour file as a single request fails as a whole on any network hiccup. Instead
+//! the audio is cut into `chunk_seconds` pieces that overlap by `overlap_seconds`, each
+//! uploaded with retries. Finished chunks are saved under `<app data dir>/uploads`, keyed
+//! by a hash of the audio, so running the same file again resumes where it stopped.
+//! Results are stitched at the middle of each overlap, so words spoken inside it appear
+//! once.
+
+use crate::bandwidth::Usage;
+use crate::history::Segment;
+use crate::network;
+use crate::prerecorded::{self, Listened, LISTEN_ENDPOINT};
+use crate::scheduler::{self, Priority};
+use crate::schema::{self, Store};
+use crate::settings::{self, ChunkingSettings};
+use crate::wav::{self, Wav};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tauri::{AppHandle, Manager};
+
+const RETRY_DELAY: Duration = Duration::from_secs(5);
+
+/// Saved results of the chunks finished so far, with chunk-relative times.
+#[derive(Serialize, Deserialize, Default)]
+struct Progress {
+    chunk_seconds: f64,
+    overlap_seconds: f64,
+    chunks: BTreeMap<usize, Vec<Segment>>,
+}
+
+pub fn uploads_dir(app: &AppHandle) -> Result<PathBuf, String> {
+    let dir = app
+        .path()
+        .app_data_dir()
+        .map_err(|e| format!("Failed to resolve app data directory: {}", e))?
+        .join("uploads");
+    fs::create_dir_all(&dir).map_err(|e| format!("Failed to create uploads directory: {}", e))?;
+    Ok(dir)
+}
+
+const STORE: Store = Store {
+    name: "upload progress",
+    wrapped: false,
+    migrations: &[schema::unversioned],
+};
+
+fn load_progress(path: &Path, config: &ChunkingSettings) -> Progress {
+    let stored: Option<Progress> = fs::read(path)
+        .ok()
+        .and_then(|bytes| STORE.decode(&bytes).ok());
+    // Chunks cut with other boundaries can't be reused
+    match stored {
+        Some(progress)
+            if progress.chunk_seconds == config.chunk_seconds
+                && progress.overlap_seconds == config.overlap_seconds =>
+        {
+            progress
+        }
+        _ => Progress {
+            chunk_seconds: config.chunk_seconds,
+            overlap_seconds: config.overlap_seconds,
+            chunks: BTreeMap::new(),
+        },
+    }
+}
+
+fn save_progress(path: &Path, progress: &Progress) -> Result<(), String> {
+    STORE.write(path, progress)
+}
+
+/// Start of every chunk in seconds; each chunk runs `chunk_seconds` from its start, so
+/// neighbours share exactly `overlap_seconds`. Expects a validated `config`.
+fn chunk_starts(duration: f64, config: &ChunkingSettings) -> Vec<f64> {
+    let step = config.chunk_seconds - config.overlap_seconds;
+    let mut starts = vec![0.0];
+    while starts.last().unwrap() + config.chunk_seconds < duration {
+        starts.push(starts.last().unwrap() + step);
+    }
+    starts
+}
+
+fn slice(audio: &Wav, start: f64, seconds: f64) -> Vec<u8> {
+    let frame = audio.channels as usize;
+    let per_second = audio.sample_rate as f64 * frame as f64;
+    let from = ((start * per_second) as usize / frame * frame).min(audio.samples.len());
+    let to = (((start + seconds) * per_second) as usize / frame * frame).min(audio.samples.len());
+    wav::encode(&Wav {
+        sample_rate: audio.sample_rate,
+        channels: audio.channels,
+        samples: audio.samples[from..to].to_vec(),
+    })
+}
+
+async fn upload_chunk(
+    app: &AppHandle,
+    api_key: &str,
+    body: Vec<u8>,
+    attempts: u32,
+) -> Result<Listened, String> {
+    let client = network::http_client(app, network::DEEPGRAM_HOST).await;
+    let mut attempt = 1;
+    loop {
+        let request = client
+            .post(LISTEN_ENDPOINT)
+            .header("Content-Type", "audio/wav")
+            .body(body.clone());
+        match prerecorded::listen(app, api_key, prerecorded::DEFAULT_MODEL, request).await {
+            Ok(listened) => return Ok(listened),
+            // A bad key fails every chunk the same way
+            Err(e) if attempt >= attempts || e.starts_with("Authentication failed") => {
+                return Err(e)
+            }
+            Err(e) => {
+                println!("[chunked] Attempt {} failed, retrying: {}", attempt, e);
+                tokio::time::sleep(RETRY_DELAY * attempt).await;
+                attempt += 1;
+            }
+        }
+    }
+}
+
+/// Keep each chunk's segments between the midpoints of its overlaps with the neighbours.
+fn stitch(starts: &[f64], config: &ChunkingSettings, progress: Progress) -> Vec<Segment> {
+    let half_overlap = config.overlap_seconds / 2.0;
+    let mut stitched = Vec::new();
+    for (index, segments) in progress.chunks {
+        let start = starts[index];
+        let keep_from = if index == 0 {
+            f64::MIN
+        } else {
+            start + half_overlap
+        };
+        let keep_until = starts
+            .get(index + 1)
+            .map_or(f64::MAX, |next| next + half_overlap);
+        stitched.extend(
+            segments
+                .into_iter()
+                .map(|mut segment| {
+                    segment.start += start;
+                    segment.end += start;
+                    segment
+                })
+                .filter(|segment| segment.start >= keep_from && segment.start < keep_until),
+        );
+    }
+    stitched
+}
+
+/// Transcribe `bytes` in chunks when chunking is enabled and the file is a WAV longer
+/// than one chunk. `Ok(None)` means the file should be uploaded in one request. The
+/// usage covers the chunks uploaded by this call, not those resumed from an earlier one.
+pub async fn transcribe_wav(
+    app: &AppHandle,
+    bytes: &[u8],
+    api_key: &str,
+    priority: Priority,
+) -> Result<Option<Listened>, String> {
+    let Some(config) = settings::load(app).chunking else {
+        return Ok(None);
+    };
+    let Ok(audio) = wav::decode(bytes) else {
+        return Ok(None);
+    };
+    let duration = audio.samples.len() as f64 / (audio.sample_rate as f64 * audio.channels as f64);
+    if let Err(e) = config.validate() {
+        println!("[chunked] Uploading in one request: {}", e);
+        return Ok(None);
+    }
+    if duration <= config.chunk_seconds {
+        return Ok(None);
+    }
+
+    let key = hex::encode(Sha256::digest(bytes));
+    let path = uploads_dir(app)?.join(format!("{}.json", key));
+    let mut progress = load_progress(&path, &config);
+    let starts = chunk_starts(duration, &config);
+    println!(
+        "[chunked] {:.0}s of audio in {} chunks ({} already done)",
+        duration,
+        starts.len(),
+        progress.chunks.len()
+    );
+
+    let mut usage = Usage::default();
+    for (index, &start) in starts.iter().enumerate() {
+        if progress.chunks.contains_key(&index) {
+            continue;
+        }
+        let body = slice(&audio, start, config.chunk_seconds);
+        // Taken per chunk so dictation can get in between chunks of a long file
+        let _permit = scheduler::acquire(app, priority).await;
+        let listened = upload_chunk(app, api_key, body, config.max_attempts.max(1))
+            .await
+            .map_err(|e| {
+                format!(
+                    "Chunk {} of {} failed (rerun to resume): {}",
+                    index + 1,
+                    starts.len(),
+                    e
+                )
+            })?;
+        usage += listened.usage;
+        progress.chunks.insert(index, listened.segments);
+        save_progress(&path, &progress)?;
+    }
+
+    let _ = fs::remove_file(&path);
+    Ok(Some(Listened {
+        segments: stitch(&starts, &config, progress),
+        duration: Some(duration),
+        usage,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(chunk_seconds: f64, overlap_seconds: f64) -> ChunkingSettings {
+        ChunkingSettings {
+            chunk_seconds,
+            overlap_seconds,
+            ..Default::default()
+        }
+    }
+
+    fn segment(start: f64, text: &str) -> Segment {
+        Segment {
+            start,
+            end: start + 0.5,
+            text: text.to_string(),
+            speaker: None,
+            confidence: 1.0,
+        }
+    }
+
+    #[test]
+    fn validates_overlap_against_chunk_length() {
+        let cases = [
+            (600.0, 5.0, true),
+            (10.0, 0.0, true),
+            (10.0, 9.0, true),
+            (10.0, 9.5, false),
+            (10.0, 10.0, false),
+            (10.0, 12.0, false),
+            (10.0, -1.0, false),
+            (f64::NAN, 1.0, false),
+        ];
+        for (chunk, overlap, valid) in cases {
+            assert_eq!(
+                config(chunk, overlap).validate().is_ok(),
+                valid,
+                "{}s chunks overlapping {}s",
+                chunk,
+                overlap
+            );
+        }
+    }
+
+    #[test]
+    fn ends_with_a_short_last_chunk() {
+        let cases = [
+            (25.0, vec![0.0, 8.0, 16.0]),
+            (26.0, vec![0.0, 8.0, 16.0]),
+            (26.5, vec![0.0, 8.0, 16.0, 24.0]),
+            (9.0, vec![0.0]),
+        ];
+        for (duration, expected) in cases {
+            assert_eq!(chunk_starts(duration, &config(10.0, 2.0)), expected);
+        }
+    }
+
+    #[test]
+    fn keeps_words_in_an_overlap_once() {
+        let config = config(10.0, 2.0);
+        let starts = chunk_starts(25.0, &config);
+        // Both neighbours heard the words at 8.5 s and 9.5 s (and 16.5 s, 17.5 s)
+        let progress = Progress {
+            chunk_seconds: 10.0,
+            overlap_seconds: 2.0,
+            chunks: BTreeMap::from([
+                (
+                    0,
+                    vec![segment(1.0, "a"), segment(8.5, "b"), segment(9.5, "c")],
+                ),
+                (
+                    1,
+                    vec![
+                        segment(0.5, "b"),
+                        segment(1.5, "c"),
+                        segment(5.0, "d"),
+                        segment(8.5, "e"),
+                        segment(9.5, "f"),
+                    ],
+                ),
+                // The last chunk is only 9 s long
+                (
+                    2,
+                    vec![segment(0.5, "e"), segment(1.5, "f"), segment(8.0, "g")],
+                ),
+            ]),
+        };
+
+        let stitched = stitch(&starts, &config, progress);
+        let words: Vec<_> = stitched.iter().map(|s| s.text.as_str()).collect();
+        assert_eq!(words, ["a", "b", "c", "d", "e", "f", "g"]);
+        let times: Vec<_> = stitched.iter().map(|s| s.start).collect();
+        assert_eq!(times, [1.0, 8.5, 9.5, 13.0, 16.5, 17.5, 24.0]);
+    }
+}
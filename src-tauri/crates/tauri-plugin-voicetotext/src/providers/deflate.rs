@@ -0,0 +1,311 @@
+//! Receiving side of the WebSocket permessage-deflate extension (RFC 7692).
+//!
+//! tungstenite rejects frames with the RSV1 bit set, so compressed messages are
+//! inflated underneath it: [`Inflate`] sits between the socket and tungstenite, passes
+//! the handshake response through, and rewrites every compressed message as a single
+//! plain frame. Outgoing audio is never compressed, which the extension allows, so
+//! writes go straight through.
+
+use flate2::{Decompress, FlushDecompress, Status};
+use std::io;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use tokio::io::{AsyncRead, AsyncWrite, ReadBuf};
+
+/// Offered in the handshake when compression is turned on.
+pub const EXTENSION_OFFER: &str = "permessage-deflate; client_no_context_takeover";
+
+/// Appended to a compressed message before inflating (RFC 7692, section 7.2.2).
+const TAIL: [u8; 4] = [0x00, 0x00, 0xFF, 0xFF];
+
+pub struct Inflate<S> {
+    inner: S,
+    /// Bytes read from `inner` that don't make up a whole frame yet.
+    raw: Vec<u8>,
+    /// Bytes ready for tungstenite, from `ready_from` on.
+    ready: Vec<u8>,
+    ready_from: usize,
+    handshake_done: bool,
+    /// One inflater for the whole connection: it also decodes messages that refer back
+    /// to earlier ones when the server keeps its context.
+    decompress: Decompress,
+    /// Opcode and payload of a compressed message still arriving in fragments.
+    partial: Option<(u8, Vec<u8>)>,
+}
+
+/// A complete frame at the start of `raw`: FIN, RSV1, opcode, where the payload starts
+/// and where the frame ends.
+struct Frame {
+    fin: bool,
+    rsv1: bool,
+    opcode: u8,
+    payload_start: usize,
+    end: usize,
+}
+
+fn parse_frame(raw: &[u8]) -> Option<Frame> {
+    let (&first, &second) = (raw.first()?, raw.get(1)?);
+    let masked = second & 0x80 != 0;
+    let (length, mut at): (u64, usize) = match second & 0x7F {
+        126 => (
+            u16::from_be_bytes(raw.get(2..4)?.try_into().ok()?) as u64,
+            4,
+        ),
+        127 => (u64::from_be_bytes(raw.get(2..10)?.try_into().ok()?), 10),
+        short => (short as u64, 2),
+    };
+    // Servers don't mask, but a mask key is skipped rather than misread as payload
+    if masked {
+        at += 4;
+    }
+    let end = at.checked_add(usize::try_from(length).ok()?)?;
+    (raw.len() >= end).then_some(Frame {
+        fin: first & 0x80 != 0,
+        rsv1: first & 0x40 != 0,
+        opcode: first & 0x0F,
+        payload_start: at,
+        end,
+    })
+}
+
+fn write_frame(out: &mut Vec<u8>, opcode: u8, payload: &[u8]) {
+    out.push(0x80 | opcode);
+    match payload.len() {
+        length if length < 126 => out.push(length as u8),
+        length if length <= u16::MAX as usize => {
+            out.push(126);
+            out.extend_from_slice(&(length as u16).to_be_bytes());
+        }
+        length => {
+            out.push(127);
+            out.extend_from_slice(&(length as u64).to_be_bytes());
+        }
+    }
+    out.extend_from_slice(payload);
+}
+
+impl<S> Inflate<S> {
+    pub fn new(inner: S) -> Self {
+        Inflate {
+            inner,
+            raw: Vec::new(),
+            ready: Vec::new(),
+            ready_from: 0,
+            handshake_done: false,
+            decompress: Decompress::new(false),
+            partial: None,
+        }
+    }
+
+    fn inflate(&mut self, mut message: Vec<u8>) -> io::Result<Vec<u8>> {
+        message.extend_from_slice(&TAIL);
+        let start = self.decompress.total_in();
+        let mut out = Vec::with_capacity(message.len() * 4);
+        loop {
+            let consumed = (self.decompress.total_in() - start) as usize;
+            if out.len() == out.capacity() {
+                out.reserve(out.capacity().max(1024));
+            }
+            let before = (self.decompress.total_in(), out.len());
+            let status = self
+                .decompress
+                .decompress_vec(&message[consumed..], &mut out, FlushDecompress::Sync)
+                .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+            let consumed = (self.decompress.total_in() - start) as usize;
+            let spare = out.len() < out.capacity();
+            if status == Status::StreamEnd || (consumed == message.len() && spare) {
+                break;
+            }
+            if (self.decompress.total_in(), out.len()) == before && spare {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    "Compressed WebSocket message does not inflate",
+                ));
+            }
+        }
+        Ok(out)
+    }
+
+    /// Move whatever can be handed to tungstenite from `raw` to `ready`.
+    fn process(&mut self) -> io::Result<()> {
+        if !self.handshake_done {
+            let Some(end) = self.raw.windows(4).position(|w| w == b"\r\n\r\n") else {
+                return Ok(());
+            };
+            self.ready.extend(self.raw.drain(..end + 4));
+            self.handshake_done = true;
+        }
+        while let Some(frame) = parse_frame(&self.raw) {
+            let bytes: Vec<u8> = self.raw.drain(..frame.end).collect();
+            let payload = &bytes[frame.payload_start..];
+            let compressed_start = frame.rsv1 && matches!(frame.opcode, 0x1 | 0x2);
+            let continues_compressed = frame.opcode == 0x0 && self.partial.is_some();
+            if !compressed_start && !continues_compressed {
+                // Control frames and uncompressed messages go through untouched
+                self.ready.extend_from_slice(&bytes);
+                continue;
+            }
+            let (opcode, message) = self
+                .partial
+                .get_or_insert_with(|| (frame.opcode, Vec::new()));
+            let opcode = *opcode;
+            message.extend_from_slice(payload);
+            if frame.fin {
+                let (_, message) = self.partial.take().unwrap_or_default();
+                let inflated = self.inflate(message)?;
+                write_frame(&mut self.ready, opcode, &inflated);
+            }
+        }
+        Ok(())
+    }
+}
+
+impl<S: AsyncRead + Unpin> AsyncRead for Inflate<S> {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        let this = &mut *self;
+        loop {
+            if this.ready_from < this.ready.len() {
+                let available = &this.ready[this.ready_from..];
+                let count = available.len().min(buf.remaining());
+                buf.put_slice(&available[..count]);
+                this.ready_from += count;
+                if this.ready_from == this.ready.len() {
+                    this.ready.clear();
+                    this.ready_from = 0;
+                }
+                return Poll::Ready(Ok(()));
+            }
+
+            let mut chunk = [0u8; 8192];
+            let mut chunk_buf = ReadBuf::new(&mut chunk);
+            match Pin::new(&mut this.inner).poll_read(cx, &mut chunk_buf) {
+                Poll::Ready(Ok(())) if chunk_buf.filled().is_empty() => {
+                    // End of stream: hand over any partial frame and let tungstenite report it
+                    this.ready.append(&mut this.raw);
+                    if this.ready.is_empty() {
+                        return Poll::Ready(Ok(()));
+                    }
+                }
+                Poll::Ready(Ok(())) => {
+                    this.raw.extend_from_slice(chunk_buf.filled());
+                    this.process()?;
+                }
+                Poll::Ready(Err(e)) => return Poll::Ready(Err(e)),
+                Poll::Pending => return Poll::Pending,
+            }
+        }
+    }
+}
+
+impl<S: AsyncWrite + Unpin> AsyncWrite for Inflate<S> {
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        Pin::new(&mut self.inner).poll_write(cx, buf)
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.inner).poll_flush(cx)
+    }
+
+    fn poll_shutdown(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.inner).poll_shutdown(cx)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use flate2::{Compress, Compression, FlushCompress};
+
+    const HANDSHAKE: &[u8] =
+        b"HTTP/1.1 101 Switching Protocols\r\nSec-WebSocket-Extensions: permessage-deflate\r\n\r\n";
+
+    /// A message as a server compresses it, without the trailing `TAIL`.
+    fn deflate(compress: &mut Compress, message: &[u8]) -> Vec<u8> {
+        let mut out = Vec::with_capacity(message.len() + 64);
+        compress
+            .compress_vec(message, &mut out, FlushCompress::Sync)
+            .unwrap();
+        assert!(out.ends_with(&TAIL));
+        out.truncate(out.len() - TAIL.len());
+        out
+    }
+
+    fn frame(first: u8, payload: &[u8]) -> Vec<u8> {
+        let mut out = Vec::new();
+        write_frame(&mut out, first & 0x0F, payload);
+        out[0] = first;
+        out
+    }
+
+    /// What `Inflate` hands tungstenite after reading `raw`.
+    fn processed(raw: &[u8]) -> Vec<u8> {
+        let mut inflate = Inflate::new(());
+        inflate.raw.extend_from_slice(HANDSHAKE);
+        inflate.raw.extend_from_slice(raw);
+        inflate.process().unwrap();
+        assert!(inflate.raw.is_empty());
+        inflate.ready.split_off(HANDSHAKE.len())
+    }
+
+    #[test]
+    fn reads_back_written_frames_of_every_length() {
+        for length in [0, 125, 126, 65535, 65536] {
+            let payload = vec![7u8; length];
+            let mut raw = Vec::new();
+            write_frame(&mut raw, 0x2, &payload);
+            let frame = parse_frame(&raw).unwrap();
+            assert!(frame.fin && !frame.rsv1);
+            assert_eq!(frame.opcode, 0x2);
+            assert_eq!(&raw[frame.payload_start..frame.end], &payload[..]);
+            assert!(parse_frame(&raw[..raw.len() - 1]).is_none());
+        }
+    }
+
+    #[test]
+    fn skips_a_mask_key() {
+        let raw = [0x81, 0x82, 1, 2, 3, 4, b'h', b'i'];
+        let frame = parse_frame(&raw).unwrap();
+        assert_eq!((frame.payload_start, frame.end), (6, 8));
+    }
+
+    #[test]
+    fn inflates_a_compressed_message() {
+        let text = br#"{"channel": {"alternatives": [{"transcript": "hello hello hello"}]}}"#;
+        let mut compress = Compress::new(Compression::default(), false);
+        let raw = frame(0xC1, &deflate(&mut compress, text));
+        assert_eq!(processed(&raw), frame(0x81, text));
+    }
+
+    #[test]
+    fn inflates_fragments_and_keeps_the_context() {
+        let first = b"the quick brown fox jumps over the lazy dog";
+        let second = b"the quick brown fox jumps over the lazy dog again";
+        let mut compress = Compress::new(Compression::default(), false);
+        let one = deflate(&mut compress, first);
+        let two = deflate(&mut compress, second);
+        let (head, rest) = two.split_at(two.len() / 2);
+
+        let mut raw = frame(0xC1, &one);
+        raw.extend(frame(0x89, b"ping"));
+        raw.extend(frame(0x41, head));
+        raw.extend(frame(0x80, rest));
+        let mut expected = frame(0x81, first);
+        expected.extend(frame(0x89, b"ping"));
+        expected.extend(frame(0x81, second));
+        assert_eq!(processed(&raw), expected);
+    }
+
+    #[test]
+    fn passes_uncompressed_messages_through() {
+        let raw = frame(0x81, b"plain");
+        assert_eq!(processed(&raw), raw);
+    }
+}
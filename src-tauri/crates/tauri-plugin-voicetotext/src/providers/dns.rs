@@ -0,0 +1,343 @@
+//! Resolving host names without the system resolver, for networks whose DNS can't be
+//! trusted to find the provider.
+//!
+//! Queries for A and AAAA records are sent either to a plain DNS server over UDP or as
+//! DNS over HTTPS (RFC 8484, `application/dns-message`). Only the answer records are
+//! read; CNAME chains are followed by the server.
+
+use crate::settings::Resolver;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+use std::time::Duration;
+use tokio::net::UdpSocket;
+
+const TYPE_A: u16 = 1;
+const TYPE_AAAA: u16 = 28;
+const CLASS_IN: u16 = 1;
+const QUERY_TIMEOUT: Duration = Duration::from_secs(4);
+
+fn encode_query(id: u16, host: &str, record_type: u16) -> Result<Vec<u8>, String> {
+    let mut query = Vec::with_capacity(32 + host.len());
+    query.extend_from_slice(&id.to_be_bytes());
+    // Recursion desired; one question
+    query.extend_from_slice(&[0x01, 0x00, 0, 1, 0, 0, 0, 0, 0, 0]);
+    for label in host.trim_end_matches('.').split('.') {
+        if label.is_empty() || label.len() > 63 {
+            return Err(format!("Invalid host name: {}", host));
+        }
+        query.push(label.len() as u8);
+        query.extend_from_slice(label.as_bytes());
+    }
+    query.push(0);
+    query.extend_from_slice(&record_type.to_be_bytes());
+    query.extend_from_slice(&CLASS_IN.to_be_bytes());
+    Ok(query)
+}
+
+/// Offset just past the (possibly compressed) name starting at `at`.
+fn skip_name(message: &[u8], mut at: usize) -> Option<usize> {
+    loop {
+        let length = *message.get(at)? as usize;
+        match length {
+            0 => return Some(at + 1),
+            // A compression pointer ends the name
+            l if l & 0xC0 == 0xC0 => return Some(at + 2),
+            l => at += l + 1,
+        }
+    }
+}
+
+fn read_u16(message: &[u8], at: usize) -> Option<u16> {
+    Some(u16::from_be_bytes([
+        *message.get(at)?,
+        *message.get(at + 1)?,
+    ]))
+}
+
+/// Addresses in the answer section of a response to query `id`.
+fn parse_response(id: u16, message: &[u8]) -> Result<Vec<IpAddr>, String> {
+    let truncated = || "Truncated DNS response".to_string();
+    if read_u16(message, 0).ok_or_else(truncated)? != id {
+        return Err("DNS response does not match the query".to_string());
+    }
+    let flags = read_u16(message, 2).ok_or_else(truncated)?;
+    match flags & 0x000F {
+        0 => {}
+        3 => return Err("No such host".to_string()),
+        code => return Err(format!("DNS server answered with error code {}", code)),
+    }
+    let questions = read_u16(message, 4).ok_or_else(truncated)?;
+    let answers = read_u16(message, 6).ok_or_else(truncated)?;
+
+    let mut at = 12;
+    for _ in 0..questions {
+        at = skip_name(message, at).ok_or_else(truncated)? + 4;
+    }
+    let mut addresses = Vec::new();
+    for _ in 0..answers {
+        at = skip_name(message, at).ok_or_else(truncated)?;
+        let record_type = read_u16(message, at).ok_or_else(truncated)?;
+        let length = read_u16(message, at + 8).ok_or_else(truncated)? as usize;
+        let data = message
+            .get(at + 10..at + 10 + length)
+            .ok_or_else(truncated)?;
+        match (record_type, data.len()) {
+            (TYPE_A, 4) => addresses.push(IpAddr::V4(Ipv4Addr::new(
+                data[0], data[1], data[2], data[3],
+            ))),
+            (TYPE_AAAA, 16) => {
+                let octets: [u8; 16] = data.try_into().map_err(|_| truncated())?;
+                addresses.push(IpAddr::V6(Ipv6Addr::from(octets)));
+            }
+            _ => {}
+        }
+        at += 10 + length;
+    }
+    Ok(addresses)
+}
+
+fn server_address(address: &str) -> Result<SocketAddr, String> {
+    address
+        .parse::<SocketAddr>()
+        .or_else(|_| address.parse::<IpAddr>().map(|ip| SocketAddr::new(ip, 53)))
+        .map_err(|_| format!("Invalid DNS server address: {}", address))
+}
+
+async fn query_udp(server: SocketAddr, query: &[u8]) -> Result<Vec<u8>, String> {
+    let local: SocketAddr = if server.is_ipv4() {
+        (Ipv4Addr::UNSPECIFIED, 0).into()
+    } else {
+        (Ipv6Addr::UNSPECIFIED, 0).into()
+    };
+    let socket = UdpSocket::bind(local)
+        .await
+        .map_err(|e| format!("Failed to open a DNS socket: {}", e))?;
+    socket
+        .send_to(query, server)
+        .await
+        .map_err(|e| format!("Failed to reach DNS server {}: {}", server, e))?;
+    let mut buffer = vec![0u8; 1232];
+    let (length, _) = socket
+        .recv_from(&mut buffer)
+        .await
+        .map_err(|e| format!("DNS server {} did not answer: {}", server, e))?;
+    buffer.truncate(length);
+    Ok(buffer)
+}
+
+async fn query_doh(url: &str, query: Vec<u8>) -> Result<Vec<u8>, String> {
+    let response = reqwest::Client::new()
+        .post(url)
+        .header("Content-Type", "application/dns-message")
+        .header("Accept", "application/dns-message")
+        .body(query)
+        .send()
+        .await
+        .map_err(|e| format!("DNS over HTTPS request failed: {}", e))?;
+    if !response.status().is_success() {
+        return Err(format!(
+            "DNS over HTTPS server returned HTTP {}",
+            response.status()
+        ));
+    }
+    response
+        .bytes()
+        .await
+        .map(|bytes| bytes.to_vec())
+        .map_err(|e| format!("Failed to read DNS over HTTPS response: {}", e))
+}
+
+/// Where queries go when the system resolver is bypassed.
+enum Upstream<'a> {
+    Doh(&'a str),
+    Udp(SocketAddr),
+}
+
+async fn lookup(
+    upstream: &Upstream<'_>,
+    host: &str,
+    record_type: u16,
+) -> Result<Vec<IpAddr>, String> {
+    // DoH messages use id 0 so responses stay cacheable
+    let id = match upstream {
+        Upstream::Doh(_) => 0,
+        Upstream::Udp(_) => random_id(),
+    };
+    let query = encode_query(id, host, record_type)?;
+    let response = match upstream {
+        Upstream::Doh(url) => query_doh(url, query).await?,
+        Upstream::Udp(server) => query_udp(*server, &query).await?,
+    };
+    parse_response(id, &response)
+}
+
+fn random_id() -> u16 {
+    let bytes = uuid::Uuid::new_v4().into_bytes();
+    u16::from_be_bytes([bytes[0], bytes[1]])
+}
+
+async fn lookup_custom(
+    upstream: Upstream<'_>,
+    host: &str,
+    port: u16,
+) -> Result<Vec<SocketAddr>, String> {
+    let (v4, v6) = tokio::time::timeout(
+        QUERY_TIMEOUT,
+        futures_util::future::join(
+            lookup(&upstream, host, TYPE_A),
+            lookup(&upstream, host, TYPE_AAAA),
+        ),
+    )
+    .await
+    .map_err(|_| "no answer in time".to_string())?;
+
+    let mut addresses: Vec<SocketAddr> = Vec::new();
+    let mut errors = Vec::new();
+    for result in [v4, v6] {
+        match result {
+            Ok(found) => addresses.extend(found.into_iter().map(|ip| SocketAddr::new(ip, port))),
+            Err(e) => errors.push(e),
+        }
+    }
+    match (addresses.is_empty(), errors.into_iter().next()) {
+        (false, _) => Ok(addresses),
+        (true, Some(e)) => Err(e),
+        (true, None) => Err("no addresses".to_string()),
+    }
+}
+
+async fn lookup_system(host: &str, port: u16) -> Result<Vec<SocketAddr>, String> {
+    let addresses: Vec<SocketAddr> = tokio::net::lookup_host((host, port))
+        .await
+        .map_err(|e| format!("Could not resolve {}: {}", host, e))?
+        .collect();
+    if addresses.is_empty() {
+        return Err(format!("No addresses for {}", host));
+    }
+    Ok(addresses)
+}
+
+/// Addresses of `host` from the configured resolver, IPv4 before IPv6. When a custom
+/// resolver fails the system one is asked instead, so a misconfigured server never
+/// makes things worse than before.
+pub async fn resolve(
+    resolver: &Resolver,
+    host: &str,
+    port: u16,
+) -> Result<Vec<SocketAddr>, String> {
+    if let Ok(ip) = host.trim_matches(['[', ']']).parse::<IpAddr>() {
+        return Ok(vec![SocketAddr::new(ip, port)]);
+    }
+    let upstream = match resolver {
+        Resolver::System => return lookup_system(host, port).await,
+        Resolver::Doh { url } => Upstream::Doh(url),
+        Resolver::Server { address } => Upstream::Udp(server_address(address)?),
+    };
+    match lookup_custom(upstream, host, port).await {
+        Ok(addresses) => Ok(addresses),
+        Err(e) => {
+            println!(
+                "[dns] Custom resolver failed for {} ({}); using the system resolver",
+                host, e
+            );
+            lookup_system(host, port).await
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A response to `query` with the given flags and answer records, each named by a
+    /// pointer to the question.
+    fn response(query: &[u8], flags: u16, answers: &[(u16, &[u8])]) -> Vec<u8> {
+        let mut message = query[..2].to_vec();
+        message.extend_from_slice(&flags.to_be_bytes());
+        message.extend_from_slice(&[0, 1]);
+        message.extend_from_slice(&(answers.len() as u16).to_be_bytes());
+        message.extend_from_slice(&[0, 0, 0, 0]);
+        message.extend_from_slice(&query[12..]);
+        for (record_type, data) in answers {
+            message.extend_from_slice(&[0xC0, 12]);
+            message.extend_from_slice(&record_type.to_be_bytes());
+            message.extend_from_slice(&CLASS_IN.to_be_bytes());
+            message.extend_from_slice(&300u32.to_be_bytes());
+            message.extend_from_slice(&(data.len() as u16).to_be_bytes());
+            message.extend_from_slice(data);
+        }
+        message
+    }
+
+    #[test]
+    fn encodes_a_query() {
+        let query = encode_query(0x1234, "api.deepgram.com.", TYPE_AAAA).unwrap();
+        let mut expected = vec![0x12, 0x34, 0x01, 0x00, 0, 1, 0, 0, 0, 0, 0, 0];
+        expected.extend_from_slice(b"\x03api\x08deepgram\x03com\x00");
+        expected.extend_from_slice(&[0, 28, 0, 1]);
+        assert_eq!(query, expected);
+    }
+
+    #[test]
+    fn refuses_invalid_host_names() {
+        for host in ["", "a..b", &"x".repeat(64)] {
+            assert!(encode_query(1, host, TYPE_A).is_err(), "{:?}", host);
+        }
+    }
+
+    #[test]
+    fn reads_addresses_and_skips_other_records() {
+        let query = encode_query(7, "api.deepgram.com", TYPE_A).unwrap();
+        let v6: [u8; 16] = "2001:db8::1".parse::<Ipv6Addr>().unwrap().octets();
+        let message = response(
+            &query,
+            0x8180,
+            &[
+                (5, b"\x03cdn\xC0\x10"),
+                (TYPE_A, &[192, 0, 2, 1]),
+                (TYPE_AAAA, &v6),
+                // Wrong length for an A record
+                (TYPE_A, &[1, 2, 3]),
+            ],
+        );
+        assert_eq!(
+            parse_response(7, &message).unwrap(),
+            vec![
+                IpAddr::V4(Ipv4Addr::new(192, 0, 2, 1)),
+                IpAddr::V6(Ipv6Addr::from(v6)),
+            ]
+        );
+    }
+
+    #[test]
+    fn refuses_bad_responses() {
+        let query = encode_query(7, "api.deepgram.com", TYPE_A).unwrap();
+        let answer: &[(u16, &[u8])] = &[(TYPE_A, &[192, 0, 2, 1])];
+        let ok = response(&query, 0x8180, answer);
+        let cases = [
+            (8, ok.clone(), "does not match"),
+            (7, response(&query, 0x8183, &[]), "No such host"),
+            (7, response(&query, 0x8182, &[]), "error code 2"),
+            (7, ok[..ok.len() - 1].to_vec(), "Truncated"),
+            (7, ok[..5].to_vec(), "Truncated"),
+        ];
+        for (id, message, error) in cases {
+            let result = parse_response(id, &message);
+            assert!(result.unwrap_err().contains(error), "{}", error);
+        }
+    }
+
+    #[test]
+    fn parses_server_addresses() {
+        let cases = [
+            ("1.1.1.1", Some("1.1.1.1:53")),
+            ("9.9.9.9:5353", Some("9.9.9.9:5353")),
+            ("2606:4700::1111", Some("[2606:4700::1111]:53")),
+            ("[::1]:5353", Some("[::1]:5353")),
+            ("dns.example", None),
+        ];
+        for (address, expected) in cases {
+            let parsed = server_address(address).ok().map(|a| a.to_string());
+            assert_eq!(parsed.as_deref(), expected, "{}", address);
+        }
+    }
+}
@@ -0,0 +1,278 @@
+//! Falling back to a downloaded whisper.cpp model when Deepgram can't be reached.
+//!
+//! Before a file goes to Deepgram, and again when sending it fails, the provider is
+//! checked with [`network::reachable`]. When it is out of reach and a model is downloaded,
+//! the file is transcribed locally instead: `ffmpeg` converts it to the 16 kHz mono WAV
+//! whisper.cpp expects and the whisper.cpp program does the rest, on the GPU its build
+//! supports unless `offline_fallback.gpu` is off (see `acceleration`). The engine that
+//! handled the latest work is announced with `engine:active` whenever it changes.
+//!
+//! URLs are downloaded and transcribed the same way. Stream sessions that can't reach
+//! Deepgram when they start are decoded by `ffmpeg` and transcribed a chunk at a time
+//! (see `stream`); a session already relayed to Deepgram stays with it.
+
+use crate::history::Segment;
+use crate::schema;
+use crate::settings::{self, OfflineFallback};
+use crate::{models, network};
+use serde::{Deserialize, Serialize};
+use specta::Type;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use tauri::{AppHandle, Manager, State};
+use tokio::process::Command;
+
+/// Names the whisper.cpp program is installed under, newest first.
+const BINARIES: [&str; 2] = ["whisper-cli", "whisper-cpp"];
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq, Type)]
+#[serde(rename_all = "snake_case")]
+pub enum Engine {
+    #[default]
+    Deepgram,
+    Local,
+}
+
+/// The engine that handled the latest work.
+#[derive(Default)]
+pub struct ActiveEngine(Mutex<Engine>);
+
+/// Payload of the `engine:active` event.
+#[derive(Serialize, Clone, Debug, Type)]
+pub struct EngineChanged {
+    pub engine: Engine,
+    /// The local model in use.
+    pub model: Option<String>,
+    /// Why Deepgram was left, e.g. the failed connection stage.
+    pub reason: Option<String>,
+}
+
+/// A downloaded model and the program to run it with.
+pub struct LocalModel {
+    pub name: String,
+    path: PathBuf,
+    binary: PathBuf,
+    gpu: bool,
+    gpu_device: Option<u32>,
+}
+
+#[derive(Deserialize)]
+struct WhisperOutput {
+    #[serde(default)]
+    transcription: Vec<WhisperSegment>,
+}
+
+#[derive(Deserialize)]
+struct WhisperSegment {
+    offsets: Offsets,
+    text: String,
+    #[serde(default)]
+    tokens: Vec<WhisperToken>,
+}
+
+/// Milliseconds from the start of the audio.
+#[derive(Deserialize)]
+struct Offsets {
+    from: u64,
+    to: u64,
+}
+
+#[derive(Deserialize)]
+struct WhisperToken {
+    text: String,
+    #[serde(default)]
+    p: f64,
+}
+
+/// The whisper.cpp program that local transcription would run.
+pub fn find_binary(config: &OfflineFallback) -> Option<PathBuf> {
+    if let Some(binary) = &config.binary {
+        return Some(PathBuf::from(binary)).filter(|path| path.is_file());
+    }
+    let path = std::env::var_os("PATH")?;
+    std::env::split_paths(&path)
+        .flat_map(|dir| {
+            BINARIES.iter().map(move |name| {
+                dir.join(name)
+                    .with_extension(std::env::consts::EXE_EXTENSION)
+            })
+        })
+        .find(|candidate| candidate.is_file())
+}
+
+/// The model to fall back to: the batch model from settings when it is downloaded, then
+/// the live one, then the largest one on disk.
+pub fn available(app: &AppHandle) -> Option<LocalModel> {
+    let config = settings::load(app);
+    if !config.offline_fallback.enabled {
+        return None;
+    }
+    let binary = find_binary(&config.offline_fallback)?;
+    let downloaded = models::downloaded(app);
+    let preferred = [config.models.batch.name(), config.models.live.name()];
+    let (name, path) = preferred
+        .iter()
+        .find_map(|wanted| downloaded.iter().find(|(name, _)| name == wanted))
+        .or_else(|| downloaded.first())?
+        .clone();
+    Some(LocalModel {
+        name,
+        path,
+        binary,
+        gpu: config.offline_fallback.gpu,
+        gpu_device: config.offline_fallback.gpu_device,
+    })
+}
+
+/// The local model to use instead of Deepgram and why, when Deepgram can't be reached
+/// and a model is ready.
+pub async fn fallback(app: &AppHandle) -> Option<(LocalModel, String)> {
+    let config = settings::load(app);
+    if !config.offline_fallback.enabled {
+        return None;
+    }
+    let error = network::reachable(&config.network, network::DEEPGRAM_HOST, 443)
+        .await
+        .err()?;
+    match available(app) {
+        Some(local) => Some((local, error.message)),
+        None => {
+            println!(
+                "[local_engine] Deepgram unreachable ({}) and no local model is ready",
+                error.message
+            );
+            None
+        }
+    }
+}
+
+/// Record which engine is handling work, emitting `engine:active` when it changed.
+pub fn set_active(app: &AppHandle, engine: Engine, model: Option<String>, reason: Option<String>) {
+    let state = app.state::<ActiveEngine>();
+    let mut active = state.0.lock().unwrap();
+    if *active == engine {
+        return;
+    }
+    *active = engine;
+    println!("[local_engine] Active engine: {:?} ({:?})", engine, reason);
+    let _ = schema::emit(
+        app,
+        "engine:active",
+        EngineChanged {
+            engine,
+            model,
+            reason,
+        },
+    );
+}
+
+async fn run(name: &str, command: &mut Command) -> Result<(), String> {
+    let output = command
+        .output()
+        .await
+        .map_err(|e| format!("Failed to run {}: {}", name, e))?;
+    if output.status.success() {
+        return Ok(());
+    }
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    Err(format!(
+        "{} exited with {}: {}",
+        name,
+        output.status,
+        stderr.trim()
+    ))
+}
+
+async fn transcribe_in(
+    local: &LocalModel,
+    dir: &Path,
+    bytes: &[u8],
+) -> Result<Vec<Segment>, String> {
+    let input = dir.join("input");
+    let audio = dir.join("audio.wav");
+    let output = dir.join("transcript");
+    tokio::fs::write(&input, bytes)
+        .await
+        .map_err(|e| format!("Failed to write {}: {}", input.display(), e))?;
+    run(
+        "ffmpeg",
+        Command::new("ffmpeg")
+            .args(["-v", "error", "-y", "-i"])
+            .arg(&input)
+            .args(["-ar", "16000", "-ac", "1", "-c:a", "pcm_s16le"])
+            .arg(&audio),
+    )
+    .await
+    .map_err(|e| format!("Local transcription needs ffmpeg: {}", e))?;
+    // Full JSON output carries token probabilities, averaged into segment confidence
+    let mut whisper = Command::new(&local.binary);
+    whisper
+        .arg("-m")
+        .arg(&local.path)
+        .arg("-f")
+        .arg(&audio)
+        .args(["-ojf", "-np", "-of"])
+        .arg(&output);
+    if !local.gpu {
+        whisper.arg("--no-gpu");
+    } else if let Some(device) = local.gpu_device {
+        whisper.arg("--device").arg(device.to_string());
+    }
+    run("whisper.cpp", &mut whisper).await?;
+
+    let json = output.with_extension("json");
+    let bytes = tokio::fs::read(&json)
+        .await
+        .map_err(|e| format!("Failed to read {}: {}", json.display(), e))?;
+    let parsed: WhisperOutput = serde_json::from_slice(&bytes)
+        .map_err(|e| format!("Unexpected whisper.cpp output: {}", e))?;
+    Ok(parsed
+        .transcription
+        .into_iter()
+        .filter(|segment| !segment.text.trim().is_empty())
+        .map(|segment| {
+            // Special tokens such as `[_BEG_]` say nothing about the words
+            let words: Vec<f64> = segment
+                .tokens
+                .iter()
+                .filter(|token| !token.text.starts_with("[_"))
+                .map(|token| token.p)
+                .collect();
+            let confidence = if words.is_empty() {
+                1.0
+            } else {
+                words.iter().sum::<f64>() / words.len() as f64
+            };
+            Segment {
+                start: segment.offsets.from as f64 / 1000.0,
+                end: segment.offsets.to as f64 / 1000.0,
+                text: segment.text.trim().to_string(),
+                speaker: None,
+                confidence,
+            }
+        })
+        .collect())
+}
+
+/// Transcribe an audio file's contents with the local model.
+pub async fn transcribe(local: &LocalModel, bytes: &[u8]) -> Result<Vec<Segment>, String> {
+    let dir = std::env::temp_dir().join(format!("voice-to-text-{}", uuid::Uuid::new_v4()));
+    tokio::fs::create_dir_all(&dir)
+        .await
+        .map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+    println!(
+        "[local_engine] Transcribing {} bytes with {}",
+        bytes.len(),
+        local.name
+    );
+    let result = transcribe_in(local, &dir, bytes).await;
+    let _ = tokio::fs::remove_dir_all(&dir).await;
+    result
+}
+
+/// The engine that handled the latest work.
+#[tauri::command]
+#[specta::specta]
+pub fn get_active_engine(state: State<'_, ActiveEngine>) -> Engine {
+    *state.0.lock().unwrap()
+}
@@ -0,0 +1,501 @@
+//! Downloading whisper.cpp models into `<app data dir>/models`.
+//!
+//! The catalog is the upstream `ggerganov/whisper.cpp` repository on Hugging Face, whose
+//! listing carries each file's size and SHA-256. Downloads go to a `.part` file that is
+//! resumed with a range request, and are only renamed into place once the checksum
+//! matches. Progress is reported with `model:download_progress` events.
+
+use crate::schema;
+use futures_util::StreamExt;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use specta::Type;
+use std::collections::HashSet;
+use std::fs;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Manager};
+use tokio::io::AsyncWriteExt;
+
+const TREE_URL: &str = "https://huggingface.co/api/models/ggerganov/whisper.cpp/tree/main";
+const RESOLVE_URL: &str = "https://huggingface.co/ggerganov/whisper.cpp/resolve/main";
+const PROGRESS_INTERVAL: Duration = Duration::from_millis(250);
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(15);
+
+/// Downloads in progress, by model name.
+#[derive(Default)]
+pub struct ModelDownloads(Mutex<HashSet<String>>);
+
+#[derive(Deserialize)]
+struct TreeEntry {
+    path: String,
+    size: u64,
+    lfs: Option<LfsInfo>,
+}
+
+#[derive(Deserialize)]
+struct LfsInfo {
+    /// SHA-256 of the file contents.
+    oid: String,
+    size: u64,
+}
+
+#[derive(Serialize, Clone, Debug, Type)]
+pub struct ModelInfo {
+    /// e.g. `base.en` or `large-v3-q5_0`.
+    pub name: String,
+    pub file: String,
+    pub size: u64,
+    /// Unknown for local files when the catalog can't be reached.
+    pub sha256: Option<String>,
+    pub downloaded: bool,
+    /// Bytes of an interrupted download that will be resumed.
+    pub partial_bytes: u64,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, Type)]
+#[serde(rename_all = "snake_case")]
+pub enum ModelSize {
+    Tiny,
+    Base,
+    Small,
+    Medium,
+    Large,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, Type)]
+#[serde(rename_all = "snake_case")]
+pub enum Quantization {
+    Q5,
+    Q8,
+    F16,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Type)]
+pub struct ModelChoice {
+    pub size: ModelSize,
+    pub quantization: Quantization,
+    /// English-only variants are a little more accurate for English; there is none for
+    /// `large`.
+    #[serde(default)]
+    pub english_only: bool,
+}
+
+#[derive(Serialize, Clone, Debug, Type)]
+pub struct ModelRequirements {
+    /// Name to pass to `download_model`.
+    pub name: String,
+    pub download_bytes: u64,
+    /// Estimated memory while transcribing: RAM on CPU, VRAM when offloaded to a GPU.
+    pub memory_bytes: u64,
+}
+
+#[derive(Serialize, Clone, Debug, Type)]
+pub struct ModelOption {
+    pub choice: ModelChoice,
+    pub requirements: ModelRequirements,
+}
+
+const MB: f64 = 1024.0 * 1024.0;
+
+impl ModelSize {
+    const ALL: [ModelSize; 5] = [
+        ModelSize::Tiny,
+        ModelSize::Base,
+        ModelSize::Small,
+        ModelSize::Medium,
+        ModelSize::Large,
+    ];
+
+    fn name(self) -> &'static str {
+        match self {
+            ModelSize::Tiny => "tiny",
+            ModelSize::Base => "base",
+            ModelSize::Small => "small",
+            ModelSize::Medium => "medium",
+            ModelSize::Large => "large-v3",
+        }
+    }
+
+    fn parameters(self) -> f64 {
+        match self {
+            ModelSize::Tiny => 39e6,
+            ModelSize::Base => 74e6,
+            ModelSize::Small => 244e6,
+            ModelSize::Medium => 769e6,
+            ModelSize::Large => 1550e6,
+        }
+    }
+
+    /// Working memory beyond the weights (KV cache and compute buffers), from whisper.cpp's
+    /// published memory figures.
+    fn overhead_bytes(self) -> f64 {
+        match self {
+            ModelSize::Tiny => 198.0 * MB,
+            ModelSize::Base => 246.0 * MB,
+            ModelSize::Small => 386.0 * MB,
+            ModelSize::Medium => 600.0 * MB,
+            ModelSize::Large => 1000.0 * MB,
+        }
+    }
+}
+
+impl Quantization {
+    const ALL: [Quantization; 3] = [Quantization::Q5, Quantization::Q8, Quantization::F16];
+
+    fn bytes_per_weight(self) -> f64 {
+        match self {
+            Quantization::Q5 => 0.69,
+            Quantization::Q8 => 1.06,
+            Quantization::F16 => 2.0,
+        }
+    }
+}
+
+impl ModelChoice {
+    /// The upstream model name, e.g. `base.en-q5_1` or `large-v3-q8_0`.
+    pub fn name(&self) -> String {
+        let mut name = self.size.name().to_string();
+        if self.english_only && self.size != ModelSize::Large {
+            name.push_str(".en");
+        }
+        match (self.quantization, self.size) {
+            (Quantization::F16, _) => {}
+            (Quantization::Q8, _) => name.push_str("-q8_0"),
+            // Upstream publishes q5_0 for the bigger models and q5_1 for the small ones
+            (Quantization::Q5, ModelSize::Medium | ModelSize::Large) => name.push_str("-q5_0"),
+            (Quantization::Q5, _) => name.push_str("-q5_1"),
+        }
+        name
+    }
+
+    pub fn requirements(&self) -> ModelRequirements {
+        let weights = self.size.parameters() * self.quantization.bytes_per_weight();
+        ModelRequirements {
+            name: self.name(),
+            download_bytes: weights as u64,
+            memory_bytes: (weights + self.size.overhead_bytes()) as u64,
+        }
+    }
+}
+
+/// Payload of the `model:download_progress` event.
+#[derive(Serialize, Clone, Debug, Type)]
+pub struct DownloadProgress {
+    pub name: String,
+    pub downloaded: u64,
+    pub total: u64,
+}
+
+pub fn models_dir(app: &AppHandle) -> Result<PathBuf, String> {
+    let dir = app
+        .path()
+        .app_data_dir()
+        .map_err(|e| format!("Failed to resolve app data directory: {}", e))?
+        .join("models");
+    fs::create_dir_all(&dir).map_err(|e| format!("Failed to create models directory: {}", e))?;
+    Ok(dir)
+}
+
+fn model_name(file: &str) -> Option<&str> {
+    file.strip_prefix("ggml-")?.strip_suffix(".bin")
+}
+
+fn part_path(path: &Path) -> PathBuf {
+    path.with_extension("bin.part")
+}
+
+fn local_info(dir: &Path, name: &str, size: u64, sha256: Option<String>) -> ModelInfo {
+    let file = format!("ggml-{}.bin", name);
+    let path = dir.join(&file);
+    ModelInfo {
+        name: name.to_string(),
+        downloaded: path.is_file(),
+        partial_bytes: fs::metadata(part_path(&path)).map_or(0, |m| m.len()),
+        file,
+        size,
+        sha256,
+    }
+}
+
+/// Downloaded models by name, largest first.
+pub fn downloaded(app: &AppHandle) -> Vec<(String, PathBuf)> {
+    let Ok(entries) = models_dir(app).and_then(|dir| {
+        fs::read_dir(dir).map_err(|e| format!("Failed to read models directory: {}", e))
+    }) else {
+        return Vec::new();
+    };
+    let mut found: Vec<(u64, String, PathBuf)> = entries
+        .filter_map(|entry| entry.ok())
+        .filter_map(|entry| {
+            let file = entry.file_name().to_string_lossy().into_owned();
+            let name = model_name(&file)?.to_string();
+            Some((entry.metadata().ok()?.len(), name, entry.path()))
+        })
+        .collect();
+    found.sort_by_key(|(size, _, _)| std::cmp::Reverse(*size));
+    found
+        .into_iter()
+        .map(|(_, name, path)| (name, path))
+        .collect()
+}
+
+async fn fetch_catalog() -> Result<Vec<(String, u64, String)>, String> {
+    let entries: Vec<TreeEntry> = reqwest::Client::new()
+        .get(TREE_URL)
+        .timeout(CONNECT_TIMEOUT)
+        .send()
+        .await
+        .map_err(|e| format!("Failed to fetch model catalog: {}", e))?
+        .error_for_status()
+        .map_err(|e| format!("Failed to fetch model catalog: {}", e))?
+        .json()
+        .await
+        .map_err(|e| format!("Unexpected model catalog: {}", e))?;
+    Ok(entries
+        .into_iter()
+        .filter_map(|entry| {
+            let name = model_name(&entry.path)?.to_string();
+            let lfs = entry.lfs?;
+            Some((name, lfs.size.max(entry.size), lfs.oid))
+        })
+        .collect())
+}
+
+/// Models offered upstream, marked with what is already downloaded. Offline, only the
+/// models on disk are listed.
+#[tauri::command]
+#[specta::specta]
+pub async fn list_available_models(app: AppHandle) -> Result<Vec<ModelInfo>, String> {
+    let dir = models_dir(&app)?;
+    match fetch_catalog().await {
+        Ok(catalog) => Ok(catalog
+            .into_iter()
+            .map(|(name, size, sha256)| local_info(&dir, &name, size, Some(sha256)))
+            .collect()),
+        Err(e) => {
+            println!("[models] {} — listing local models only", e);
+            let entries = fs::read_dir(&dir)
+                .map_err(|e| format!("Failed to read models directory: {}", e))?;
+            Ok(entries
+                .filter_map(|entry| entry.ok())
+                .filter_map(|entry| {
+                    let file = entry.file_name().to_string_lossy().into_owned();
+                    let size = entry.metadata().ok()?.len();
+                    Some(local_info(&dir, model_name(&file)?, size, None))
+                })
+                .collect())
+        }
+    }
+}
+
+fn file_sha256(path: &Path) -> Result<String, String> {
+    let mut file =
+        fs::File::open(path).map_err(|e| format!("Failed to open {}: {}", path.display(), e))?;
+    let mut hasher = Sha256::new();
+    let mut buffer = vec![0u8; 1 << 20];
+    loop {
+        let read = file
+            .read(&mut buffer)
+            .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+        if read == 0 {
+            break;
+        }
+        hasher.update(&buffer[..read]);
+    }
+    Ok(hex::encode(hasher.finalize()))
+}
+
+/// Download into `part`, resuming after `present` bytes when the server allows it.
+async fn fetch_into(
+    app: &AppHandle,
+    name: &str,
+    part: &Path,
+    present: u64,
+    total: u64,
+) -> Result<(), String> {
+    let mut downloaded = present;
+    let mut request = reqwest::Client::new()
+        .get(format!("{}/ggml-{}.bin", RESOLVE_URL, name))
+        .header("User-Agent", "voice-to-text-app");
+    if downloaded > 0 && downloaded < total {
+        request = request.header("Range", format!("bytes={}-", downloaded));
+    }
+    let response = request
+        .send()
+        .await
+        .map_err(|e| format!("Download failed: {}", e))?
+        .error_for_status()
+        .map_err(|e| format!("Download failed: {}", e))?;
+    // A server that ignores the range sends the whole file again
+    let resumed = response.status() == reqwest::StatusCode::PARTIAL_CONTENT;
+    if !resumed {
+        downloaded = 0;
+    }
+    println!(
+        "[models] Downloading {} ({} of {} bytes already present)",
+        name, downloaded, total
+    );
+
+    let mut file = tokio::fs::OpenOptions::new()
+        .create(true)
+        .write(true)
+        .append(resumed)
+        .truncate(!resumed)
+        .open(part)
+        .await
+        .map_err(|e| format!("Failed to open {}: {}", part.display(), e))?;
+    let mut body = response.bytes_stream();
+    let mut last_progress = Instant::now();
+    while let Some(chunk) = body.next().await {
+        let chunk = chunk.map_err(|e| format!("Download interrupted (will resume): {}", e))?;
+        file.write_all(&chunk)
+            .await
+            .map_err(|e| format!("Failed to write {}: {}", part.display(), e))?;
+        downloaded += chunk.len() as u64;
+        if last_progress.elapsed() >= PROGRESS_INTERVAL {
+            last_progress = Instant::now();
+            let _ = schema::emit(
+                app,
+                "model:download_progress",
+                DownloadProgress {
+                    name: name.to_string(),
+                    downloaded,
+                    total,
+                },
+            );
+        }
+    }
+    file.flush()
+        .await
+        .map_err(|e| format!("Failed to write {}: {}", part.display(), e))
+}
+
+async fn download(app: &AppHandle, name: &str) -> Result<ModelInfo, String> {
+    let (_, total, sha256) = fetch_catalog()
+        .await?
+        .into_iter()
+        .find(|(candidate, _, _)| candidate == name)
+        .ok_or_else(|| format!("Unknown model: {}", name))?;
+    let dir = models_dir(app)?;
+    let path = dir.join(format!("ggml-{}.bin", name));
+    let part = part_path(&path);
+    if path.is_file() {
+        return Ok(local_info(&dir, name, total, Some(sha256)));
+    }
+
+    let present = fs::metadata(&part).map_or(0, |m| m.len());
+    // A complete `.part` is left behind when the app quit before verifying it
+    if present != total {
+        fetch_into(app, name, &part, present, total).await?;
+    }
+
+    let hash_path = part.clone();
+    let actual = tauri::async_runtime::spawn_blocking(move || file_sha256(&hash_path))
+        .await
+        .map_err(|e| format!("Checksum task failed: {}", e))??;
+    if actual != sha256 {
+        let _ = fs::remove_file(&part);
+        return Err(format!(
+            "Checksum mismatch for {} (expected {}, got {}); the download was discarded",
+            name, sha256, actual
+        ));
+    }
+    fs::rename(&part, &path).map_err(|e| format!("Failed to move model into place: {}", e))?;
+    let _ = schema::emit(
+        app,
+        "model:download_progress",
+        DownloadProgress {
+            name: name.to_string(),
+            downloaded: total,
+            total,
+        },
+    );
+    println!("[models] Downloaded {}", name);
+    Ok(local_info(&dir, name, total, Some(sha256)))
+}
+
+/// Download (or resume downloading) a model and verify its checksum.
+#[tauri::command]
+#[specta::specta]
+pub async fn download_model(app: AppHandle, name: String) -> Result<ModelInfo, String> {
+    if !app
+        .state::<ModelDownloads>()
+        .0
+        .lock()
+        .unwrap()
+        .insert(name.clone())
+    {
+        return Err(format!("{} is already downloading", name));
+    }
+    let result = download(&app, &name).await;
+    app.state::<ModelDownloads>()
+        .0
+        .lock()
+        .unwrap()
+        .remove(&name);
+    result
+}
+
+/// Delete a downloaded model and any partial download of it.
+#[tauri::command]
+#[specta::specta]
+pub fn delete_model(app: AppHandle, name: String) -> Result<(), String> {
+    if name.is_empty() || name.contains(['/', '\\']) || name.contains("..") {
+        return Err(format!("Invalid model name: {}", name));
+    }
+    if app
+        .state::<ModelDownloads>()
+        .0
+        .lock()
+        .unwrap()
+        .contains(&name)
+    {
+        return Err(format!("{} is still downloading", name));
+    }
+    let path = models_dir(&app)?.join(format!("ggml-{}.bin", name));
+    let mut removed = false;
+    for candidate in [part_path(&path), path] {
+        if candidate.is_file() {
+            fs::remove_file(&candidate)
+                .map_err(|e| format!("Failed to delete {}: {}", candidate.display(), e))?;
+            removed = true;
+        }
+    }
+    if !removed {
+        return Err(format!("Model {} is not downloaded", name));
+    }
+    println!("[models] Deleted {}", name);
+    Ok(())
+}
+
+/// Estimated download size and memory for a model choice.
+#[tauri::command]
+#[specta::specta]
+pub fn estimate_model_requirements(choice: ModelChoice) -> ModelRequirements {
+    choice.requirements()
+}
+
+/// Every size and quantization with its estimate, smallest first.
+#[tauri::command]
+#[specta::specta]
+pub fn list_model_options(english_only: Option<bool>) -> Vec<ModelOption> {
+    ModelSize::ALL
+        .iter()
+        .flat_map(|&size| {
+            Quantization::ALL
+                .iter()
+                .map(move |&quantization| ModelChoice {
+                    size,
+                    quantization,
+                    english_only: english_only.unwrap_or(false),
+                })
+        })
+        .map(|choice| ModelOption {
+            requirements: choice.requirements(),
+            choice,
+        })
+        .collect()
+}
@@ -0,0 +1,304 @@
+//! Opening WebSocket connections to Deepgram one stage at a time.
+//!
+//! `connect_async` hides where a connection hangs behind a single future, so a broken
+//! resolver and a proxy that swallows the upgrade look the same. Here DNS, TCP, TLS and
+//! the WebSocket upgrade each get their own timeout from `network.timeouts`, and a
+//! failure names the stage it happened in. Host names go through `network.resolver`
+//! (see [`dns::resolve`]), and the resolved addresses are raced happy-eyeballs style in
+//! the order `network.ip_preference` asks for.
+
+use crate::bandwidth::{Meter, Metered};
+use crate::deflate::{self, Inflate};
+use crate::dns;
+use crate::settings::{self, IpPreference, NetworkSettings};
+use futures_util::stream::{FuturesUnordered, StreamExt};
+use serde::{Deserialize, Serialize};
+use specta::Type;
+use std::fmt;
+use std::future::Future;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::Duration;
+use tauri::AppHandle;
+use tokio::net::TcpStream;
+use tokio_rustls::rustls::pki_types::ServerName;
+use tokio_rustls::rustls::{ClientConfig, RootCertStore};
+use tokio_rustls::TlsConnector;
+use tokio_tungstenite::tungstenite::handshake::client::Request;
+use tokio_tungstenite::tungstenite::http::HeaderValue;
+use tokio_tungstenite::tungstenite::Error;
+use tokio_tungstenite::{MaybeTlsStream, WebSocketStream};
+
+/// How long a connection attempt gets before the next address is tried alongside it.
+const ATTEMPT_DELAY: Duration = Duration::from_millis(250);
+
+/// Host of every Deepgram endpoint the app talks to.
+pub const DEEPGRAM_HOST: &str = "api.deepgram.com";
+
+pub type Socket = WebSocketStream<Inflate<MaybeTlsStream<Metered<TcpStream>>>>;
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, Type)]
+#[serde(rename_all = "snake_case")]
+pub enum Stage {
+    Dns,
+    Tcp,
+    Tls,
+    Upgrade,
+    FirstMessage,
+}
+
+impl fmt::Display for Stage {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            Stage::Dns => "DNS lookup",
+            Stage::Tcp => "TCP connect",
+            Stage::Tls => "TLS handshake",
+            Stage::Upgrade => "WebSocket upgrade",
+            Stage::FirstMessage => "first message",
+        })
+    }
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct ConnectError {
+    pub stage: Stage,
+    pub timed_out: bool,
+    pub message: String,
+    /// HTTP status of a refused WebSocket upgrade.
+    pub status: Option<u16>,
+}
+
+impl fmt::Display for ConnectError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+impl ConnectError {
+    fn failed(stage: Stage, error: impl fmt::Display) -> Self {
+        ConnectError {
+            stage,
+            timed_out: false,
+            message: format!("{} failed: {}", stage, error),
+            status: None,
+        }
+    }
+
+    pub fn timed_out(stage: Stage, limit: Duration) -> Self {
+        ConnectError {
+            stage,
+            timed_out: true,
+            message: format!("{} timed out ({}ms)", stage, limit.as_millis()),
+            status: None,
+        }
+    }
+}
+
+/// Run one stage under its own time limit.
+pub async fn stage<T, E: fmt::Display>(
+    stage: Stage,
+    limit_ms: u64,
+    future: impl Future<Output = Result<T, E>>,
+) -> Result<T, ConnectError> {
+    let limit = Duration::from_millis(limit_ms);
+    match tokio::time::timeout(limit, future).await {
+        Ok(Ok(value)) => Ok(value),
+        Ok(Err(e)) => Err(ConnectError::failed(stage, e)),
+        Err(_) => Err(ConnectError::timed_out(stage, limit)),
+    }
+}
+
+fn tls_connector() -> TlsConnector {
+    let mut roots = RootCertStore::empty();
+    roots.extend(webpki_roots::TLS_SERVER_ROOTS.iter().cloned());
+    let config = ClientConfig::builder()
+        .with_root_certificates(roots)
+        .with_no_client_auth();
+    TlsConnector::from(Arc::new(config))
+}
+
+/// Put the preferred address family first and alternate between families after that,
+/// dropping the other family entirely in the `*Only` modes. `Auto` keeps the family of
+/// the resolver's first answer in front.
+fn order(addresses: Vec<SocketAddr>, preference: IpPreference) -> Vec<SocketAddr> {
+    let (v6, v4): (Vec<SocketAddr>, Vec<SocketAddr>) =
+        addresses.iter().partition(|address| address.is_ipv6());
+    let (first, second) = match preference {
+        IpPreference::Ipv4Only => (v4, Vec::new()),
+        IpPreference::Ipv6Only => (v6, Vec::new()),
+        IpPreference::PreferIpv4 => (v4, v6),
+        IpPreference::PreferIpv6 => (v6, v4),
+        IpPreference::Auto if addresses.first().is_some_and(SocketAddr::is_ipv6) => (v6, v4),
+        IpPreference::Auto => (v4, v6),
+    };
+    let mut ordered = Vec::with_capacity(first.len() + second.len());
+    let (mut first, mut second) = (first.into_iter(), second.into_iter());
+    loop {
+        match (first.next(), second.next()) {
+            (None, None) => return ordered,
+            (a, b) => ordered.extend(a.into_iter().chain(b)),
+        }
+    }
+}
+
+async fn attempt(
+    address: SocketAddr,
+    limit_ms: u64,
+) -> (SocketAddr, Result<TcpStream, ConnectError>) {
+    (
+        address,
+        stage(Stage::Tcp, limit_ms, TcpStream::connect(address)).await,
+    )
+}
+
+/// Happy eyeballs (RFC 8305): start with the first address and open another connection
+/// attempt whenever the newest one hasn't succeeded within `ATTEMPT_DELAY` or fails.
+/// The first to connect wins, so a dead IPv6 route costs a fraction of a second rather
+/// than the whole TCP time limit.
+async fn connect_tcp(addresses: &[SocketAddr], limit_ms: u64) -> Result<TcpStream, ConnectError> {
+    let mut pending = addresses.iter().copied().peekable();
+    let mut attempts = FuturesUnordered::new();
+    let mut last_error = None;
+    attempts.extend(pending.next().map(|address| attempt(address, limit_ms)));
+    while !attempts.is_empty() {
+        let more = pending.peek().is_some();
+        tokio::select! {
+            Some((address, result)) = attempts.next() => match result {
+                Ok(tcp) => return Ok(tcp),
+                Err(e) => {
+                    println!("[network] {}: {}", address, e.message);
+                    last_error = Some(e);
+                    attempts.extend(pending.next().map(|address| attempt(address, limit_ms)));
+                }
+            },
+            _ = tokio::time::sleep(ATTEMPT_DELAY), if more => {
+                attempts.extend(pending.next().map(|address| attempt(address, limit_ms)));
+            }
+        }
+    }
+    let mut error = last_error.unwrap_or_else(|| ConnectError::failed(Stage::Tcp, "no addresses"));
+    if addresses.len() > 1 {
+        error.message = format!("{} (tried {} addresses)", error.message, addresses.len());
+    }
+    Err(error)
+}
+
+/// Whether `host` accepts TCP connections on `port`, checked with the DNS and TCP stages
+/// alone, under their usual time limits.
+pub async fn reachable(
+    network: &NetworkSettings,
+    host: &str,
+    port: u16,
+) -> Result<(), ConnectError> {
+    let addresses = stage(
+        Stage::Dns,
+        network.timeouts.dns_ms,
+        dns::resolve(&network.resolver, host, port),
+    )
+    .await?;
+    let addresses = order(addresses, network.ip_preference);
+    connect_tcp(&addresses, network.timeouts.tcp_ms)
+        .await
+        .map(drop)
+}
+
+/// Open the WebSocket described by `request`, a `ws://` or `wss://` handshake request.
+/// Its traffic is counted on `meter`.
+pub async fn connect(
+    mut request: Request,
+    network: &NetworkSettings,
+    meter: &Meter,
+) -> Result<Socket, ConnectError> {
+    let timeouts = &network.timeouts;
+    let uri = request.uri().clone();
+    let secure = match uri.scheme_str() {
+        Some("wss") => true,
+        Some("ws") => false,
+        _ => {
+            return Err(ConnectError::failed(
+                Stage::Dns,
+                format!("not a WebSocket URL: {}", uri),
+            ))
+        }
+    };
+    let host = uri
+        .host()
+        .ok_or_else(|| ConnectError::failed(Stage::Dns, format!("no host in {}", uri)))?
+        .to_string();
+    let port = uri.port_u16().unwrap_or(if secure { 443 } else { 80 });
+
+    let addresses = stage(
+        Stage::Dns,
+        timeouts.dns_ms,
+        dns::resolve(&network.resolver, &host, port),
+    )
+    .await?;
+    let addresses = order(addresses, network.ip_preference);
+    if addresses.is_empty() {
+        return Err(ConnectError::failed(
+            Stage::Dns,
+            format!("no address of {} matches the IP version setting", host),
+        ));
+    }
+    let tcp = connect_tcp(&addresses, timeouts.tcp_ms).await?;
+    let _ = tcp.set_nodelay(true);
+    let tcp = Metered::new(tcp, meter.clone());
+
+    let stream = if secure {
+        let name =
+            ServerName::try_from(host.clone()).map_err(|e| ConnectError::failed(Stage::Tls, e))?;
+        let tls = stage(
+            Stage::Tls,
+            timeouts.tls_ms,
+            tls_connector().connect(name, tcp),
+        )
+        .await?;
+        MaybeTlsStream::Rustls(tls)
+    } else {
+        MaybeTlsStream::Plain(tcp)
+    };
+
+    // Compressed replies are inflated below tungstenite; without the offer the server
+    // sends none and `Inflate` passes everything through
+    if network.permessage_deflate {
+        request.headers_mut().insert(
+            "Sec-WebSocket-Extensions",
+            HeaderValue::from_static(deflate::EXTENSION_OFFER),
+        );
+    }
+    let stream = Inflate::new(stream);
+    let limit = Duration::from_millis(timeouts.upgrade_ms);
+    match tokio::time::timeout(limit, tokio_tungstenite::client_async(request, stream)).await {
+        Ok(Ok((socket, _))) => Ok(socket),
+        Ok(Err(Error::Http(response))) => Err(ConnectError {
+            stage: Stage::Upgrade,
+            timed_out: false,
+            message: format!("WebSocket upgrade refused with HTTP {}", response.status()),
+            status: Some(response.status().as_u16()),
+        }),
+        Ok(Err(e)) => Err(ConnectError::failed(Stage::Upgrade, e)),
+        Err(_) => Err(ConnectError::timed_out(Stage::Upgrade, limit)),
+    }
+}
+
+/// An HTTP client for requests to `host`. With a custom resolver or IP version setting
+/// the host is resolved up front and the client pinned to those addresses, in order;
+/// reqwest races the two families the same way `connect` does.
+pub async fn http_client(app: &AppHandle, host: &str) -> reqwest::Client {
+    let network = settings::load(app).network;
+    if matches!(network.resolver, settings::Resolver::System)
+        && matches!(network.ip_preference, IpPreference::Auto)
+    {
+        return reqwest::Client::new();
+    }
+    match dns::resolve(&network.resolver, host, 443).await {
+        Ok(addresses) => reqwest::Client::builder()
+            .resolve_to_addrs(host, &order(addresses, network.ip_preference))
+            .build()
+            .unwrap_or_default(),
+        Err(e) => {
+            println!("[network] {}", e);
+            reqwest::Client::new()
+        }
+    }
+}
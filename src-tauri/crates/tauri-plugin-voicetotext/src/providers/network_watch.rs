@@ -0,0 +1,85 @@
+//! Reacting to network changes such as a Wi-Fi switch or a VPN coming up or going down.
+//!
+//! After such a change a WebSocket can sit on a dead route until TCP gives up, which
+//! takes minutes. The interface addresses are polled instead, and as soon as they change
+//! every active stream session is told to reconnect (see `stream.rs`). The change itself
+//! is announced as `network:changed`.
+
+use crate::schema;
+use crate::stream::StreamSessions;
+use serde::Serialize;
+use specta::Type;
+use std::net::IpAddr;
+use std::time::Duration;
+use tauri::{AppHandle, Manager};
+
+const POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Payload of the `network:changed` event.
+#[derive(Serialize, Clone, Debug, Type)]
+pub struct NetworkChanged {
+    /// Interfaces with an address after the change.
+    pub interfaces: Vec<String>,
+    /// Stream sessions asked to reconnect.
+    pub reconnecting: usize,
+}
+
+/// Interface names and addresses, sorted. Loopback and link-local addresses are left out:
+/// they neither carry traffic to the provider nor say anything about the route.
+fn snapshot() -> Option<Vec<(String, IpAddr)>> {
+    let interfaces = match if_addrs::get_if_addrs() {
+        Ok(interfaces) => interfaces,
+        Err(e) => {
+            println!("[network_watch] Failed to list interfaces: {}", e);
+            return None;
+        }
+    };
+    let mut addresses: Vec<(String, IpAddr)> = interfaces
+        .into_iter()
+        .filter(|interface| !interface.is_loopback() && !interface.is_link_local())
+        .map(|interface| {
+            let ip = interface.ip();
+            (interface.name, ip)
+        })
+        .collect();
+    addresses.sort();
+    Some(addresses)
+}
+
+pub fn spawn(app: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        let mut interval = tokio::time::interval(POLL_INTERVAL);
+        let mut known = snapshot();
+        loop {
+            interval.tick().await;
+            let Some(current) = snapshot() else {
+                continue;
+            };
+            if known.as_ref() == Some(&current) {
+                continue;
+            }
+            let first = known.is_none();
+            known = Some(current.clone());
+            if first {
+                continue;
+            }
+
+            let reconnecting = app.state::<StreamSessions>().reconnect_all();
+            let mut interfaces: Vec<String> = current.into_iter().map(|(name, _)| name).collect();
+            interfaces.dedup();
+            println!(
+                "[network_watch] Network changed ({}); reconnecting {} stream session(s)",
+                interfaces.join(", "),
+                reconnecting
+            );
+            let _ = schema::emit(
+                &app,
+                "network:changed",
+                NetworkChanged {
+                    interfaces,
+                    reconnecting,
+                },
+            );
+        }
+    });
+}
@@ -0,0 +1,456 @@
+//! Transcription of complete recordings through Deepgram's prerecorded (REST) API.
+
+use crate::bandwidth::{self, Usage};
+use crate::history::{self, Segment, Session};
+use crate::local_engine::{self, Engine, LocalModel};
+use crate::scheduler::{self, Priority};
+use crate::{api_keys, audio_filters, chunked, crash, network, silence, sync, telemetry, webhook};
+use serde::Deserialize;
+use std::path::Path;
+use tauri::AppHandle;
+
+pub const LISTEN_ENDPOINT: &str = "https://api.deepgram.com/v1/listen";
+pub const DEFAULT_MODEL: &str = "nova-2";
+
+#[derive(Deserialize)]
+struct PrerecordedResponse {
+    #[serde(default)]
+    metadata: Option<Metadata>,
+    results: PrerecordedResults,
+}
+
+#[derive(Deserialize)]
+struct Metadata {
+    /// Length of the audio in seconds.
+    #[serde(default)]
+    duration: f64,
+}
+
+/// Result of one prerecorded request.
+pub struct Listened {
+    pub segments: Vec<Segment>,
+    /// Audio length reported by Deepgram, when present.
+    pub duration: Option<f64>,
+    /// Request and response body sizes.
+    pub usage: Usage,
+}
+
+#[derive(Deserialize)]
+struct PrerecordedResults {
+    #[serde(default)]
+    channels: Vec<PrerecordedChannel>,
+    #[serde(default)]
+    utterances: Vec<Utterance>,
+}
+
+#[derive(Deserialize)]
+struct PrerecordedChannel {
+    alternatives: Vec<PrerecordedAlternative>,
+}
+
+#[derive(Deserialize)]
+struct PrerecordedAlternative {
+    transcript: String,
+    #[serde(default)]
+    confidence: f64,
+    #[serde(default)]
+    words: Vec<Word>,
+}
+
+#[derive(Deserialize)]
+struct Word {
+    start: f64,
+    end: f64,
+}
+
+#[derive(Deserialize)]
+struct Utterance {
+    start: f64,
+    end: f64,
+    transcript: String,
+    #[serde(default)]
+    confidence: f64,
+    speaker: Option<u32>,
+}
+
+/// Turn a prerecorded response into segments. Utterances are preferred; without them
+/// the whole transcript becomes a single segment spanning the recognized words.
+fn segments_from_response(response: PrerecordedResponse) -> Vec<Segment> {
+    let results = response.results;
+    if !results.utterances.is_empty() {
+        return results
+            .utterances
+            .into_iter()
+            .map(|u| Segment {
+                start: u.start,
+                end: u.end,
+                text: u.transcript,
+                speaker: u.speaker,
+                confidence: u.confidence,
+            })
+            .collect();
+    }
+
+    results
+        .channels
+        .into_iter()
+        .next()
+        .and_then(|channel| channel.alternatives.into_iter().next())
+        .filter(|alternative| !alternative.transcript.is_empty())
+        .map(|alternative| {
+            vec![Segment {
+                start: alternative.words.first().map_or(0.0, |w| w.start),
+                end: alternative.words.last().map_or(0.0, |w| w.end),
+                text: alternative.transcript,
+                speaker: None,
+                confidence: alternative.confidence,
+            }]
+        })
+        .unwrap_or_default()
+}
+
+/// Send a prepared request (body already attached) to the prerecorded endpoint and
+/// parse the segments. A rate-limited or out-of-quota key is swapped for the next
+/// configured one and the request sent again; see [`api_keys::rotate`]. The traffic is
+/// added to today's bandwidth.
+pub async fn listen(
+    app: &AppHandle,
+    api_key: &str,
+    model: &str,
+    mut request: reqwest::RequestBuilder,
+) -> Result<Listened, String> {
+    let mut api_key = api_keys::resolve(app, api_key);
+    loop {
+        let retry = request.try_clone();
+        match listen_with_model(&api_key, model, request).await {
+            Err(e) if api_keys::is_exhausted(&e) => {
+                match (api_keys::rotate(app, &api_key), retry) {
+                    (Some(next), Some(retry)) => {
+                        api_key = next;
+                        request = retry;
+                    }
+                    _ => return Err(e),
+                }
+            }
+            result => {
+                if let Ok(listened) = &result {
+                    bandwidth::record(app, listened.usage);
+                }
+                return result;
+            }
+        }
+    }
+}
+
+/// Like `listen`, for exactly the given key.
+pub async fn listen_with_model(
+    api_key: &str,
+    model: &str,
+    request: reqwest::RequestBuilder,
+) -> Result<Listened, String> {
+    let (client, request) = request
+        .header("Authorization", format!("Token {}", api_key))
+        .query(&[
+            ("model", model),
+            ("language", "en-US"),
+            ("punctuate", "true"),
+            ("smart_format", "true"),
+            ("utterances", "true"),
+            ("diarize", "true"),
+        ])
+        .build_split();
+    let request = request.map_err(|e| format!("Prerecorded request failed: {}", e))?;
+    let mut usage = Usage {
+        bytes_sent: request
+            .body()
+            .and_then(|body| body.as_bytes())
+            .map_or(0, |bytes| bytes.len() as u64),
+        ..Usage::default()
+    };
+    let response = client
+        .execute(request)
+        .await
+        .map_err(|e| format!("Prerecorded request failed: {}", e))?;
+
+    let status = response.status();
+    if status == reqwest::StatusCode::UNAUTHORIZED {
+        return Err("Authentication failed — check your Deepgram API key".to_string());
+    }
+    if api_keys::is_exhausted_status(status.as_u16()) {
+        return Err(format!("{} (HTTP {})", api_keys::EXHAUSTED, status));
+    }
+    if !status.is_success() {
+        let detail = response.text().await.unwrap_or_default();
+        return Err(format!("Deepgram returned HTTP {}: {}", status, detail));
+    }
+
+    let body = response
+        .bytes()
+        .await
+        .map_err(|e| format!("Failed to read Deepgram response: {}", e))?;
+    usage.bytes_received = body.len() as u64;
+    let parsed: PrerecordedResponse = serde_json::from_slice(&body)
+        .map_err(|e| format!("Unexpected Deepgram response: {}", e))?;
+    let duration = parsed.metadata.as_ref().map(|m| m.duration);
+    Ok(Listened {
+        segments: segments_from_response(parsed),
+        duration,
+        usage,
+    })
+}
+
+/// Title for a session created from a URL: the last path component, or the host.
+fn title_from_url(url: &str) -> String {
+    reqwest::Url::parse(url)
+        .ok()
+        .and_then(|parsed| {
+            let name = parsed.path_segments()?.next_back()?.to_string();
+            if name.is_empty() {
+                parsed.host_str().map(str::to_string)
+            } else {
+                Some(name)
+            }
+        })
+        .unwrap_or_else(|| url.to_string())
+}
+
+/// Have Deepgram fetch and transcribe a remote audio file.
+async fn listen_url(app: &AppHandle, url: &str, api_key: &str) -> Result<Listened, String> {
+    let request = network::http_client(app, network::DEEPGRAM_HOST)
+        .await
+        .post(LISTEN_ENDPOINT)
+        .json(&serde_json::json!({ "url": url }));
+    let _permit = scheduler::acquire(app, Priority::Normal).await;
+    listen(app, api_key, DEFAULT_MODEL, request).await
+}
+
+/// Download a remote audio file and transcribe it with a local model instead.
+async fn listen_url_locally(
+    app: &AppHandle,
+    local: LocalModel,
+    reason: String,
+    url: &str,
+) -> Result<Listened, String> {
+    let response = reqwest::get(url)
+        .await
+        .map_err(|e| format!("Failed to download {}: {}", url, e))?;
+    if !response.status().is_success() {
+        return Err(format!("{} returned HTTP {}", url, response.status()));
+    }
+    let bytes = response
+        .bytes()
+        .await
+        .map_err(|e| format!("Failed to download {}: {}", url, e))?;
+    listen_locally(app, local, reason, &bytes, Priority::Normal).await
+}
+
+/// Transcribe a remote audio file. Deepgram fetches the URL itself, so nothing is
+/// downloaded locally unless Deepgram can't be reached and a local model is used. The
+/// result is stored in history and returned.
+#[tauri::command]
+#[specta::specta]
+pub async fn transcribe_url(
+    app: AppHandle,
+    url: String,
+    api_key: String,
+) -> Result<Session, String> {
+    reqwest::Url::parse(&url).map_err(|e| format!("Invalid URL: {}", e))?;
+    println!("[transcribe_url] Submitting {}", url);
+    crash::breadcrumb("transcribe_url submitted");
+
+    let listened = match local_engine::fallback(&app).await {
+        Some((local, reason)) => listen_url_locally(&app, local, reason, &url).await,
+        None => match listen_url(&app, &url, &api_key).await {
+            Ok(listened) => {
+                local_engine::set_active(&app, Engine::Deepgram, None, None);
+                Ok(listened)
+            }
+            Err(e) => match local_engine::fallback(&app).await {
+                Some((local, reason)) => {
+                    println!("[transcribe_url] {}; continuing with the local model", e);
+                    listen_url_locally(&app, local, reason, &url).await
+                }
+                None => Err(e),
+            },
+        },
+    }
+    .inspect_err(|_| {
+        telemetry::error(&app, "transcribe_url");
+    })?;
+
+    let mut session = Session::new(
+        title_from_url(&url),
+        format!("url:{}", url),
+        listened.segments,
+    );
+    session.bandwidth = Some(listened.usage);
+    history::save(&app, &session)?;
+    webhook::session_completed(&app, &session);
+    sync::session_completed(&app, &session);
+    telemetry::session_completed(&app, &session);
+    println!(
+        "[transcribe_url] Stored session {} ({} segments)",
+        session.id,
+        session.segments.len()
+    );
+    Ok(session)
+}
+
+/// Content type for a local audio file; Deepgram sniffs the container when unsure.
+pub fn content_type(path: &Path) -> &'static str {
+    let extension = path
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .map(str::to_ascii_lowercase);
+    match extension.as_deref() {
+        Some("wav") => "audio/wav",
+        Some("mp3") => "audio/mpeg",
+        Some("m4a") | Some("mp4") => "audio/mp4",
+        Some("flac") => "audio/flac",
+        Some("ogg") | Some("opus") => "audio/ogg",
+        Some("webm") => "audio/webm",
+        _ => "audio/*",
+    }
+}
+
+/// Extensions picked up when transcribing a whole folder.
+pub fn is_audio_file(path: &Path) -> bool {
+    content_type(path) != "audio/*"
+}
+
+/// Transcribe with a local model instead of Deepgram; see [`local_engine`].
+async fn listen_locally(
+    app: &AppHandle,
+    local: LocalModel,
+    reason: String,
+    bytes: &[u8],
+    priority: Priority,
+) -> Result<Listened, String> {
+    local_engine::set_active(app, Engine::Local, Some(local.name.clone()), Some(reason));
+    let _permit = scheduler::acquire(app, priority).await;
+    Ok(Listened {
+        segments: local_engine::transcribe(&local, bytes).await?,
+        duration: None,
+        usage: Usage::default(),
+    })
+}
+
+/// Send a file's contents to Deepgram, in chunks when chunking applies.
+async fn upload_file(
+    app: &AppHandle,
+    path: &Path,
+    bytes: &[u8],
+    api_key: &str,
+    priority: Priority,
+) -> Result<Listened, String> {
+    if let Some(listened) = chunked::transcribe_wav(app, bytes, api_key, priority).await? {
+        return Ok(listened);
+    }
+    let _permit = scheduler::acquire(app, priority).await;
+    let request = network::http_client(app, network::DEEPGRAM_HOST)
+        .await
+        .post(LISTEN_ENDPOINT)
+        .header("Content-Type", content_type(path))
+        .body(bytes.to_vec());
+    listen(app, api_key, DEFAULT_MODEL, request).await
+}
+
+/// Upload a local audio file and store the result in history. When Deepgram can't be
+/// reached, before or during the upload, a downloaded local model is used if there is one.
+pub async fn transcribe_file(
+    app: &AppHandle,
+    path: &Path,
+    api_key: &str,
+    priority: Priority,
+) -> Result<Session, String> {
+    let bytes = tokio::fs::read(path)
+        .await
+        .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    let bytes = audio_filters::filter_wav(app, &bytes).unwrap_or(bytes);
+    let (bytes, time_map) = match silence::trim_wav(app, &bytes) {
+        Some((trimmed, map)) => (trimmed, Some(map)),
+        None => (bytes, None),
+    };
+    println!(
+        "[transcribe_file] Uploading {} ({} bytes)",
+        path.display(),
+        bytes.len()
+    );
+    crash::breadcrumb(format!("transcribe_file uploading {} bytes", bytes.len()));
+
+    let mut listened = match local_engine::fallback(app).await {
+        Some((local, reason)) => listen_locally(app, local, reason, &bytes, priority).await,
+        None => match upload_file(app, path, &bytes, api_key, priority).await {
+            Ok(listened) => {
+                local_engine::set_active(app, Engine::Deepgram, None, None);
+                Ok(listened)
+            }
+            // The connection may have dropped partway through
+            Err(e) => match local_engine::fallback(app).await {
+                Some((local, reason)) => {
+                    println!("[transcribe_file] {}; continuing with the local model", e);
+                    listen_locally(app, local, reason, &bytes, priority).await
+                }
+                None => Err(e),
+            },
+        },
+    }
+    .inspect_err(|_| {
+        telemetry::error(app, "transcribe_file");
+    })?;
+    if let Some(map) = &time_map {
+        map.remap(&mut listened.segments);
+    }
+
+    let title = path
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_else(|| path.display().to_string());
+    let mut session = Session::new(title, format!("file:{}", path.display()), listened.segments);
+    session.bandwidth = Some(listened.usage);
+    history::save(app, &session)?;
+    webhook::session_completed(app, &session);
+    sync::session_completed(app, &session);
+    telemetry::session_completed(app, &session);
+    Ok(session)
+}
+
+/// Transcribe a stored session's audio again, e.g. with a more accurate model. Only
+/// sessions from a URL or from a file that still exists can be; streams keep no audio.
+pub async fn retranscribe(
+    app: &AppHandle,
+    source: &str,
+    api_key: &str,
+    model: &str,
+) -> Result<Vec<Segment>, String> {
+    let client = network::http_client(app, network::DEEPGRAM_HOST).await;
+    if let Some(url) = source.strip_prefix("url:") {
+        let request = client
+            .post(LISTEN_ENDPOINT)
+            .json(&serde_json::json!({ "url": url }));
+        let _permit = scheduler::acquire(app, Priority::Normal).await;
+        return Ok(listen(app, api_key, model, request).await?.segments);
+    }
+    let Some(path) = source.strip_prefix("file:").map(Path::new) else {
+        return Err(format!("No audio is kept for {} sessions", source));
+    };
+
+    let bytes = tokio::fs::read(path)
+        .await
+        .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    let bytes = audio_filters::filter_wav(app, &bytes).unwrap_or(bytes);
+    let (bytes, time_map) = match silence::trim_wav(app, &bytes) {
+        Some((trimmed, map)) => (trimmed, Some(map)),
+        None => (bytes, None),
+    };
+    let request = client
+        .post(LISTEN_ENDPOINT)
+        .header("Content-Type", content_type(path))
+        .body(bytes);
+    let _permit = scheduler::acquire(app, Priority::Normal).await;
+    let mut segments = listen(app, api_key, model, request).await?.segments;
+    if let Some(map) = &time_map {
+        map.remap(&mut segments);
+    }
+    Ok(segments)
+}
@@ -0,0 +1,808 @@
+//! Scheduled and recurring transcription jobs, persisted in `<app data dir>/jobs.json`.
+//!
+//! A background task checks the job list every [`TICK`] and runs whatever is due.
+//! Microphone capture lives in the webview, so capture jobs are handed to the frontend
+//! as `scheduler:capture` events; stream and folder jobs run entirely in the backend.
+//!
+//! Every provider request also goes through the [`RequestLimiter`]: at most
+//! `jobs.max_concurrent_requests` run at once, higher priorities are admitted first, and
+//! batch work doesn't start new requests while interactive dictation or a live stream is
+//! running.
+
+use crate::schema::{self, Store};
+use crate::{crash, history, prerecorded, recent, settings, stream};
+use chrono::{DateTime, Datelike, Local, MappedLocalTime, NaiveDate, TimeDelta, TimeZone};
+use serde::{Deserialize, Serialize};
+use specta::Type;
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::ops::Bound;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tauri::{AppHandle, Manager, State};
+use tokio::sync::Notify;
+
+const TICK: Duration = Duration::from_secs(30);
+
+/// How late a recurring slot may still fire, e.g. when the app starts at 9:05 for a 9:00 job.
+const MISSED_GRACE_MS: u64 = 15 * 60 * 1000;
+
+/// Longest a dictation may hold its slot when the frontend never calls `end_dictation`.
+const DICTATION_TIMEOUT: Duration = Duration::from_secs(2 * 60 * 60);
+
+#[derive(Serialize, Deserialize, Clone, Debug, Type)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Schedule {
+    /// Run once at a unix time in milliseconds.
+    Once { at: u64 },
+    /// Run at a local wall-clock time. `weekdays` uses 0 = Monday … 6 = Sunday; empty means every day.
+    Daily {
+        hour: u32,
+        minute: u32,
+        #[serde(default)]
+        weekdays: Vec<u32>,
+    },
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Type)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum JobAction {
+    /// Ask the frontend to record from the microphone for a while.
+    Capture { duration_minutes: u32 },
+    /// Transcribe a network stream for a while.
+    StreamUrl { url: String, duration_minutes: u32 },
+    /// Transcribe every audio file in a folder that isn't in history yet.
+    TranscribeFolder { path: String },
+}
+
+/// Order in which waiting provider requests are admitted.
+#[derive(
+    Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Type,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum Priority {
+    Low,
+    #[default]
+    Normal,
+    High,
+    /// Live dictation and streams. Never queued, and batch work yields to it.
+    Interactive,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Type)]
+pub struct Job {
+    pub id: String,
+    pub name: String,
+    pub schedule: Schedule,
+    pub action: JobAction,
+    pub enabled: bool,
+    pub created_at: u64,
+    pub last_run: Option<u64>,
+    #[serde(default)]
+    pub priority: Priority,
+}
+
+/// Payload of the `scheduler:capture` event.
+#[derive(Serialize, Clone, Debug, Type)]
+pub struct CaptureRequest {
+    pub job_id: String,
+    pub duration_minutes: u32,
+}
+
+/// Payload of the `scheduler:job_finished` event.
+#[derive(Serialize, Clone, Debug, Type)]
+pub struct JobFinished {
+    pub job_id: String,
+    pub error: Option<String>,
+}
+
+#[derive(Default)]
+pub struct Scheduler {
+    jobs: Mutex<Vec<Job>>,
+}
+
+#[derive(Default)]
+struct LimiterState {
+    running: usize,
+    interactive: usize,
+    waiting: BTreeMap<Priority, usize>,
+}
+
+#[derive(Default)]
+struct LimiterInner {
+    state: Mutex<LimiterState>,
+    changed: Notify,
+}
+
+/// Admission control for provider requests. See the module docs.
+#[derive(Default)]
+pub struct RequestLimiter {
+    inner: Arc<LimiterInner>,
+    /// Permits held on behalf of the frontend's dictation, by id.
+    dictation: Mutex<HashMap<String, Permit>>,
+}
+
+/// A slot for one provider request or live session; released on drop.
+pub struct Permit {
+    inner: Arc<LimiterInner>,
+    priority: Priority,
+}
+
+impl Drop for Permit {
+    fn drop(&mut self) {
+        let mut state = self.inner.state.lock().unwrap();
+        state.running -= 1;
+        if self.priority == Priority::Interactive {
+            state.interactive -= 1;
+        }
+        drop(state);
+        self.inner.changed.notify_waiters();
+    }
+}
+
+/// Counts a request as waiting until it is admitted or its future is dropped.
+struct Waiting<'a> {
+    inner: &'a LimiterInner,
+    priority: Priority,
+}
+
+impl Drop for Waiting<'_> {
+    fn drop(&mut self) {
+        let mut state = self.inner.state.lock().unwrap();
+        if let Some(count) = state.waiting.get_mut(&self.priority) {
+            *count -= 1;
+        }
+        drop(state);
+        self.inner.changed.notify_waiters();
+    }
+}
+
+/// How many provider requests hold a slot right now, and how many wait for one.
+pub fn occupancy(app: &AppHandle) -> (usize, usize) {
+    let limiter = app.state::<RequestLimiter>();
+    let state = limiter.inner.state.lock().unwrap();
+    (state.running, state.waiting.values().sum())
+}
+
+fn admit_interactive(inner: Arc<LimiterInner>) -> Permit {
+    let mut state = inner.state.lock().unwrap();
+    state.running += 1;
+    state.interactive += 1;
+    drop(state);
+    Permit {
+        inner,
+        priority: Priority::Interactive,
+    }
+}
+
+impl RequestLimiter {
+    /// Keep `permit` for the dictation `id`. The frontend dictates one at a time, so any
+    /// earlier dictation whose `end_dictation` never arrived is released.
+    fn hold_dictation(&self, id: String, permit: Permit) {
+        let mut dictation = self.dictation.lock().unwrap();
+        dictation.clear();
+        dictation.insert(id, permit);
+    }
+}
+
+/// Wait for a slot. Interactive requests are admitted immediately, even past the limit;
+/// everything else waits for a free slot, for higher priorities, and for interactive
+/// sessions to end.
+pub async fn acquire(app: &AppHandle, priority: Priority) -> Permit {
+    let inner = app.state::<RequestLimiter>().inner.clone();
+    if priority == Priority::Interactive {
+        return admit_interactive(inner);
+    }
+
+    let max = settings::load(app).jobs.max_concurrent_requests.max(1);
+    *inner
+        .state
+        .lock()
+        .unwrap()
+        .waiting
+        .entry(priority)
+        .or_default() += 1;
+    let waiting = Waiting {
+        inner: &inner,
+        priority,
+    };
+    loop {
+        // Created before checking so a release in between isn't missed
+        let changed = inner.changed.notified();
+        {
+            let mut state = inner.state.lock().unwrap();
+            let higher_waiting = state
+                .waiting
+                .range((Bound::Excluded(priority), Bound::Unbounded))
+                .any(|(_, count)| *count > 0);
+            if state.interactive == 0 && state.running < max && !higher_waiting {
+                state.running += 1;
+                break;
+            }
+        }
+        changed.await;
+    }
+    drop(waiting);
+    Permit { inner, priority }
+}
+
+/// Hold an interactive slot while the frontend dictates, so batch jobs stay out of the
+/// way. Returns an id for `end_dictation`; the slot is also released by the next
+/// `begin_dictation` or after [`DICTATION_TIMEOUT`].
+#[tauri::command]
+#[specta::specta]
+pub async fn begin_dictation(app: AppHandle) -> String {
+    let permit = acquire(&app, Priority::Interactive).await;
+    recent::begin_dictation(&app);
+    let id = uuid::Uuid::new_v4().to_string();
+    app.state::<RequestLimiter>()
+        .hold_dictation(id.clone(), permit);
+
+    let expired = id.clone();
+    tauri::async_runtime::spawn(async move {
+        tokio::time::sleep(DICTATION_TIMEOUT).await;
+        if app
+            .state::<RequestLimiter>()
+            .dictation
+            .lock()
+            .unwrap()
+            .remove(&expired)
+            .is_some()
+        {
+            println!("[scheduler] Released dictation {} after timeout", expired);
+        }
+    });
+    id
+}
+
+#[tauri::command]
+#[specta::specta]
+pub fn end_dictation(limiter: State<'_, RequestLimiter>, dictation_id: String) {
+    limiter.dictation.lock().unwrap().remove(&dictation_id);
+}
+
+impl Schedule {
+    fn validate(&self) -> Result<(), String> {
+        match self {
+            Schedule::Once { .. } => Ok(()),
+            Schedule::Daily {
+                hour,
+                minute,
+                weekdays,
+            } => {
+                if *hour > 23 || *minute > 59 {
+                    return Err(format!("Invalid time {:02}:{:02}", hour, minute));
+                }
+                if weekdays.iter().any(|day| *day > 6) {
+                    return Err("Weekdays must be between 0 (Monday) and 6 (Sunday)".to_string());
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+/// `hour:minute` on `day` in `zone`, in unix ms. A time skipped when clocks go forward
+/// fires an hour later instead; a time repeated when they go back fires the first time.
+fn local_slot<Tz: TimeZone>(zone: &Tz, day: NaiveDate, hour: u32, minute: u32) -> Option<u64> {
+    let time = day.and_hms_opt(hour, minute, 0)?;
+    let slot = match zone.from_local_datetime(&time) {
+        MappedLocalTime::Single(slot) | MappedLocalTime::Ambiguous(slot, _) => slot,
+        MappedLocalTime::None => zone
+            .from_local_datetime(&(time + TimeDelta::hours(1)))
+            .earliest()?,
+    };
+    Some(slot.timestamp_millis() as u64)
+}
+
+impl Job {
+    /// The slot (unix ms) that should fire now, if any.
+    fn due_slot<Tz: TimeZone>(&self, now: DateTime<Tz>) -> Option<u64> {
+        if !self.enabled {
+            return None;
+        }
+        let now_ms = now.timestamp_millis() as u64;
+        let slot = match &self.schedule {
+            Schedule::Once { at } => {
+                return (self.last_run.is_none() && now_ms >= *at).then_some(*at);
+            }
+            Schedule::Daily {
+                hour,
+                minute,
+                weekdays,
+            } => {
+                // Yesterday's slot is still within the grace period just after midnight
+                let today = now.date_naive();
+                [today.pred_opt()?, today]
+                    .into_iter()
+                    .filter(|day| {
+                        let weekday = day.weekday().num_days_from_monday();
+                        weekdays.is_empty() || weekdays.contains(&weekday)
+                    })
+                    .filter_map(|day| local_slot(&now.timezone(), day, *hour, *minute))
+                    .filter(|slot| *slot <= now_ms)
+                    .max()?
+            }
+        };
+
+        let fresh = now_ms >= slot && now_ms - slot <= MISSED_GRACE_MS;
+        let already_ran = self.last_run.is_some_and(|last| last >= slot);
+        (fresh && !already_ran && self.created_at <= slot).then_some(slot)
+    }
+}
+
+pub fn jobs_path(app: &AppHandle) -> Result<PathBuf, String> {
+    let dir = app
+        .path()
+        .app_data_dir()
+        .map_err(|e| format!("Failed to resolve app data directory: {}", e))?;
+    fs::create_dir_all(&dir).map_err(|e| format!("Failed to create app data directory: {}", e))?;
+    Ok(dir.join("jobs.json"))
+}
+
+const STORE: Store = Store {
+    name: "jobs",
+    wrapped: true,
+    migrations: &[schema::wrap_records],
+};
+
+fn load_jobs(app: &AppHandle) -> Result<Vec<Job>, String> {
+    Ok(STORE.read(&jobs_path(app)?)?.unwrap_or_default())
+}
+
+fn save_jobs(app: &AppHandle, jobs: &[Job]) -> Result<(), String> {
+    STORE.write(&jobs_path(app)?, &jobs)
+}
+
+/// Load persisted jobs and start the background tick loop. Called once from `setup`.
+pub fn spawn(app: AppHandle) {
+    let jobs = load_jobs(&app).unwrap_or_else(|e| {
+        println!("[scheduler] {}", e);
+        Vec::new()
+    });
+    *app.state::<Scheduler>().jobs.lock().unwrap() = jobs;
+
+    tauri::async_runtime::spawn(async move {
+        let mut interval = tokio::time::interval(TICK);
+        loop {
+            interval.tick().await;
+            tick(&app);
+        }
+    });
+}
+
+/// Forget every job in memory, e.g. after the jobs file was erased.
+pub fn clear(app: &AppHandle) {
+    app.state::<Scheduler>().jobs.lock().unwrap().clear();
+}
+
+fn tick(app: &AppHandle) {
+    let now = Local::now();
+    let due: Vec<Job> = {
+        let scheduler = app.state::<Scheduler>();
+        let mut jobs = scheduler.jobs.lock().unwrap();
+        let mut due = Vec::new();
+        for job in jobs.iter_mut() {
+            if let Some(slot) = job.due_slot(now) {
+                job.last_run = Some(slot.max(history::now_ms()));
+                due.push(job.clone());
+            }
+        }
+        if !due.is_empty() {
+            if let Err(e) = save_jobs(app, &jobs) {
+                println!("[scheduler] {}", e);
+            }
+        }
+        due
+    };
+
+    for job in due {
+        println!("[scheduler] Running job {} ({})", job.id, job.name);
+        crash::breadcrumb(format!("scheduler job {} started", job.id));
+        let app = app.clone();
+        tauri::async_runtime::spawn(async move {
+            let error = run_job(&app, &job).await.err();
+            if let Some(e) = &error {
+                println!("[scheduler] Job {} failed: {}", job.id, e);
+            }
+            let _ = schema::emit(
+                &app,
+                "scheduler:job_finished",
+                JobFinished {
+                    job_id: job.id,
+                    error,
+                },
+            );
+        });
+    }
+}
+
+async fn run_job(app: &AppHandle, job: &Job) -> Result<(), String> {
+    match &job.action {
+        JobAction::Capture { duration_minutes } => schema::emit(
+            app,
+            "scheduler:capture",
+            CaptureRequest {
+                job_id: job.id.clone(),
+                duration_minutes: *duration_minutes,
+            },
+        )
+        .map_err(|e| format!("Failed to notify frontend: {}", e)),
+        JobAction::StreamUrl {
+            url,
+            duration_minutes,
+        } => {
+            let api_key = settings::api_key(app)?;
+            // Nobody watches an unattended recording live, so only finals are requested
+            let session_id = stream::start(app, url.clone(), &api_key, false, job.priority).await?;
+            tokio::time::sleep(Duration::from_secs(u64::from(*duration_minutes) * 60)).await;
+            // The stream may already have ended on its own
+            let _ = stream::stop(&app.state::<stream::StreamSessions>(), &session_id);
+            Ok(())
+        }
+        JobAction::TranscribeFolder { path } => {
+            transcribe_folder(app, Path::new(path), job.priority).await
+        }
+    }
+}
+
+/// Transcribe files in `dir` that have no session in history yet, one at a time.
+async fn transcribe_folder(app: &AppHandle, dir: &Path, priority: Priority) -> Result<(), String> {
+    let api_key = settings::api_key(app)?;
+    let known: Vec<String> = history::load_all(app)?
+        .into_iter()
+        .map(|session| session.source)
+        .collect();
+
+    let entries =
+        fs::read_dir(dir).map_err(|e| format!("Failed to read {}: {}", dir.display(), e))?;
+    let mut failures = 0;
+    for path in entries
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+    {
+        let source = format!("file:{}", path.display());
+        if !path.is_file() || !prerecorded::is_audio_file(&path) || known.contains(&source) {
+            continue;
+        }
+        if let Err(e) = prerecorded::transcribe_file(app, &path, &api_key, priority).await {
+            println!("[scheduler] {}", e);
+            failures += 1;
+        }
+    }
+
+    if failures > 0 {
+        return Err(format!(
+            "{} file(s) in {} failed to transcribe",
+            failures,
+            dir.display()
+        ));
+    }
+    Ok(())
+}
+
+#[tauri::command]
+#[specta::specta]
+pub fn list_jobs(scheduler: State<'_, Scheduler>) -> Vec<Job> {
+    scheduler.jobs.lock().unwrap().clone()
+}
+
+#[tauri::command]
+#[specta::specta]
+pub fn add_job(
+    app: AppHandle,
+    scheduler: State<'_, Scheduler>,
+    name: String,
+    schedule: Schedule,
+    action: JobAction,
+    priority: Option<Priority>,
+) -> Result<Job, String> {
+    schedule.validate()?;
+    let priority = priority.unwrap_or_default();
+    if priority == Priority::Interactive {
+        return Err("Jobs can't use the interactive priority".to_string());
+    }
+    let job = Job {
+        id: uuid::Uuid::new_v4().to_string(),
+        name,
+        schedule,
+        action,
+        enabled: true,
+        created_at: history::now_ms(),
+        last_run: None,
+        priority,
+    };
+
+    let mut jobs = scheduler.jobs.lock().unwrap();
+    jobs.push(job.clone());
+    save_jobs(&app, &jobs)?;
+    Ok(job)
+}
+
+#[tauri::command]
+#[specta::specta]
+pub fn remove_job(
+    app: AppHandle,
+    scheduler: State<'_, Scheduler>,
+    job_id: String,
+) -> Result<(), String> {
+    let mut jobs = scheduler.jobs.lock().unwrap();
+    let before = jobs.len();
+    jobs.retain(|job| job.id != job_id);
+    if jobs.len() == before {
+        return Err(format!("No such job: {}", job_id));
+    }
+    save_jobs(&app, &jobs)
+}
+
+#[tauri::command]
+#[specta::specta]
+pub fn set_job_enabled(
+    app: AppHandle,
+    scheduler: State<'_, Scheduler>,
+    job_id: String,
+    enabled: bool,
+) -> Result<(), String> {
+    let mut jobs = scheduler.jobs.lock().unwrap();
+    let job = jobs
+        .iter_mut()
+        .find(|job| job.id == job_id)
+        .ok_or_else(|| format!("No such job: {}", job_id))?;
+    job.enabled = enabled;
+    save_jobs(&app, &jobs)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{FixedOffset, NaiveDateTime};
+
+    /// Central European rules for 2026: UTC+1, and UTC+2 from 29 March to 25 October.
+    #[derive(Clone, Copy)]
+    struct TestZone;
+
+    impl TestZone {
+        fn offset_at(utc: &NaiveDateTime) -> FixedOffset {
+            let change = |month, day| {
+                NaiveDate::from_ymd_opt(2026, month, day)
+                    .unwrap()
+                    .and_hms_opt(1, 0, 0)
+                    .unwrap()
+            };
+            let hours = if (change(3, 29)..change(10, 25)).contains(utc) {
+                2
+            } else {
+                1
+            };
+            FixedOffset::east_opt(hours * 3600).unwrap()
+        }
+    }
+
+    impl TimeZone for TestZone {
+        type Offset = FixedOffset;
+
+        fn from_offset(_: &FixedOffset) -> Self {
+            TestZone
+        }
+
+        fn offset_from_local_date(&self, local: &NaiveDate) -> MappedLocalTime<FixedOffset> {
+            self.offset_from_local_datetime(&local.and_hms_opt(0, 0, 0).unwrap())
+        }
+
+        fn offset_from_local_datetime(
+            &self,
+            local: &NaiveDateTime,
+        ) -> MappedLocalTime<FixedOffset> {
+            let valid: Vec<FixedOffset> = [2, 1]
+                .into_iter()
+                .map(|hours| FixedOffset::east_opt(hours * 3600).unwrap())
+                .filter(|offset| {
+                    let utc = *local - TimeDelta::seconds(offset.local_minus_utc().into());
+                    Self::offset_at(&utc) == *offset
+                })
+                .collect();
+            match valid[..] {
+                [offset] => MappedLocalTime::Single(offset),
+                [earlier, later] => MappedLocalTime::Ambiguous(earlier, later),
+                _ => MappedLocalTime::None,
+            }
+        }
+
+        fn offset_from_utc_date(&self, utc: &NaiveDate) -> FixedOffset {
+            Self::offset_at(&utc.and_hms_opt(0, 0, 0).unwrap())
+        }
+
+        fn offset_from_utc_datetime(&self, utc: &NaiveDateTime) -> FixedOffset {
+            Self::offset_at(utc)
+        }
+    }
+
+    fn utc(month: u32, day: u32, hour: u32, minute: u32) -> DateTime<TestZone> {
+        let time = NaiveDate::from_ymd_opt(2026, month, day)
+            .unwrap()
+            .and_hms_opt(hour, minute, 0)
+            .unwrap();
+        TestZone.from_utc_datetime(&time)
+    }
+
+    fn local(month: u32, day: u32, hour: u32, minute: u32) -> DateTime<TestZone> {
+        TestZone
+            .with_ymd_and_hms(2026, month, day, hour, minute, 0)
+            .single()
+            .unwrap()
+    }
+
+    fn ms(time: DateTime<TestZone>) -> u64 {
+        time.timestamp_millis() as u64
+    }
+
+    fn job(schedule: Schedule, last_run: Option<DateTime<TestZone>>) -> Job {
+        Job {
+            id: "job".to_string(),
+            name: "job".to_string(),
+            schedule,
+            action: JobAction::TranscribeFolder {
+                path: String::new(),
+            },
+            enabled: true,
+            created_at: 0,
+            last_run: last_run.map(ms),
+            priority: Priority::Normal,
+        }
+    }
+
+    fn daily(hour: u32, minute: u32, weekdays: &[u32]) -> Schedule {
+        Schedule::Daily {
+            hour,
+            minute,
+            weekdays: weekdays.to_vec(),
+        }
+    }
+
+    #[test]
+    fn validates_times_and_weekdays() {
+        let cases = [
+            (Schedule::Once { at: 0 }, true),
+            (daily(0, 0, &[]), true),
+            (daily(23, 59, &[0, 6]), true),
+            (daily(24, 0, &[]), false),
+            (daily(12, 60, &[]), false),
+            (daily(9, 0, &[7]), false),
+        ];
+        for (schedule, valid) in cases {
+            assert_eq!(schedule.validate().is_ok(), valid, "{:?}", schedule);
+        }
+    }
+
+    #[test]
+    fn finds_the_due_slot() {
+        let once = Schedule::Once {
+            at: ms(local(10, 15, 9, 0)),
+        };
+        // 2026-10-12 is a Monday, 2026-01-31 a Saturday
+        let cases = [
+            // Once: any time after it until it has run
+            (once.clone(), local(10, 15, 8, 59), None, None),
+            (
+                once.clone(),
+                local(10, 15, 9, 0),
+                None,
+                Some(local(10, 15, 9, 0)),
+            ),
+            (
+                once.clone(),
+                local(10, 17, 9, 0),
+                None,
+                Some(local(10, 15, 9, 0)),
+            ),
+            (once, local(10, 17, 9, 0), Some(local(10, 15, 9, 0)), None),
+            // Daily: within the grace period, once per slot
+            (daily(9, 0, &[]), local(10, 15, 8, 59), None, None),
+            (
+                daily(9, 0, &[]),
+                local(10, 15, 9, 5),
+                None,
+                Some(local(10, 15, 9, 0)),
+            ),
+            (daily(9, 0, &[]), local(10, 15, 9, 20), None, None),
+            (
+                daily(9, 0, &[]),
+                local(10, 15, 9, 10),
+                Some(local(10, 15, 9, 1)),
+                None,
+            ),
+            (
+                daily(9, 0, &[]),
+                local(10, 15, 9, 10),
+                Some(local(10, 14, 9, 1)),
+                Some(local(10, 15, 9, 0)),
+            ),
+            // Weekly: only on the listed days
+            (
+                daily(9, 0, &[0]),
+                local(10, 12, 9, 5),
+                None,
+                Some(local(10, 12, 9, 0)),
+            ),
+            (daily(9, 0, &[0]), local(10, 13, 9, 5), None, None),
+            // Month end: a slot just before midnight is still due just after it
+            (
+                daily(23, 55, &[]),
+                local(2, 1, 0, 5),
+                None,
+                Some(local(1, 31, 23, 55)),
+            ),
+            (
+                daily(23, 55, &[5]),
+                local(2, 1, 0, 5),
+                None,
+                Some(local(1, 31, 23, 55)),
+            ),
+            (daily(23, 55, &[6]), local(2, 1, 0, 5), None, None),
+            // Clocks go forward at 02:00 local: 02:30 doesn't exist and runs at 03:30
+            (
+                daily(2, 30, &[]),
+                local(3, 29, 3, 35),
+                None,
+                Some(local(3, 29, 3, 30)),
+            ),
+            (daily(2, 30, &[]), local(3, 29, 3, 25), None, None),
+            // Clocks go back at 03:00 local: 02:30 happens twice and runs the first time
+            (
+                daily(2, 30, &[]),
+                utc(10, 25, 0, 40),
+                None,
+                Some(utc(10, 25, 0, 30)),
+            ),
+            (
+                daily(2, 30, &[]),
+                utc(10, 25, 1, 40),
+                Some(utc(10, 25, 0, 31)),
+                None,
+            ),
+        ];
+        for (schedule, now, last_run, expected) in cases {
+            let job = job(schedule, last_run);
+            assert_eq!(
+                job.due_slot(now),
+                expected.map(ms),
+                "{:?} at {}",
+                job.schedule,
+                now
+            );
+        }
+    }
+
+    #[test]
+    fn skips_disabled_and_newer_jobs() {
+        let mut disabled = job(daily(9, 0, &[]), None);
+        disabled.enabled = false;
+        assert_eq!(disabled.due_slot(local(10, 15, 9, 5)), None);
+
+        let mut created_after = job(daily(9, 0, &[]), None);
+        created_after.created_at = ms(local(10, 15, 9, 2));
+        assert_eq!(created_after.due_slot(local(10, 15, 9, 5)), None);
+    }
+
+    #[test]
+    fn a_new_dictation_releases_a_stale_one() {
+        let limiter = RequestLimiter::default();
+        let interactive = || limiter.inner.state.lock().unwrap().interactive;
+
+        limiter.hold_dictation(
+            "first".to_string(),
+            admit_interactive(limiter.inner.clone()),
+        );
+        limiter.hold_dictation(
+            "second".to_string(),
+            admit_interactive(limiter.inner.clone()),
+        );
+        assert_eq!(interactive(), 1);
+        assert!(limiter.dictation.lock().unwrap().contains_key("second"));
+
+        limiter.dictation.lock().unwrap().remove("second");
+        assert_eq!(interactive(), 0);
+        assert_eq!(limiter.inner.state.lock().unwrap().running, 0);
+    }
+}
@@ -0,0 +1,1159 @@
+//! Live transcription of network audio streams (HTTP / Icecast radio, RTSP cameras).
+//!
+//! The stream is pulled by the backend and relayed to Deepgram as containerized audio,
+//! so Deepgram takes care of decoding MP3/AAC/Ogg payloads. RTSP sources are pulled by
+//! `ffmpeg`, which drops the video and hands the audio over as WAV.
+//!
+//! When Deepgram can't be reached as the session starts and a local model is ready,
+//! `ffmpeg` pulls and decodes the stream instead and the local model transcribes it in
+//! chunks of `LOCAL_CHUNK_SECONDS`, emitting only final results.
+
+use crate::bandwidth::{self, Meter, Usage};
+use crate::caption_server::{self, Caption};
+use crate::history::{self, LatencyStats, Segment, Session};
+use crate::local_engine::{self, Engine, LocalModel};
+use crate::scheduler::{self, Permit, Priority};
+use crate::schema;
+use crate::settings::{self, CommandSettings, InterimSettings};
+use crate::wav::{self, Wav};
+use crate::{
+    api_keys, commands, crash, dictation, network, overlay, spelling, sync, telemetry, webhook,
+};
+use futures_util::stream::{self, BoxStream, SplitSink, SplitStream};
+use futures_util::{SinkExt, StreamExt};
+use serde::{Deserialize, Serialize};
+use specta::Type;
+use std::collections::HashMap;
+use std::process::Stdio;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Manager, State};
+use tokio::io::{AsyncBufReadExt, AsyncReadExt, BufReader};
+use tokio::process::{Child, ChildStderr, ChildStdout, Command};
+use tokio::sync::{mpsc, oneshot, Notify};
+use tokio_tungstenite::tungstenite::Message;
+
+type DeepgramSocket = network::Socket;
+
+/// Tells Deepgram to flush pending results and close once the audio is done.
+const CLOSE_STREAM: &str = r#"{"type":"CloseStream"}"#;
+/// Tells Deepgram to finalize what it has heard so far and carry on.
+const FINALIZE: &str = r#"{"type":"Finalize"}"#;
+/// How long the connection of a rolled-over part gets to deliver its last results.
+const DRAIN_TIMEOUT: Duration = Duration::from_secs(10);
+/// Audio the local model transcribes at a time; its results trail the stream by as much.
+const LOCAL_CHUNK_SECONDS: u32 = 10;
+const LOCAL_SAMPLE_RATE: u32 = 16000;
+/// Deepgram's live transcription endpoint.
+pub const LISTEN_ENDPOINT: &str = "wss://api.deepgram.com/v1/listen";
+
+/// Active stream sessions, keyed by session id.
+#[derive(Default)]
+pub struct StreamSessions(Mutex<HashMap<String, ActiveStream>>);
+
+struct ActiveStream {
+    /// Sending on (or dropping) this stops the relay.
+    stop: oneshot::Sender<()>,
+    /// Makes the relay replace its Deepgram connection.
+    reconnect: Arc<Notify>,
+    /// Sessions the stream continued in after rolling over, oldest first.
+    continuations: Vec<String>,
+}
+
+impl StreamSessions {
+    pub fn len(&self) -> usize {
+        self.0.lock().unwrap().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.lock().unwrap().is_empty()
+    }
+
+    /// Whether the stream `start` returned `session_id` for is still running.
+    pub fn contains(&self, session_id: &str) -> bool {
+        self.0.lock().unwrap().contains_key(session_id)
+    }
+
+    /// Ask one session to reconnect to Deepgram. Returns whether it is running.
+    pub fn reconnect(&self, session_id: &str) -> bool {
+        match self.0.lock().unwrap().get(session_id) {
+            Some(session) => {
+                session.reconnect.notify_one();
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Ask every active session to reconnect to Deepgram. Returns how many were asked.
+    pub fn reconnect_all(&self) -> usize {
+        let sessions = self.0.lock().unwrap();
+        for session in sessions.values() {
+            session.reconnect.notify_one();
+        }
+        sessions.len()
+    }
+}
+
+/// Payload of the `stream:connection` event, sent whenever the Deepgram connection of a
+/// session is opened or replaced.
+#[derive(Serialize, Clone, Debug, Type)]
+pub struct ConnectionEvent {
+    pub session_id: String,
+    pub state: ConnectionState,
+    /// Why reconnecting failed; the session carries on over the old connection.
+    pub error: Option<String>,
+}
+
+#[derive(Serialize, Clone, Copy, Debug, Type)]
+#[serde(rename_all = "snake_case")]
+pub enum ConnectionState {
+    Connected,
+    Reconnecting,
+    ReconnectFailed,
+}
+
+fn emit_connection(
+    app: &AppHandle,
+    session_id: &str,
+    state: ConnectionState,
+    error: Option<String>,
+) {
+    let _ = schema::emit(
+        app,
+        "stream:connection",
+        ConnectionEvent {
+            session_id: session_id.to_string(),
+            state,
+            error,
+        },
+    );
+}
+
+/// Payload of the `transcript:update` event.
+#[derive(Serialize, Clone, Debug, Type)]
+pub struct TranscriptEvent {
+    pub session_id: String,
+    pub transcript: String,
+    pub is_final: bool,
+    pub confidence: f64,
+    pub start: f64,
+    pub duration: f64,
+    /// Time from the audio being relayed to this result arriving, when it can be told.
+    pub latency_ms: Option<f64>,
+}
+
+/// Payload of the `session:ended` event. `error` is set when the session ended abnormally.
+#[derive(Serialize, Clone, Debug, Type)]
+pub struct SessionEnded {
+    pub session_id: String,
+    pub error: Option<String>,
+    pub latency: Option<LatencyStats>,
+}
+
+/// Payload of the `session:save_failed` event: a finished session could not be stored
+/// and its transcript is only in the events already sent.
+#[derive(Serialize, Clone, Debug, Type)]
+pub struct SaveFailed {
+    pub session_id: String,
+    pub error: String,
+}
+
+#[derive(Deserialize)]
+struct LiveResponse {
+    #[serde(rename = "type")]
+    kind: String,
+    #[serde(default)]
+    is_final: bool,
+    #[serde(default)]
+    start: f64,
+    #[serde(default)]
+    duration: f64,
+    channel: Option<LiveChannel>,
+}
+
+#[derive(Deserialize)]
+struct LiveChannel {
+    alternatives: Vec<LiveAlternative>,
+}
+
+#[derive(Deserialize)]
+struct LiveAlternative {
+    transcript: String,
+    #[serde(default)]
+    confidence: f64,
+}
+
+/// Drops interim results that arrive too fast or change too little, per `InterimSettings`.
+struct InterimThrottle {
+    config: InterimSettings,
+    last_emit: Option<Instant>,
+    last_len: usize,
+}
+
+impl InterimThrottle {
+    fn new(config: InterimSettings) -> Self {
+        InterimThrottle {
+            config,
+            last_emit: None,
+            last_len: 0,
+        }
+    }
+
+    fn allow(&mut self, text: &str, is_final: bool) -> bool {
+        let len = text.chars().count();
+        if !is_final {
+            if let (Some(rate), Some(last)) = (self.config.max_per_second, self.last_emit) {
+                if rate > 0.0 && last.elapsed() < Duration::from_secs_f64(1.0 / rate) {
+                    return false;
+                }
+            }
+            if self.last_emit.is_some() && len.abs_diff(self.last_len) < self.config.min_char_change
+            {
+                return false;
+            }
+        }
+        // A final starts the next utterance from scratch
+        self.last_emit = (!is_final).then(Instant::now);
+        self.last_len = if is_final { 0 } else { len };
+        true
+    }
+}
+
+/// How a stream URL is pulled.
+#[derive(Clone, Copy, PartialEq, Debug)]
+enum SourceKind {
+    /// Relayed as served.
+    Http,
+    /// Pulled and demuxed by `ffmpeg`.
+    Rtsp,
+}
+
+fn check_source_url(url: &str) -> Result<SourceKind, String> {
+    let parsed = reqwest::Url::parse(url).map_err(|e| format!("Invalid stream URL: {}", e))?;
+    match parsed.scheme() {
+        "http" | "https" => Ok(SourceKind::Http),
+        "rtsp" | "rtsps" => Ok(SourceKind::Rtsp),
+        other => Err(format!("Unsupported stream URL scheme: {}", other)),
+    }
+}
+
+/// An `ffmpeg` reading `url`. RTSP goes over TCP, which cameras behind NAT or a
+/// firewall still answer.
+fn ffmpeg_input(url: &str, kind: SourceKind) -> Command {
+    let mut command = Command::new("ffmpeg");
+    command.args(["-v", "error"]);
+    if kind == SourceKind::Rtsp {
+        command.args(["-rtsp_transport", "tcp"]);
+    }
+    command.arg("-i").arg(url);
+    command
+}
+
+/// Audio relayed to Deepgram, with the `ffmpeg` producing it for RTSP sources.
+struct Source {
+    audio: BoxStream<'static, Result<Vec<u8>, String>>,
+    /// Killed when the session drops it.
+    decoder: Option<Child>,
+}
+
+async fn open_source(url: &str, kind: SourceKind) -> Result<Source, String> {
+    if kind == SourceKind::Http {
+        let response = reqwest::get(url)
+            .await
+            .map_err(|e| format!("Failed to open stream: {}", e))?;
+        if !response.status().is_success() {
+            return Err(format!("Stream source returned HTTP {}", response.status()));
+        }
+        let audio = response
+            .bytes_stream()
+            .map(|chunk| chunk.map(|bytes| bytes.to_vec()).map_err(|e| e.to_string()))
+            .boxed();
+        return Ok(Source {
+            audio,
+            decoder: None,
+        });
+    }
+
+    let mut decoder = ffmpeg_input(url, kind)
+        .args(["-vn", "-ac", "1", "-ar", "16000"])
+        .args(["-c:a", "pcm_s16le", "-f", "wav", "-"])
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true)
+        .spawn()
+        .map_err(|e| format!("RTSP streams need ffmpeg: {}", e))?;
+    let stdout = decoder.stdout.take().ok_or("ffmpeg has no output")?;
+    let mut audio = stream::unfold(stdout, |mut stdout| async move {
+        let mut buffer = vec![0u8; 16 * 1024];
+        match stdout.read(&mut buffer).await {
+            Ok(0) => None,
+            Ok(n) => {
+                buffer.truncate(n);
+                Some((Ok(buffer), stdout))
+            }
+            Err(e) => Some((Err(e.to_string()), stdout)),
+        }
+    })
+    .boxed();
+
+    // Wait for the first audio, so a camera that can't be reached fails the start like an
+    // HTTP source does
+    match audio.next().await {
+        Some(Ok(first)) => {
+            if let Some(stderr) = decoder.stderr.take() {
+                tauri::async_runtime::spawn(log_decoder_errors(stderr));
+            }
+            Ok(Source {
+                audio: stream::iter([Ok(first)]).chain(audio).boxed(),
+                decoder: Some(decoder),
+            })
+        }
+        Some(Err(e)) => Err(format!("Failed to open stream: {}", e)),
+        None => {
+            let mut reason = String::new();
+            if let Some(mut stderr) = decoder.stderr.take() {
+                let _ = stderr.read_to_string(&mut reason).await;
+            }
+            let reason = reason.lines().last().unwrap_or("no audio").to_string();
+            Err(format!("Failed to open stream: {}", reason))
+        }
+    }
+}
+
+/// Log what `ffmpeg` reports while relaying, which also keeps its pipe from filling up.
+async fn log_decoder_errors(stderr: ChildStderr) {
+    let mut lines = BufReader::new(stderr).lines();
+    while let Ok(Some(line)) = lines.next_line().await {
+        println!("[stream] ffmpeg: {}", line);
+    }
+}
+
+/// Start transcribing a network audio stream. Returns the new session id; results are
+/// delivered as `transcript:update` events and the end of the session as `session:ended`.
+/// Final segments are stored in history when the session ends. With `interim_results`
+/// off (default on) only final results are requested and emitted.
+#[tauri::command]
+#[specta::specta]
+pub async fn start_stream_transcription(
+    app: AppHandle,
+    url: String,
+    api_key: String,
+    interim_results: Option<bool>,
+) -> Result<String, String> {
+    start(
+        &app,
+        url,
+        &api_key,
+        interim_results.unwrap_or(true),
+        Priority::Interactive,
+    )
+    .await
+}
+
+/// Open the Deepgram socket, moving on to the next configured key while the handshake
+/// is refused for a rate limit or exhausted quota.
+async fn connect_deepgram(
+    app: &AppHandle,
+    endpoint: &str,
+    api_key: &str,
+    interim_results: bool,
+    meter: &Meter,
+) -> Result<DeepgramSocket, String> {
+    // No encoding/sample_rate: Deepgram detects the container format itself
+    let dg_url = format!(
+        "{}?model=nova-2&language=en-US&punctuate=true&smart_format=true&interim_results={}",
+        endpoint, interim_results
+    );
+    let config = settings::load(app);
+    let mut api_key = api_keys::resolve(app, api_key);
+    loop {
+        let request = api_keys::websocket_request(&dg_url, &api_key, config.deepgram_key_in_url)?;
+        match network::connect(request, &config.network, meter).await {
+            Ok(socket) => return Ok(socket),
+            Err(e) if e.status.is_some_and(api_keys::is_exhausted_status) => {
+                match api_keys::rotate(app, &api_key) {
+                    Some(next) => api_key = next,
+                    None => return Err(format!("{} ({})", api_keys::EXHAUSTED, e)),
+                }
+            }
+            Err(e) => return Err(format!("WebSocket connection failed: {}", e)),
+        }
+    }
+}
+
+pub async fn start(
+    app: &AppHandle,
+    url: String,
+    api_key: &str,
+    interim_results: bool,
+    priority: Priority,
+) -> Result<String, String> {
+    start_with_endpoint(
+        app,
+        url,
+        api_key,
+        interim_results,
+        priority,
+        LISTEN_ENDPOINT,
+    )
+    .await
+}
+
+/// `start`, with the session talking to a Deepgram-compatible `endpoint` instead of
+/// Deepgram itself, such as the mock provider of a soak test.
+pub async fn start_with_endpoint(
+    app: &AppHandle,
+    url: String,
+    api_key: &str,
+    interim_results: bool,
+    priority: Priority,
+    endpoint: &str,
+) -> Result<String, String> {
+    let kind = check_source_url(&url)?;
+    // Held for the whole session and released when `run_session` returns
+    let permit = scheduler::acquire(app, priority).await;
+    if let Some((local, reason)) = local_engine::fallback(app).await {
+        return start_local(app, url, kind, local, reason, permit);
+    }
+
+    println!("[stream] Opening source: {}", url);
+    let source = open_source(&url, kind).await?;
+
+    let meter = Meter::default();
+    let socket = match connect_deepgram(app, endpoint, api_key, interim_results, &meter).await {
+        Ok(socket) => socket,
+        Err(e) => {
+            return match local_engine::fallback(app).await {
+                Some((local, reason)) => {
+                    println!("[stream] {}; continuing with the local model", e);
+                    start_local(app, url, kind, local, reason, permit)
+                }
+                None => Err(e),
+            };
+        }
+    };
+    local_engine::set_active(app, Engine::Deepgram, None, None);
+
+    let session_id = uuid::Uuid::new_v4().to_string();
+    let (stop_tx, stop_rx) = oneshot::channel();
+    let reconnect = Arc::new(Notify::new());
+    app.state::<StreamSessions>().0.lock().unwrap().insert(
+        session_id.clone(),
+        ActiveStream {
+            stop: stop_tx,
+            reconnect: reconnect.clone(),
+            continuations: Vec::new(),
+        },
+    );
+
+    println!("[stream] Session {} started", session_id);
+    crash::breadcrumb(format!("stream {} started", session_id));
+    emit_connection(app, &session_id, ConnectionState::Connected, None);
+    tauri::async_runtime::spawn(run_session(
+        app.clone(),
+        session_id.clone(),
+        url,
+        source,
+        Deepgram {
+            socket,
+            endpoint: endpoint.to_string(),
+            api_key: api_key.to_string(),
+            interim_results,
+            reconnect,
+            meter,
+        },
+        stop_rx,
+        permit,
+    ));
+    Ok(session_id)
+}
+
+/// Start transcribing a stream with the local model, a chunk at a time. `ffmpeg` pulls
+/// the source itself and decodes it to 16 kHz mono samples.
+fn start_local(
+    app: &AppHandle,
+    url: String,
+    kind: SourceKind,
+    local: LocalModel,
+    reason: String,
+    permit: Permit,
+) -> Result<String, String> {
+    println!("[stream] Opening source for {}: {}", local.name, url);
+    let decoder = ffmpeg_input(&url, kind)
+        .args(["-vn", "-f", "f32le", "-ar", "16000", "-ac", "1", "-"])
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .kill_on_drop(true)
+        .spawn()
+        .map_err(|e| format!("Local transcription needs ffmpeg: {}", e))?;
+    local_engine::set_active(app, Engine::Local, Some(local.name.clone()), Some(reason));
+
+    let session_id = uuid::Uuid::new_v4().to_string();
+    let (stop_tx, stop_rx) = oneshot::channel();
+    app.state::<StreamSessions>().0.lock().unwrap().insert(
+        session_id.clone(),
+        ActiveStream {
+            stop: stop_tx,
+            // There is no connection to replace
+            reconnect: Arc::new(Notify::new()),
+            continuations: Vec::new(),
+        },
+    );
+
+    println!("[stream] Session {} started locally", session_id);
+    crash::breadcrumb(format!("stream {} started locally", session_id));
+    tauri::async_runtime::spawn(run_local_session(
+        app.clone(),
+        session_id.clone(),
+        url,
+        local,
+        decoder,
+        stop_rx,
+        permit,
+    ));
+    Ok(session_id)
+}
+
+/// Cut decoded samples into chunks of `LOCAL_CHUNK_SECONDS`, the last one shorter.
+async fn read_chunks(
+    mut samples: ChildStdout,
+    chunks: mpsc::UnboundedSender<Result<Vec<f32>, String>>,
+) {
+    let chunk_len = (LOCAL_SAMPLE_RATE * LOCAL_CHUNK_SECONDS) as usize * 4;
+    let mut buffer = Vec::with_capacity(chunk_len);
+    let mut read = vec![0u8; 16 * 1024];
+    loop {
+        let n = match samples.read(&mut read).await {
+            Ok(n) => n,
+            Err(e) => {
+                let _ = chunks.send(Err(format!("Stream read failed: {}", e)));
+                return;
+            }
+        };
+        buffer.extend_from_slice(&read[..n]);
+        while buffer.len() >= chunk_len || (n == 0 && buffer.len() >= 4) {
+            let take = buffer.len().min(chunk_len) / 4 * 4;
+            let chunk = buffer
+                .drain(..take)
+                .as_slice()
+                .chunks_exact(4)
+                .map(|bytes| f32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+                .collect();
+            if chunks.send(Ok(chunk)).is_err() {
+                return;
+            }
+        }
+        if n == 0 {
+            return;
+        }
+    }
+}
+
+async fn run_local_session(
+    app: AppHandle,
+    session_id: String,
+    url: String,
+    local: LocalModel,
+    mut decoder: Child,
+    mut stop_rx: oneshot::Receiver<()>,
+    _permit: Permit,
+) {
+    let config = settings::load(&app);
+    let mut throttle = InterimThrottle::new(config.interim);
+    let stream_id = session_id.clone();
+    let mut part = Part::new(session_id);
+    part.timing.first_sent = Some(Instant::now());
+    let (chunks_tx, mut chunks) = mpsc::unbounded_channel();
+    // Read on while a chunk is transcribed, so a live source isn't held up
+    if let Some(samples) = decoder.stdout.take() {
+        tauri::async_runtime::spawn(read_chunks(samples, chunks_tx));
+    }
+    let mut stopping = false;
+    let mut offset = 0.0;
+    let mut error = None;
+
+    loop {
+        tokio::select! {
+            _ = &mut stop_rx, if !stopping => {
+                // The samples read so far are still transcribed
+                stopping = true;
+                let _ = decoder.start_kill();
+            }
+            chunk = chunks.recv() => match chunk {
+                Some(Ok(samples)) => {
+                    let seconds = samples.len() as f64 / LOCAL_SAMPLE_RATE as f64;
+                    let audio = wav::encode(&Wav {
+                        sample_rate: LOCAL_SAMPLE_RATE,
+                        channels: 1,
+                        samples,
+                    });
+                    let segments = match local_engine::transcribe(&local, &audio).await {
+                        Ok(segments) => segments,
+                        Err(e) => {
+                            error = Some(e);
+                            break;
+                        }
+                    };
+                    for segment in segments {
+                        let heard = Heard {
+                            transcript: segment.text,
+                            is_final: true,
+                            confidence: segment.confidence,
+                            start: offset + segment.start,
+                            duration: segment.end - segment.start,
+                        };
+                        if let Some(segment) = deliver(
+                            &app,
+                            &part.session_id,
+                            heard,
+                            &config.commands,
+                            &mut throttle,
+                            &mut part.timing,
+                        ) {
+                            webhook::segment_final(&app, &part.session_id, &segment);
+                            part.segments.push(segment);
+                        }
+                    }
+                    offset += seconds;
+                }
+                Some(Err(e)) => {
+                    error = Some(e);
+                    break;
+                }
+                None => break,
+            },
+        }
+    }
+
+    let _ = decoder.kill().await;
+    app.state::<StreamSessions>()
+        .0
+        .lock()
+        .unwrap()
+        .remove(&stream_id);
+    finish_part(&app, &url, part, Usage::default(), error);
+}
+
+/// Stop a running stream session. Pending results are still flushed before `session:ended`.
+#[tauri::command]
+#[specta::specta]
+pub fn stop_stream_transcription(
+    sessions: State<'_, StreamSessions>,
+    session_id: String,
+) -> Result<(), String> {
+    stop(&sessions, &session_id)
+}
+
+/// `session_id` may be the id `start` returned or that of any continuation after it.
+pub fn stop(sessions: &StreamSessions, session_id: &str) -> Result<(), String> {
+    let mut sessions = sessions.0.lock().unwrap();
+    let key = sessions
+        .iter()
+        .find(|(key, active)| {
+            *key == session_id || active.continuations.iter().any(|id| id == session_id)
+        })
+        .map(|(key, _)| key.clone());
+    match key.and_then(|key| sessions.remove(&key)) {
+        Some(session) => {
+            let _ = session.stop.send(());
+            Ok(())
+        }
+        None => Err(format!("No active stream session: {}", session_id)),
+    }
+}
+
+/// A session's Deepgram connection and what it takes to open it again.
+struct Deepgram {
+    socket: DeepgramSocket,
+    endpoint: String,
+    api_key: String,
+    interim_results: bool,
+    reconnect: Arc<Notify>,
+    /// Counts the traffic of every connection the session opens.
+    meter: Meter,
+}
+
+/// How a session's results line up with the audio sent.
+#[derive(Default)]
+struct Timing {
+    /// When the first audio went out. Latency is measured against it, assuming a live
+    /// source.
+    first_sent: Option<Instant>,
+    /// Deepgram times results from the start of its connection, so after a reconnect
+    /// they are shifted by how far into the stream the new connection started.
+    offset: f64,
+    latencies: Vec<f64>,
+    /// When audio was first sent after the last final, for `max_utterance_seconds`. Set by
+    /// sending rather than by interim results, which may be turned off.
+    utterance_started: Option<tokio::time::Instant>,
+}
+
+/// The part of a session since it started or last rolled over.
+struct Part {
+    session_id: String,
+    /// The part this one continues.
+    continues: Option<String>,
+    segments: Vec<Segment>,
+    timing: Timing,
+    /// Session traffic when the part began.
+    usage_before: Usage,
+}
+
+impl Part {
+    fn new(session_id: String) -> Self {
+        Part {
+            session_id,
+            continues: None,
+            segments: Vec::new(),
+            timing: Timing::default(),
+            usage_before: Usage::default(),
+        }
+    }
+}
+
+/// Payload of the `session:rolled_over` event.
+#[derive(Serialize, Clone, Debug, Type)]
+pub struct RolledOver {
+    pub session_id: String,
+    /// The session results go to from now on.
+    pub continuation_id: String,
+}
+
+async fn run_session(
+    app: AppHandle,
+    session_id: String,
+    url: String,
+    source: Source,
+    deepgram: Deepgram,
+    mut stop_rx: oneshot::Receiver<()>,
+    _permit: Permit,
+) {
+    let (mut sink, mut results) = deepgram.socket.split();
+    let mut audio = source.audio;
+    // Keeps an RTSP source's ffmpeg running until the session ends
+    let _decoder = source.decoder;
+    let mut sending = true;
+    let mut error = None;
+    let config = settings::load(&app);
+    let mut throttle = InterimThrottle::new(config.interim);
+    // The session id handed out by `start` keeps naming the stream across rollovers
+    let stream_id = session_id.clone();
+    let mut part = Part::new(session_id);
+    // Deadline for Deepgram's first message, armed by the first audio sent
+    let mut first_message_due: Option<tokio::time::Instant> = None;
+    let first_message_limit = Duration::from_millis(config.network.timeouts.first_message_ms);
+    let max_utterance = config
+        .max_utterance_seconds
+        .filter(|seconds| *seconds > 0.0)
+        .map(Duration::from_secs_f64);
+    let max_session = config
+        .max_session_minutes
+        .filter(|minutes| *minutes > 0)
+        .map(|minutes| Duration::from_secs(minutes as u64 * 60));
+    let mut rollover_due = max_session.map(|limit| tokio::time::Instant::now() + limit);
+
+    loop {
+        tokio::select! {
+            _ = &mut stop_rx, if sending => {
+                sending = false;
+                let _ = sink.send(Message::Text(CLOSE_STREAM.to_string())).await;
+            }
+            chunk = audio.next(), if sending => match chunk {
+                Some(Ok(bytes)) => {
+                    if part.timing.first_sent.is_none() {
+                        part.timing.first_sent = Some(Instant::now());
+                        first_message_due = Some(tokio::time::Instant::now() + first_message_limit);
+                    }
+                    if let Err(e) = sink.send(Message::Binary(bytes)).await {
+                        error = Some(format!("Failed to send audio: {}", e));
+                        break;
+                    }
+                    part.timing
+                        .utterance_started
+                        .get_or_insert_with(tokio::time::Instant::now);
+                }
+                Some(Err(e)) => {
+                    error = Some(format!("Stream read failed: {}", e));
+                    sending = false;
+                    let _ = sink.send(Message::Text(CLOSE_STREAM.to_string())).await;
+                }
+                None => {
+                    println!("[stream] Source ended for session {}", part.session_id);
+                    sending = false;
+                    let _ = sink.send(Message::Text(CLOSE_STREAM.to_string())).await;
+                }
+            },
+            _ = deepgram.reconnect.notified(), if sending => {
+                // Results still pending on the old connection are lost with it
+                println!("[stream] Reconnecting session {}", part.session_id);
+                emit_connection(&app, &part.session_id, ConnectionState::Reconnecting, None);
+                let reconnected = connect_deepgram(
+                    &app,
+                    &deepgram.endpoint,
+                    &deepgram.api_key,
+                    deepgram.interim_results,
+                    &deepgram.meter,
+                )
+                .await;
+                match reconnected {
+                    Ok(socket) => {
+                        part.timing.offset = part
+                            .timing
+                            .first_sent
+                            .map_or(0.0, |sent| sent.elapsed().as_secs_f64());
+                        (sink, results) = socket.split();
+                        first_message_due = part
+                            .timing
+                            .first_sent
+                            .map(|_| tokio::time::Instant::now() + first_message_limit);
+                        emit_connection(&app, &part.session_id, ConnectionState::Connected, None);
+                    }
+                    Err(e) => {
+                        println!(
+                            "[stream] Reconnect failed for session {}: {}",
+                            part.session_id, e
+                        );
+                        emit_connection(
+                            &app,
+                            &part.session_id,
+                            ConnectionState::ReconnectFailed,
+                            Some(e),
+                        );
+                    }
+                }
+            }
+            _ = tokio::time::sleep_until(
+                rollover_due.unwrap_or_else(tokio::time::Instant::now)
+            ), if sending && rollover_due.is_some() => {
+                rollover_due = max_session.map(|limit| tokio::time::Instant::now() + limit);
+                let reconnected = connect_deepgram(
+                    &app,
+                    &deepgram.endpoint,
+                    &deepgram.api_key,
+                    deepgram.interim_results,
+                    &deepgram.meter,
+                )
+                .await;
+                let socket = match reconnected {
+                    Ok(socket) => socket,
+                    Err(e) => {
+                        // Tried again once the next part would be due
+                        println!("[stream] Rollover of {} failed: {}", part.session_id, e);
+                        continue;
+                    }
+                };
+                let (next_sink, next_results) = socket.split();
+                let previous_sink = std::mem::replace(&mut sink, next_sink);
+                let previous_results = std::mem::replace(&mut results, next_results);
+
+                let usage = deepgram.meter.usage();
+                let mut next = Part::new(uuid::Uuid::new_v4().to_string());
+                next.continues = Some(part.session_id.clone());
+                next.usage_before = usage;
+                // The new connection times results from now
+                next.timing.first_sent = Some(Instant::now());
+                first_message_due = Some(tokio::time::Instant::now() + first_message_limit);
+                let finished = std::mem::replace(&mut part, next);
+
+                let sessions = app.state::<StreamSessions>();
+                if let Some(active) = sessions.0.lock().unwrap().get_mut(&stream_id) {
+                    active.continuations.push(part.session_id.clone());
+                }
+                println!(
+                    "[stream] Session {} rolled over to {}",
+                    finished.session_id, part.session_id
+                );
+                let _ = schema::emit(
+                    &app,
+                    "session:rolled_over",
+                    RolledOver {
+                        session_id: finished.session_id.clone(),
+                        continuation_id: part.session_id.clone(),
+                    },
+                );
+                emit_connection(&app, &part.session_id, ConnectionState::Connected, None);
+                tauri::async_runtime::spawn(drain_part(
+                    app.clone(),
+                    url.clone(),
+                    finished,
+                    usage,
+                    previous_sink,
+                    previous_results,
+                ));
+            }
+            _ = tokio::time::sleep_until(
+                part.timing.utterance_started.zip(max_utterance).map_or_else(
+                    tokio::time::Instant::now,
+                    |(started, limit)| started + limit,
+                )
+            ), if sending && part.timing.utterance_started.is_some() && max_utterance.is_some() => {
+                // The next audio sent starts the clock again
+                part.timing.utterance_started = None;
+                if let Err(e) = sink.send(Message::Text(FINALIZE.to_string())).await {
+                    error = Some(format!("Failed to send Finalize: {}", e));
+                    break;
+                }
+            }
+            _ = tokio::time::sleep_until(
+                first_message_due.unwrap_or_else(tokio::time::Instant::now)
+            ), if first_message_due.is_some() => {
+                let timed_out =
+                    network::ConnectError::timed_out(network::Stage::FirstMessage, first_message_limit);
+                error = Some(timed_out.message);
+                break;
+            }
+            message = results.next() => match message {
+                Some(Ok(Message::Text(text))) => {
+                    first_message_due = None;
+                    if let Some(segment) = handle_message(
+                        &app,
+                        &part.session_id,
+                        &text,
+                        &config.commands,
+                        &mut throttle,
+                        &mut part.timing,
+                    ) {
+                        webhook::segment_final(&app, &part.session_id, &segment);
+                        part.segments.push(segment);
+                    }
+                }
+                Some(Ok(Message::Close(_))) | None => break,
+                Some(Ok(_)) => {}
+                Some(Err(e)) => {
+                    error = Some(format!("WebSocket error: {}", e));
+                    break;
+                }
+            },
+        }
+    }
+
+    app.state::<StreamSessions>()
+        .0
+        .lock()
+        .unwrap()
+        .remove(&stream_id);
+    let usage = deepgram.meter.usage();
+    finish_part(&app, &url, part, usage, error);
+}
+
+/// Let the connection of a rolled-over part deliver its last results, then store the
+/// part. `usage` is the session traffic at the rollover.
+async fn drain_part(
+    app: AppHandle,
+    url: String,
+    mut part: Part,
+    usage: Usage,
+    mut sink: SplitSink<DeepgramSocket, Message>,
+    mut results: SplitStream<DeepgramSocket>,
+) {
+    let _ = sink.send(Message::Text(CLOSE_STREAM.to_string())).await;
+    let config = settings::load(&app);
+    let mut throttle = InterimThrottle::new(config.interim);
+    let drained = tokio::time::timeout(DRAIN_TIMEOUT, async {
+        while let Some(Ok(message)) = results.next().await {
+            match message {
+                Message::Text(text) => {
+                    if let Some(segment) = handle_message(
+                        &app,
+                        &part.session_id,
+                        &text,
+                        &config.commands,
+                        &mut throttle,
+                        &mut part.timing,
+                    ) {
+                        webhook::segment_final(&app, &part.session_id, &segment);
+                        part.segments.push(segment);
+                    }
+                }
+                Message::Close(_) => break,
+                _ => {}
+            }
+        }
+    })
+    .await;
+    if drained.is_err() {
+        println!(
+            "[stream] Gave up waiting for the last results of {}",
+            part.session_id
+        );
+    }
+    finish_part(&app, &url, part, usage, None);
+}
+
+/// Store a finished part in history and announce its end. `usage` is the session
+/// traffic when it ended.
+fn finish_part(app: &AppHandle, url: &str, part: Part, usage: Usage, error: Option<String>) {
+    let session_id = part.session_id;
+    println!("[stream] Session {} ended (error: {:?})", session_id, error);
+    crash::breadcrumb(format!("stream {} ended (error: {:?})", session_id, error));
+    if error.is_some() {
+        telemetry::error(app, "stream");
+    }
+
+    let latency = LatencyStats::from_samples(&part.timing.latencies);
+    let usage = usage.since(part.usage_before);
+    bandwidth::record(app, usage);
+    if !part.segments.is_empty() {
+        let title = reqwest::Url::parse(url)
+            .ok()
+            .and_then(|parsed| parsed.host_str().map(str::to_string))
+            .unwrap_or_else(|| url.to_string());
+        let mut session = Session::new(title, format!("stream:{}", url), part.segments);
+        session.id = session_id.clone();
+        session.latency = latency.clone();
+        session.bandwidth = Some(usage);
+        session.continues = part.continues;
+        match history::save(app, &session) {
+            Ok(()) => {
+                webhook::session_completed(app, &session);
+                sync::session_completed(app, &session);
+                telemetry::session_completed(app, &session);
+            }
+            Err(e) => {
+                println!("[stream] {}", e);
+                let _ = schema::emit(
+                    app,
+                    "session:save_failed",
+                    SaveFailed {
+                        session_id: session_id.clone(),
+                        error: e,
+                    },
+                );
+            }
+        }
+    }
+    let _ = schema::emit(
+        app,
+        "session:ended",
+        SessionEnded {
+            session_id,
+            error,
+            latency,
+        },
+    );
+}
+
+/// Handle a Deepgram message; see [`deliver`].
+fn handle_message(
+    app: &AppHandle,
+    session_id: &str,
+    text: &str,
+    commands: &CommandSettings,
+    throttle: &mut InterimThrottle,
+    timing: &mut Timing,
+) -> Option<Segment> {
+    let response: LiveResponse = match serde_json::from_str(text) {
+        Ok(response) => response,
+        Err(e) => {
+            println!("[stream] Ignoring unparseable message: {}", e);
+            return None;
+        }
+    };
+    if response.kind != "Results" {
+        return None;
+    }
+    // Even an empty final closes the utterance; the next audio sent opens another
+    if response.is_final {
+        timing.utterance_started = None;
+    }
+
+    let alternative = response
+        .channel
+        .and_then(|channel| channel.alternatives.into_iter().next())?;
+    if alternative.transcript.is_empty() {
+        return None;
+    }
+    deliver(
+        app,
+        session_id,
+        Heard {
+            transcript: alternative.transcript,
+            is_final: response.is_final,
+            confidence: alternative.confidence,
+            start: response.start + timing.offset,
+            duration: response.duration,
+        },
+        commands,
+        throttle,
+        timing,
+    )
+}
+
+/// A result from the engine transcribing a session, timed from the start of the session.
+struct Heard {
+    transcript: String,
+    is_final: bool,
+    confidence: f64,
+    start: f64,
+    duration: f64,
+}
+
+/// Emit a result as a `transcript:update` event. Returns the segment for final results
+/// so the session can be stored. In command mode, finals are dispatched as commands
+/// instead and nothing is dictated or stored.
+fn deliver(
+    app: &AppHandle,
+    session_id: &str,
+    heard: Heard,
+    commands: &CommandSettings,
+    throttle: &mut InterimThrottle,
+    timing: &mut Timing,
+) -> Option<Segment> {
+    if commands.enabled {
+        if heard.is_final {
+            commands::dispatch(app, &commands.grammar, Some(session_id), &heard.transcript);
+        }
+        return None;
+    }
+    let transcript = if heard.is_final {
+        // Voice commands such as "spelling mode" aren't transcribed
+        dictation::finalize(app, &heard.transcript, None)?
+    } else if spelling::is_enabled(app) {
+        spelling::spell(&heard.transcript)
+    } else {
+        heard.transcript.clone()
+    };
+
+    let segment = heard.is_final.then(|| Segment {
+        start: heard.start,
+        end: heard.start + heard.duration,
+        text: transcript.clone(),
+        speaker: None,
+        confidence: heard.confidence,
+    });
+    // A negative delay means the source is faster than real time, e.g. a file served
+    // over HTTP, where latency can't be told this way
+    let latency_ms = timing
+        .first_sent
+        .map(|sent| sent.elapsed().as_secs_f64() * 1000.0 - (heard.start + heard.duration) * 1000.0)
+        .filter(|latency| *latency >= 0.0);
+    timing.latencies.extend(latency_ms);
+    if !throttle.allow(&transcript, heard.is_final) {
+        return segment;
+    }
+
+    let caption = Caption {
+        text: transcript.clone(),
+        is_final: heard.is_final,
+        session_id: Some(session_id.to_string()),
+    };
+    caption_server::publish(app, &caption);
+    overlay::update(app, &caption);
+    let _ = schema::emit(
+        app,
+        "transcript:update",
+        TranscriptEvent {
+            session_id: session_id.to_string(),
+            transcript,
+            is_final: heard.is_final,
+            confidence: heard.confidence,
+            start: heard.start,
+            duration: heard.duration,
+            latency_ms,
+        },
+    );
+    segment
+}
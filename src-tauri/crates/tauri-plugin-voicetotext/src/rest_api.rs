@@ -0,0 +1,364 @@
+//! Optional localhost REST API for editors, note apps and scripts.
+//!
+//! Every request must carry `Authorization: Bearer <token>`; a random token is generated
+//! and saved to settings the first time the API starts without one.
+//!
+//! - `GET /sessions`, `GET /sessions/{id}`, `DELETE /sessions/{id}`
+//! - `GET /search?q=<text>`
+//! - `POST /streams` `{"url":..., "api_key"?:..., "interim_results"?:...}` → `{"session_id":...}`, `DELETE /streams/{id}`
+//! - `POST /transcriptions` `{"url":..., "api_key"?:...}` → stored session
+
+use crate::scheduler::Priority;
+use crate::{app_lock, encryption, history, prerecorded, settings, stream};
+use axum::extract::{Path, Query, Request, State as AxumState};
+use axum::http::StatusCode;
+use axum::middleware::{self, Next};
+use axum::response::{IntoResponse, Response};
+use axum::routing::{delete, get, post};
+use axum::{Json, Router};
+use serde::{Deserialize, Serialize};
+use specta::Type;
+use std::sync::Mutex;
+use tauri::{AppHandle, Manager, State};
+use tokio::net::TcpListener;
+use tokio::sync::oneshot;
+
+type ApiResult<T> = Result<Json<T>, ApiError>;
+
+struct ApiError(StatusCode, String);
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        (self.0, Json(serde_json::json!({ "error": self.1 }))).into_response()
+    }
+}
+
+/// For requests refused while the app lock is engaged.
+fn forbidden(message: String) -> ApiError {
+    ApiError(StatusCode::FORBIDDEN, message)
+}
+
+impl From<String> for ApiError {
+    fn from(message: String) -> Self {
+        ApiError(StatusCode::BAD_REQUEST, message)
+    }
+}
+
+#[derive(Clone)]
+struct ApiState {
+    app: AppHandle,
+    token: String,
+}
+
+#[derive(Deserialize)]
+struct SearchQuery {
+    q: String,
+}
+
+#[derive(Deserialize)]
+struct SourceRequest {
+    url: String,
+    api_key: Option<String>,
+    /// Streams only; defaults to on.
+    interim_results: Option<bool>,
+}
+
+#[derive(Serialize)]
+struct StreamStarted {
+    session_id: String,
+}
+
+#[derive(Serialize, Clone, Debug, Type)]
+pub struct RestApiStatus {
+    pub running: bool,
+    pub port: Option<u16>,
+    pub token: Option<String>,
+}
+
+struct Running {
+    port: u16,
+    token: String,
+    stop: oneshot::Sender<()>,
+}
+
+#[derive(Default)]
+pub struct RestApi {
+    running: Mutex<Option<Running>>,
+}
+
+fn authorized(authorization: Option<&str>, token: &str) -> bool {
+    authorization
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .is_some_and(|given| encryption::constant_time_eq(given.as_bytes(), token.as_bytes()))
+}
+
+async fn require_token(
+    AxumState(state): AxumState<ApiState>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let authorization = request
+        .headers()
+        .get("authorization")
+        .and_then(|value| value.to_str().ok());
+    if authorized(authorization, &state.token) {
+        next.run(request).await
+    } else {
+        ApiError(
+            StatusCode::UNAUTHORIZED,
+            "Missing or invalid token".to_string(),
+        )
+        .into_response()
+    }
+}
+
+fn api_key(app: &AppHandle, api_key: Option<String>) -> Result<String, String> {
+    match api_key {
+        Some(key) if !key.is_empty() => Ok(key),
+        _ => settings::api_key(app),
+    }
+}
+
+async fn list_sessions(
+    AxumState(state): AxumState<ApiState>,
+) -> ApiResult<Vec<history::SessionSummary>> {
+    app_lock::ensure_unlocked(&state.app).map_err(forbidden)?;
+    let sessions = history::load_all(&state.app)?;
+    Ok(Json(
+        sessions.iter().map(history::Session::summary).collect(),
+    ))
+}
+
+async fn get_session(
+    AxumState(state): AxumState<ApiState>,
+    Path(id): Path<String>,
+) -> ApiResult<history::Session> {
+    app_lock::ensure_unlocked(&state.app).map_err(forbidden)?;
+    history::load(&state.app, &id)
+        .map(Json)
+        .map_err(|e| ApiError(StatusCode::NOT_FOUND, e))
+}
+
+async fn delete_session(
+    AxumState(state): AxumState<ApiState>,
+    Path(id): Path<String>,
+) -> Result<StatusCode, ApiError> {
+    app_lock::ensure_unlocked(&state.app).map_err(forbidden)?;
+    history::delete(&state.app, &id).map_err(|e| ApiError(StatusCode::NOT_FOUND, e))?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+async fn search(
+    AxumState(state): AxumState<ApiState>,
+    Query(query): Query<SearchQuery>,
+) -> ApiResult<Vec<history::SearchMatch>> {
+    app_lock::ensure_unlocked(&state.app).map_err(forbidden)?;
+    Ok(Json(history::search(&state.app, &query.q)?))
+}
+
+async fn start_stream(
+    AxumState(state): AxumState<ApiState>,
+    Json(body): Json<SourceRequest>,
+) -> ApiResult<StreamStarted> {
+    app_lock::ensure_unlocked(&state.app).map_err(forbidden)?;
+    let api_key = api_key(&state.app, body.api_key)?;
+    let session_id = stream::start(
+        &state.app,
+        body.url,
+        &api_key,
+        body.interim_results.unwrap_or(true),
+        Priority::Interactive,
+    )
+    .await?;
+    Ok(Json(StreamStarted { session_id }))
+}
+
+async fn stop_stream(
+    AxumState(state): AxumState<ApiState>,
+    Path(id): Path<String>,
+) -> Result<StatusCode, ApiError> {
+    stream::stop(&state.app.state::<stream::StreamSessions>(), &id)
+        .map_err(|e| ApiError(StatusCode::NOT_FOUND, e))?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+async fn transcribe(
+    AxumState(state): AxumState<ApiState>,
+    Json(body): Json<SourceRequest>,
+) -> ApiResult<history::Session> {
+    app_lock::ensure_unlocked(&state.app).map_err(forbidden)?;
+    let api_key = api_key(&state.app, body.api_key)?;
+    let session = prerecorded::transcribe_url(state.app.clone(), body.url, api_key)
+        .await
+        .map_err(|e| ApiError(StatusCode::BAD_GATEWAY, e))?;
+    Ok(Json(session))
+}
+
+/// Start the API on the configured port, generating a token if none is configured.
+pub async fn start(app: &AppHandle) -> Result<RestApiStatus, String> {
+    let mut current = settings::load(app);
+    if app.state::<RestApi>().running.lock().unwrap().is_some() {
+        return Err("REST API is already running".to_string());
+    }
+    let token = match current.rest_api.token.clone().filter(|t| !t.is_empty()) {
+        Some(token) => token,
+        None => {
+            let token = uuid::Uuid::new_v4().simple().to_string();
+            current.rest_api.token = Some(token.clone());
+            settings::save(app, &current)?;
+            token
+        }
+    };
+
+    let listener = TcpListener::bind(("127.0.0.1", current.rest_api.port))
+        .await
+        .map_err(|e| {
+            format!(
+                "Failed to bind REST API to port {}: {}",
+                current.rest_api.port, e
+            )
+        })?;
+    let port = listener
+        .local_addr()
+        .map_err(|e| format!("Failed to read REST API address: {}", e))?
+        .port();
+
+    let state = ApiState {
+        app: app.clone(),
+        token: token.clone(),
+    };
+    let router = Router::new()
+        .route("/sessions", get(list_sessions))
+        .route("/sessions/{id}", get(get_session).delete(delete_session))
+        .route("/search", get(search))
+        .route("/streams", post(start_stream))
+        .route("/streams/{id}", delete(stop_stream))
+        .route("/transcriptions", post(transcribe))
+        .layer(middleware::from_fn_with_state(state.clone(), require_token))
+        .with_state(state);
+
+    let (stop_tx, stop_rx) = oneshot::channel::<()>();
+    *app.state::<RestApi>().running.lock().unwrap() = Some(Running {
+        port,
+        token: token.clone(),
+        stop: stop_tx,
+    });
+
+    println!("[rest_api] Listening on http://127.0.0.1:{}", port);
+    tauri::async_runtime::spawn(async move {
+        let shutdown = async {
+            let _ = stop_rx.await;
+        };
+        if let Err(e) = axum::serve(listener, router)
+            .with_graceful_shutdown(shutdown)
+            .await
+        {
+            println!("[rest_api] Server error: {}", e);
+        }
+        println!("[rest_api] Stopped");
+    });
+
+    Ok(RestApiStatus {
+        running: true,
+        port: Some(port),
+        token: Some(token),
+    })
+}
+
+/// Stop the API if it is running. Returns whether it was.
+pub fn stop(api: &RestApi) -> bool {
+    match api.running.lock().unwrap().take() {
+        Some(running) => {
+            let _ = running.stop.send(());
+            true
+        }
+        None => false,
+    }
+}
+
+fn status(api: &RestApi) -> RestApiStatus {
+    match api.running.lock().unwrap().as_ref() {
+        Some(running) => RestApiStatus {
+            running: true,
+            port: Some(running.port),
+            token: Some(running.token.clone()),
+        },
+        None => RestApiStatus {
+            running: false,
+            port: None,
+            token: None,
+        },
+    }
+}
+
+#[tauri::command]
+#[specta::specta]
+pub async fn start_rest_api(app: AppHandle) -> Result<RestApiStatus, String> {
+    start(&app).await
+}
+
+#[tauri::command]
+#[specta::specta]
+pub fn stop_rest_api(api: State<'_, RestApi>) -> RestApiStatus {
+    stop(&api);
+    status(&api)
+}
+
+#[tauri::command]
+#[specta::specta]
+pub fn get_rest_api_status(api: State<'_, RestApi>) -> RestApiStatus {
+    status(&api)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn accepts_only_the_exact_bearer_token() {
+        let cases = [
+            (Some("Bearer 0123abcd"), true),
+            (Some("Bearer 0123abce"), false),
+            (Some("Bearer 0123abc"), false),
+            (Some("Bearer 0123abcd0"), false),
+            (Some("Bearer "), false),
+            (Some("bearer 0123abcd"), false),
+            (Some("Basic 0123abcd"), false),
+            (Some("0123abcd"), false),
+            (None, false),
+        ];
+        for (authorization, expected) in cases {
+            assert_eq!(
+                authorized(authorization, "0123abcd"),
+                expected,
+                "{:?}",
+                authorization
+            );
+        }
+    }
+
+    #[test]
+    fn answers_401_without_a_token_and_403_while_locked() {
+        let unauthorized = ApiError(StatusCode::UNAUTHORIZED, "Missing or invalid token".into());
+        assert_eq!(
+            unauthorized.into_response().status(),
+            StatusCode::UNAUTHORIZED
+        );
+
+        let locked = app_lock::AppLockStatus {
+            enabled: true,
+            unlocked: false,
+        };
+        let refused = app_lock::refuse_when_locked(&locked).map_err(forbidden);
+        assert_eq!(
+            refused.err().unwrap().into_response().status(),
+            StatusCode::FORBIDDEN
+        );
+
+        let unlocked = app_lock::AppLockStatus {
+            enabled: true,
+            unlocked: true,
+        };
+        assert!(app_lock::refuse_when_locked(&unlocked).is_ok());
+    }
+}
@@ -0,0 +1,26 @@
+//! Transcripts once they exist: history, editing and formatting, dictation commands,
+//! injection into other apps, export and publishing, captions and readback.
+
+pub mod capitalization;
+pub mod caption_server;
+pub mod chapters;
+pub mod clipboard;
+pub mod commands;
+pub mod dates;
+pub mod dictation;
+pub mod edit;
+pub mod emoji;
+pub mod export;
+pub mod history;
+pub mod import;
+pub mod inject;
+pub mod numbers;
+pub mod overlay;
+pub mod profiles;
+pub mod publish;
+pub mod recent;
+pub mod snippets;
+pub mod spelling;
+pub mod templates;
+pub mod tts;
+pub mod versions;
@@ -0,0 +1,113 @@
+//! Capitalization rules applied to final results.
+//!
+//! `capitalization.mode` decides the base behavior: leave the provider's casing, sentence
+//! case, or all lowercase (for code comments). Words from `proper_nouns` are then written
+//! the way they are listed ("GitHub", "New York"), and an utterance starting with "all
+//! caps" is written in capitals.
+
+use crate::settings::{CapitalizationMode, CapitalizationSettings};
+
+/// Split a token into leading punctuation, the word itself and trailing punctuation.
+fn split_core(token: &str) -> (&str, &str, &str) {
+    let start = token
+        .char_indices()
+        .find(|(_, c)| c.is_alphanumeric())
+        .map_or(token.len(), |(i, _)| i);
+    let end = token
+        .char_indices()
+        .rev()
+        .find(|(_, c)| c.is_alphanumeric())
+        .map_or(start, |(i, c)| i + c.len_utf8());
+    (&token[..start], &token[start..end], &token[end..])
+}
+
+/// Words like "NASA", which keep their capitals in every mode.
+fn is_acronym(word: &str) -> bool {
+    let letters: Vec<char> = word.chars().filter(|c| c.is_alphabetic()).collect();
+    letters.len() > 1 && letters.iter().all(|c| c.is_uppercase())
+}
+
+fn capitalize_first(word: &str) -> String {
+    let mut chars = word.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+fn ends_sentence(token: &str) -> bool {
+    token
+        .trim_end_matches(['"', '\'', ')', ']', '”', '’'])
+        .ends_with(['.', '!', '?'])
+}
+
+fn is_pronoun_i(word: &str) -> bool {
+    matches!(
+        word.to_lowercase().replace('’', "'").as_str(),
+        "i" | "i'm" | "i've" | "i'll" | "i'd"
+    )
+}
+
+fn sentence_case(tokens: &mut [String], lowercase_only: bool) {
+    let mut sentence_start = true;
+    for token in tokens.iter_mut() {
+        let (prefix, core, suffix) = split_core(token);
+        let mut word = if is_acronym(core) {
+            core.to_string()
+        } else {
+            core.to_lowercase()
+        };
+        if !lowercase_only && (sentence_start || is_pronoun_i(&word)) {
+            word = capitalize_first(&word);
+        }
+        if !core.is_empty() {
+            sentence_start = false;
+        }
+        if ends_sentence(token) {
+            sentence_start = true;
+        }
+        *token = format!("{}{}{}", prefix, word, suffix);
+    }
+}
+
+fn proper_nouns(tokens: &mut [String], nouns: &[String]) {
+    for noun in nouns {
+        let noun_words: Vec<&str> = noun.split_whitespace().collect();
+        if noun_words.is_empty() || noun_words.len() > tokens.len() {
+            continue;
+        }
+        for start in 0..=tokens.len() - noun_words.len() {
+            let window = &tokens[start..start + noun_words.len()];
+            let matches = window.iter().zip(&noun_words).all(|(token, noun_word)| {
+                split_core(token).1.to_lowercase() == split_core(noun_word).1.to_lowercase()
+            });
+            if !matches {
+                continue;
+            }
+            for (token, noun_word) in tokens[start..].iter_mut().zip(&noun_words) {
+                let (prefix, _, suffix) = split_core(token);
+                *token = format!("{}{}{}", prefix, split_core(noun_word).1, suffix);
+            }
+        }
+    }
+}
+
+pub fn apply(text: &str, config: &CapitalizationSettings, mode: CapitalizationMode) -> String {
+    let mut tokens: Vec<String> = text.split_whitespace().map(str::to_string).collect();
+
+    // "all caps ..." writes the rest of the utterance in capitals
+    let command = tokens.len() > 2
+        && split_core(&tokens[0]).1.eq_ignore_ascii_case("all")
+        && split_core(&tokens[1]).1.eq_ignore_ascii_case("caps");
+    if command {
+        return tokens[2..].join(" ").to_uppercase();
+    }
+
+    match mode {
+        CapitalizationMode::Provider => {}
+        CapitalizationMode::Sentence => sentence_case(&mut tokens, false),
+        CapitalizationMode::Lower => sentence_case(&mut tokens, true),
+    }
+    proper_nouns(&mut tokens, &config.proper_nouns);
+    tokens.join(" ")
+}
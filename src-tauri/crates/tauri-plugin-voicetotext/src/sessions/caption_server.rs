@@ -0,0 +1,417 @@
+//! Optional localhost caption server for OBS browser sources, overlays and other tools.
+//!
+//! A single port serves both a ready-made overlay page (`GET /`), the latest caption as
+//! JSON (`GET /latest`), and a WebSocket feed of every caption update. Clients must pass
+//! the token as `?token=` or `Authorization: Bearer <token>`; browsers are also held to
+//! pages served from this machine, so other websites can't read captions.
+
+use crate::{overlay, settings};
+use futures_util::{SinkExt, StreamExt};
+use serde::{Deserialize, Serialize};
+use specta::Type;
+use std::sync::Mutex;
+use tauri::{AppHandle, Manager, State};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::{broadcast, watch};
+use tokio_tungstenite::tungstenite::handshake::server::{ErrorResponse, Request, Response};
+use tokio_tungstenite::tungstenite::http::StatusCode;
+use tokio_tungstenite::tungstenite::Message;
+
+const OVERLAY_HTML: &str = r#"<!doctype html>
+<html>
+<head>
+<meta charset="utf-8">
+<style>
+  html, body { margin: 0; background: transparent; }
+  #caption { position: fixed; bottom: 5%; width: 100%; text-align: center;
+    font: 600 42px/1.3 sans-serif; color: #fff; text-shadow: 0 0 6px #000, 0 0 2px #000; }
+  .interim { opacity: 0.7; }
+</style>
+</head>
+<body>
+<div id="caption"></div>
+<script>
+  const el = document.getElementById('caption');
+  function connect() {
+    const ws = new WebSocket(`ws://${location.host}/${location.search}`);
+    ws.onmessage = (msg) => {
+      const caption = JSON.parse(msg.data);
+      el.textContent = caption.text;
+      el.className = caption.is_final ? '' : 'interim';
+    };
+    ws.onclose = () => setTimeout(connect, 1000);
+  }
+  connect();
+</script>
+</body>
+</html>
+"#;
+
+#[derive(Serialize, Deserialize, Clone, Debug, Type)]
+pub struct Caption {
+    pub text: String,
+    pub is_final: bool,
+    pub session_id: Option<String>,
+}
+
+#[derive(Serialize, Clone, Debug, Type)]
+pub struct CaptionServerStatus {
+    pub running: bool,
+    pub port: Option<u16>,
+    pub token: Option<String>,
+    pub clients: usize,
+}
+
+struct Running {
+    port: u16,
+    token: String,
+    /// Stops the accept loop and closes every connected client.
+    stop: watch::Sender<bool>,
+}
+
+pub struct CaptionServer {
+    captions: broadcast::Sender<String>,
+    latest: Mutex<Option<String>>,
+    running: Mutex<Option<Running>>,
+}
+
+impl Default for CaptionServer {
+    fn default() -> Self {
+        CaptionServer {
+            captions: broadcast::channel(64).0,
+            latest: Mutex::new(None),
+            running: Mutex::new(None),
+        }
+    }
+}
+
+/// Broadcast a caption to connected clients. Cheap no-op when nobody is listening.
+pub fn publish(app: &AppHandle, caption: &Caption) {
+    let server = app.state::<CaptionServer>();
+    if server.running.lock().unwrap().is_none() {
+        return;
+    }
+    let Ok(json) = serde_json::to_string(caption) else {
+        return;
+    };
+    *server.latest.lock().unwrap() = Some(json.clone());
+    let _ = server.captions.send(json);
+}
+
+fn token_matches(expected: &str, query: Option<&str>, authorization: Option<&str>) -> bool {
+    let from_query = query
+        .unwrap_or("")
+        .split('&')
+        .filter_map(|pair| pair.strip_prefix("token="))
+        .any(|token| token == expected);
+    let from_header = authorization
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .is_some_and(|token| token == expected);
+    from_query || from_header
+}
+
+/// Tools other than browsers send no `Origin`; browsers must be on a page served from this
+/// machine, such as the overlay itself.
+fn origin_allowed(origin: Option<&str>) -> bool {
+    let Some(origin) = origin else {
+        return true;
+    };
+    reqwest::Url::parse(origin).is_ok_and(|url| {
+        matches!(url.scheme(), "http" | "https")
+            && matches!(url.host_str(), Some("127.0.0.1" | "localhost" | "[::1]"))
+    })
+}
+
+/// Start serving on the configured port. Fails if already running or the port is taken.
+pub async fn start(app: &AppHandle) -> Result<u16, String> {
+    let mut current = settings::load(app);
+    let server = app.state::<CaptionServer>();
+    if let Some(running) = server.running.lock().unwrap().as_ref() {
+        return Err(format!(
+            "Caption server already running on port {}",
+            running.port
+        ));
+    }
+    let token = match current
+        .caption_server
+        .token
+        .clone()
+        .filter(|t| !t.is_empty())
+    {
+        Some(token) => token,
+        None => {
+            let token = uuid::Uuid::new_v4().simple().to_string();
+            current.caption_server.token = Some(token.clone());
+            settings::save(app, &current)?;
+            token
+        }
+    };
+    let config = current.caption_server;
+
+    let listener = TcpListener::bind(("127.0.0.1", config.port))
+        .await
+        .map_err(|e| {
+            format!(
+                "Failed to bind caption server to port {}: {}",
+                config.port, e
+            )
+        })?;
+    let port = listener
+        .local_addr()
+        .map_err(|e| format!("Failed to read caption server address: {}", e))?
+        .port();
+
+    let (stop_tx, mut stop_rx) = watch::channel(false);
+    *server.running.lock().unwrap() = Some(Running {
+        port,
+        token: token.clone(),
+        stop: stop_tx,
+    });
+    println!("[caption_server] Listening on http://127.0.0.1:{}", port);
+
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        loop {
+            tokio::select! {
+                _ = stop_rx.changed() => break,
+                accepted = listener.accept() => match accepted {
+                    Ok((stream, _)) => {
+                        let app = app.clone();
+                        let token = token.clone();
+                        let stop = stop_rx.clone();
+                        tauri::async_runtime::spawn(async move {
+                            if let Err(e) = handle_connection(&app, stream, &token, stop).await {
+                                println!("[caption_server] {}", e);
+                            }
+                        });
+                    }
+                    Err(e) => println!("[caption_server] Accept failed: {}", e),
+                },
+            }
+        }
+        println!("[caption_server] Stopped");
+    });
+    Ok(port)
+}
+
+pub fn stop(server: &CaptionServer) -> bool {
+    match server.running.lock().unwrap().take() {
+        Some(running) => {
+            let _ = running.stop.send(true);
+            true
+        }
+        None => false,
+    }
+}
+
+async fn handle_connection(
+    app: &AppHandle,
+    stream: TcpStream,
+    token: &str,
+    stop: watch::Receiver<bool>,
+) -> Result<(), String> {
+    // Peek at the request head to tell WebSocket upgrades from plain HTTP
+    let mut head = [0u8; 2048];
+    let n = stream
+        .peek(&mut head)
+        .await
+        .map_err(|e| format!("Failed to read request: {}", e))?;
+    let head = String::from_utf8_lossy(&head[..n]).to_ascii_lowercase();
+
+    if head.contains("upgrade: websocket") {
+        serve_websocket(app, stream, token, stop).await
+    } else {
+        serve_http(app, stream, token).await
+    }
+}
+
+async fn serve_websocket(
+    app: &AppHandle,
+    stream: TcpStream,
+    token: &str,
+    mut stop: watch::Receiver<bool>,
+) -> Result<(), String> {
+    // The error type is fixed by tungstenite's handshake callback
+    #[allow(clippy::result_large_err)]
+    let check = |request: &Request, response: Response| -> Result<Response, ErrorResponse> {
+        let header = |name: &str| {
+            request
+                .headers()
+                .get(name)
+                .and_then(|value| value.to_str().ok())
+        };
+        let (status, reason) = if !origin_allowed(header("origin")) {
+            (StatusCode::FORBIDDEN, "Origin not allowed")
+        } else if !token_matches(token, request.uri().query(), header("authorization")) {
+            (StatusCode::UNAUTHORIZED, "Invalid caption server token")
+        } else {
+            return Ok(response);
+        };
+        let mut denied = ErrorResponse::new(Some(reason.to_string()));
+        *denied.status_mut() = status;
+        Err(denied)
+    };
+    let socket = tokio_tungstenite::accept_hdr_async(stream, check)
+        .await
+        .map_err(|e| format!("WebSocket handshake failed: {}", e))?;
+
+    let server = app.state::<CaptionServer>();
+    let mut captions = server.captions.subscribe();
+    let latest = server.latest.lock().unwrap().clone();
+    let (mut sink, mut incoming) = socket.split();
+
+    if let Some(latest) = latest {
+        let _ = sink.send(Message::Text(latest)).await;
+    }
+    loop {
+        tokio::select! {
+            _ = stop.changed() => {
+                let _ = sink.send(Message::Close(None)).await;
+                break;
+            }
+            caption = captions.recv() => match caption {
+                Ok(json) => {
+                    if sink.send(Message::Text(json)).await.is_err() {
+                        break;
+                    }
+                }
+                // A slow client skipped some interim updates; keep going with the newest
+                Err(broadcast::error::RecvError::Lagged(_)) => continue,
+                Err(broadcast::error::RecvError::Closed) => break,
+            },
+            message = incoming.next() => match message {
+                Some(Ok(Message::Close(_))) | None | Some(Err(_)) => break,
+                Some(Ok(_)) => {}
+            },
+        }
+    }
+    Ok(())
+}
+
+async fn serve_http(app: &AppHandle, mut stream: TcpStream, token: &str) -> Result<(), String> {
+    let mut buffer = vec![0u8; 4096];
+    let n = stream
+        .read(&mut buffer)
+        .await
+        .map_err(|e| format!("Failed to read request: {}", e))?;
+    let request = String::from_utf8_lossy(&buffer[..n]);
+
+    let target = request.split_whitespace().nth(1).unwrap_or("/");
+    let (path, query) = target.split_once('?').unwrap_or((target, ""));
+    let header = |wanted: &str| {
+        request.lines().skip(1).find_map(|line| {
+            let (name, value) = line.split_once(':')?;
+            name.eq_ignore_ascii_case(wanted).then(|| value.trim())
+        })
+    };
+
+    let (status, content_type, body) = if !origin_allowed(header("origin")) {
+        (
+            "403 Forbidden",
+            "text/plain",
+            "Origin not allowed".to_string(),
+        )
+    } else if !token_matches(token, Some(query), header("authorization")) {
+        (
+            "401 Unauthorized",
+            "text/plain",
+            "Invalid caption server token".to_string(),
+        )
+    } else if path == "/" {
+        (
+            "200 OK",
+            "text/html; charset=utf-8",
+            OVERLAY_HTML.to_string(),
+        )
+    } else if path == "/latest" {
+        let latest = app.state::<CaptionServer>().latest.lock().unwrap().clone();
+        (
+            "200 OK",
+            "application/json",
+            latest.unwrap_or_else(|| "null".to_string()),
+        )
+    } else {
+        ("404 Not Found", "text/plain", "Not found".to_string())
+    };
+
+    let response = format!(
+        "HTTP/1.1 {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nCache-Control: no-store\r\nConnection: close\r\n\r\n{}",
+        status,
+        content_type,
+        body.len(),
+        body
+    );
+    stream
+        .write_all(response.as_bytes())
+        .await
+        .map_err(|e| format!("Failed to write response: {}", e))
+}
+
+fn status(server: &CaptionServer) -> CaptionServerStatus {
+    let running = server.running.lock().unwrap();
+    let port = running.as_ref().map(|r| r.port);
+    CaptionServerStatus {
+        running: port.is_some(),
+        port,
+        token: running.as_ref().map(|r| r.token.clone()),
+        clients: if port.is_some() {
+            server.captions.receiver_count()
+        } else {
+            0
+        },
+    }
+}
+
+#[tauri::command]
+#[specta::specta]
+pub async fn start_caption_server(app: AppHandle) -> Result<CaptionServerStatus, String> {
+    start(&app).await?;
+    Ok(status(&app.state::<CaptionServer>()))
+}
+
+#[tauri::command]
+#[specta::specta]
+pub fn stop_caption_server(server: State<'_, CaptionServer>) -> CaptionServerStatus {
+    stop(&server);
+    status(&server)
+}
+
+#[tauri::command]
+#[specta::specta]
+pub fn get_caption_server_status(server: State<'_, CaptionServer>) -> CaptionServerStatus {
+    status(&server)
+}
+
+/// Lets the webview forward captions from its own (microphone) sessions to the caption
+/// server and the overlay window.
+#[tauri::command]
+#[specta::specta]
+pub fn publish_caption(app: AppHandle, caption: Caption) {
+    publish(&app, &caption);
+    overlay::update(&app, &caption);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn accepts_the_token_from_the_query_or_header() {
+        assert!(token_matches("abc", Some("x=1&token=abc"), None));
+        assert!(token_matches("abc", None, Some("Bearer abc")));
+        assert!(!token_matches("abc", Some("token=abd"), Some("Bearer ab")));
+        assert!(!token_matches("abc", None, None));
+    }
+
+    #[test]
+    fn only_allows_local_browser_origins() {
+        assert!(origin_allowed(None));
+        assert!(origin_allowed(Some("http://127.0.0.1:7890")));
+        assert!(origin_allowed(Some("http://localhost:3000")));
+        assert!(origin_allowed(Some("http://[::1]:7890")));
+        assert!(!origin_allowed(Some("https://example.com")));
+        assert!(!origin_allowed(Some("http://localhost.example.com")));
+        assert!(!origin_allowed(Some("null")));
+    }
+}
@@ -0,0 +1,133 @@
+//! Splitting long sessions into chapters, automatically or with manual markers.
+
+use crate::app_lock;
+use crate::history::{self, Chapter, Session};
+use serde::Deserialize;
+use specta::Type;
+use tauri::AppHandle;
+
+#[derive(Deserialize, Clone, Copy, Debug, Type)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum ChapterMode {
+    /// Start a chapter wherever nobody spoke for at least `min_gap_seconds`.
+    Silence { min_gap_seconds: f64 },
+    /// Start a chapter every `seconds`.
+    Interval { seconds: f64 },
+}
+
+/// Chapter start offsets for a session under the given mode (always including 0).
+fn boundaries(session: &Session, mode: ChapterMode) -> Result<Vec<f64>, String> {
+    let mut starts = vec![0.0];
+    match mode {
+        ChapterMode::Silence { min_gap_seconds } => {
+            if min_gap_seconds <= 0.0 {
+                return Err("Silence gap must be positive".to_string());
+            }
+            for pair in session.segments.windows(2) {
+                if pair[1].start - pair[0].end >= min_gap_seconds {
+                    starts.push(pair[1].start);
+                }
+            }
+        }
+        ChapterMode::Interval { seconds } => {
+            if seconds <= 0.0 {
+                return Err("Chapter interval must be positive".to_string());
+            }
+            let duration = session.duration();
+            let mut start = seconds;
+            while start < duration {
+                starts.push(start);
+                start += seconds;
+            }
+        }
+    }
+    Ok(starts)
+}
+
+/// Sort chapters and renumber the automatic ones.
+fn normalize(chapters: &mut [Chapter]) {
+    chapters.sort_by(|a, b| a.start.total_cmp(&b.start));
+    for (index, chapter) in chapters.iter_mut().enumerate() {
+        if !chapter.manual {
+            chapter.title = format!("Chapter {}", index + 1);
+        }
+    }
+}
+
+/// Replace the automatic chapters of a session; manual markers are kept.
+pub fn generate(session: &mut Session, mode: ChapterMode) -> Result<(), String> {
+    let starts = boundaries(session, mode)?;
+    session.chapters.retain(|chapter| chapter.manual);
+    for start in starts {
+        // A manual marker close to an automatic boundary wins
+        if session
+            .chapters
+            .iter()
+            .any(|chapter| (chapter.start - start).abs() < 1.0)
+        {
+            continue;
+        }
+        session.chapters.push(Chapter {
+            start,
+            title: String::new(),
+            manual: false,
+        });
+    }
+    normalize(&mut session.chapters);
+    Ok(())
+}
+
+#[tauri::command]
+#[specta::specta]
+pub fn generate_chapters(
+    app: AppHandle,
+    session_id: String,
+    mode: ChapterMode,
+) -> Result<Vec<Chapter>, String> {
+    app_lock::ensure_unlocked(&app)?;
+    let mut session = history::load(&app, &session_id)?;
+    generate(&mut session, mode)?;
+    history::save(&app, &session)?;
+    Ok(session.chapters)
+}
+
+#[tauri::command]
+#[specta::specta]
+pub fn add_chapter(
+    app: AppHandle,
+    session_id: String,
+    start: f64,
+    title: String,
+) -> Result<Vec<Chapter>, String> {
+    app_lock::ensure_unlocked(&app)?;
+    let mut session = history::load(&app, &session_id)?;
+    if start < 0.0 {
+        return Err("Chapter start must not be negative".to_string());
+    }
+    session.chapters.push(Chapter {
+        start,
+        title,
+        manual: true,
+    });
+    normalize(&mut session.chapters);
+    history::save(&app, &session)?;
+    Ok(session.chapters)
+}
+
+#[tauri::command]
+#[specta::specta]
+pub fn remove_chapter(
+    app: AppHandle,
+    session_id: String,
+    index: usize,
+) -> Result<Vec<Chapter>, String> {
+    app_lock::ensure_unlocked(&app)?;
+    let mut session = history::load(&app, &session_id)?;
+    if index >= session.chapters.len() {
+        return Err(format!("No chapter at index {}", index));
+    }
+    session.chapters.remove(index);
+    normalize(&mut session.chapters);
+    history::save(&app, &session)?;
+    Ok(session.chapters)
+}
@@ -0,0 +1,180 @@
+//! Command-and-control mode: recognized speech matched against a user grammar.
+//!
+//! Each rule pairs an intent with phrases such as `"(open|show) [the] history"` or
+//! `"switch to {language}"`. Parentheses list alternatives, square brackets mark optional
+//! words and braces capture one or more words into a named slot. While
+//! `commands.enabled` is set, final results of backend streams are matched here and
+//! emitted as `command:recognized` instead of being dictated; the frontend passes its
+//! own results through `recognize_command`.
+
+use crate::schema;
+use crate::settings::{self, CommandRule};
+use serde::Serialize;
+use specta::Type;
+use std::collections::BTreeMap;
+use tauri::AppHandle;
+
+enum Token {
+    /// One of the listed words; `optional` also matches nothing.
+    Word {
+        choices: Vec<String>,
+        optional: bool,
+    },
+    Slot(String),
+}
+
+/// Payload of the `command:recognized` event.
+#[derive(Serialize, Clone, Debug, Type)]
+pub struct RecognizedCommand {
+    pub intent: String,
+    /// Slot values by name, as spoken.
+    pub slots: BTreeMap<String, String>,
+    /// The recognized text the command was matched in.
+    pub text: String,
+    /// Backend stream the text came from; `None` for text passed in by the frontend.
+    pub session_id: Option<String>,
+}
+
+/// Lowercase words without punctuation, which recognizers add freely.
+pub fn words(text: &str) -> Vec<String> {
+    text.split_whitespace()
+        .map(|word| {
+            word.chars()
+                .filter(|c| c.is_alphanumeric() || *c == '\'')
+                .collect::<String>()
+                .to_lowercase()
+        })
+        .filter(|word| !word.is_empty())
+        .collect()
+}
+
+fn parse(phrase: &str) -> Result<Vec<Token>, String> {
+    let mut tokens = Vec::new();
+    for part in phrase.split_whitespace() {
+        let (inner, optional) = match part.strip_prefix('[') {
+            Some(rest) => (
+                rest.strip_suffix(']')
+                    .ok_or_else(|| format!("Unclosed [ in \"{}\"", phrase))?,
+                true,
+            ),
+            None => (part, false),
+        };
+        if let Some(name) = inner.strip_prefix('{') {
+            let name = name
+                .strip_suffix('}')
+                .filter(|name| !name.is_empty())
+                .ok_or_else(|| format!("Malformed slot in \"{}\"", phrase))?;
+            if optional {
+                return Err(format!("Slot {{{}}} can't be optional", name));
+            }
+            tokens.push(Token::Slot(name.to_string()));
+            continue;
+        }
+        let inner = match inner.strip_prefix('(') {
+            Some(rest) => rest
+                .strip_suffix(')')
+                .ok_or_else(|| format!("Unclosed ( in \"{}\"", phrase))?,
+            None => inner,
+        };
+        let choices: Vec<String> = inner.split('|').flat_map(words).collect();
+        if choices.is_empty() {
+            return Err(format!("Empty word in \"{}\"", phrase));
+        }
+        tokens.push(Token::Word { choices, optional });
+    }
+    if tokens.is_empty() {
+        return Err("Command phrase is empty".to_string());
+    }
+    Ok(tokens)
+}
+
+/// Match all of `spoken` against `tokens`, backtracking over optional words and slot
+/// lengths. Slots take as few words as the rest of the phrase allows.
+fn match_tokens(tokens: &[Token], spoken: &[String], slots: &mut BTreeMap<String, String>) -> bool {
+    let Some((token, rest)) = tokens.split_first() else {
+        return spoken.is_empty();
+    };
+    match token {
+        Token::Word { choices, optional } => {
+            (spoken.first().is_some_and(|word| choices.contains(word))
+                && match_tokens(rest, &spoken[1..], slots))
+                || (*optional && match_tokens(rest, spoken, slots))
+        }
+        Token::Slot(name) => (1..=spoken.len()).any(|len| {
+            if match_tokens(rest, &spoken[len..], slots) {
+                slots.insert(name.clone(), spoken[..len].join(" "));
+                true
+            } else {
+                false
+            }
+        }),
+    }
+}
+
+/// The first rule with a phrase matching `text` as a whole.
+fn recognize(grammar: &[CommandRule], text: &str) -> Option<(String, BTreeMap<String, String>)> {
+    let spoken = words(text);
+    for rule in grammar {
+        for phrase in &rule.phrases {
+            let Ok(tokens) = parse(phrase) else {
+                continue;
+            };
+            let mut slots = BTreeMap::new();
+            if match_tokens(&tokens, &spoken, &mut slots) {
+                return Some((rule.intent.clone(), slots));
+            }
+        }
+    }
+    None
+}
+
+/// Match `text` against `grammar` and emit the command it names, if any.
+pub fn dispatch(
+    app: &AppHandle,
+    grammar: &[CommandRule],
+    session_id: Option<&str>,
+    text: &str,
+) -> Option<RecognizedCommand> {
+    let Some((intent, slots)) = recognize(grammar, text) else {
+        println!("[commands] No command matches \"{}\"", text);
+        return None;
+    };
+    println!("[commands] Recognized {} {:?}", intent, slots);
+    let command = RecognizedCommand {
+        intent,
+        slots,
+        text: text.to_string(),
+        session_id: session_id.map(str::to_string),
+    };
+    let _ = schema::emit(app, "command:recognized", command.clone());
+    Some(command)
+}
+
+/// Match text recognized by the frontend against the grammar. Returns `None` when
+/// command mode is off or nothing matched; a match is also emitted as `command:recognized`.
+#[tauri::command]
+#[specta::specta]
+pub fn recognize_command(app: AppHandle, text: String) -> Option<RecognizedCommand> {
+    let config = settings::load(&app).commands;
+    if !config.enabled {
+        return None;
+    }
+    dispatch(&app, &config.grammar, None, &text)
+}
+
+/// Validate and save the command grammar.
+#[tauri::command]
+#[specta::specta]
+pub fn set_command_grammar(app: AppHandle, grammar: Vec<CommandRule>) -> Result<(), String> {
+    for rule in &grammar {
+        if rule.intent.trim().is_empty() {
+            return Err("Command intent is empty".to_string());
+        }
+        for phrase in &rule.phrases {
+            parse(phrase).map_err(|e| format!("Invalid phrase for {}: {}", rule.intent, e))?;
+        }
+    }
+    let mut current = settings::load(&app);
+    current.commands.grammar = grammar;
+    settings::save(&app, &current)
+}
@@ -0,0 +1,527 @@
+//! Writing spoken dates and times in the configured locale's format.
+//!
+//! "march third at four thirty pm" becomes "3/3/2026 4:30 PM" for `en-US`,
+//! "03.03.2026 16:30" for `de-DE` or "2026-03-03T16:30" with ISO 8601. Dates are a month
+//! name with a day ("march third", "the third of march"), optionally followed by a year
+//! ("twenty twenty six", "2026"); without one the current year is used. Times need "am",
+//! "pm" or "o'clock" unless they follow a date with "at", so ordinary numbers are left
+//! alone.
+
+use crate::numbers::{SMALL, TENS};
+use crate::settings::DateFormatting;
+use chrono::{Datelike, Local, NaiveDate};
+
+const MONTHS: &[&str] = &[
+    "january",
+    "february",
+    "march",
+    "april",
+    "may",
+    "june",
+    "july",
+    "august",
+    "september",
+    "october",
+    "november",
+    "december",
+];
+
+const ORDINALS: &[(&str, u32)] = &[
+    ("first", 1),
+    ("second", 2),
+    ("third", 3),
+    ("fourth", 4),
+    ("fifth", 5),
+    ("sixth", 6),
+    ("seventh", 7),
+    ("eighth", 8),
+    ("ninth", 9),
+    ("tenth", 10),
+    ("eleventh", 11),
+    ("twelfth", 12),
+    ("thirteenth", 13),
+    ("fourteenth", 14),
+    ("fifteenth", 15),
+    ("sixteenth", 16),
+    ("seventeenth", 17),
+    ("eighteenth", 18),
+    ("nineteenth", 19),
+    ("twentieth", 20),
+    ("thirtieth", 30),
+];
+
+/// A word of the input with the punctuation around it.
+struct Token<'a> {
+    prefix: &'a str,
+    /// Lowercase letters and digits only, so "a.m." is "am" and "o'clock" is "oclock".
+    word: String,
+    /// The word as written, for times already spoken as "4:30".
+    core: &'a str,
+    tail: &'a str,
+}
+
+#[derive(Clone, Copy)]
+enum Order {
+    MonthDayYear,
+    DayMonthYear,
+    YearMonthDay,
+}
+
+struct Locale {
+    order: Order,
+    separator: char,
+    /// Two-digit days and months ("03.03.2026").
+    padded: bool,
+    twelve_hour: bool,
+}
+
+struct Time {
+    hour: u32,
+    minute: u32,
+    /// Whether "am", "pm" or a part of the day was said, so the hour is known.
+    meridiem: bool,
+}
+
+fn locale(tag: &str) -> Locale {
+    let tag = tag.replace('_', "-").to_lowercase();
+    let language = tag.split('-').next().unwrap_or("en");
+    let (order, separator, padded, twelve_hour) = match language {
+        "en" if matches!(tag.as_str(), "en" | "en-us" | "en-ph") => {
+            (Order::MonthDayYear, '/', false, true)
+        }
+        "en" => (Order::DayMonthYear, '/', true, true),
+        "de" | "ru" | "pl" | "cs" | "fi" | "nb" | "no" | "da" | "tr" | "uk" => {
+            (Order::DayMonthYear, '.', true, false)
+        }
+        "ja" | "zh" | "ko" => (Order::YearMonthDay, '/', true, false),
+        "sv" | "lt" | "hu" => (Order::YearMonthDay, '-', true, false),
+        _ => (Order::DayMonthYear, '/', true, false),
+    };
+    Locale {
+        order,
+        separator,
+        padded,
+        twelve_hour,
+    }
+}
+
+fn is_number_word(word: &str) -> bool {
+    let word = word.to_lowercase();
+    small(&word).is_some()
+        || tens(&word).is_some()
+        || ORDINALS.iter().any(|(ordinal, _)| *ordinal == word)
+}
+
+fn tokenize(text: &str) -> Vec<Token<'_>> {
+    let mut tokens = Vec::new();
+    for raw in text.split_whitespace() {
+        let start = raw
+            .char_indices()
+            .find(|(_, c)| c.is_alphanumeric())
+            .map_or(raw.len(), |(i, _)| i);
+        let end = raw
+            .char_indices()
+            .rev()
+            .find(|(_, c)| c.is_alphanumeric())
+            .map_or(start, |(i, c)| i + c.len_utf8());
+        let (prefix, core, tail) = (&raw[..start], &raw[start..end], &raw[end..]);
+        // "twenty-first" is two words
+        let parts: Vec<&str> = core.split('-').collect();
+        if parts.len() > 1 && parts.iter().all(|part| is_number_word(part)) {
+            let last = parts.len() - 1;
+            for (i, part) in parts.into_iter().enumerate() {
+                tokens.push(Token {
+                    prefix: if i == 0 { prefix } else { "" },
+                    word: part.to_lowercase(),
+                    core: part,
+                    tail: if i == last { tail } else { "" },
+                });
+            }
+            continue;
+        }
+        tokens.push(Token {
+            prefix,
+            word: core
+                .chars()
+                .filter(|c| c.is_alphanumeric())
+                .collect::<String>()
+                .to_lowercase(),
+            core,
+            tail,
+        });
+    }
+    tokens
+}
+
+fn word<'a>(tokens: &'a [Token], index: usize) -> &'a str {
+    tokens.get(index).map_or("", |token| token.word.as_str())
+}
+
+fn small(word: &str) -> Option<u32> {
+    SMALL
+        .iter()
+        .position(|w| *w == word)
+        .map(|value| value as u32)
+}
+
+fn tens(word: &str) -> Option<u32> {
+    TENS.iter()
+        .find(|(w, _)| *w == word)
+        .map(|(_, value)| *value as u32)
+}
+
+/// A number from zero to ninety-nine spoken in words, and how many words it took.
+fn cardinal(tokens: &[Token]) -> Option<(u32, usize)> {
+    let first = word(tokens, 0);
+    if let Some(value) = small(first) {
+        return Some((value, 1));
+    }
+    let value = tens(first)?;
+    match small(word(tokens, 1)) {
+        Some(unit) if (1..10).contains(&unit) => Some((value + unit, 2)),
+        _ => Some((value, 1)),
+    }
+}
+
+/// A day of the month, and whether it was clearly a date ("third", "3rd", "3") rather
+/// than a number word that could mean anything ("three").
+fn day(tokens: &[Token]) -> Option<(u32, usize, bool)> {
+    let skip = usize::from(word(tokens, 0) == "the");
+    let tokens = &tokens[skip.min(tokens.len())..];
+    let first = word(tokens, 0);
+    let day = |(value, len, clear): (u32, usize, bool)| {
+        (1..=31)
+            .contains(&value)
+            .then_some((value, len + skip, clear))
+    };
+
+    let digits = first.trim_end_matches(['s', 't', 'n', 'd', 'r', 'h']);
+    if !digits.is_empty() && digits.len() <= 2 && digits.chars().all(|c| c.is_ascii_digit()) {
+        let suffix = &first[digits.len()..];
+        if matches!(suffix, "" | "st" | "nd" | "rd" | "th") {
+            return day((digits.parse().ok()?, 1, true));
+        }
+        return None;
+    }
+    if let Some((_, value)) = ORDINALS.iter().find(|(w, _)| *w == first) {
+        return day((*value, 1, true));
+    }
+    if let Some(value) = tens(first) {
+        if let Some((_, unit)) = ORDINALS.iter().find(|(w, _)| *w == word(tokens, 1)) {
+            if *unit < 10 {
+                return day((value + unit, 2, true));
+            }
+        }
+    }
+    cardinal(tokens).and_then(|(value, len)| day((value, len, false)))
+}
+
+/// A year such as "2026", "twenty twenty six", "nineteen oh five" or "two thousand ten".
+fn year(tokens: &[Token]) -> Option<(i32, usize)> {
+    let first = word(tokens, 0);
+    if first.len() == 4 && first.chars().all(|c| c.is_ascii_digit()) {
+        let year: i32 = first.parse().ok()?;
+        return (1000..3000).contains(&year).then_some((year, 1));
+    }
+    if first == "two" && word(tokens, 1) == "thousand" {
+        let rest = &tokens[2..];
+        let skip = usize::from(word(rest, 0) == "and");
+        return match cardinal(&rest[skip.min(rest.len())..]) {
+            Some((value, len)) => Some((2000 + value as i32, 2 + skip + len)),
+            None if skip == 0 => Some((2000, 2)),
+            None => None,
+        };
+    }
+    let century = match cardinal(tokens)? {
+        (value, 1) if (11..=20).contains(&value) => value as i32,
+        _ => return None,
+    };
+    let rest = &tokens[1..];
+    match word(rest, 0) {
+        "hundred" => Some((century * 100, 2)),
+        "oh" | "o" => {
+            let unit = small(word(rest, 1)).filter(|unit| (1..10).contains(unit))?;
+            Some((century * 100 + unit as i32, 3))
+        }
+        _ => match cardinal(rest)? {
+            (value, len) if value >= 10 => Some((century * 100 + value as i32, 1 + len)),
+            _ => None,
+        },
+    }
+}
+
+/// A date at the start of `tokens`: "march third [2026]" or "the third of march [2026]".
+fn date(tokens: &[Token]) -> Option<(NaiveDate, usize)> {
+    let (month, (day, clear), consumed) =
+        if let Some(month) = MONTHS.iter().position(|m| *m == word(tokens, 0)) {
+            let (day, len, clear) = day(&tokens[1..])?;
+            (month, (day, clear), 1 + len)
+        } else {
+            let (day, len, clear) = day(tokens)?;
+            if word(tokens, len) != "of" {
+                return None;
+            }
+            let month = MONTHS.iter().position(|m| *m == word(tokens, len + 1))?;
+            (month, (day, clear), len + 2)
+        };
+    let year = year(&tokens[consumed..]);
+    // "march four" and "may second" are only dates with a year after them
+    let clear = clear && !(month == 4 && word(tokens, 1) == "second");
+    if !clear && year.is_none() {
+        return None;
+    }
+    let (year, len) = year.unwrap_or_else(|| (Local::now().year(), 0));
+    let date = NaiveDate::from_ymd_opt(year, month as u32 + 1, day)?;
+    Some((date, consumed + len))
+}
+
+/// A time at the start of `tokens`. Without "am", "pm" or "o'clock" it is only accepted
+/// when `expected`, i.e. after a date and "at".
+fn time(tokens: &[Token], expected: bool) -> Option<(Time, usize)> {
+    let first = tokens.first()?;
+    match first.word.as_str() {
+        "noon" if expected => {
+            let time = Time {
+                hour: 12,
+                minute: 0,
+                meridiem: true,
+            };
+            return Some((time, 1));
+        }
+        "midnight" if expected => {
+            let time = Time {
+                hour: 0,
+                minute: 0,
+                meridiem: true,
+            };
+            return Some((time, 1));
+        }
+        _ => {}
+    }
+
+    let (mut hour, mut minute, mut len, written) = match first.core.split_once(':') {
+        // Already written as "4:30"
+        Some((hour, minute)) if minute.len() == 2 => (
+            hour.parse::<u32>().ok()?,
+            minute.parse::<u32>().ok()?,
+            1,
+            true,
+        ),
+        Some(_) => return None,
+        None if !first.word.is_empty() && first.word.chars().all(|c| c.is_ascii_digit()) => {
+            (first.word.parse::<u32>().ok()?, 0, 1, false)
+        }
+        None => {
+            let (hour, len) = cardinal(tokens)?;
+            (hour, 0, len, false)
+        }
+    };
+    if !written {
+        if (1..=12).contains(&hour) {
+            let rest = &tokens[len..];
+            match word(rest, 0) {
+                "oh" | "o" => {
+                    if let Some(unit) = small(word(rest, 1)).filter(|unit| (1..10).contains(unit)) {
+                        minute = unit;
+                        len += 2;
+                    }
+                }
+                other if other.len() == 2 && other.chars().all(|c| c.is_ascii_digit()) => {
+                    minute = other.parse().ok()?;
+                    len += 1;
+                }
+                _ => {
+                    if let Some((value, words)) = cardinal(rest).filter(|(value, _)| *value >= 10) {
+                        minute = value;
+                        len += words;
+                    }
+                }
+            }
+        } else {
+            return None;
+        }
+    }
+
+    let oclock = usize::from(word(tokens, len) == "oclock");
+    let rest = &tokens[len + oclock..];
+    let (pm, meridiem) = match (word(rest, 0), word(rest, 1), word(rest, 2)) {
+        ("am", _, _) => (Some(false), 1),
+        ("pm", _, _) => (Some(true), 1),
+        ("in", "the", "morning") => (Some(false), 3),
+        ("in", "the", "afternoon" | "evening") => (Some(true), 3),
+        _ => (None, 0),
+    };
+    let marker = oclock + meridiem;
+    if marker == 0 && !expected && !written {
+        return None;
+    }
+    if let Some(pm) = pm {
+        if !(1..=12).contains(&hour) {
+            return None;
+        }
+        hour = match (pm, hour) {
+            (false, 12) => 0,
+            (true, 12) => 12,
+            (true, hour) => hour + 12,
+            (false, hour) => hour,
+        };
+    }
+    if hour > 23 || minute > 59 {
+        return None;
+    }
+    let time = Time {
+        hour,
+        minute,
+        meridiem: pm.is_some(),
+    };
+    Some((time, len + marker))
+}
+
+fn write_date(date: NaiveDate, config: &DateFormatting, locale: &Locale) -> String {
+    if config.iso8601 {
+        return date.format("%Y-%m-%d").to_string();
+    }
+    let (day, month) = if locale.padded {
+        (format!("{:02}", date.day()), format!("{:02}", date.month()))
+    } else {
+        (date.day().to_string(), date.month().to_string())
+    };
+    let year = date.year().to_string();
+    let parts = match locale.order {
+        Order::MonthDayYear => [month, day, year],
+        Order::DayMonthYear => [day, month, year],
+        Order::YearMonthDay => [year, month, day],
+    };
+    parts.join(&locale.separator.to_string())
+}
+
+fn write_time(time: &Time, config: &DateFormatting, locale: &Locale) -> String {
+    if config.iso8601 || !locale.twelve_hour {
+        return format!("{:02}:{:02}", time.hour, time.minute);
+    }
+    if !time.meridiem {
+        // "four o'clock" doesn't say which four
+        return format!("{}:{:02}", time.hour, time.minute);
+    }
+    let hour = match time.hour % 12 {
+        0 => 12,
+        hour => hour,
+    };
+    let suffix = if time.hour < 12 { "AM" } else { "PM" };
+    format!("{}:{:02} {}", hour, time.minute, suffix)
+}
+
+pub fn format(text: &str, config: &DateFormatting) -> String {
+    let tokens = tokenize(text);
+    let locale = locale(&config.locale);
+    let mut out: Vec<String> = Vec::new();
+    let mut i = 0;
+    while i < tokens.len() {
+        let rest = &tokens[i..];
+        let (written, len) = if let Some((date, mut len)) = date(rest) {
+            let mut written = write_date(date, config, &locale);
+            let at = usize::from(word(rest, len) == "at");
+            if let Some((time, time_len)) = time(&rest[len + at..], at == 1) {
+                let separator = if config.iso8601 { "T" } else { " " };
+                written = format!(
+                    "{}{}{}",
+                    written,
+                    separator,
+                    write_time(&time, config, &locale)
+                );
+                len += at + time_len;
+            }
+            (written, len)
+        } else if let Some((time, len)) = time(rest, false) {
+            (write_time(&time, config, &locale), len)
+        } else {
+            let token = &tokens[i];
+            out.push(format!("{}{}{}", token.prefix, token.core, token.tail));
+            i += 1;
+            continue;
+        };
+        let last = &tokens[i + len - 1];
+        let mut tail = last.tail;
+        // The period of "p.m." isn't the end of a sentence unless the text ends there
+        if last.core.contains('.') && i + len < tokens.len() {
+            tail = tail.strip_prefix('.').unwrap_or(tail);
+        }
+        out.push(format!("{}{}{}", tokens[i].prefix, written, tail));
+        i += len;
+    }
+    out.join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(locale: &str, iso8601: bool) -> DateFormatting {
+        DateFormatting {
+            locale: locale.to_string(),
+            iso8601,
+        }
+    }
+
+    #[test]
+    fn writes_dates_and_times_in_the_locale_format() {
+        let spoken = "march third twenty twenty six at four thirty pm";
+        let cases = [
+            ("en-US", false, "3/3/2026 4:30 PM"),
+            ("en-GB", false, "03/03/2026 4:30 PM"),
+            ("de-DE", false, "03.03.2026 16:30"),
+            ("ja-JP", false, "2026/03/03 16:30"),
+            ("sv-SE", false, "2026-03-03 16:30"),
+            ("en-US", true, "2026-03-03T16:30"),
+        ];
+        for (locale, iso8601, written) in cases {
+            assert_eq!(
+                format(spoken, &config(locale, iso8601)),
+                written,
+                "{}",
+                locale
+            );
+        }
+    }
+
+    #[test]
+    fn recognizes_spoken_forms() {
+        let cases = [
+            ("the third of march 2026", "3/3/2026"),
+            ("meet on december twenty first 2025.", "meet on 12/21/2025."),
+            ("call at four o'clock", "call at 4:00"),
+            ("at 9:15 a.m. tomorrow", "at 9:15 AM tomorrow"),
+            ("it ends at eleven p.m.", "it ends at 11:00 PM."),
+            ("twelve am", "12:00 AM"),
+        ];
+        for (spoken, written) in cases {
+            assert_eq!(
+                format(spoken, &config("en-US", false)),
+                written,
+                "{}",
+                spoken
+            );
+        }
+    }
+
+    #[test]
+    fn leaves_ordinary_numbers_alone() {
+        for text in [
+            "four thirty people came",
+            "may I ask",
+            "the second one",
+            "at four",
+        ] {
+            assert_eq!(format(text, &config("en-US", false)), text);
+        }
+    }
+
+    #[test]
+    fn uses_the_current_year_when_none_is_said() {
+        let year = Local::now().year();
+        assert_eq!(
+            format("march third", &config("en-US", true)),
+            format!("{}-03-03", year)
+        );
+    }
+}
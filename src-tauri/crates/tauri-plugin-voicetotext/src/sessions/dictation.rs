@@ -0,0 +1,76 @@
+//! Text passes applied to each final dictation result before it is shown, typed or stored.
+
+use crate::settings::AppProfile;
+use crate::{
+    app_lock, capitalization, dates, emoji, numbers, profiles, recent, settings, snippets,
+    spelling, templates,
+};
+use tauri::AppHandle;
+
+/// Run `text` through spelling mode, capitalization, date, number and emoji formatting and
+/// snippet expansion, adjusted by the foreground app's `profile`. Returns `None` when the
+/// utterance was a voice command for one of the passes and isn't dictated.
+pub fn finalize(app: &AppHandle, text: &str, profile: Option<&AppProfile>) -> Option<String> {
+    let text = spelling::apply(app, text)?;
+    // Spelled text is exactly what was asked for
+    if spelling::is_enabled(app) {
+        return Some(text);
+    }
+    let config = settings::load(app);
+    let mode = profile
+        .and_then(|profile| profile.capitalization)
+        .unwrap_or(config.capitalization.mode);
+    let text = capitalization::apply(&text, &config.capitalization, mode);
+    let dates = match profile.and_then(|profile| profile.format_dates) {
+        Some(true) => Some(config.date_formatting.unwrap_or_default()),
+        Some(false) => None,
+        None => config.date_formatting,
+    };
+    // Before numbers, which would turn "four thirty" into "4 30"
+    let text = match &dates {
+        Some(dates) => dates::format(&text, dates),
+        None => text,
+    };
+    let numbers = match profile.and_then(|profile| profile.format_numbers) {
+        Some(true) => Some(config.number_formatting.unwrap_or_default()),
+        Some(false) => None,
+        None => config.number_formatting,
+    };
+    let text = match &numbers {
+        Some(numbers) => numbers::format(&text, numbers),
+        None => text,
+    };
+    let emoji = match profile.and_then(|profile| profile.emoji) {
+        Some(true) => Some(config.emoji.unwrap_or_default()),
+        Some(false) => None,
+        None => config.emoji,
+    };
+    let text = match &emoji {
+        Some(emoji) => emoji::apply(&text, emoji),
+        None => text,
+    };
+    if profile.and_then(|profile| profile.expand_snippets) == Some(false) {
+        return Some(text);
+    }
+    Some(snippets::expand(&text, &config.snippets))
+}
+
+/// Apply the dictation passes to a final result recognized by the frontend. While a
+/// template document is open the text goes into it instead, and `None` is returned.
+#[tauri::command]
+#[specta::specta]
+pub async fn finalize_dictation(app: AppHandle, text: String) -> Result<Option<String>, String> {
+    app_lock::ensure_unlocked(&app)?;
+    if templates::handle_command(&app, &text) {
+        return Ok(None);
+    }
+    let profile = profiles::active(&app).await;
+    let Some(text) = finalize(&app, &text, profile.as_ref()) else {
+        return Ok(None);
+    };
+    if templates::dictate(&app, &text) {
+        return Ok(None);
+    }
+    recent::record(&app, &text);
+    Ok(Some(text))
+}
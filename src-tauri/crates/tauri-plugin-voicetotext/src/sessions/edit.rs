@@ -0,0 +1,154 @@
+//! Operations that rewrite stored sessions.
+
+use crate::history::{self, Session};
+use crate::{app_lock, prerecorded};
+use tauri::AppHandle;
+
+/// Concatenate sessions in recording order into one new session.
+///
+/// Each later session is offset by how much later it started (wall clock), but never
+/// earlier than the end of what precedes it, so real gaps such as a reconnect are kept
+/// and overlapping clocks can't produce out-of-order segments. Speaker labels are kept.
+pub fn merge(mut sessions: Vec<Session>) -> Result<Session, String> {
+    if sessions.len() < 2 {
+        return Err("Select at least two sessions to merge".to_string());
+    }
+    sessions.sort_by_key(|session| session.created_at);
+    if sessions.windows(2).any(|pair| pair[0].id == pair[1].id) {
+        return Err("The same session was selected more than once".to_string());
+    }
+
+    let first_started = sessions[0].created_at;
+    let mut segments = Vec::new();
+    let mut chapters = Vec::new();
+    let mut end = 0.0_f64;
+    for session in &sessions {
+        let wall_offset = (session.created_at - first_started) as f64 / 1000.0;
+        let offset = wall_offset.max(end);
+        for chapter in &session.chapters {
+            let mut chapter = chapter.clone();
+            chapter.start += offset;
+            chapters.push(chapter);
+        }
+        for segment in &session.segments {
+            let mut segment = segment.clone();
+            segment.start += offset;
+            segment.end += offset;
+            end = end.max(segment.end);
+            segments.push(segment);
+        }
+    }
+
+    let ids: Vec<&str> = sessions.iter().map(|s| s.id.as_str()).collect();
+    let mut merged = Session::new(
+        sessions[0].title.clone(),
+        format!("merge:{}", ids.join(",")),
+        segments,
+    );
+    merged.created_at = first_started;
+    merged.chapters = chapters;
+    Ok(merged)
+}
+
+/// Merge stored sessions into a new one, optionally deleting the originals afterwards.
+#[tauri::command]
+#[specta::specta]
+pub fn merge_sessions(
+    app: AppHandle,
+    session_ids: Vec<String>,
+    delete_originals: bool,
+) -> Result<Session, String> {
+    app_lock::ensure_unlocked(&app)?;
+    let sessions = session_ids
+        .iter()
+        .map(|id| history::load(&app, id))
+        .collect::<Result<Vec<_>, _>>()?;
+    let merged = merge(sessions)?;
+    history::save(&app, &merged)?;
+
+    if delete_originals {
+        for id in &session_ids {
+            history::delete(&app, id)?;
+        }
+    }
+    println!(
+        "[merge_sessions] Merged {} sessions into {}",
+        session_ids.len(),
+        merged.id
+    );
+    Ok(merged)
+}
+
+/// Correct the text of one segment. The previous segments are kept as a version.
+#[tauri::command]
+#[specta::specta]
+pub fn edit_segment(
+    app: AppHandle,
+    session_id: String,
+    segment_index: usize,
+    text: String,
+) -> Result<Session, String> {
+    app_lock::ensure_unlocked(&app)?;
+    let mut session = history::load(&app, &session_id)?;
+    let mut segments = session.segments.clone();
+    let segment = segments
+        .get_mut(segment_index)
+        .ok_or_else(|| format!("No segment at index {}", segment_index))?;
+    if segment.text == text {
+        return Ok(session);
+    }
+    segment.text = text;
+    session.replace_segments(segments, "edit");
+    history::save(&app, &session)?;
+    Ok(session)
+}
+
+/// Transcribe a session's audio again with `model`, keeping the previous segments as a
+/// version so the results can be compared.
+#[tauri::command]
+#[specta::specta]
+pub async fn retranscribe_session(
+    app: AppHandle,
+    session_id: String,
+    api_key: String,
+    model: String,
+) -> Result<Session, String> {
+    app_lock::ensure_unlocked(&app)?;
+    let source = history::load(&app, &session_id)?.source;
+    println!(
+        "[retranscribe_session] Transcribing {} again with {}",
+        session_id, model
+    );
+    let segments = prerecorded::retranscribe(&app, &source, &api_key, &model).await?;
+    // Edits made while the request ran are kept in the version
+    let mut session = history::load(&app, &session_id)?;
+    session.replace_segments(segments, &format!("retranscribe:{}", model));
+    history::save(&app, &session)?;
+    Ok(session)
+}
+
+/// Undo the latest edit or re-transcription of a session. The undone segments can be
+/// restored with `redo_edit` until the session is edited again.
+#[tauri::command]
+#[specta::specta]
+pub fn undo_edit(app: AppHandle, session_id: String) -> Result<Session, String> {
+    app_lock::ensure_unlocked(&app)?;
+    let mut session = history::load(&app, &session_id)?;
+    if !session.undo() {
+        return Err("Nothing to undo".to_string());
+    }
+    history::save(&app, &session)?;
+    Ok(session)
+}
+
+#[tauri::command]
+#[specta::specta]
+pub fn redo_edit(app: AppHandle, session_id: String) -> Result<Session, String> {
+    app_lock::ensure_unlocked(&app)?;
+    let mut session = history::load(&app, &session_id)?;
+    if !session.redo() {
+        return Err("Nothing to redo".to_string());
+    }
+    history::save(&app, &session)?;
+    Ok(session)
+}
@@ -0,0 +1,61 @@
+//! Spoken emoji: phrases such as "thumbs up emoji" or "smiley face" become the emoji.
+//!
+//! The mapping table lives in `emoji.mappings` and starts out with the common ones below,
+//! so users can edit, remove or add phrases.
+
+use crate::settings::{EmojiMapping, EmojiSettings};
+use crate::snippets;
+
+const DEFAULTS: &[(&str, &str)] = &[
+    ("smiley face", "🙂"),
+    ("smiley", "🙂"),
+    ("smile emoji", "😄"),
+    ("laughing emoji", "😂"),
+    ("crying laughing emoji", "😂"),
+    ("wink emoji", "😉"),
+    ("winking face", "😉"),
+    ("sad face", "🙁"),
+    ("crying emoji", "😢"),
+    ("heart emoji", "❤️"),
+    ("thumbs up emoji", "👍"),
+    ("thumbs up", "👍"),
+    ("thumbs down emoji", "👎"),
+    ("thumbs down", "👎"),
+    ("clapping emoji", "👏"),
+    ("party emoji", "🎉"),
+    ("fire emoji", "🔥"),
+    ("rocket emoji", "🚀"),
+    ("eyes emoji", "👀"),
+    ("thinking face", "🤔"),
+    ("thinking emoji", "🤔"),
+    ("shrug emoji", "🤷"),
+    ("facepalm emoji", "🤦"),
+    ("pray emoji", "🙏"),
+    ("wave emoji", "👋"),
+    ("ok hand emoji", "👌"),
+    ("check mark emoji", "✅"),
+    ("cross mark emoji", "❌"),
+    ("warning emoji", "⚠️"),
+    ("hundred emoji", "💯"),
+    ("coffee emoji", "☕"),
+];
+
+pub fn default_mappings() -> Vec<EmojiMapping> {
+    DEFAULTS
+        .iter()
+        .map(|(phrase, emoji)| EmojiMapping {
+            phrase: phrase.to_string(),
+            emoji: emoji.to_string(),
+        })
+        .collect()
+}
+
+pub fn apply(text: &str, config: &EmojiSettings) -> String {
+    snippets::replace_phrases(
+        text,
+        config
+            .mappings
+            .iter()
+            .map(|mapping| (mapping.phrase.as_str(), mapping.emoji.as_str())),
+    )
+}
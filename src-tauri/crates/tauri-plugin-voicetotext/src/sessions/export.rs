@@ -0,0 +1,386 @@
+//! Rendering stored sessions to plain text, subtitle, JSON and Word formats.
+//!
+//! Chapters become headings in text and DOCX, `NOTE` blocks in WebVTT and zero-length
+//! `{Chapter: …}` cues in SRT, which has no comment syntax.
+
+use crate::app_lock;
+use crate::history::{self, Chapter, Segment, Session};
+use crate::zip::ZipWriter;
+use chrono::DateTime;
+use serde::{Deserialize, Serialize};
+use specta::Type;
+use std::fs;
+use tauri::AppHandle;
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, Type)]
+#[serde(rename_all = "lowercase")]
+pub enum ExportFormat {
+    Txt,
+    Srt,
+    Vtt,
+    Json,
+    Docx,
+}
+
+impl ExportFormat {
+    pub fn extension(self) -> &'static str {
+        match self {
+            ExportFormat::Txt => "txt",
+            ExportFormat::Srt => "srt",
+            ExportFormat::Vtt => "vtt",
+            ExportFormat::Json => "json",
+            ExportFormat::Docx => "docx",
+        }
+    }
+}
+
+const CONTENT_TYPES: &str = concat!(
+    r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>"#,
+    r#"<Types xmlns="http://schemas.openxmlformats.org/package/2006/content-types">"#,
+    r#"<Default Extension="rels" ContentType="application/vnd.openxmlformats-package.relationships+xml"/>"#,
+    r#"<Default Extension="xml" ContentType="application/xml"/>"#,
+    r#"<Override PartName="/word/document.xml" ContentType="application/vnd.openxmlformats-officedocument.wordprocessingml.document.main+xml"/>"#,
+    r#"<Override PartName="/word/styles.xml" ContentType="application/vnd.openxmlformats-officedocument.wordprocessingml.styles+xml"/>"#,
+    r#"</Types>"#,
+);
+
+const PACKAGE_RELATIONSHIPS: &str = concat!(
+    r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>"#,
+    r#"<Relationships xmlns="http://schemas.openxmlformats.org/package/2006/relationships">"#,
+    r#"<Relationship Id="rId1" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/officeDocument" Target="word/document.xml"/>"#,
+    r#"</Relationships>"#,
+);
+
+const DOCUMENT_RELATIONSHIPS: &str = concat!(
+    r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>"#,
+    r#"<Relationships xmlns="http://schemas.openxmlformats.org/package/2006/relationships">"#,
+    r#"<Relationship Id="rId1" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/styles" Target="styles.xml"/>"#,
+    r#"</Relationships>"#,
+);
+
+/// Word only lists a paragraph in its navigation pane when its style has an outline level.
+const STYLES: &str = concat!(
+    r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>"#,
+    r#"<w:styles xmlns:w="http://schemas.openxmlformats.org/wordprocessingml/2006/main">"#,
+    r#"<w:style w:type="paragraph" w:default="1" w:styleId="Normal"><w:name w:val="Normal"/>"#,
+    r#"<w:pPr><w:spacing w:after="120"/></w:pPr></w:style>"#,
+    r#"<w:style w:type="paragraph" w:styleId="Heading1"><w:name w:val="heading 1"/>"#,
+    r#"<w:basedOn w:val="Normal"/><w:next w:val="Normal"/><w:qFormat/>"#,
+    r#"<w:pPr><w:keepNext/><w:spacing w:before="240"/><w:outlineLvl w:val="0"/></w:pPr>"#,
+    r#"<w:rPr><w:b/><w:sz w:val="32"/></w:rPr></w:style>"#,
+    r#"</w:styles>"#,
+);
+
+/// `HH:MM:SS<sep>mmm`, the timestamp shape shared by SRT (`,`) and WebVTT (`.`).
+fn timestamp(seconds: f64, separator: char) -> String {
+    let total_ms = (seconds.max(0.0) * 1000.0).round() as u64;
+    format!(
+        "{:02}:{:02}:{:02}{}{:03}",
+        total_ms / 3_600_000,
+        (total_ms / 60_000) % 60,
+        (total_ms / 1000) % 60,
+        separator,
+        total_ms % 1000
+    )
+}
+
+fn speaker_prefix(speaker: Option<u32>) -> String {
+    speaker
+        .map(|s| format!("Speaker {}: ", s))
+        .unwrap_or_default()
+}
+
+enum Item<'a> {
+    Chapter(&'a Chapter),
+    Segment(&'a Segment),
+}
+
+/// Chapters and segments in export order: each chapter before the first segment that
+/// starts at or after it, and chapters beginning after the last segment at the end.
+fn items(session: &Session) -> Vec<Item<'_>> {
+    let mut chapters = session.chapters.iter().peekable();
+    let mut items = Vec::with_capacity(session.chapters.len() + session.segments.len());
+    for segment in &session.segments {
+        while let Some(chapter) = chapters.next_if(|chapter| chapter.start <= segment.start) {
+            items.push(Item::Chapter(chapter));
+        }
+        items.push(Item::Segment(segment));
+    }
+    items.extend(chapters.map(Item::Chapter));
+    items
+}
+
+/// Text for an XML element, without the control characters XML 1.0 can't hold.
+fn xml_text(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '\t' | '\n' | '\r' => out.push(' '),
+            c if c.is_control() => {}
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+fn docx_run(text: &str, bold: bool) -> String {
+    format!(
+        r#"<w:r>{}<w:t xml:space="preserve">{}</w:t></w:r>"#,
+        if bold { "<w:rPr><w:b/></w:rPr>" } else { "" },
+        xml_text(text)
+    )
+}
+
+fn document_xml(session: &Session) -> String {
+    let mut out = String::from(concat!(
+        r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>"#,
+        r#"<w:document xmlns:w="http://schemas.openxmlformats.org/wordprocessingml/2006/main">"#,
+        "<w:body>",
+    ));
+    for item in items(session) {
+        match item {
+            Item::Chapter(chapter) => {
+                out.push_str(r#"<w:p><w:pPr><w:pStyle w:val="Heading1"/></w:pPr>"#);
+                out.push_str(&docx_run(chapter.title.trim(), false));
+                out.push_str("</w:p>");
+            }
+            Item::Segment(segment) => {
+                out.push_str("<w:p>");
+                if segment.speaker.is_some() {
+                    out.push_str(&docx_run(&speaker_prefix(segment.speaker), true));
+                }
+                out.push_str(&docx_run(segment.text.trim(), false));
+                out.push_str("</w:p>");
+            }
+        }
+    }
+    out.push_str("<w:sectPr/></w:body></w:document>");
+    out
+}
+
+fn docx(session: &Session) -> Result<Vec<u8>, String> {
+    let created = DateTime::from_timestamp_millis(session.created_at as i64).unwrap_or_default();
+    let mut zip = ZipWriter::new(created);
+    zip.add("[Content_Types].xml", CONTENT_TYPES.as_bytes(), true)?;
+    zip.add("_rels/.rels", PACKAGE_RELATIONSHIPS.as_bytes(), true)?;
+    zip.add(
+        "word/_rels/document.xml.rels",
+        DOCUMENT_RELATIONSHIPS.as_bytes(),
+        true,
+    )?;
+    zip.add("word/styles.xml", STYLES.as_bytes(), true)?;
+    zip.add("word/document.xml", document_xml(session).as_bytes(), true)?;
+    zip.finish()
+}
+
+pub fn render(session: &Session, format: ExportFormat) -> Result<Vec<u8>, String> {
+    let mut out = String::new();
+    match format {
+        ExportFormat::Txt => {
+            for item in items(session) {
+                match item {
+                    Item::Chapter(chapter) => {
+                        if !out.is_empty() {
+                            out.push('\n');
+                        }
+                        out.push_str(&format!("## {}\n\n", chapter.title.trim()));
+                    }
+                    Item::Segment(segment) => {
+                        out.push_str(&speaker_prefix(segment.speaker));
+                        out.push_str(segment.text.trim());
+                        out.push('\n');
+                    }
+                }
+            }
+        }
+        ExportFormat::Srt => {
+            for (index, item) in items(session).into_iter().enumerate() {
+                let (start, end, text) = match item {
+                    // Zero-length, so players never show it
+                    Item::Chapter(chapter) => (
+                        chapter.start,
+                        chapter.start,
+                        format!("{{Chapter: {}}}", chapter.title.trim()),
+                    ),
+                    Item::Segment(segment) => (
+                        segment.start,
+                        segment.end,
+                        speaker_prefix(segment.speaker) + segment.text.trim(),
+                    ),
+                };
+                out.push_str(&format!(
+                    "{}\n{} --> {}\n{}\n\n",
+                    index + 1,
+                    timestamp(start, ','),
+                    timestamp(end, ','),
+                    text
+                ));
+            }
+        }
+        ExportFormat::Vtt => {
+            out.push_str("WEBVTT\n\n");
+            for item in items(session) {
+                let segment = match item {
+                    Item::Chapter(chapter) => {
+                        out.push_str(&format!("NOTE Chapter: {}\n\n", chapter.title.trim()));
+                        continue;
+                    }
+                    Item::Segment(segment) => segment,
+                };
+                let text = match segment.speaker {
+                    Some(speaker) => format!("<v Speaker {}>{}", speaker, segment.text.trim()),
+                    None => segment.text.trim().to_string(),
+                };
+                out.push_str(&format!(
+                    "{} --> {}\n{}\n\n",
+                    timestamp(segment.start, '.'),
+                    timestamp(segment.end, '.'),
+                    text
+                ));
+            }
+        }
+        ExportFormat::Json => {
+            out = serde_json::to_string_pretty(session)
+                .map_err(|e| format!("Failed to serialize session: {}", e))?;
+        }
+        ExportFormat::Docx => return docx(session),
+    }
+    Ok(out.into_bytes())
+}
+
+/// Render a stored session and write it to `path`.
+#[tauri::command]
+#[specta::specta]
+pub fn export_session(
+    app: AppHandle,
+    session_id: String,
+    format: ExportFormat,
+    path: String,
+) -> Result<(), String> {
+    app_lock::ensure_unlocked(&app)?;
+    let session = history::load(&app, &session_id)?;
+    fs::write(&path, render(&session, format)?)
+        .map_err(|e| format!("Failed to write {}: {}", path, e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn session() -> Session {
+        let segment = |start: f64, text: &str, speaker: Option<u32>| Segment {
+            start,
+            end: start + 1.0,
+            text: text.to_string(),
+            speaker,
+            confidence: 1.0,
+        };
+        let chapter = |start: f64, title: &str| Chapter {
+            start,
+            title: title.to_string(),
+            manual: true,
+        };
+        let mut session = Session::new(
+            "Meeting".to_string(),
+            "test".to_string(),
+            vec![
+                segment(0.0, "Hello", Some(1)),
+                segment(2.0, "Budget <draft> & plans", None),
+            ],
+        );
+        session.chapters = vec![
+            chapter(0.0, "Intro"),
+            chapter(1.5, "Budget"),
+            chapter(9.0, "Wrap-up"),
+        ];
+        session
+    }
+
+    fn rendered(format: ExportFormat) -> String {
+        String::from_utf8(render(&session(), format).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn writes_chapters_as_text_headings() {
+        assert_eq!(
+            rendered(ExportFormat::Txt),
+            "## Intro\n\nSpeaker 1: Hello\n\n## Budget\n\nBudget <draft> & plans\n\n## Wrap-up\n\n"
+        );
+    }
+
+    #[test]
+    fn writes_chapters_as_zero_length_srt_cues() {
+        let srt = rendered(ExportFormat::Srt);
+        let cues: Vec<&str> = srt.trim_end().split("\n\n").collect();
+        assert_eq!(
+            cues,
+            vec![
+                "1\n00:00:00,000 --> 00:00:00,000\n{Chapter: Intro}",
+                "2\n00:00:00,000 --> 00:00:01,000\nSpeaker 1: Hello",
+                "3\n00:00:01,500 --> 00:00:01,500\n{Chapter: Budget}",
+                "4\n00:00:02,000 --> 00:00:03,000\nBudget <draft> & plans",
+                "5\n00:00:09,000 --> 00:00:09,000\n{Chapter: Wrap-up}",
+            ]
+        );
+    }
+
+    #[test]
+    fn writes_chapters_as_vtt_notes() {
+        let vtt = rendered(ExportFormat::Vtt);
+        assert!(vtt.starts_with("WEBVTT\n\nNOTE Chapter: Intro\n\n00:00:00.000"));
+        assert!(vtt.ends_with("Budget <draft> & plans\n\nNOTE Chapter: Wrap-up\n\n"));
+    }
+
+    #[test]
+    fn writes_chapters_as_docx_headings() {
+        let document = document_xml(&session());
+        let heading = |title: &str| {
+            format!(
+                r#"<w:p><w:pPr><w:pStyle w:val="Heading1"/></w:pPr><w:r><w:t xml:space="preserve">{}</w:t></w:r></w:p>"#,
+                title
+            )
+        };
+        let positions: Vec<usize> = [
+            heading("Intro"),
+            "Hello".to_string(),
+            heading("Budget"),
+            "Budget &lt;draft&gt; &amp; plans".to_string(),
+            heading("Wrap-up"),
+        ]
+        .iter()
+        .map(|part| document.find(part.as_str()).unwrap())
+        .collect();
+        assert!(positions.windows(2).all(|pair| pair[0] < pair[1]));
+        assert!(document.contains("<w:b/></w:rPr><w:t xml:space=\"preserve\">Speaker 1: </w:t>"));
+    }
+
+    #[test]
+    fn packages_docx_as_a_zip() {
+        let bytes = render(&session(), ExportFormat::Docx).unwrap();
+        assert!(bytes.starts_with(b"PK\x03\x04"));
+        let names = [
+            "[Content_Types].xml",
+            "_rels/.rels",
+            "word/_rels/document.xml.rels",
+            "word/styles.xml",
+            "word/document.xml",
+        ];
+        for name in names {
+            assert!(
+                bytes
+                    .windows(name.len())
+                    .any(|window| window == name.as_bytes()),
+                "{}",
+                name
+            );
+        }
+    }
+
+    #[test]
+    fn drops_control_characters_from_xml() {
+        assert_eq!(xml_text("a\u{1}b\tc"), "ab c");
+    }
+}
@@ -0,0 +1,430 @@
+//! On-disk transcript history. Each session is stored as one JSON file under
+//! `<app data dir>/history/<id>.json`, sealed by [`encryption`] when that is enabled.
+
+use crate::bandwidth::Usage;
+use crate::schema::{self, Store};
+use crate::{app_lock, encryption, recordings};
+use serde::{Deserialize, Serialize};
+use specta::Type;
+use std::fs;
+use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
+use tauri::{AppHandle, Manager};
+
+#[derive(Serialize, Deserialize, Clone, Debug, Type)]
+pub struct Segment {
+    /// Offsets in seconds from the start of the recording.
+    pub start: f64,
+    pub end: f64,
+    pub text: String,
+    #[serde(default)]
+    pub speaker: Option<u32>,
+    #[serde(default)]
+    pub confidence: f64,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Type)]
+pub struct Session {
+    pub id: String,
+    pub title: String,
+    /// Where the audio came from, e.g. `stream:<url>` or `url:<url>`.
+    pub source: String,
+    /// Unix time in milliseconds.
+    pub created_at: u64,
+    pub segments: Vec<Segment>,
+    #[serde(default)]
+    pub chapters: Vec<Chapter>,
+    /// Result latency of live sessions.
+    #[serde(default)]
+    pub latency: Option<LatencyStats>,
+    /// Earlier segments, oldest first, kept when they are edited or re-transcribed.
+    #[serde(default)]
+    pub versions: Vec<SessionVersion>,
+    /// Undone changes, most recently undone last, until the next edit.
+    #[serde(default)]
+    pub redo: Vec<SessionVersion>,
+    /// Starred sessions are exempt from retention cleanup.
+    #[serde(default)]
+    pub starred: bool,
+    /// Traffic with the provider, for sessions the backend transcribed.
+    #[serde(default)]
+    pub bandwidth: Option<Usage>,
+    /// The session this one continues after a stream rolled over.
+    #[serde(default)]
+    pub continues: Option<String>,
+}
+
+/// Segments as they were before a change.
+#[derive(Serialize, Deserialize, Clone, Debug, Type)]
+pub struct SessionVersion {
+    pub segments: Vec<Segment>,
+    /// Unix time in milliseconds when they were replaced.
+    pub replaced_at: u64,
+    /// What replaced them, e.g. `edit` or `retranscribe:nova-3`.
+    pub replaced_by: String,
+}
+
+/// Aggregated delay between audio being sent and its transcript arriving.
+#[derive(Serialize, Deserialize, Clone, Debug, Type)]
+pub struct LatencyStats {
+    pub samples: usize,
+    pub p50_ms: f64,
+    pub p95_ms: f64,
+    pub max_ms: f64,
+}
+
+impl LatencyStats {
+    pub fn from_samples(samples: &[f64]) -> Option<Self> {
+        if samples.is_empty() {
+            return None;
+        }
+        let mut sorted = samples.to_vec();
+        sorted.sort_by(f64::total_cmp);
+        // Nearest-rank percentile
+        let rank =
+            |p: f64| sorted[((p * sorted.len() as f64).ceil() as usize).clamp(1, sorted.len()) - 1];
+        Some(LatencyStats {
+            samples: sorted.len(),
+            p50_ms: rank(0.50),
+            p95_ms: rank(0.95),
+            max_ms: sorted[sorted.len() - 1],
+        })
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Type)]
+pub struct Chapter {
+    /// Offset in seconds where the chapter begins.
+    pub start: f64,
+    pub title: String,
+    /// User-placed markers survive automatic re-chaptering.
+    #[serde(default)]
+    pub manual: bool,
+}
+
+/// Lightweight listing entry returned by `list_sessions`.
+#[derive(Serialize, Clone, Debug, Type)]
+pub struct SessionSummary {
+    pub id: String,
+    pub title: String,
+    pub source: String,
+    pub created_at: u64,
+    pub duration: f64,
+    pub segment_count: usize,
+    pub starred: bool,
+}
+
+/// A segment matching a history search.
+#[derive(Serialize, Clone, Debug, Type)]
+pub struct SearchMatch {
+    pub session_id: String,
+    pub title: String,
+    pub segment_index: usize,
+    pub start: f64,
+    pub end: f64,
+    pub text: String,
+}
+
+/// A query hit inside one session. `offset` is the estimated audio position of the hit,
+/// interpolated across the segment since word timings aren't stored.
+#[derive(Serialize, Clone, Debug, Type)]
+pub struct SessionMatch {
+    pub segment_index: usize,
+    /// Character range of the hit within the segment text.
+    pub char_start: usize,
+    pub char_end: usize,
+    pub offset: f64,
+}
+
+/// The segment playing at a given timestamp.
+#[derive(Serialize, Clone, Debug, Type)]
+pub struct SegmentAt {
+    pub segment_index: usize,
+    pub segment: Segment,
+}
+
+impl Session {
+    pub fn new(title: String, source: String, segments: Vec<Segment>) -> Self {
+        Session {
+            id: uuid::Uuid::new_v4().to_string(),
+            title,
+            source,
+            created_at: now_ms(),
+            segments,
+            chapters: Vec::new(),
+            latency: None,
+            versions: Vec::new(),
+            redo: Vec::new(),
+            starred: false,
+            bandwidth: None,
+            continues: None,
+        }
+    }
+
+    /// Replace the segments, keeping the current ones as a version. Clears the redo stack.
+    pub fn replace_segments(&mut self, segments: Vec<Segment>, reason: &str) {
+        let previous = std::mem::replace(&mut self.segments, segments);
+        self.versions.push(SessionVersion {
+            segments: previous,
+            replaced_at: now_ms(),
+            replaced_by: reason.to_string(),
+        });
+        self.redo.clear();
+    }
+
+    /// Restore the latest version. Returns false when there is nothing to undo.
+    pub fn undo(&mut self) -> bool {
+        let Some(version) = self.versions.pop() else {
+            return false;
+        };
+        let undone = std::mem::replace(&mut self.segments, version.segments);
+        self.redo.push(SessionVersion {
+            segments: undone,
+            replaced_at: now_ms(),
+            replaced_by: version.replaced_by,
+        });
+        true
+    }
+
+    /// Re-apply the most recently undone change. Returns false when there is none.
+    pub fn redo(&mut self) -> bool {
+        let Some(undone) = self.redo.pop() else {
+            return false;
+        };
+        let previous = std::mem::replace(&mut self.segments, undone.segments);
+        self.versions.push(SessionVersion {
+            segments: previous,
+            replaced_at: now_ms(),
+            replaced_by: undone.replaced_by,
+        });
+        true
+    }
+
+    pub fn duration(&self) -> f64 {
+        self.segments.iter().map(|s| s.end).fold(0.0, f64::max)
+    }
+
+    pub fn summary(&self) -> SessionSummary {
+        SessionSummary {
+            id: self.id.clone(),
+            title: self.title.clone(),
+            source: self.source.clone(),
+            created_at: self.created_at,
+            duration: self.duration(),
+            segment_count: self.segments.len(),
+            starred: self.starred,
+        }
+    }
+
+    /// Every case-insensitive occurrence of `query`, in playback order.
+    pub fn find(&self, query: &str) -> Vec<SessionMatch> {
+        let needle = query.trim().to_lowercase();
+        if needle.is_empty() {
+            return Vec::new();
+        }
+
+        let needle_chars = needle.chars().count();
+        let mut matches = Vec::new();
+        for (index, segment) in self.segments.iter().enumerate() {
+            let haystack = segment.text.to_lowercase();
+            let total_chars = haystack.chars().count().max(1);
+            for (byte_start, _) in haystack.match_indices(&needle) {
+                let char_start = haystack[..byte_start].chars().count();
+                let fraction = char_start as f64 / total_chars as f64;
+                matches.push(SessionMatch {
+                    segment_index: index,
+                    char_start,
+                    char_end: char_start + needle_chars,
+                    offset: segment.start + (segment.end - segment.start) * fraction,
+                });
+            }
+        }
+        matches
+    }
+
+    /// The segment covering `timestamp`, or the last one starting before it when the
+    /// timestamp falls into a pause.
+    pub fn segment_at(&self, timestamp: f64) -> Option<SegmentAt> {
+        let index = self
+            .segments
+            .iter()
+            .position(|s| s.start <= timestamp && timestamp < s.end)
+            .or_else(|| self.segments.iter().rposition(|s| s.start <= timestamp))?;
+        Some(SegmentAt {
+            segment_index: index,
+            segment: self.segments[index].clone(),
+        })
+    }
+}
+
+pub fn now_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+pub fn history_dir(app: &AppHandle) -> Result<PathBuf, String> {
+    let dir = app
+        .path()
+        .app_data_dir()
+        .map_err(|e| format!("Failed to resolve app data directory: {}", e))?
+        .join("history");
+    fs::create_dir_all(&dir).map_err(|e| format!("Failed to create history directory: {}", e))?;
+    Ok(dir)
+}
+
+fn session_path(app: &AppHandle, id: &str) -> Result<PathBuf, String> {
+    // Ids are generated by us, but they also arrive from the frontend — keep them inside the dir
+    if id.is_empty() || id.contains(['/', '\\', '.']) {
+        return Err(format!("Invalid session id: {}", id));
+    }
+    Ok(history_dir(app)?.join(format!("{}.json", id)))
+}
+
+/// Sessions as stored; they are synced, backed up and sent to other devices in this form.
+pub const STORE: Store = Store {
+    name: "session",
+    wrapped: false,
+    migrations: &[schema::unversioned],
+};
+
+pub fn save(app: &AppHandle, session: &Session) -> Result<(), String> {
+    let json = STORE.encode(session)?;
+    let bytes = encryption::seal(app, json)?;
+    schema::write_atomic(&session_path(app, &session.id)?, &bytes)
+        .map_err(|e| format!("Failed to write session {}: {}", session.id, e))
+}
+
+pub fn exists(app: &AppHandle, id: &str) -> bool {
+    session_path(app, id).is_ok_and(|path| path.is_file())
+}
+
+pub fn load(app: &AppHandle, id: &str) -> Result<Session, String> {
+    let bytes = fs::read(session_path(app, id)?)
+        .map_err(|e| format!("Failed to read session {}: {}", id, e))?;
+    let bytes = encryption::open(app, bytes)?;
+    STORE
+        .decode(&bytes)
+        .map_err(|e| format!("Session file {}: {}", id, e))
+}
+
+/// Delete a stored session together with its archived recording.
+pub fn delete(app: &AppHandle, id: &str) -> Result<(), String> {
+    fs::remove_file(session_path(app, id)?)
+        .map_err(|e| format!("Failed to delete session {}: {}", id, e))?;
+    recordings::delete(app, id)
+}
+
+/// All stored sessions, newest first. Unreadable files are skipped.
+pub fn load_all(app: &AppHandle) -> Result<Vec<Session>, String> {
+    let entries = fs::read_dir(history_dir(app)?)
+        .map_err(|e| format!("Failed to read history directory: {}", e))?;
+
+    let mut sessions: Vec<Session> = entries
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.path().extension().is_some_and(|ext| ext == "json"))
+        .filter_map(|entry| {
+            let bytes = fs::read(entry.path()).ok()?;
+            // Sealed files can't be listed while history is locked
+            let bytes = encryption::open(app, bytes).ok()?;
+            match STORE.decode(&bytes) {
+                Ok(session) => Some(session),
+                Err(e) => {
+                    println!("[history] Skipping {:?}: {}", entry.path(), e);
+                    None
+                }
+            }
+        })
+        .collect();
+    sessions.sort_by_key(|s| std::cmp::Reverse(s.created_at));
+    Ok(sessions)
+}
+
+/// Case-insensitive substring search over every stored segment, newest sessions first.
+pub fn search(app: &AppHandle, query: &str) -> Result<Vec<SearchMatch>, String> {
+    let needle = query.trim().to_lowercase();
+    if needle.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let mut matches = Vec::new();
+    for session in load_all(app)? {
+        for (index, segment) in session.segments.iter().enumerate() {
+            if segment.text.to_lowercase().contains(&needle) {
+                matches.push(SearchMatch {
+                    session_id: session.id.clone(),
+                    title: session.title.clone(),
+                    segment_index: index,
+                    start: segment.start,
+                    end: segment.end,
+                    text: segment.text.clone(),
+                });
+            }
+        }
+    }
+    Ok(matches)
+}
+
+#[tauri::command]
+#[specta::specta]
+pub fn list_sessions(app: AppHandle) -> Result<Vec<SessionSummary>, String> {
+    app_lock::ensure_unlocked(&app)?;
+    Ok(load_all(&app)?.iter().map(Session::summary).collect())
+}
+
+#[tauri::command]
+#[specta::specta]
+pub fn get_session(app: AppHandle, session_id: String) -> Result<Session, String> {
+    app_lock::ensure_unlocked(&app)?;
+    load(&app, &session_id)
+}
+
+#[tauri::command]
+#[specta::specta]
+pub fn delete_session(app: AppHandle, session_id: String) -> Result<(), String> {
+    app_lock::ensure_unlocked(&app)?;
+    delete(&app, &session_id)
+}
+
+#[tauri::command]
+#[specta::specta]
+pub fn set_session_starred(
+    app: AppHandle,
+    session_id: String,
+    starred: bool,
+) -> Result<(), String> {
+    app_lock::ensure_unlocked(&app)?;
+    let mut session = load(&app, &session_id)?;
+    session.starred = starred;
+    save(&app, &session)
+}
+
+#[tauri::command]
+#[specta::specta]
+pub fn search_sessions(app: AppHandle, query: String) -> Result<Vec<SearchMatch>, String> {
+    app_lock::ensure_unlocked(&app)?;
+    search(&app, &query)
+}
+
+#[tauri::command]
+#[specta::specta]
+pub fn find_in_session(
+    app: AppHandle,
+    session_id: String,
+    query: String,
+) -> Result<Vec<SessionMatch>, String> {
+    app_lock::ensure_unlocked(&app)?;
+    Ok(load(&app, &session_id)?.find(&query))
+}
+
+#[tauri::command]
+#[specta::specta]
+pub fn get_segment_at(
+    app: AppHandle,
+    session_id: String,
+    timestamp: f64,
+) -> Result<Option<SegmentAt>, String> {
+    app_lock::ensure_unlocked(&app)?;
+    Ok(load(&app, &session_id)?.segment_at(timestamp))
+}
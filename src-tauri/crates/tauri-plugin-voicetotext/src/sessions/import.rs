@@ -0,0 +1,268 @@
+//! Importing existing SRT / WebVTT / plain-text transcripts into history.
+
+use crate::app_lock;
+use crate::history::{self, Segment, Session};
+use serde::Deserialize;
+use specta::Type;
+use std::fs;
+use std::path::Path;
+use tauri::AppHandle;
+
+#[derive(Deserialize, Clone, Copy, Debug, PartialEq, Eq, Type)]
+#[serde(rename_all = "lowercase")]
+pub enum ImportFormat {
+    Srt,
+    Vtt,
+    Txt,
+}
+
+impl ImportFormat {
+    fn detect(path: &Path, contents: &str) -> Self {
+        let extension = path
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .map(str::to_ascii_lowercase);
+        match extension.as_deref() {
+            Some("srt") => ImportFormat::Srt,
+            Some("vtt") => ImportFormat::Vtt,
+            _ if contents.trim_start().starts_with("WEBVTT") => ImportFormat::Vtt,
+            _ => ImportFormat::Txt,
+        }
+    }
+}
+
+/// Parse `HH:MM:SS,mmm`, `HH:MM:SS.mmm` or `MM:SS.mmm` into seconds.
+fn parse_timestamp(value: &str) -> Option<f64> {
+    let value = value.trim().replace(',', ".");
+    let parts: Vec<&str> = value.split(':').collect();
+    let (hours, minutes, seconds) = match parts.as_slice() {
+        [h, m, s] => (
+            h.parse::<f64>().ok()?,
+            m.parse::<f64>().ok()?,
+            s.parse::<f64>().ok()?,
+        ),
+        [m, s] => (0.0, m.parse::<f64>().ok()?, s.parse::<f64>().ok()?),
+        _ => return None,
+    };
+    Some(hours * 3600.0 + minutes * 60.0 + seconds)
+}
+
+/// Parse a cue timing line (`start --> end [settings]`).
+fn parse_timing(line: &str) -> Option<(f64, f64)> {
+    let (start, rest) = line.split_once("-->")?;
+    let end = rest.split_whitespace().next()?;
+    Some((parse_timestamp(start)?, parse_timestamp(end)?))
+}
+
+/// Split a leading speaker label — `Speaker 2: …` (our export) or `<v Speaker 2>…` (VTT).
+fn split_speaker(text: &str) -> (Option<u32>, String) {
+    let text = text.trim();
+    if let Some(rest) = text.strip_prefix("<v ") {
+        if let Some((voice, body)) = rest.split_once('>') {
+            let speaker = voice
+                .trim()
+                .strip_prefix("Speaker ")
+                .and_then(|n| n.parse().ok());
+            return (speaker, body.replace("</v>", "").trim().to_string());
+        }
+    }
+    if let Some(rest) = text.strip_prefix("Speaker ") {
+        if let Some((number, body)) = rest.split_once(':') {
+            if let Ok(speaker) = number.trim().parse() {
+                return (Some(speaker), body.trim().to_string());
+            }
+        }
+    }
+    (None, text.to_string())
+}
+
+/// Parse SRT or WebVTT cues. Both are blank-line separated blocks with a timing line;
+/// blocks without one (indices alone, `WEBVTT`, `NOTE`, `STYLE`) are skipped.
+fn parse_cues(contents: &str) -> Vec<Segment> {
+    let normalized = contents.replace("\r\n", "\n");
+    let mut segments = Vec::new();
+    for block in normalized.split("\n\n") {
+        let mut lines = block.lines().skip_while(|line| !line.contains("-->"));
+        let Some((start, end)) = lines.next().and_then(parse_timing) else {
+            continue;
+        };
+        let text = lines.collect::<Vec<_>>().join(" ");
+        let (speaker, text) = split_speaker(&text);
+        // Chapter markers written by our SRT export
+        let chapter = start == end && text.starts_with("{Chapter: ") && text.ends_with('}');
+        if text.is_empty() || chapter {
+            continue;
+        }
+        segments.push(Segment {
+            start,
+            end,
+            text,
+            speaker,
+            confidence: 1.0,
+        });
+    }
+    segments
+}
+
+/// Plain text has no timing, so each paragraph becomes an untimed segment.
+fn parse_text(contents: &str) -> Vec<Segment> {
+    contents
+        .replace("\r\n", "\n")
+        .split("\n\n")
+        .map(|paragraph| {
+            paragraph
+                .lines()
+                .map(str::trim)
+                .collect::<Vec<_>>()
+                .join(" ")
+        })
+        .filter(|paragraph| !paragraph.is_empty())
+        .map(|paragraph| {
+            let (speaker, text) = split_speaker(&paragraph);
+            Segment {
+                start: 0.0,
+                end: 0.0,
+                text,
+                speaker,
+                confidence: 1.0,
+            }
+        })
+        .collect()
+}
+
+pub fn parse(contents: &str, format: ImportFormat) -> Vec<Segment> {
+    match format {
+        ImportFormat::Srt | ImportFormat::Vtt => parse_cues(contents),
+        ImportFormat::Txt => parse_text(contents),
+    }
+}
+
+/// Import a transcript file as a new session. The format is detected from the extension
+/// (or a `WEBVTT` header) unless given explicitly.
+#[tauri::command]
+#[specta::specta]
+pub fn import_transcript(
+    app: AppHandle,
+    path: String,
+    format: Option<ImportFormat>,
+) -> Result<Session, String> {
+    app_lock::ensure_unlocked(&app)?;
+    let file = Path::new(&path);
+    let contents =
+        fs::read_to_string(file).map_err(|e| format!("Failed to read {}: {}", path, e))?;
+    let format = format.unwrap_or_else(|| ImportFormat::detect(file, &contents));
+
+    let segments = parse(&contents, format);
+    if segments.is_empty() {
+        return Err(format!("No transcript text found in {}", path));
+    }
+
+    let title = file
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().into_owned())
+        .unwrap_or_else(|| path.clone());
+    let session = Session::new(title, format!("import:{}", path), segments);
+    history::save(&app, &session)?;
+    println!(
+        "[import_transcript] Imported {} segments from {}",
+        session.segments.len(),
+        path
+    );
+    Ok(session)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_timestamps() {
+        let cases = [
+            ("00:01:02,500", Some(62.5)),
+            ("01:00:00.000", Some(3600.0)),
+            ("02:03.250", Some(123.25)),
+            (" 00:00:01,000 ", Some(1.0)),
+            ("12", None),
+            ("aa:bb", None),
+        ];
+        for (value, seconds) in cases {
+            assert_eq!(parse_timestamp(value), seconds, "{}", value);
+        }
+    }
+
+    #[test]
+    fn parses_timing_lines() {
+        let cases = [
+            ("00:00:01,000 --> 00:00:02,500", Some((1.0, 2.5))),
+            (
+                "00:01.000 --> 00:02.000 align:start position:10%",
+                Some((1.0, 2.0)),
+            ),
+            ("00:00:01,000 -> 00:00:02,500", None),
+            ("Speaker 1: hello", None),
+        ];
+        for (line, timing) in cases {
+            assert_eq!(parse_timing(line), timing, "{}", line);
+        }
+    }
+
+    fn summary(segments: &[Segment]) -> Vec<(f64, f64, Option<u32>, &str)> {
+        segments
+            .iter()
+            .map(|s| (s.start, s.end, s.speaker, s.text.as_str()))
+            .collect()
+    }
+
+    #[test]
+    fn parses_srt() {
+        let contents = "1\r\n00:00:01,000 --> 00:00:02,000\r\nSpeaker 2: Hello\r\nthere\r\n\r\n\
+                        2\r\n00:00:03,000 --> 00:00:04,000\r\n\r\n\
+                        3\r\n00:00:05,000 --> 00:00:05,000\r\n{Chapter: Outro}\r\n\r\n\
+                        4\r\n00:00:05,000 --> 00:00:06,500\r\nBye\r\n";
+        assert_eq!(
+            summary(&parse(contents, ImportFormat::Srt)),
+            vec![(1.0, 2.0, Some(2), "Hello there"), (5.0, 6.5, None, "Bye")]
+        );
+    }
+
+    #[test]
+    fn parses_vtt() {
+        let contents = "WEBVTT\n\nNOTE exported\n\n\
+                        intro\n00:01.000 --> 00:02.000\n<v Speaker 1>Hi</v>\n\n\
+                        00:00:03.000 --> 00:00:04.000 line:0\n<v Guest>Hey\n";
+        assert_eq!(
+            summary(&parse(contents, ImportFormat::Vtt)),
+            vec![(1.0, 2.0, Some(1), "Hi"), (3.0, 4.0, None, "Hey")]
+        );
+    }
+
+    #[test]
+    fn parses_plain_text_paragraphs() {
+        let contents = "Speaker 1: First line\n  continued\n\n\n\nSecond paragraph\n";
+        assert_eq!(
+            summary(&parse(contents, ImportFormat::Txt)),
+            vec![
+                (0.0, 0.0, Some(1), "First line continued"),
+                (0.0, 0.0, None, "Second paragraph"),
+            ]
+        );
+    }
+
+    #[test]
+    fn detects_the_format() {
+        let cases = [
+            ("talk.SRT", "", ImportFormat::Srt),
+            ("talk.vtt", "", ImportFormat::Vtt),
+            ("talk.txt", "  WEBVTT\n\n", ImportFormat::Vtt),
+            ("talk", "hello", ImportFormat::Txt),
+        ];
+        for (path, contents, format) in cases {
+            assert_eq!(
+                ImportFormat::detect(Path::new(path), contents),
+                format,
+                "{}",
+                path
+            );
+        }
+    }
+}
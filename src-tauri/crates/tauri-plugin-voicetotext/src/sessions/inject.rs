@@ -0,0 +1,274 @@
+//! Typing dictated text into the focused application.
+//!
+//! Keystrokes are sent with the platform's own tooling: System Events on macOS, SendKeys
+//! on Windows and `wtype` or `xdotool` on Linux (Wayland and X11). Every injected
+//! utterance is remembered with what was typed for it, so saying one of the
+//! `injection.scratch_phrases` ("scratch that") deletes the last one again with
+//! backspaces. The `insert_last` hotkey puts the last dictation's transcript into the
+//! focused app.
+
+use crate::settings::{self, PasteMethod};
+use crate::{app_lock, clipboard, commands, profiles, recent};
+use serde::Serialize;
+use specta::Type;
+use tauri::{AppHandle, Manager, State};
+use tokio::process::Command;
+use tokio::sync::Mutex;
+
+/// Utterances remembered for scratching; older ones can no longer be removed.
+const MAX_REMEMBERED: usize = 50;
+
+/// Text typed for each injected utterance, oldest first.
+#[derive(Default)]
+pub struct Injector(Mutex<Vec<String>>);
+
+#[derive(Serialize, Clone, Debug, Type)]
+pub struct InjectResult {
+    /// What was typed, including the separating space.
+    pub typed: String,
+    /// Characters deleted by a scratch phrase.
+    pub deleted: usize,
+}
+
+async fn run(program: &str, args: &[&str]) -> Result<(), String> {
+    let status = Command::new(program)
+        .args(args)
+        .status()
+        .await
+        .map_err(|e| format!("Failed to run {}: {}", program, e))?;
+    if status.success() {
+        Ok(())
+    } else {
+        Err(format!("{} exited with {}", program, status))
+    }
+}
+
+fn wayland() -> bool {
+    std::env::var_os("WAYLAND_DISPLAY").is_some()
+}
+
+/// Escape text for SendKeys, where `+^%~(){}[]` have meanings.
+fn send_keys_escape(text: &str) -> String {
+    text.chars()
+        .map(|c| match c {
+            '+' | '^' | '%' | '~' | '(' | ')' | '{' | '}' | '[' | ']' => format!("{{{}}}", c),
+            '\n' => "{ENTER}".to_string(),
+            _ => c.to_string(),
+        })
+        .collect()
+}
+
+async fn type_text(text: &str) -> Result<(), String> {
+    if cfg!(target_os = "macos") {
+        let escaped = text.replace('\\', "\\\\").replace('"', "\\\"");
+        let script = format!(
+            "tell application \"System Events\" to keystroke \"{}\"",
+            escaped
+        );
+        run("osascript", &["-e", &script]).await
+    } else if cfg!(target_os = "windows") {
+        let script = format!(
+            "Add-Type -AssemblyName System.Windows.Forms; [System.Windows.Forms.SendKeys]::SendWait('{}')",
+            send_keys_escape(text).replace('\'', "''")
+        );
+        run("powershell", &["-NoProfile", "-Command", &script]).await
+    } else if wayland() {
+        run("wtype", &["--", text]).await
+    } else {
+        run(
+            "xdotool",
+            &["type", "--clearmodifiers", "--delay", "0", "--", text],
+        )
+        .await
+    }
+}
+
+/// Press the platform's paste shortcut.
+async fn paste_shortcut() -> Result<(), String> {
+    if cfg!(target_os = "macos") {
+        run(
+            "osascript",
+            &[
+                "-e",
+                "tell application \"System Events\" to keystroke \"v\" using command down",
+            ],
+        )
+        .await
+    } else if cfg!(target_os = "windows") {
+        run(
+            "powershell",
+            &[
+                "-NoProfile",
+                "-Command",
+                "Add-Type -AssemblyName System.Windows.Forms; [System.Windows.Forms.SendKeys]::SendWait('^v')",
+            ],
+        )
+        .await
+    } else if wayland() {
+        run("wtype", &["-M", "ctrl", "v", "-m", "ctrl"]).await
+    } else {
+        run("xdotool", &["key", "--clearmodifiers", "ctrl+v"]).await
+    }
+}
+
+async fn backspace(count: usize) -> Result<(), String> {
+    if count == 0 {
+        return Ok(());
+    }
+    let count_arg = count.to_string();
+    if cfg!(target_os = "macos") {
+        let script = format!(
+            "tell application \"System Events\" to repeat {} times\nkey code 51\nend repeat",
+            count
+        );
+        run("osascript", &["-e", &script]).await
+    } else if cfg!(target_os = "windows") {
+        let script = format!(
+            "Add-Type -AssemblyName System.Windows.Forms; [System.Windows.Forms.SendKeys]::SendWait('{{BS {}}}')",
+            count
+        );
+        run("powershell", &["-NoProfile", "-Command", &script]).await
+    } else if wayland() {
+        let mut args = Vec::with_capacity(count * 2);
+        for _ in 0..count {
+            args.extend(["-k", "BackSpace"]);
+        }
+        run("wtype", &args).await
+    } else {
+        run(
+            "xdotool",
+            &[
+                "key",
+                "--clearmodifiers",
+                "--repeat",
+                &count_arg,
+                "BackSpace",
+            ],
+        )
+        .await
+    }
+}
+
+/// Delete the most recently injected utterance. Returns the characters removed.
+async fn scratch(typed: &mut Vec<String>) -> Result<usize, String> {
+    let Some(last) = typed.pop() else {
+        return Ok(0);
+    };
+    let count = last.chars().count();
+    if let Err(e) = backspace(count).await {
+        typed.push(last);
+        return Err(e);
+    }
+    println!("[inject] Scratched {} characters", count);
+    Ok(count)
+}
+
+/// When `text` ends with a scratch phrase, the number of words spoken before it.
+fn words_before_scratch(text: &str, phrases: &[String]) -> Option<usize> {
+    let spoken = commands::words(text);
+    phrases.iter().find_map(|phrase| {
+        let phrase = commands::words(phrase);
+        (!phrase.is_empty() && spoken.ends_with(&phrase)).then(|| spoken.len() - phrase.len())
+    })
+}
+
+/// Type one final utterance into the focused app, unless its profile turns injection
+/// off. An utterance that is (or ends with) a scratch phrase is not typed: on its own it
+/// deletes the previous utterance, and after other words it cancels just those words.
+#[tauri::command]
+#[specta::specta]
+pub async fn inject_text(
+    app: AppHandle,
+    injector: State<'_, Injector>,
+    text: String,
+) -> Result<InjectResult, String> {
+    if let Some(profile) = profiles::active(&app)
+        .await
+        .filter(|profile| !profile.inject)
+    {
+        println!("[inject] Injection is off for profile {}", profile.name);
+        return Ok(InjectResult {
+            typed: String::new(),
+            deleted: 0,
+        });
+    }
+    let config = settings::load(&app).injection;
+    let mut typed = injector.0.lock().await;
+
+    if let Some(before) = words_before_scratch(&text, &config.scratch_phrases) {
+        let deleted = if before == 0 {
+            scratch(&mut typed).await?
+        } else {
+            0
+        };
+        return Ok(InjectResult {
+            typed: String::new(),
+            deleted,
+        });
+    }
+
+    let text = text.trim();
+    if text.is_empty() {
+        return Ok(InjectResult {
+            typed: String::new(),
+            deleted: 0,
+        });
+    }
+    let output = if typed.is_empty() {
+        text.to_string()
+    } else {
+        format!(" {}", text)
+    };
+    type_text(&output).await?;
+    typed.push(output.clone());
+    if typed.len() > MAX_REMEMBERED {
+        typed.remove(0);
+    }
+    Ok(InjectResult {
+        typed: output,
+        deleted: 0,
+    })
+}
+
+/// Put the last dictation's transcript into the focused app, by typing it or pasting it
+/// through the clipboard per `injection.paste_method`. "scratch that" can remove it again.
+pub async fn paste_last(app: &AppHandle) -> Result<String, String> {
+    app_lock::ensure_unlocked(app)?;
+    let text = recent::last_transcript(app).ok_or("Nothing has been dictated yet")?;
+    match settings::load(app).injection.paste_method {
+        PasteMethod::Type => type_text(&text).await?,
+        PasteMethod::Clipboard => {
+            clipboard::write(&text).await?;
+            paste_shortcut().await?;
+        }
+    }
+    let injector = app.state::<Injector>();
+    let mut typed = injector.0.lock().await;
+    typed.push(text.clone());
+    if typed.len() > MAX_REMEMBERED {
+        typed.remove(0);
+    }
+    Ok(text)
+}
+
+#[tauri::command]
+#[specta::specta]
+pub async fn paste_last_transcript(app: AppHandle) -> Result<String, String> {
+    paste_last(&app).await
+}
+
+/// Delete the last injected utterance, as the spoken scratch phrase does.
+#[tauri::command]
+#[specta::specta]
+pub async fn scratch_that(injector: State<'_, Injector>) -> Result<usize, String> {
+    scratch(&mut *injector.0.lock().await).await
+}
+
+/// Forget injected utterances, e.g. when focus moves to another app and backspacing
+/// would delete the wrong text.
+#[tauri::command]
+#[specta::specta]
+pub async fn reset_injection(injector: State<'_, Injector>) -> Result<(), String> {
+    injector.0.lock().await.clear();
+    Ok(())
+}
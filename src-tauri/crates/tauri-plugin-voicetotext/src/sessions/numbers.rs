@@ -0,0 +1,504 @@
+//! Writing spoken numbers, amounts and measurements as figures.
+//!
+//! "two thousand twenty five dollars" becomes "$2,025" and "three point five kilometers"
+//! becomes "3.5 km", with digit grouping, decimal separator and currency placement taken
+//! from the configured locale. Bare numbers below ten stay words ("one of them"), as do
+//! digit strings without a unit, which are often years.
+
+use crate::settings::NumberFormatting;
+
+#[derive(Clone, Copy, PartialEq)]
+enum Kind {
+    /// zero to nine
+    Digit,
+    /// ten to nineteen
+    Teen,
+    Tens,
+    Hundred,
+    Scale,
+}
+
+pub const SMALL: &[&str] = &[
+    "zero",
+    "one",
+    "two",
+    "three",
+    "four",
+    "five",
+    "six",
+    "seven",
+    "eight",
+    "nine",
+    "ten",
+    "eleven",
+    "twelve",
+    "thirteen",
+    "fourteen",
+    "fifteen",
+    "sixteen",
+    "seventeen",
+    "eighteen",
+    "nineteen",
+];
+
+pub const TENS: &[(&str, u64)] = &[
+    ("twenty", 20),
+    ("thirty", 30),
+    ("forty", 40),
+    ("fifty", 50),
+    ("sixty", 60),
+    ("seventy", 70),
+    ("eighty", 80),
+    ("ninety", 90),
+];
+
+const SCALES: &[(&str, u64)] = &[
+    ("thousand", 1_000),
+    ("million", 1_000_000),
+    ("billion", 1_000_000_000),
+    ("trillion", 1_000_000_000_000),
+];
+
+/// Spoken units, longest first, and how they are written.
+const UNITS: &[(&[&str], &str)] = &[
+    (&["kilometers", "per", "hour"], "km/h"),
+    (&["kilometres", "per", "hour"], "km/h"),
+    (&["miles", "per", "hour"], "mph"),
+    (&["degrees", "celsius"], "°C"),
+    (&["degrees", "fahrenheit"], "°F"),
+    (&["square", "meters"], "m²"),
+    (&["square", "feet"], "sq ft"),
+    (&["kilometers"], "km"),
+    (&["kilometer"], "km"),
+    (&["kilometres"], "km"),
+    (&["kilometre"], "km"),
+    (&["meters"], "m"),
+    (&["meter"], "m"),
+    (&["metres"], "m"),
+    (&["metre"], "m"),
+    (&["centimeters"], "cm"),
+    (&["centimeter"], "cm"),
+    (&["millimeters"], "mm"),
+    (&["millimeter"], "mm"),
+    (&["miles"], "mi"),
+    (&["mile"], "mi"),
+    (&["feet"], "ft"),
+    (&["foot"], "ft"),
+    (&["inches"], "in"),
+    (&["inch"], "in"),
+    (&["kilograms"], "kg"),
+    (&["kilogram"], "kg"),
+    (&["kilos"], "kg"),
+    (&["grams"], "g"),
+    (&["gram"], "g"),
+    (&["pounds"], "lb"),
+    (&["pound"], "lb"),
+    (&["ounces"], "oz"),
+    (&["ounce"], "oz"),
+    (&["liters"], "L"),
+    (&["liter"], "L"),
+    (&["litres"], "L"),
+    (&["litre"], "L"),
+    (&["milliliters"], "mL"),
+    (&["milliliter"], "mL"),
+    (&["gigabytes"], "GB"),
+    (&["gigabyte"], "GB"),
+    (&["megabytes"], "MB"),
+    (&["megabyte"], "MB"),
+    (&["percent"], "%"),
+    (&["degrees"], "°"),
+    (&["degree"], "°"),
+];
+
+const CURRENCIES: &[(&[&str], &str)] = &[
+    (&["pounds", "sterling"], "£"),
+    (&["dollars"], "$"),
+    (&["dollar"], "$"),
+    (&["euros"], "€"),
+    (&["euro"], "€"),
+    (&["yen"], "¥"),
+    (&["rupees"], "₹"),
+    (&["rupee"], "₹"),
+];
+
+/// A word of the input with its case and surrounding punctuation.
+struct Token<'a> {
+    /// The word as spoken, without `tail`.
+    raw: &'a str,
+    /// Lowercase, without punctuation.
+    word: String,
+    /// Punctuation that followed the word, such as a comma.
+    tail: &'a str,
+}
+
+struct Number {
+    integer: u64,
+    fraction: Option<String>,
+    /// Whether the number was spoken in words, rather than recognized as digits.
+    spoken: bool,
+    consumed: usize,
+}
+
+struct Locale {
+    group: &'static str,
+    decimal: char,
+    /// "$5" rather than "5 $".
+    symbol_first: bool,
+}
+
+fn locale(tag: &str) -> Locale {
+    let language = tag.split(['-', '_']).next().unwrap_or("en").to_lowercase();
+    match language.as_str() {
+        "de" | "es" | "it" | "nl" | "pt" | "id" | "tr" | "da" | "el" => Locale {
+            group: ".",
+            decimal: ',',
+            symbol_first: false,
+        },
+        "fr" | "sv" | "nb" | "no" | "fi" | "pl" | "cs" | "ru" | "uk" => Locale {
+            group: "\u{202f}",
+            decimal: ',',
+            symbol_first: false,
+        },
+        _ => Locale {
+            group: ",",
+            decimal: '.',
+            symbol_first: true,
+        },
+    }
+}
+
+fn tokenize(text: &str) -> Vec<Token<'_>> {
+    let mut tokens = Vec::new();
+    for raw in text.split_whitespace() {
+        let core_end = raw
+            .char_indices()
+            .rev()
+            .find(|(_, c)| c.is_alphanumeric())
+            .map_or(0, |(i, c)| i + c.len_utf8());
+        let (core, tail) = raw.split_at(core_end);
+        // "twenty-five" is two number words
+        let parts: Vec<&str> = core.split('-').collect();
+        if parts.len() > 1
+            && parts
+                .iter()
+                .all(|part| kind(&part.to_lowercase()).is_some())
+        {
+            let last = parts.len() - 1;
+            for (i, part) in parts.into_iter().enumerate() {
+                tokens.push(Token {
+                    raw: part,
+                    word: part.to_lowercase(),
+                    tail: if i == last { tail } else { "" },
+                });
+            }
+            continue;
+        }
+        tokens.push(Token {
+            raw: core,
+            word: core
+                .chars()
+                .filter(|c| c.is_alphanumeric())
+                .collect::<String>()
+                .to_lowercase(),
+            tail,
+        });
+    }
+    tokens
+}
+
+fn kind(word: &str) -> Option<(Kind, u64)> {
+    if let Some(value) = SMALL.iter().position(|w| *w == word) {
+        let kind = if value < 10 { Kind::Digit } else { Kind::Teen };
+        return Some((kind, value as u64));
+    }
+    if let Some((_, value)) = TENS.iter().find(|(w, _)| *w == word) {
+        return Some((Kind::Tens, *value));
+    }
+    if word == "hundred" {
+        return Some((Kind::Hundred, 100));
+    }
+    SCALES
+        .iter()
+        .find(|(w, _)| *w == word)
+        .map(|(_, value)| (Kind::Scale, *value))
+}
+
+/// Read a number spoken in words at the start of `tokens`.
+fn parse_words(tokens: &[Token]) -> Option<Number> {
+    let mut total: u64 = 0;
+    let mut current: u64 = 0;
+    let mut last: Option<Kind> = None;
+    let mut consumed = 0;
+    let mut i = 0;
+    while i < tokens.len() {
+        let token = &tokens[i];
+        if token.word == "and"
+            && matches!(last, Some(Kind::Hundred | Kind::Scale))
+            && tokens
+                .get(i + 1)
+                .and_then(|next| kind(&next.word))
+                .is_some_and(|(k, _)| matches!(k, Kind::Digit | Kind::Teen | Kind::Tens))
+            && token.tail.is_empty()
+        {
+            i += 1;
+            continue;
+        }
+        let Some((word_kind, value)) = kind(&token.word) else {
+            break;
+        };
+        let fits = match word_kind {
+            // "twenty five", but not "twenty fifteen" or "five six"
+            Kind::Digit => !matches!(last, Some(Kind::Digit | Kind::Teen)),
+            Kind::Teen => !matches!(last, Some(Kind::Digit | Kind::Teen | Kind::Tens)),
+            Kind::Tens => !matches!(last, Some(Kind::Digit | Kind::Teen | Kind::Tens)),
+            Kind::Hundred => matches!(last, Some(Kind::Digit | Kind::Teen)) && current < 100,
+            Kind::Scale => current > 0,
+        };
+        if !fits {
+            break;
+        }
+        match word_kind {
+            Kind::Digit | Kind::Teen | Kind::Tens => current += value,
+            Kind::Hundred => current *= 100,
+            Kind::Scale => {
+                total = total.saturating_add(current.saturating_mul(value));
+                current = 0;
+            }
+        }
+        last = Some(word_kind);
+        i += 1;
+        consumed = i;
+        if !token.tail.is_empty() {
+            break;
+        }
+    }
+    if consumed == 0 {
+        return None;
+    }
+
+    // "point" and single digits for the fraction
+    let mut fraction = String::new();
+    let mut end = consumed;
+    if tokens[consumed - 1].tail.is_empty()
+        && tokens.get(consumed).is_some_and(|t| t.word == "point")
+        && tokens[consumed].tail.is_empty()
+    {
+        for token in &tokens[consumed + 1..] {
+            match kind(&token.word) {
+                Some((Kind::Digit, digit)) => {
+                    fraction.push(char::from(b'0' + digit as u8));
+                    end += 1;
+                    if !token.tail.is_empty() {
+                        break;
+                    }
+                }
+                _ => break,
+            }
+        }
+        if !fraction.is_empty() {
+            end += 1;
+        }
+    }
+    Some(Number {
+        integer: total.saturating_add(current),
+        fraction: (!fraction.is_empty()).then_some(fraction),
+        spoken: true,
+        consumed: end,
+    })
+}
+
+fn parse_number(tokens: &[Token]) -> Option<Number> {
+    let first = tokens.first()?;
+    if !first.word.is_empty() && first.raw.chars().all(|c| c.is_ascii_digit()) {
+        return Some(Number {
+            integer: first.raw.parse().ok()?,
+            fraction: None,
+            spoken: false,
+            consumed: 1,
+        });
+    }
+    parse_words(tokens)
+}
+
+/// The phrase from `table` at the start of `tokens`, with the number of tokens it spans.
+fn phrase_at(tokens: &[Token], table: &[(&[&str], &'static str)]) -> Option<(&'static str, usize)> {
+    table.iter().find_map(|(words, written)| {
+        let matches = words.len() <= tokens.len()
+            && words
+                .iter()
+                .zip(tokens)
+                .enumerate()
+                .all(|(i, (word, token))| {
+                    token.word == *word && (i + 1 == words.len() || token.tail.is_empty())
+                });
+        matches.then_some((*written, words.len()))
+    })
+}
+
+fn digits(number: &Number, locale: &Locale, group: bool) -> String {
+    let integer = number.integer.to_string();
+    let mut out = String::new();
+    for (i, c) in integer.chars().enumerate() {
+        let remaining = integer.len() - i;
+        if group && i > 0 && remaining.is_multiple_of(3) && integer.len() > 3 {
+            out.push_str(locale.group);
+        }
+        out.push(c);
+    }
+    if let Some(fraction) = &number.fraction {
+        out.push(locale.decimal);
+        out.push_str(fraction);
+    }
+    out
+}
+
+/// "and fifty cents" after a currency amount, as two fraction digits.
+fn cents(tokens: &[Token]) -> Option<(String, usize)> {
+    if tokens.first()?.word != "and" || !tokens[0].tail.is_empty() {
+        return None;
+    }
+    let number = parse_words(&tokens[1..])?;
+    let unit = tokens.get(1 + number.consumed)?;
+    if !matches!(unit.word.as_str(), "cents" | "cent")
+        || number.integer >= 100
+        || number.fraction.is_some()
+        || !tokens[number.consumed].tail.is_empty()
+    {
+        return None;
+    }
+    Some((format!("{:02}", number.integer), number.consumed + 2))
+}
+
+pub fn format(text: &str, config: &NumberFormatting) -> String {
+    let locale = locale(&config.locale);
+    let tokens = tokenize(text);
+    let mut out: Vec<String> = Vec::new();
+    let mut i = 0;
+    while i < tokens.len() {
+        let Some(mut number) = parse_number(&tokens[i..]) else {
+            out.push(tokens[i].raw.to_string() + tokens[i].tail);
+            i += 1;
+            continue;
+        };
+        let mut end = i + number.consumed;
+        let open = tokens[end - 1].tail.is_empty();
+        let after = &tokens[end..];
+
+        let currency = open.then(|| phrase_at(after, CURRENCIES)).flatten();
+        let unit = match currency {
+            None if config.units && open => phrase_at(after, UNITS),
+            _ => None,
+        };
+        let written = if let Some((symbol, span)) = currency.filter(|_| config.currency) {
+            end += span;
+            if number.fraction.is_none() && tokens[end - 1].tail.is_empty() {
+                if let Some((fraction, span)) = cents(&tokens[end..]) {
+                    number.fraction = Some(fraction);
+                    end += span;
+                }
+            }
+            let amount = digits(&number, &locale, config.group_digits);
+            if locale.symbol_first {
+                format!("{}{}", symbol, amount)
+            } else {
+                format!("{} {}", amount, symbol)
+            }
+        } else if let Some((symbol, span)) = unit {
+            end += span;
+            let amount = digits(&number, &locale, config.group_digits);
+            let attached = symbol.starts_with('°') || symbol == "%";
+            if attached && locale.symbol_first {
+                format!("{}{}", amount, symbol)
+            } else {
+                format!("{} {}", amount, symbol)
+            }
+        } else if number.spoken && (number.integer >= 10 || number.fraction.is_some()) {
+            digits(&number, &locale, config.group_digits)
+        } else {
+            // Small numbers and digit strings without a unit are left as they are
+            for token in &tokens[i..end] {
+                out.push(token.raw.to_string() + token.tail);
+            }
+            i = end;
+            continue;
+        };
+        out.push(written + tokens[end - 1].tail);
+        i = end;
+    }
+    out.join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(locale: &str) -> NumberFormatting {
+        NumberFormatting {
+            locale: locale.to_string(),
+            ..NumberFormatting::default()
+        }
+    }
+
+    #[test]
+    fn writes_numbers_as_figures() {
+        let cases = [
+            ("two thousand twenty five dollars", "$2,025"),
+            ("three point five kilometers", "3.5 km"),
+            (
+                "it costs twelve dollars and fifty cents.",
+                "it costs $12.50.",
+            ),
+            ("one of them", "one of them"),
+            ("in 2025 we grew", "in 2025 we grew"),
+            ("forty-two people", "42 people"),
+            ("one hundred and five", "105"),
+            ("seventy two degrees fahrenheit", "72°F"),
+            ("fifty percent, roughly", "50%, roughly"),
+            ("ten miles per hour", "10 mph"),
+            ("five kilometers", "5 km"),
+            ("three million", "3,000,000"),
+            ("Twenty Five", "25"),
+        ];
+        for (spoken, written) in cases {
+            assert_eq!(format(spoken, &config("en-US")), written, "{}", spoken);
+        }
+    }
+
+    #[test]
+    fn follows_the_locale() {
+        let cases = [
+            ("de-DE", "two thousand twenty five euros", "2.025 €"),
+            ("de-DE", "three point five kilometers", "3,5 km"),
+            ("fr-FR", "twelve thousand", "12\u{202f}000"),
+            ("de-DE", "fifty percent", "50 %"),
+            ("en-GB", "ten pounds sterling", "£10"),
+        ];
+        for (locale, spoken, written) in cases {
+            assert_eq!(
+                format(spoken, &config(locale)),
+                written,
+                "{} {}",
+                locale,
+                spoken
+            );
+        }
+    }
+
+    #[test]
+    fn leaves_units_and_currency_when_turned_off() {
+        let config = NumberFormatting {
+            group_digits: false,
+            units: false,
+            currency: false,
+            ..config("en-US")
+        };
+        let cases = [
+            ("two thousand twenty five dollars", "2025 dollars"),
+            ("three point five kilometers", "3.5 kilometers"),
+            ("five kilometers", "five kilometers"),
+        ];
+        for (spoken, written) in cases {
+            assert_eq!(format(spoken, &config), written, "{}", spoken);
+        }
+    }
+}
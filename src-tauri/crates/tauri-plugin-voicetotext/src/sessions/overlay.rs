@@ -0,0 +1,119 @@
+//! Always-on-top, click-through caption overlay window.
+//!
+//! The window loads the static `overlay.html` page and is fed directly from the backend
+//! through `WebviewWindow::eval`, so it needs no IPC permissions of its own.
+
+use crate::caption_server::Caption;
+use tauri::{
+    AppHandle, LogicalPosition, LogicalSize, Manager, WebviewUrl, WebviewWindow,
+    WebviewWindowBuilder,
+};
+
+const LABEL: &str = "caption-overlay";
+const DEFAULT_WIDTH: f64 = 900.0;
+const DEFAULT_HEIGHT: f64 = 140.0;
+
+fn build(app: &AppHandle) -> Result<WebviewWindow, String> {
+    let builder = WebviewWindowBuilder::new(app, LABEL, WebviewUrl::App("overlay.html".into()))
+        .title("Captions")
+        .inner_size(DEFAULT_WIDTH, DEFAULT_HEIGHT)
+        .decorations(false)
+        .always_on_top(true)
+        .skip_taskbar(true)
+        .resizable(false)
+        .shadow(false)
+        .focused(false)
+        .visible(false);
+    // Transparent windows need the private API on macOS; there the page keeps its dark box
+    #[cfg(not(target_os = "macos"))]
+    let builder = builder.transparent(true);
+
+    let window = builder
+        .build()
+        .map_err(|e| format!("Failed to create caption overlay: {}", e))?;
+    window
+        .set_ignore_cursor_events(true)
+        .map_err(|e| format!("Failed to make caption overlay click-through: {}", e))?;
+
+    // Default to bottom-centre of the primary monitor
+    if let Ok(Some(monitor)) = window.primary_monitor() {
+        let size = monitor.size().to_logical::<f64>(monitor.scale_factor());
+        let origin = monitor.position().to_logical::<f64>(monitor.scale_factor());
+        let _ = window.set_position(LogicalPosition::new(
+            origin.x + (size.width - DEFAULT_WIDTH) / 2.0,
+            origin.y + size.height - DEFAULT_HEIGHT - 80.0,
+        ));
+    }
+    Ok(window)
+}
+
+/// Show the latest caption in the overlay, if it is open.
+pub fn update(app: &AppHandle, caption: &Caption) {
+    let Some(window) = app.get_webview_window(LABEL) else {
+        return;
+    };
+    if !window.is_visible().unwrap_or(false) {
+        return;
+    }
+    let Ok(json) = serde_json::to_string(caption) else {
+        return;
+    };
+    let _ = window.eval(format!("window.setCaption && window.setCaption({})", json));
+}
+
+// Window creation must not happen on the main thread from a sync command (deadlocks on
+// Windows), hence async.
+#[tauri::command]
+#[specta::specta]
+pub async fn show_caption_overlay(app: AppHandle) -> Result<(), String> {
+    let window = match app.get_webview_window(LABEL) {
+        Some(window) => window,
+        None => build(&app)?,
+    };
+    window
+        .show()
+        .map_err(|e| format!("Failed to show caption overlay: {}", e))
+}
+
+#[tauri::command]
+#[specta::specta]
+pub fn hide_caption_overlay(app: AppHandle) -> Result<(), String> {
+    match app.get_webview_window(LABEL) {
+        Some(window) => window
+            .hide()
+            .map_err(|e| format!("Failed to hide caption overlay: {}", e)),
+        None => Ok(()),
+    }
+}
+
+/// Move/resize the overlay, in logical pixels. Omitted size values are left unchanged.
+#[tauri::command]
+#[specta::specta]
+pub fn set_caption_overlay_position(
+    app: AppHandle,
+    x: f64,
+    y: f64,
+    width: Option<f64>,
+    height: Option<f64>,
+) -> Result<(), String> {
+    let window = app
+        .get_webview_window(LABEL)
+        .ok_or_else(|| "Caption overlay is not open".to_string())?;
+    window
+        .set_position(LogicalPosition::new(x, y))
+        .map_err(|e| format!("Failed to move caption overlay: {}", e))?;
+
+    if width.is_some() || height.is_some() {
+        let current = window
+            .inner_size()
+            .map_err(|e| format!("Failed to read caption overlay size: {}", e))?
+            .to_logical::<f64>(window.scale_factor().unwrap_or(1.0));
+        window
+            .set_size(LogicalSize::new(
+                width.unwrap_or(current.width),
+                height.unwrap_or(current.height),
+            ))
+            .map_err(|e| format!("Failed to resize caption overlay: {}", e))?;
+    }
+    Ok(())
+}
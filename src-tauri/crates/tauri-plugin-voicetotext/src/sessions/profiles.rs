@@ -0,0 +1,95 @@
+//! Per-application dictation profiles.
+//!
+//! The foreground application is looked up when a final result is processed and when it
+//! is typed, and the first profile listing it adjusts the dictation passes for it, e.g.
+//! no number formatting in an IDE or no injection into a terminal. The lookup uses
+//! System Events on macOS, the foreground window's process on Windows and `xdotool` on
+//! X11; Wayland compositors don't expose the focused window, so no profile applies there.
+
+use crate::settings::{self, AppProfile};
+use serde::Serialize;
+use specta::Type;
+use tauri::AppHandle;
+use tokio::process::Command;
+
+const WINDOWS_FOREGROUND: &str = r#"Add-Type @"
+using System; using System.Runtime.InteropServices;
+public class Fg {
+  [DllImport("user32.dll")] public static extern IntPtr GetForegroundWindow();
+  [DllImport("user32.dll")] public static extern uint GetWindowThreadProcessId(IntPtr h, out uint p);
+}
+"@
+$p = 0; [void][Fg]::GetWindowThreadProcessId([Fg]::GetForegroundWindow(), [ref]$p)
+(Get-Process -Id $p).ProcessName"#;
+
+#[derive(Serialize, Clone, Debug, Type)]
+pub struct ForegroundApp {
+    pub name: Option<String>,
+    /// Profile that applies to it, if any.
+    pub profile: Option<String>,
+}
+
+async fn output(program: &str, args: &[&str]) -> Option<String> {
+    let output = Command::new(program).args(args).output().await.ok()?;
+    let text = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    (output.status.success() && !text.is_empty()).then_some(text)
+}
+
+/// Name of the application that has keyboard focus, when it can be told.
+pub async fn foreground_app() -> Option<String> {
+    if cfg!(target_os = "macos") {
+        output(
+            "osascript",
+            &[
+                "-e",
+                "tell application \"System Events\" to get name of first application process whose frontmost is true",
+            ],
+        )
+        .await
+    } else if cfg!(target_os = "windows") {
+        output(
+            "powershell",
+            &["-NoProfile", "-Command", WINDOWS_FOREGROUND],
+        )
+        .await
+    } else if std::env::var_os("WAYLAND_DISPLAY").is_some() {
+        None
+    } else {
+        let pid = output("xdotool", &["getactivewindow", "getwindowpid"]).await?;
+        let comm = std::fs::read_to_string(format!("/proc/{}/comm", pid.trim())).ok()?;
+        Some(comm.trim().to_string())
+    }
+}
+
+fn matches(profile: &AppProfile, name: &str) -> bool {
+    let name = name.trim_end_matches(".exe");
+    profile
+        .apps
+        .iter()
+        .any(|app| app.trim_end_matches(".exe").eq_ignore_ascii_case(name))
+}
+
+/// The profile for the foreground application. No lookup happens without profiles.
+pub async fn active(app: &AppHandle) -> Option<AppProfile> {
+    let profiles = settings::load(app).profiles;
+    if profiles.is_empty() {
+        return None;
+    }
+    let name = foreground_app().await?;
+    profiles.into_iter().find(|profile| matches(profile, &name))
+}
+
+/// Report the foreground application and its profile, for setting up profiles.
+#[tauri::command]
+#[specta::specta]
+pub async fn get_foreground_app(app: AppHandle) -> ForegroundApp {
+    let name = foreground_app().await;
+    let profile = name.as_ref().and_then(|name| {
+        settings::load(&app)
+            .profiles
+            .into_iter()
+            .find(|profile| matches(profile, name))
+            .map(|profile| profile.name)
+    });
+    ForegroundApp { name, profile }
+}
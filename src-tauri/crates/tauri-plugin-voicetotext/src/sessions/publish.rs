@@ -0,0 +1,153 @@
+//! Read-only pages for sharing a session: one self-contained HTML file with the
+//! transcript and, optionally, the audio embedded, so the link works without the app.
+
+use crate::history::{self, Session};
+use crate::{app_lock, prerecorded, recordings, settings, sync};
+use base64::Engine;
+use std::fs;
+use std::path::PathBuf;
+use tauri::{AppHandle, Manager};
+
+const STYLE: &str = "body{font-family:system-ui,sans-serif;max-width:46rem;margin:2rem auto;\
+padding:0 1rem;line-height:1.5;color:#222}h1{font-size:1.5rem}h2{font-size:1.1rem;\
+margin-top:2rem}audio{width:100%;position:sticky;top:0}p{margin:.4rem 0}\
+.time{color:#888;font-size:.8rem;margin-right:.5rem;cursor:pointer}.speaker{font-weight:600}";
+
+/// Seeks the player when a timestamp is clicked.
+const SCRIPT: &str = "document.querySelectorAll('.time').forEach(function(t){t.onclick=\
+function(){var a=document.querySelector('audio');if(a){a.currentTime=+t.dataset.start;\
+a.play();}};});";
+
+fn escape(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&#39;"),
+            _ => out.push(c),
+        }
+    }
+    out
+}
+
+/// `M:SS`, or `H:MM:SS` past the hour.
+fn clock(seconds: f64) -> String {
+    let total = seconds.max(0.0) as u64;
+    match total / 3600 {
+        0 => format!("{}:{:02}", total / 60, total % 60),
+        hours => format!("{}:{:02}:{:02}", hours, (total / 60) % 60, total % 60),
+    }
+}
+
+/// `src` for the session's audio: a data URI for local recordings, the original address
+/// for URL sessions. Other sessions keep no audio.
+fn audio_source(app: &AppHandle, session: &Session) -> Result<Option<String>, String> {
+    let Some((path, bytes)) = recordings::read(app, session)? else {
+        return Ok(session.source.strip_prefix("url:").map(str::to_string));
+    };
+    Ok(Some(format!(
+        "data:{};base64,{}",
+        prerecorded::content_type(&path),
+        base64::engine::general_purpose::STANDARD.encode(bytes)
+    )))
+}
+
+pub fn render(session: &Session, audio: Option<&str>) -> String {
+    let title = escape(&session.title);
+    let mut out = format!(
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n\
+<meta name=\"viewport\" content=\"width=device-width, initial-scale=1\">\n\
+<title>{}</title>\n<style>{}</style>\n</head>\n<body>\n<h1>{}</h1>\n",
+        title, STYLE, title
+    );
+    if let Some(src) = audio {
+        out.push_str(&format!(
+            "<audio controls preload=\"metadata\" src=\"{}\"></audio>\n",
+            escape(src)
+        ));
+    }
+
+    let mut chapters = session.chapters.iter().peekable();
+    for segment in &session.segments {
+        while let Some(chapter) = chapters.next_if(|chapter| chapter.start <= segment.start) {
+            out.push_str(&format!("<h2>{}</h2>\n", escape(&chapter.title)));
+        }
+        out.push_str(&format!(
+            "<p><span class=\"time\" data-start=\"{}\">{}</span>",
+            segment.start,
+            clock(segment.start)
+        ));
+        if let Some(speaker) = segment.speaker {
+            out.push_str(&format!(
+                "<span class=\"speaker\">Speaker {}:</span> ",
+                speaker
+            ));
+        }
+        out.push_str(&escape(segment.text.trim()));
+        out.push_str("</p>\n");
+    }
+    out.push_str(&format!("<script>{}</script>\n</body>\n</html>\n", SCRIPT));
+    out
+}
+
+pub fn published_dir(app: &AppHandle) -> Result<PathBuf, String> {
+    let dir = app
+        .path()
+        .app_data_dir()
+        .map_err(|e| format!("Failed to resolve app data directory: {}", e))?
+        .join("published");
+    fs::create_dir_all(&dir).map_err(|e| format!("Failed to create published directory: {}", e))?;
+    Ok(dir)
+}
+
+/// Render a session as a shareable page and return where it can be opened.
+///
+/// With `path` the page is written there. Otherwise it is uploaded to the configured
+/// publishing host and its public URL returned, or written to `<app data dir>/published`
+/// when none is configured.
+#[tauri::command]
+#[specta::specta]
+pub async fn publish_session(
+    app: AppHandle,
+    session_id: String,
+    include_audio: bool,
+    path: Option<String>,
+) -> Result<String, String> {
+    app_lock::ensure_unlocked(&app)?;
+    let session = history::load(&app, &session_id)?;
+    let audio = if include_audio {
+        audio_source(&app, &session)?
+    } else {
+        None
+    };
+    let page = render(&session, audio.as_deref());
+    let name = format!("{}.html", session.id);
+
+    if let Some(path) = path {
+        fs::write(&path, page).map_err(|e| format!("Failed to write {}: {}", path, e))?;
+        return Ok(path);
+    }
+    match settings::load(&app).publishing {
+        Some(config) => {
+            sync::upload(
+                config.target,
+                &name,
+                "text/html; charset=utf-8",
+                page.into_bytes(),
+            )
+            .await?;
+            let url = format!("{}/{}", config.public_url.trim_end_matches('/'), name);
+            println!("[publish] Published session {} at {}", session.id, url);
+            Ok(url)
+        }
+        None => {
+            let path = published_dir(&app)?.join(name);
+            fs::write(&path, page)
+                .map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+            Ok(path.display().to_string())
+        }
+    }
+}
@@ -0,0 +1,123 @@
+//! A short in-memory history of the last final utterances.
+//!
+//! Lets users get back something dictated a minute ago without opening full history.
+//! Only dictation is kept (not backend streams), and nothing is written to disk.
+
+use crate::settings;
+use crate::{app_lock, clipboard, history};
+use serde::Serialize;
+use specta::Type;
+use std::collections::VecDeque;
+use std::sync::Mutex;
+use tauri::{AppHandle, Manager, State};
+
+#[derive(Serialize, Clone, Debug, Type)]
+pub struct Utterance {
+    pub text: String,
+    pub created_at: u64,
+    /// Which dictation the utterance belongs to, so its transcript can be put together.
+    #[serde(skip)]
+    dictation: u64,
+}
+
+#[derive(Default)]
+struct Recent {
+    /// Newest last.
+    utterances: VecDeque<Utterance>,
+    dictation: u64,
+}
+
+#[derive(Default)]
+pub struct RecentUtterances(Mutex<Recent>);
+
+pub fn record(app: &AppHandle, text: &str) {
+    let limit = settings::load(app).recent.max_utterances;
+    if limit == 0 || text.trim().is_empty() {
+        return;
+    }
+    let recent = app.state::<RecentUtterances>();
+    let mut recent = recent.0.lock().unwrap();
+    let dictation = recent.dictation;
+    recent.utterances.push_back(Utterance {
+        text: text.trim().to_string(),
+        created_at: history::now_ms(),
+        dictation,
+    });
+    while recent.utterances.len() > limit {
+        recent.utterances.pop_front();
+    }
+}
+
+/// Start a new dictation; utterances recorded from now on form its transcript.
+pub fn begin_dictation(app: &AppHandle) {
+    app.state::<RecentUtterances>().0.lock().unwrap().dictation += 1;
+}
+
+pub fn clear(app: &AppHandle) {
+    app.state::<RecentUtterances>()
+        .0
+        .lock()
+        .unwrap()
+        .utterances
+        .clear();
+}
+
+/// The `n`th most recent utterance, 0 being the last one.
+pub fn get(recent: &RecentUtterances, n: usize) -> Option<Utterance> {
+    recent
+        .0
+        .lock()
+        .unwrap()
+        .utterances
+        .iter()
+        .rev()
+        .nth(n)
+        .cloned()
+}
+
+/// The final transcript of the most recent dictation that is still in the buffer.
+pub fn last_transcript(app: &AppHandle) -> Option<String> {
+    let recent = app.state::<RecentUtterances>();
+    let recent = recent.0.lock().unwrap();
+    let last = recent.utterances.back()?.dictation;
+    let texts: Vec<&str> = recent
+        .utterances
+        .iter()
+        .filter(|utterance| utterance.dictation == last)
+        .map(|utterance| utterance.text.as_str())
+        .collect();
+    Some(texts.join(" "))
+}
+
+/// Recent utterances, newest first.
+#[tauri::command]
+#[specta::specta]
+pub fn get_recent_utterances(
+    app: AppHandle,
+    recent: State<'_, RecentUtterances>,
+) -> Result<Vec<Utterance>, String> {
+    app_lock::ensure_unlocked(&app)?;
+    Ok(recent
+        .0
+        .lock()
+        .unwrap()
+        .utterances
+        .iter()
+        .rev()
+        .cloned()
+        .collect())
+}
+
+/// Copy the `n`th most recent utterance (0 is the last) to the clipboard.
+#[tauri::command]
+#[specta::specta]
+pub async fn copy_utterance(
+    app: AppHandle,
+    recent: State<'_, RecentUtterances>,
+    n: usize,
+) -> Result<Utterance, String> {
+    app_lock::ensure_unlocked(&app)?;
+    let utterance = get(&recent, n).ok_or_else(|| format!("No utterance {}", n))?;
+    clipboard::write(&utterance.text).await?;
+    Ok(utterance)
+}
@@ -0,0 +1,80 @@
+//! Spoken snippets: trigger phrases that expand to longer stored text.
+//!
+//! Saying a trigger such as "insert disclaimer" anywhere in an utterance replaces it with
+//! the snippet's text. Expansion is the last dictation pass, so the stored text is
+//! injected and saved exactly as written.
+
+use crate::commands;
+use crate::settings::{self, Snippet};
+use tauri::AppHandle;
+
+/// Replace every trigger phrase in `text` with its snippet.
+pub fn expand(text: &str, snippets: &[Snippet]) -> String {
+    replace_phrases(
+        text,
+        snippets
+            .iter()
+            .map(|snippet| (snippet.trigger.as_str(), snippet.text.as_str())),
+    )
+}
+
+/// Replace each spoken `(phrase, replacement)` in `text`. Matching ignores case and
+/// punctuation; longer phrases win over shorter ones starting at the same word.
+pub fn replace_phrases<'a>(
+    text: &str,
+    phrases: impl IntoIterator<Item = (&'a str, &'a str)>,
+) -> String {
+    let mut triggers: Vec<(Vec<String>, &str)> = phrases
+        .into_iter()
+        .map(|(phrase, replacement)| (commands::words(phrase), replacement))
+        .filter(|(words, _)| !words.is_empty())
+        .collect();
+    if triggers.is_empty() {
+        return text.to_string();
+    }
+    triggers.sort_by_key(|(words, _)| std::cmp::Reverse(words.len()));
+
+    let raw: Vec<&str> = text.split_whitespace().collect();
+    let spoken: Vec<String> = raw
+        .iter()
+        .map(|word| commands::words(word).concat())
+        .collect();
+    let mut out: Vec<&str> = Vec::new();
+    let mut i = 0;
+    while i < raw.len() {
+        match triggers
+            .iter()
+            .find(|(words, _)| spoken[i..].starts_with(words))
+        {
+            Some((words, expansion)) => {
+                out.push(expansion);
+                i += words.len();
+            }
+            None => {
+                out.push(raw[i]);
+                i += 1;
+            }
+        }
+    }
+    out.join(" ")
+}
+
+/// Validate and save the snippets table.
+#[tauri::command]
+#[specta::specta]
+pub fn set_snippets(app: AppHandle, snippets: Vec<Snippet>) -> Result<(), String> {
+    let mut seen = Vec::new();
+    for snippet in &snippets {
+        let trigger = commands::words(&snippet.trigger);
+        if trigger.is_empty() {
+            return Err("Snippet trigger is empty".to_string());
+        }
+        if seen.contains(&trigger) {
+            return Err(format!("Duplicate snippet trigger: {}", snippet.trigger));
+        }
+        seen.push(trigger);
+    }
+    let mut current = settings::load(&app);
+    current.snippets = snippets;
+    settings::save(&app, &current)
+}
@@ -0,0 +1,249 @@
+//! Spelling mode, for dictating identifiers, email addresses and license plates.
+//!
+//! While it is on, recognized words are read as characters: NATO alphabet words become
+//! letters ("alpha bravo" → "ab"), number words become digits and symbol names become
+//! symbols ("at", "dot", "underscore"). "capital" or "uppercase" raises the next letter.
+//! The mode is toggled by saying "spelling mode" / "stop spelling", with the
+//! `toggle_spelling` hotkey or from the frontend, and changes are announced as
+//! `spelling:changed`.
+
+use crate::commands;
+use crate::schema;
+use serde::Serialize;
+use specta::Type;
+use std::sync::atomic::{AtomicBool, Ordering};
+use tauri::{AppHandle, Manager, State};
+
+const START_PHRASES: &[&str] = &["spelling mode", "start spelling", "spell mode"];
+const STOP_PHRASES: &[&str] = &[
+    "stop spelling",
+    "end spelling",
+    "spelling off",
+    "dictation mode",
+];
+
+const LETTERS: &[(&str, char)] = &[
+    ("alpha", 'a'),
+    ("alfa", 'a'),
+    ("bravo", 'b'),
+    ("charlie", 'c'),
+    ("delta", 'd'),
+    ("echo", 'e'),
+    ("foxtrot", 'f'),
+    ("golf", 'g'),
+    ("hotel", 'h'),
+    ("india", 'i'),
+    ("juliet", 'j'),
+    ("juliett", 'j'),
+    ("kilo", 'k'),
+    ("lima", 'l'),
+    ("mike", 'm'),
+    ("november", 'n'),
+    ("oscar", 'o'),
+    ("papa", 'p'),
+    ("quebec", 'q'),
+    ("romeo", 'r'),
+    ("sierra", 's'),
+    ("tango", 't'),
+    ("uniform", 'u'),
+    ("victor", 'v'),
+    ("whiskey", 'w'),
+    ("xray", 'x'),
+    ("x-ray", 'x'),
+    ("yankee", 'y'),
+    ("zulu", 'z'),
+];
+
+const DIGITS: &[(&str, char)] = &[
+    ("zero", '0'),
+    ("oh", '0'),
+    ("one", '1'),
+    ("two", '2'),
+    ("three", '3'),
+    ("four", '4'),
+    ("five", '5'),
+    ("six", '6'),
+    ("seven", '7'),
+    ("eight", '8'),
+    ("nine", '9'),
+    ("niner", '9'),
+];
+
+const SYMBOLS: &[(&str, &str)] = &[
+    ("at", "@"),
+    ("dot", "."),
+    ("period", "."),
+    ("point", "."),
+    ("dash", "-"),
+    ("hyphen", "-"),
+    ("minus", "-"),
+    ("underscore", "_"),
+    ("slash", "/"),
+    ("backslash", "\\"),
+    ("colon", ":"),
+    ("semicolon", ";"),
+    ("comma", ","),
+    ("plus", "+"),
+    ("equals", "="),
+    ("hash", "#"),
+    ("pound", "#"),
+    ("dollar", "$"),
+    ("percent", "%"),
+    ("ampersand", "&"),
+    ("asterisk", "*"),
+    ("star", "*"),
+    ("tilde", "~"),
+    ("exclamation", "!"),
+    ("question", "?"),
+    ("space", " "),
+];
+
+/// Symbol names spoken as two words.
+const PAIRS: &[(&str, &str, &str)] = &[
+    ("at", "sign", "@"),
+    ("dollar", "sign", "$"),
+    ("percent", "sign", "%"),
+    ("plus", "sign", "+"),
+    ("equals", "sign", "="),
+    ("question", "mark", "?"),
+    ("exclamation", "mark", "!"),
+    ("exclamation", "point", "!"),
+    ("forward", "slash", "/"),
+    ("back", "slash", "\\"),
+];
+
+#[derive(Default)]
+pub struct SpellingMode(AtomicBool);
+
+/// Payload of the `spelling:changed` event.
+#[derive(Serialize, Clone, Debug, Type)]
+pub struct SpellingChanged {
+    pub enabled: bool,
+}
+
+fn lookup<T: Copy>(table: &[(&str, T)], word: &str) -> Option<T> {
+    table
+        .iter()
+        .find(|(name, _)| *name == word)
+        .map(|(_, value)| *value)
+}
+
+/// Convert spoken spelling to the characters it names.
+pub fn spell(text: &str) -> String {
+    // Hyphens are kept so "x-ray" survives; apostrophes are dropped
+    let words: Vec<String> = text
+        .split_whitespace()
+        .map(|word| {
+            word.chars()
+                .filter(|c| c.is_alphanumeric() || *c == '-')
+                .collect::<String>()
+                .to_lowercase()
+        })
+        .filter(|word| !word.is_empty())
+        .collect();
+
+    let mut out = String::new();
+    let mut upper = false;
+    let mut i = 0;
+    while i < words.len() {
+        let word = words[i].as_str();
+        if let Some(symbol) = words.get(i + 1).and_then(|next| {
+            PAIRS
+                .iter()
+                .find(|(first, second, _)| *first == word && second == next)
+                .map(|(_, _, symbol)| *symbol)
+        }) {
+            out.push_str(symbol);
+            i += 2;
+            continue;
+        }
+        i += 1;
+        if matches!(word, "capital" | "uppercase" | "cap") {
+            upper = true;
+            continue;
+        }
+        let letter = lookup(LETTERS, word).or_else(|| {
+            // Single letters and digits as recognized ("a", "b", "7")
+            let mut chars = word.chars();
+            match (chars.next(), chars.next()) {
+                (Some(c), None) if c.is_alphanumeric() => Some(c),
+                _ => None,
+            }
+        });
+        if let Some(letter) = letter {
+            out.extend(if upper {
+                letter.to_uppercase().collect::<Vec<_>>()
+            } else {
+                vec![letter]
+            });
+        } else if let Some(digit) = lookup(DIGITS, word) {
+            out.push(digit);
+        } else if let Some(symbol) = lookup(SYMBOLS, word) {
+            out.push_str(symbol);
+        } else {
+            // Numbers as recognized, and unknown words so nothing spoken is lost
+            out.push_str(word);
+        }
+        upper = false;
+    }
+    out
+}
+
+pub fn is_enabled(app: &AppHandle) -> bool {
+    app.state::<SpellingMode>().0.load(Ordering::Relaxed)
+}
+
+pub fn set(app: &AppHandle, enabled: bool) {
+    let previous = app
+        .state::<SpellingMode>()
+        .0
+        .swap(enabled, Ordering::Relaxed);
+    if previous != enabled {
+        println!(
+            "[spelling] Spelling mode {}",
+            if enabled { "on" } else { "off" }
+        );
+        let _ = schema::emit(app, "spelling:changed", SpellingChanged { enabled });
+    }
+}
+
+pub fn toggle(app: &AppHandle) {
+    set(app, !is_enabled(app));
+}
+
+fn is_phrase(spoken: &[String], phrases: &[&str]) -> bool {
+    phrases
+        .iter()
+        .any(|phrase| commands::words(phrase) == spoken)
+}
+
+/// Apply spelling mode to one recognized utterance. Returns `None` when the utterance
+/// toggled the mode and shouldn't be used as text.
+pub fn apply(app: &AppHandle, text: &str) -> Option<String> {
+    let spoken = commands::words(text);
+    if is_phrase(&spoken, START_PHRASES) {
+        set(app, true);
+        return None;
+    }
+    if is_phrase(&spoken, STOP_PHRASES) {
+        set(app, false);
+        return None;
+    }
+    Some(if is_enabled(app) {
+        spell(text)
+    } else {
+        text.to_string()
+    })
+}
+
+#[tauri::command]
+#[specta::specta]
+pub fn set_spelling_mode(app: AppHandle, enabled: bool) {
+    set(&app, enabled);
+}
+
+#[tauri::command]
+#[specta::specta]
+pub fn get_spelling_mode(mode: State<'_, SpellingMode>) -> bool {
+    mode.0.load(Ordering::Relaxed)
+}
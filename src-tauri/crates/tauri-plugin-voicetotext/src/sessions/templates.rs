@@ -0,0 +1,273 @@
+//! Structured dictation into templates such as an email, a SOAP note or meeting minutes.
+//!
+//! A template is a text with named slots, e.g. `"Subject: {subject}\n\n{body}"`. While a
+//! document is open, dictated text fills the current slot instead of the flat transcript,
+//! and spoken commands move between slots: "next section", "previous section",
+//! "go to <slot>" and "finish document". Saying "new <template>" opens a document by
+//! voice. Every change is emitted as `template:updated`; finishing emits
+//! `template:finished` with the rendered text.
+
+use crate::app_lock;
+use crate::commands;
+use crate::schema;
+use crate::settings::{self, Template};
+use serde::Serialize;
+use specta::Type;
+use std::collections::BTreeMap;
+use std::sync::Mutex;
+use tauri::{AppHandle, Manager};
+
+const NEXT_PHRASES: &[&str] = &["next section", "next field", "next slot"];
+const PREVIOUS_PHRASES: &[&str] = &["previous section", "previous field", "go back"];
+const FINISH_PHRASES: &[&str] = &["finish document", "end document", "close document"];
+const CLEAR_PHRASES: &[&str] = &["clear section", "clear field"];
+
+/// Built-in templates, available unless settings define one with the same name.
+const BUILT_IN: &[(&str, &str)] = &[
+    ("email", "To: {to}\nSubject: {subject}\n\n{body}\n\n{closing}"),
+    (
+        "soap note",
+        "Subjective:\n{subjective}\n\nObjective:\n{objective}\n\nAssessment:\n{assessment}\n\nPlan:\n{plan}",
+    ),
+    (
+        "meeting minutes",
+        "Meeting: {title}\nAttendees: {attendees}\n\nDiscussion:\n{discussion}\n\nDecisions:\n{decisions}\n\nAction items:\n{action items}",
+    ),
+];
+
+struct Document {
+    template: Template,
+    slots: Vec<String>,
+    current: usize,
+    values: BTreeMap<String, String>,
+}
+
+/// The document being dictated, if any.
+#[derive(Default)]
+pub struct TemplateDocument(Mutex<Option<Document>>);
+
+/// Payload of `template:updated` and `template:finished`, and what the commands return.
+#[derive(Serialize, Clone, Debug, Type)]
+pub struct DocumentState {
+    pub template: String,
+    pub slots: Vec<String>,
+    /// The slot dictation goes into.
+    pub current: String,
+    pub values: BTreeMap<String, String>,
+    pub text: String,
+}
+
+/// Slot names in order of appearance, without duplicates.
+fn slots(body: &str) -> Vec<String> {
+    let mut slots: Vec<String> = Vec::new();
+    let mut rest = body;
+    while let Some(start) = rest.find('{') {
+        let Some(len) = rest[start + 1..].find('}') else {
+            break;
+        };
+        let name = rest[start + 1..start + 1 + len].trim().to_string();
+        if !name.is_empty() && !slots.contains(&name) {
+            slots.push(name);
+        }
+        rest = &rest[start + 2 + len..];
+    }
+    slots
+}
+
+fn render(template: &Template, values: &BTreeMap<String, String>) -> String {
+    let mut text = String::new();
+    let mut rest = template.body.as_str();
+    while let Some(start) = rest.find('{') {
+        let Some(len) = rest[start + 1..].find('}') else {
+            break;
+        };
+        text.push_str(&rest[..start]);
+        let name = rest[start + 1..start + 1 + len].trim();
+        text.push_str(values.get(name).map_or("", |value| value.trim()));
+        rest = &rest[start + 2 + len..];
+    }
+    text.push_str(rest);
+    text
+}
+
+/// Built-in templates followed by those from settings, which replace built-ins by name.
+pub fn all(app: &AppHandle) -> Vec<Template> {
+    let custom = settings::load(app).templates;
+    let mut templates: Vec<Template> = BUILT_IN
+        .iter()
+        .filter(|(name, _)| !custom.iter().any(|t| t.name.eq_ignore_ascii_case(name)))
+        .map(|(name, body)| Template {
+            name: name.to_string(),
+            body: body.to_string(),
+        })
+        .collect();
+    templates.extend(custom);
+    templates
+}
+
+fn state(document: &Document) -> DocumentState {
+    DocumentState {
+        template: document.template.name.clone(),
+        slots: document.slots.clone(),
+        current: document.slots[document.current].clone(),
+        values: document.values.clone(),
+        text: render(&document.template, &document.values),
+    }
+}
+
+fn updated(app: &AppHandle, document: &Document) -> DocumentState {
+    let state = state(document);
+    let _ = schema::emit(app, "template:updated", state.clone());
+    state
+}
+
+fn open(app: &AppHandle, name: &str) -> Result<DocumentState, String> {
+    let template = all(app)
+        .into_iter()
+        .find(|template| template.name.eq_ignore_ascii_case(name))
+        .ok_or_else(|| format!("No such template: {}", name))?;
+    let slots = slots(&template.body);
+    if slots.is_empty() {
+        return Err(format!("Template {} has no slots", template.name));
+    }
+    println!("[templates] Opened {}", template.name);
+    let document = Document {
+        template,
+        slots,
+        current: 0,
+        values: BTreeMap::new(),
+    };
+    let state = updated(app, &document);
+    *app.state::<TemplateDocument>().0.lock().unwrap() = Some(document);
+    Ok(state)
+}
+
+fn finish(app: &AppHandle) -> Option<DocumentState> {
+    let document = app.state::<TemplateDocument>().0.lock().unwrap().take()?;
+    let state = state(&document);
+    println!("[templates] Finished {}", state.template);
+    let _ = schema::emit(app, "template:finished", state.clone());
+    Some(state)
+}
+
+fn is_phrase(spoken: &[String], phrases: &[&str]) -> bool {
+    phrases
+        .iter()
+        .any(|phrase| commands::words(phrase) == spoken)
+}
+
+/// Handle a template voice command in `text`. Returns false when it isn't one.
+pub fn handle_command(app: &AppHandle, text: &str) -> bool {
+    let spoken = commands::words(text);
+    let document = app.state::<TemplateDocument>();
+    let mut guard = document.0.lock().unwrap();
+
+    let Some(document) = guard.as_mut() else {
+        // Only opening a document works without one
+        let name = match spoken.split_first() {
+            Some((first, rest)) if first == "new" && !rest.is_empty() => rest.join(" "),
+            _ => return false,
+        };
+        drop(guard);
+        return open(app, &name).is_ok();
+    };
+
+    if is_phrase(&spoken, NEXT_PHRASES) {
+        document.current = (document.current + 1).min(document.slots.len() - 1);
+    } else if is_phrase(&spoken, PREVIOUS_PHRASES) {
+        document.current = document.current.saturating_sub(1);
+    } else if is_phrase(&spoken, CLEAR_PHRASES) {
+        let slot = document.slots[document.current].clone();
+        document.values.remove(&slot);
+    } else if is_phrase(&spoken, FINISH_PHRASES) {
+        drop(guard);
+        finish(app);
+        return true;
+    } else if let Some(index) = spoken
+        .strip_prefix(&["go".to_string(), "to".to_string()])
+        .and_then(|target| {
+            let target = target.join(" ");
+            document
+                .slots
+                .iter()
+                .position(|slot| commands::words(slot).join(" ") == target)
+        })
+    {
+        document.current = index;
+    } else {
+        return false;
+    }
+    updated(app, document);
+    true
+}
+
+/// Add dictated text to the current slot of the open document. Returns false when no
+/// document is open and the text belongs in the transcript.
+pub fn dictate(app: &AppHandle, text: &str) -> bool {
+    let document = app.state::<TemplateDocument>();
+    let mut guard = document.0.lock().unwrap();
+    let Some(document) = guard.as_mut() else {
+        return false;
+    };
+    let slot = document.slots[document.current].clone();
+    let value = document.values.entry(slot).or_default();
+    if !value.is_empty() {
+        value.push(' ');
+    }
+    value.push_str(text.trim());
+    updated(app, document);
+    true
+}
+
+#[tauri::command]
+#[specta::specta]
+pub fn list_templates(app: AppHandle) -> Vec<Template> {
+    all(&app)
+}
+
+/// Open a document from the named template; dictation fills it until it is finished.
+#[tauri::command]
+#[specta::specta]
+pub fn start_template(app: AppHandle, name: String) -> Result<DocumentState, String> {
+    open(&app, &name)
+}
+
+/// Move to another slot, by name or by `"next"` / `"previous"`.
+#[tauri::command]
+#[specta::specta]
+pub fn select_template_slot(app: AppHandle, slot: String) -> Result<DocumentState, String> {
+    let document = app.state::<TemplateDocument>();
+    let mut guard = document.0.lock().unwrap();
+    let document = guard.as_mut().ok_or("No template document is open")?;
+    document.current = match slot.as_str() {
+        "next" => (document.current + 1).min(document.slots.len() - 1),
+        "previous" => document.current.saturating_sub(1),
+        name => document
+            .slots
+            .iter()
+            .position(|s| s == name)
+            .ok_or_else(|| format!("No such slot: {}", name))?,
+    };
+    Ok(updated(&app, document))
+}
+
+#[tauri::command]
+#[specta::specta]
+pub fn get_template_document(app: AppHandle) -> Result<Option<DocumentState>, String> {
+    app_lock::ensure_unlocked(&app)?;
+    Ok(app
+        .state::<TemplateDocument>()
+        .0
+        .lock()
+        .unwrap()
+        .as_ref()
+        .map(state))
+}
+
+/// Close the open document and return its rendered text.
+#[tauri::command]
+#[specta::specta]
+pub fn finish_template(app: AppHandle) -> Result<DocumentState, String> {
+    app_lock::ensure_unlocked(&app)?;
+    finish(&app).ok_or_else(|| "No template document is open".to_string())
+}
@@ -0,0 +1,346 @@
+//! Reading transcripts aloud, for low-vision users and for proofreading by ear.
+//!
+//! Speech comes from the platform's voice (`say` on macOS, `espeak-ng` on Linux,
+//! System.Speech on Windows) or, when `tts.piper_model` is set, from piper, whose output
+//! is played with the platform's player. Text is spoken one sentence or segment at a time,
+//! so a readback can be paused, resumed and skipped through with `control_readback`;
+//! pausing restarts the current sentence on resume. Progress is emitted as `tts:state`.
+//! Only one readback plays at a time: starting another stops the current one.
+
+use crate::app_lock;
+use crate::history;
+use crate::schema;
+use crate::settings::{self, TtsSettings};
+use serde::{Deserialize, Serialize};
+use specta::Type;
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::sync::Mutex;
+use tauri::{AppHandle, Manager, State};
+use tokio::io::AsyncWriteExt;
+use tokio::process::Command;
+use tokio::sync::mpsc;
+
+/// Words per minute of `say` and `espeak-ng` at their normal rate.
+const NORMAL_WPM: f32 = 175.0;
+
+/// The readback playing now.
+#[derive(Default)]
+pub struct Readback(Mutex<Option<ActiveReadback>>);
+
+struct ActiveReadback {
+    id: String,
+    controls: mpsc::UnboundedSender<ReadbackControl>,
+}
+
+#[derive(Deserialize, Clone, Copy, Debug, Type)]
+#[serde(rename_all = "snake_case")]
+pub enum ReadbackControl {
+    Pause,
+    Resume,
+    Stop,
+    /// Skip to the next sentence or segment.
+    Next,
+    /// Go back to the previous sentence or segment.
+    Previous,
+}
+
+#[derive(Serialize, Clone, Copy, Debug, PartialEq, Eq, Type)]
+#[serde(rename_all = "snake_case")]
+pub enum PlaybackState {
+    Playing,
+    Paused,
+    Finished,
+    Stopped,
+    Failed,
+}
+
+/// Payload of the `tts:state` event.
+#[derive(Serialize, Clone, Debug, Type)]
+pub struct ReadbackEvent {
+    pub readback_id: String,
+    pub state: PlaybackState,
+    /// The sentence or segment being read, counted from zero.
+    pub index: usize,
+    pub total: usize,
+    pub text: Option<String>,
+    pub error: Option<String>,
+}
+
+/// Split free text into sentences, keeping their punctuation.
+fn sentences(text: &str) -> Vec<String> {
+    let mut sentences = Vec::new();
+    let mut current = String::new();
+    for c in text.chars() {
+        current.push(c);
+        if matches!(c, '.' | '!' | '?' | '\n') {
+            sentences.push(std::mem::take(&mut current));
+        }
+    }
+    sentences.push(current);
+    sentences
+        .into_iter()
+        .map(|sentence| sentence.trim().to_string())
+        .filter(|sentence| !sentence.is_empty())
+        .collect()
+}
+
+async fn run(name: &str, command: &mut Command, input: Option<&str>) -> Result<(), String> {
+    let mut child = command
+        .stdin(if input.is_some() {
+            Stdio::piped()
+        } else {
+            Stdio::null()
+        })
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        // A paused or stopped readback drops the future mid-sentence
+        .kill_on_drop(true)
+        .spawn()
+        .map_err(|e| format!("Failed to run {}: {}", name, e))?;
+    if let (Some(input), Some(mut stdin)) = (input, child.stdin.take()) {
+        stdin
+            .write_all(input.as_bytes())
+            .await
+            .map_err(|e| format!("Failed to write to {}: {}", name, e))?;
+    }
+    let status = child
+        .wait()
+        .await
+        .map_err(|e| format!("{} failed: {}", name, e))?;
+    if status.success() {
+        Ok(())
+    } else {
+        Err(format!("{} exited with {}", name, status))
+    }
+}
+
+async fn speak_with_platform(config: &TtsSettings, text: &str) -> Result<(), String> {
+    let wpm = (NORMAL_WPM * config.rate).round().to_string();
+    if cfg!(target_os = "macos") {
+        let mut command = Command::new("say");
+        command.args(["-r", &wpm]);
+        if let Some(voice) = &config.voice {
+            command.args(["-v", voice]);
+        }
+        run("say", command.arg("--").arg(text), None).await
+    } else if cfg!(windows) {
+        // System.Speech rates run from -10 to 10, roughly doubling every 10 steps
+        let rate = (config.rate.log2() * 10.0).round().clamp(-10.0, 10.0);
+        let voice = config
+            .voice
+            .as_deref()
+            .map(|voice| format!("$s.SelectVoice('{}');", voice.replace('\'', "''")))
+            .unwrap_or_default();
+        let script = format!(
+            "Add-Type -AssemblyName System.Speech; \
+             $s = New-Object System.Speech.Synthesis.SpeechSynthesizer; \
+             $s.Rate = {}; {} $s.Speak([Console]::In.ReadToEnd())",
+            rate, voice
+        );
+        let mut command = Command::new("powershell");
+        command.args(["-NoProfile", "-NonInteractive", "-Command", &script]);
+        run("PowerShell", &mut command, Some(text)).await
+    } else {
+        let mut command = Command::new("espeak-ng");
+        command.args(["-s", &wpm]);
+        if let Some(voice) = &config.voice {
+            command.args(["-v", voice]);
+        }
+        run("espeak-ng", command.arg("--").arg(text), None).await
+    }
+}
+
+async fn play_wav(path: &Path) -> Result<(), String> {
+    if cfg!(target_os = "macos") {
+        run("afplay", Command::new("afplay").arg(path), None).await
+    } else if cfg!(windows) {
+        let script = format!(
+            "(New-Object Media.SoundPlayer '{}').PlaySync()",
+            path.display().to_string().replace('\'', "''")
+        );
+        let mut command = Command::new("powershell");
+        command.args(["-NoProfile", "-NonInteractive", "-Command", &script]);
+        run("PowerShell", &mut command, None).await
+    } else {
+        run("aplay", Command::new("aplay").arg("-q").arg(path), None).await
+    }
+}
+
+async fn speak_with_piper(
+    config: &TtsSettings,
+    model: &str,
+    wav: &Path,
+    text: &str,
+) -> Result<(), String> {
+    // piper stretches phonemes by the length scale, so slower speech is a larger scale
+    let length_scale = (1.0 / config.rate).to_string();
+    let binary = config.piper_binary.as_deref().unwrap_or("piper");
+    run(
+        "piper",
+        Command::new(binary)
+            .args(["--model", model, "--length_scale", &length_scale])
+            .arg("--output_file")
+            .arg(wav),
+        Some(text),
+    )
+    .await?;
+    play_wav(wav).await
+}
+
+async fn speak(config: &TtsSettings, wav: &Path, text: &str) -> Result<(), String> {
+    match &config.piper_model {
+        Some(model) => speak_with_piper(config, model, wav, text).await,
+        None => speak_with_platform(config, text).await,
+    }
+}
+
+fn emit_state(
+    app: &AppHandle,
+    readback_id: &str,
+    state: PlaybackState,
+    utterances: &[String],
+    index: usize,
+    error: Option<String>,
+) {
+    let _ = schema::emit(
+        app,
+        "tts:state",
+        ReadbackEvent {
+            readback_id: readback_id.to_string(),
+            state,
+            index,
+            total: utterances.len(),
+            text: utterances.get(index).cloned(),
+            error,
+        },
+    );
+}
+
+async fn play(
+    app: AppHandle,
+    readback_id: String,
+    utterances: Vec<String>,
+    mut controls: mpsc::UnboundedReceiver<ReadbackControl>,
+) {
+    let config = settings::load(&app).tts;
+    let wav: PathBuf = std::env::temp_dir().join(format!("voice-to-text-tts-{}.wav", readback_id));
+    let mut index = 0;
+    let mut paused = false;
+    let mut outcome = (PlaybackState::Finished, None);
+
+    while index < utterances.len() {
+        let state = if paused {
+            PlaybackState::Paused
+        } else {
+            PlaybackState::Playing
+        };
+        emit_state(&app, &readback_id, state, &utterances, index, None);
+        let control = if paused {
+            controls.recv().await
+        } else {
+            tokio::select! {
+                result = speak(&config, &wav, &utterances[index]) => {
+                    if let Err(e) = result {
+                        println!("[tts] {}", e);
+                        outcome = (PlaybackState::Failed, Some(e));
+                        break;
+                    }
+                    index += 1;
+                    continue;
+                }
+                control = controls.recv() => control,
+            }
+        };
+        match control {
+            Some(ReadbackControl::Pause) => paused = true,
+            Some(ReadbackControl::Resume) => paused = false,
+            Some(ReadbackControl::Next) => index += 1,
+            Some(ReadbackControl::Previous) => index = index.saturating_sub(1),
+            Some(ReadbackControl::Stop) | None => {
+                outcome = (PlaybackState::Stopped, None);
+                break;
+            }
+        }
+    }
+
+    let _ = tokio::fs::remove_file(&wav).await;
+    let (state, error) = outcome;
+    emit_state(
+        &app,
+        &readback_id,
+        state,
+        &utterances,
+        index.min(utterances.len().saturating_sub(1)),
+        error,
+    );
+    let readback = app.state::<Readback>();
+    let mut active = readback.0.lock().unwrap();
+    if active
+        .as_ref()
+        .is_some_and(|active| active.id == readback_id)
+    {
+        *active = None;
+    }
+}
+
+/// Stop whatever is being read and start reading `utterances`. Returns the readback id.
+fn start(app: &AppHandle, utterances: Vec<String>) -> Result<String, String> {
+    if utterances.is_empty() {
+        return Err("Nothing to read".to_string());
+    }
+    let id = uuid::Uuid::new_v4().to_string();
+    let (controls, receiver) = mpsc::unbounded_channel();
+    let previous = app
+        .state::<Readback>()
+        .0
+        .lock()
+        .unwrap()
+        .replace(ActiveReadback {
+            id: id.clone(),
+            controls,
+        });
+    if let Some(previous) = previous {
+        let _ = previous.controls.send(ReadbackControl::Stop);
+    }
+    println!("[tts] Reading {} part(s)", utterances.len());
+    tauri::async_runtime::spawn(play(app.clone(), id.clone(), utterances, receiver));
+    Ok(id)
+}
+
+/// Read text aloud, sentence by sentence. Returns the readback id used in `tts:state`.
+#[tauri::command]
+#[specta::specta]
+pub fn speak_text(app: AppHandle, text: String) -> Result<String, String> {
+    start(&app, sentences(&text))
+}
+
+/// Read a stored session aloud, one segment at a time.
+#[tauri::command]
+#[specta::specta]
+pub fn speak_session(app: AppHandle, session_id: String) -> Result<String, String> {
+    app_lock::ensure_unlocked(&app)?;
+    let session = history::load(&app, &session_id)?;
+    let segments = session
+        .segments
+        .into_iter()
+        .map(|segment| segment.text)
+        .filter(|text| !text.trim().is_empty())
+        .collect();
+    start(&app, segments)
+}
+
+/// Pause, resume, stop or skip within the current readback.
+#[tauri::command]
+#[specta::specta]
+pub fn control_readback(
+    readback: State<'_, Readback>,
+    action: ReadbackControl,
+) -> Result<(), String> {
+    let active = readback.0.lock().unwrap();
+    let active = active.as_ref().ok_or("Nothing is being read")?;
+    active
+        .controls
+        .send(action)
+        .map_err(|_| "The readback already ended".to_string())
+}
@@ -0,0 +1,245 @@
+//! Earlier versions of a session's transcript and what changed between them.
+//!
+//! Editing a segment or re-transcribing a session keeps the replaced segments in
+//! [`Session::versions`]. Versions are numbered from 0, the original recognition, up to
+//! the current segments, and any two can be compared word by word.
+
+use crate::app_lock;
+use crate::history::{self, Segment, Session};
+use serde::Serialize;
+use specta::Type;
+use tauri::AppHandle;
+
+/// Give up on a minimal diff after this many changed words and report the rest as
+/// replaced, so comparing two unrelated transcripts stays cheap.
+const MAX_EDITS: usize = 2000;
+
+#[derive(Serialize, Clone, Debug, Type)]
+pub struct VersionInfo {
+    pub version: usize,
+    /// Unix time in milliseconds when this version was created.
+    pub created_at: u64,
+    /// `transcribed` for the original, otherwise what produced it, e.g. `edit`.
+    pub origin: String,
+    pub segment_count: usize,
+    pub current: bool,
+}
+
+#[derive(Serialize, Clone, Copy, Debug, PartialEq, Eq, Type)]
+#[serde(rename_all = "snake_case")]
+pub enum DiffKind {
+    Equal,
+    Insert,
+    Delete,
+}
+
+/// A run of words that is the same in both versions, only in the newer one, or only in
+/// the older one.
+#[derive(Serialize, Clone, Debug, Type)]
+pub struct DiffChunk {
+    pub kind: DiffKind,
+    pub text: String,
+}
+
+fn history_of(session: &Session) -> Vec<VersionInfo> {
+    let count = session.versions.len() + 1;
+    (0..count)
+        .map(|version| {
+            let (created_at, origin) = match version.checked_sub(1) {
+                None => (session.created_at, "transcribed".to_string()),
+                Some(previous) => {
+                    let replaced = &session.versions[previous];
+                    (replaced.replaced_at, replaced.replaced_by.clone())
+                }
+            };
+            VersionInfo {
+                version,
+                created_at,
+                origin,
+                segment_count: segments_of(session, version).map_or(0, <[Segment]>::len),
+                current: version + 1 == count,
+            }
+        })
+        .collect()
+}
+
+fn segments_of(session: &Session, version: usize) -> Option<&[Segment]> {
+    match session.versions.get(version) {
+        Some(replaced) => Some(&replaced.segments),
+        None if version == session.versions.len() => Some(&session.segments),
+        None => None,
+    }
+}
+
+fn words(segments: &[Segment]) -> Vec<&str> {
+    segments
+        .iter()
+        .flat_map(|segment| segment.text.split_whitespace())
+        .collect()
+}
+
+fn push(chunks: &mut Vec<DiffChunk>, kind: DiffKind, word: &str) {
+    match chunks.last_mut() {
+        Some(last) if last.kind == kind => {
+            last.text.push(' ');
+            last.text.push_str(word);
+        }
+        _ => chunks.push(DiffChunk {
+            kind,
+            text: word.to_string(),
+        }),
+    }
+}
+
+/// Shortest edit script from `old` to `new` (Myers), as (kind, word) pairs in order.
+/// `None` when it would take more than `MAX_EDITS` changes.
+fn edit_script<'a>(old: &[&'a str], new: &[&'a str]) -> Option<Vec<(DiffKind, &'a str)>> {
+    let (n, m) = (old.len() as isize, new.len() as isize);
+    let max = (n + m).min(MAX_EDITS as isize);
+    let offset = max + 1;
+    let mut v = vec![0isize; 2 * offset as usize + 1];
+    // The furthest x per diagonal before each step, for backtracking
+    let mut trace: Vec<Vec<isize>> = Vec::new();
+
+    for d in 0..=max {
+        trace.push(v.clone());
+        for k in (-d..=d).step_by(2) {
+            let index = (k + offset) as usize;
+            let mut x = if k == -d || (k != d && v[index - 1] < v[index + 1]) {
+                v[index + 1]
+            } else {
+                v[index - 1] + 1
+            };
+            let mut y = x - k;
+            while x < n && y < m && old[x as usize] == new[y as usize] {
+                x += 1;
+                y += 1;
+            }
+            v[index] = x;
+            if x >= n && y >= m {
+                return Some(backtrack(old, new, &trace, offset));
+            }
+        }
+    }
+    None
+}
+
+fn backtrack<'a>(
+    old: &[&'a str],
+    new: &[&'a str],
+    trace: &[Vec<isize>],
+    offset: isize,
+) -> Vec<(DiffKind, &'a str)> {
+    let (mut x, mut y) = (old.len() as isize, new.len() as isize);
+    let mut script = Vec::new();
+    for (d, v) in trace.iter().enumerate().rev() {
+        let d = d as isize;
+        let k = x - y;
+        let at = |k: isize| v[(k + offset) as usize];
+        let previous_k = if k == -d || (k != d && at(k - 1) < at(k + 1)) {
+            k + 1
+        } else {
+            k - 1
+        };
+        let previous_x = at(previous_k);
+        let previous_y = previous_x - previous_k;
+        while x > previous_x && y > previous_y {
+            script.push((DiffKind::Equal, old[x as usize - 1]));
+            x -= 1;
+            y -= 1;
+        }
+        if d > 0 {
+            if x == previous_x {
+                script.push((DiffKind::Insert, new[y as usize - 1]));
+            } else {
+                script.push((DiffKind::Delete, old[x as usize - 1]));
+            }
+        }
+        x = previous_x;
+        y = previous_y;
+    }
+    script.reverse();
+    script
+}
+
+/// Word-level differences from `old` to `new`.
+pub fn diff(old: &[Segment], new: &[Segment]) -> Vec<DiffChunk> {
+    let (old, new) = (words(old), words(new));
+    // The common start and end need no search, and edits are usually local
+    let prefix = old.iter().zip(&new).take_while(|(a, b)| a == b).count();
+    let suffix = old[prefix..]
+        .iter()
+        .rev()
+        .zip(new[prefix..].iter().rev())
+        .take_while(|(a, b)| a == b)
+        .count();
+    let (old_middle, new_middle) = (
+        &old[prefix..old.len() - suffix],
+        &new[prefix..new.len() - suffix],
+    );
+
+    let mut chunks = Vec::new();
+    for word in &old[..prefix] {
+        push(&mut chunks, DiffKind::Equal, word);
+    }
+    match edit_script(old_middle, new_middle) {
+        Some(script) => {
+            for (kind, word) in script {
+                push(&mut chunks, kind, word);
+            }
+        }
+        None => {
+            for word in old_middle {
+                push(&mut chunks, DiffKind::Delete, word);
+            }
+            for word in new_middle {
+                push(&mut chunks, DiffKind::Insert, word);
+            }
+        }
+    }
+    for word in &old[old.len() - suffix..] {
+        push(&mut chunks, DiffKind::Equal, word);
+    }
+    chunks
+}
+
+/// Every version of a session, oldest first; the last one is the current transcript.
+#[tauri::command]
+#[specta::specta]
+pub fn get_session_history(app: AppHandle, session_id: String) -> Result<Vec<VersionInfo>, String> {
+    app_lock::ensure_unlocked(&app)?;
+    Ok(history_of(&history::load(&app, &session_id)?))
+}
+
+#[tauri::command]
+#[specta::specta]
+pub fn get_session_version(
+    app: AppHandle,
+    session_id: String,
+    version: usize,
+) -> Result<Vec<Segment>, String> {
+    app_lock::ensure_unlocked(&app)?;
+    let session = history::load(&app, &session_id)?;
+    segments_of(&session, version)
+        .map(<[Segment]>::to_vec)
+        .ok_or_else(|| format!("No version {} of session {}", version, session_id))
+}
+
+/// Compare two versions. `to` defaults to the current transcript, so `from: 0` shows
+/// every correction made to the original recognition.
+#[tauri::command]
+#[specta::specta]
+pub fn diff_session_versions(
+    app: AppHandle,
+    session_id: String,
+    from: usize,
+    to: Option<usize>,
+) -> Result<Vec<DiffChunk>, String> {
+    app_lock::ensure_unlocked(&app)?;
+    let session = history::load(&app, &session_id)?;
+    let to = to.unwrap_or(session.versions.len());
+    let missing = |version: usize| format!("No version {} of session {}", version, session_id);
+    let old = segments_of(&session, from).ok_or_else(|| missing(from))?;
+    let new = segments_of(&session, to).ok_or_else(|| missing(to))?;
+    Ok(diff(old, new))
+}
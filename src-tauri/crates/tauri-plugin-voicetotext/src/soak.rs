@@ -0,0 +1,831 @@
+//! Soak test mode: simulated stream sessions kept running for hours while memory, queue
+//! depths and reconnects are sampled, to check the engine survives all-day dictation
+//! without leaking.
+//!
+//! Each session pulls synthetic audio from a server on 127.0.0.1 (tone bursts the length
+//! of syllables, pauses between words, a little noise) through the ordinary `stream`
+//! pipeline. With the mock provider the same server answers like Deepgram's live API;
+//! with `deepgram` the audio goes to Deepgram and costs what any other stream does.
+//! Sessions that end are started again, every session is asked to reconnect now and then
+//! as after a network change, and the sessions' transcripts are deleted from history as
+//! they end. Webhooks and sync still see them, so turn those off for a run.
+//!
+//! Every sample is emitted as `soak:sample` and the report so far is written to
+//! `<app data dir>/soak/<started_at>.json`, so a run that crashes still leaves its numbers.
+//! The end of a run is announced as `soak:finished`. Headless runs are started with
+//! `--soak=<sessions>` and exit when done.
+
+use crate::scheduler::{self, Priority};
+use crate::schema::{self, Store};
+use crate::wav::{self, Wav};
+use crate::{history, outbox, settings, stream};
+use futures_util::{SinkExt, StreamExt};
+use serde::{Deserialize, Serialize};
+use specta::Type;
+use std::collections::HashSet;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tauri::{AppHandle, EventId, Listener, Manager, State};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::process::Command;
+use tokio::sync::{oneshot, watch};
+use tokio::time::Instant;
+use tokio_tungstenite::tungstenite::Message;
+
+const SAMPLE_RATE: u32 = 16_000;
+/// Synthetic audio is sent in chunks this long, in real time.
+const CHUNK: Duration = Duration::from_millis(100);
+/// How often sessions that ended are started again.
+const CHECK_INTERVAL: Duration = Duration::from_secs(5);
+/// How long stopped sessions get to flush their results at the end of a run.
+const STOP_TIMEOUT: Duration = Duration::from_secs(15);
+/// Memory growth is fitted to the samples after this, once caches and pools have filled.
+const WARM_UP: Duration = Duration::from_secs(5 * 60);
+/// Audio after which the mock provider sends an interim result, and a final one.
+const MOCK_INTERIM_SECONDS: f64 = 0.5;
+const MOCK_FINAL_SECONDS: f64 = 2.0;
+/// What the mock provider hears.
+const MOCK_WORDS: [&str; 12] = [
+    "the", "quick", "brown", "fox", "jumps", "over", "a", "lazy", "dog", "and", "keeps", "running",
+];
+
+const REPORT_STORE: Store = Store {
+    name: "soak report",
+    wrapped: false,
+    migrations: &[schema::unversioned],
+};
+
+/// Where the sessions of a soak test send their audio.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq, Type)]
+#[serde(rename_all = "snake_case")]
+pub enum SoakProvider {
+    /// A local imitation of Deepgram's live API; free, and needs no network.
+    #[default]
+    Mock,
+    Deepgram,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Type)]
+#[serde(default)]
+pub struct SoakConfig {
+    /// Sessions kept running at once.
+    pub sessions: u32,
+    pub duration_minutes: u32,
+    pub provider: SoakProvider,
+    /// Key for Deepgram; the configured one when not given. Unused by the mock provider.
+    pub api_key: Option<String>,
+    /// Every session is asked to reconnect this often; never when unset or 0.
+    pub reconnect_every_minutes: Option<u32>,
+    pub sample_every_seconds: u32,
+}
+
+impl Default for SoakConfig {
+    fn default() -> Self {
+        SoakConfig {
+            sessions: 4,
+            duration_minutes: 8 * 60,
+            provider: SoakProvider::Mock,
+            api_key: None,
+            reconnect_every_minutes: Some(15),
+            sample_every_seconds: 30,
+        }
+    }
+}
+
+/// Payload of the `soak:sample` event. Counts are totals since the run started.
+#[derive(Serialize, Deserialize, Clone, Debug, Type)]
+pub struct SoakSample {
+    pub elapsed_seconds: u64,
+    /// Resident memory of the app, when the platform tells.
+    pub resident_bytes: Option<u64>,
+    /// Stream sessions running, the run's own and any others.
+    pub active_sessions: usize,
+    /// Provider requests holding a slot of the request limiter, and waiting for one.
+    pub requests_running: usize,
+    pub requests_waiting: usize,
+    /// Webhook and sync deliveries waiting in the outbox.
+    pub outbox_pending: usize,
+    pub sessions_started: u64,
+    /// Sessions that failed to start or ended with an error.
+    pub sessions_failed: u64,
+    pub reconnects: u64,
+    pub reconnects_failed: u64,
+    pub rollovers: u64,
+    /// Transcript updates received, interim and final.
+    pub transcripts: u64,
+}
+
+/// A soak test and what it measured; also the payload of the `soak:finished` event.
+#[derive(Serialize, Deserialize, Clone, Debug, Type)]
+pub struct SoakReport {
+    pub id: String,
+    pub config: SoakConfig,
+    pub started_at: u64,
+    /// Set once the run is over.
+    pub finished_at: Option<u64>,
+    pub samples: Vec<SoakSample>,
+    /// Resident memory growth fitted over the samples after the first five minutes. A
+    /// steady rise over a long run points to a leak.
+    pub memory_growth_bytes_per_hour: Option<f64>,
+    /// Where the report is written.
+    pub path: Option<String>,
+}
+
+/// The soak test running now, and the report of the latest one.
+#[derive(Default)]
+pub struct SoakTest(Mutex<SoakState>);
+
+#[derive(Default)]
+struct SoakState {
+    /// Ends the running test when set to `true`.
+    stop: Option<watch::Sender<bool>>,
+    report: Option<SoakReport>,
+}
+
+/// What the sessions of a run talk to.
+struct Run {
+    config: SoakConfig,
+    /// Port of the local audio source and mock provider.
+    port: u16,
+    endpoint: String,
+    api_key: String,
+    exit_when_done: bool,
+}
+
+/// Session events of the run, counted as they are emitted.
+#[derive(Default)]
+struct Counters {
+    /// Sessions of the run that haven't ended, continuations included.
+    sessions: Mutex<HashSet<String>>,
+    started: AtomicU64,
+    failed: AtomicU64,
+    reconnects: AtomicU64,
+    reconnects_failed: AtomicU64,
+    rollovers: AtomicU64,
+    transcripts: AtomicU64,
+}
+
+impl Counters {
+    fn tracks(&self, session_id: &str) -> bool {
+        self.sessions.lock().unwrap().contains(session_id)
+    }
+}
+
+/// The fields of the stream events a run counts.
+#[derive(Deserialize)]
+struct SessionEvent {
+    session_id: String,
+    #[serde(default)]
+    state: Option<String>,
+    #[serde(default)]
+    error: Option<String>,
+    #[serde(default)]
+    continuation_id: Option<String>,
+}
+
+/// Start a soak test in the background. Returns its id; progress is reported as
+/// `soak:sample` events and the end as `soak:finished`.
+#[tauri::command]
+#[specta::specta]
+pub async fn start_soak_test(app: AppHandle, config: SoakConfig) -> Result<String, String> {
+    start(&app, config, false).await
+}
+
+/// Stop the running soak test. Its sessions are stopped and the report finished as if
+/// its time were up.
+#[tauri::command]
+#[specta::specta]
+pub fn stop_soak_test(soak: State<'_, SoakTest>) -> Result<(), String> {
+    match soak.0.lock().unwrap().stop.as_ref() {
+        Some(stop) => {
+            let _ = stop.send(true);
+            Ok(())
+        }
+        None => Err("No soak test is running".to_string()),
+    }
+}
+
+/// The report of the running soak test, or else of the latest one since the app started.
+#[tauri::command]
+#[specta::specta]
+pub fn get_soak_report(soak: State<'_, SoakTest>) -> Option<SoakReport> {
+    soak.0.lock().unwrap().report.clone()
+}
+
+/// With `exit_when_done` the app exits once the run is over, for headless runs.
+pub async fn start(
+    app: &AppHandle,
+    config: SoakConfig,
+    exit_when_done: bool,
+) -> Result<String, String> {
+    if config.sessions == 0 || config.duration_minutes == 0 {
+        return Err("A soak test needs at least one session and one minute".to_string());
+    }
+    let api_key = match (config.provider, &config.api_key) {
+        (SoakProvider::Mock, _) => "soak".to_string(),
+        (SoakProvider::Deepgram, Some(key)) if !key.is_empty() => key.clone(),
+        (SoakProvider::Deepgram, _) => settings::api_key(app)?,
+    };
+    let (stop_tx, stop_rx) = watch::channel(false);
+    {
+        let soak = app.state::<SoakTest>();
+        let mut state = soak.0.lock().unwrap();
+        if state.stop.is_some() {
+            return Err("A soak test is already running".to_string());
+        }
+        state.stop = Some(stop_tx);
+    }
+
+    let (listener, port) = match bind().await {
+        Ok(bound) => bound,
+        Err(e) => {
+            app.state::<SoakTest>().0.lock().unwrap().stop = None;
+            return Err(e);
+        }
+    };
+    let endpoint = match config.provider {
+        SoakProvider::Mock => format!("ws://127.0.0.1:{}/v1/listen", port),
+        SoakProvider::Deepgram => stream::LISTEN_ENDPOINT.to_string(),
+    };
+
+    let report = SoakReport {
+        id: uuid::Uuid::new_v4().to_string(),
+        config: config.clone(),
+        started_at: history::now_ms(),
+        finished_at: None,
+        samples: Vec::new(),
+        memory_growth_bytes_per_hour: None,
+        path: None,
+    };
+    let id = report.id.clone();
+    app.state::<SoakTest>().0.lock().unwrap().report = Some(report.clone());
+    println!(
+        "[soak] Starting {} sessions for {} minutes against the {:?} provider",
+        config.sessions, config.duration_minutes, config.provider
+    );
+
+    let (shutdown_tx, shutdown_rx) = oneshot::channel();
+    tauri::async_runtime::spawn(serve(listener, shutdown_rx));
+    let run = Run {
+        config,
+        port,
+        endpoint,
+        api_key,
+        exit_when_done,
+    };
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        supervise(&app, run, stop_rx, report).await;
+        // Ends the audio source and mock provider
+        drop(shutdown_tx);
+    });
+    Ok(id)
+}
+
+/// Listen on a free port of 127.0.0.1 for the audio source and mock provider.
+async fn bind() -> Result<(TcpListener, u16), String> {
+    let listener = TcpListener::bind(("127.0.0.1", 0))
+        .await
+        .map_err(|e| format!("Failed to bind the soak audio source: {}", e))?;
+    let port = listener
+        .local_addr()
+        .map_err(|e| format!("Failed to read soak audio source address: {}", e))?
+        .port();
+    Ok((listener, port))
+}
+
+/// Keep the run's sessions going and sample them until the run is stopped or its time is up.
+async fn supervise(
+    app: &AppHandle,
+    run: Run,
+    mut stop_rx: watch::Receiver<bool>,
+    mut report: SoakReport,
+) {
+    let counters = Arc::new(Counters::default());
+    let listeners = listen(app, &counters);
+    let started = Instant::now();
+    let deadline = started + Duration::from_secs(run.config.duration_minutes as u64 * 60);
+    let mut streams: Vec<Option<String>> = vec![None; run.config.sessions as usize];
+    let mut check = tokio::time::interval(CHECK_INTERVAL);
+    let mut sample = tokio::time::interval(Duration::from_secs(
+        run.config.sample_every_seconds.max(1) as u64,
+    ));
+    let reconnect_every = run
+        .config
+        .reconnect_every_minutes
+        .filter(|minutes| *minutes > 0)
+        .map(|minutes| Duration::from_secs(minutes as u64 * 60));
+    let mut reconnect_due = reconnect_every.map(|every| started + every);
+
+    loop {
+        tokio::select! {
+            _ = stop_rx.changed() => break,
+            _ = tokio::time::sleep_until(deadline) => break,
+            _ = check.tick() => fill(app, &run, &counters, &mut streams).await,
+            _ = sample.tick() => {
+                let taken = take_sample(app, started.elapsed(), &counters).await;
+                let _ = schema::emit(app, "soak:sample", taken.clone());
+                report.samples.push(taken);
+                report.memory_growth_bytes_per_hour = memory_growth(&report.samples);
+                save(app, &mut report);
+            }
+            _ = tokio::time::sleep_until(
+                reconnect_due.unwrap_or_else(Instant::now)
+            ), if reconnect_due.is_some() => {
+                reconnect_due = reconnect_every.map(|every| Instant::now() + every);
+                let sessions = app.state::<stream::StreamSessions>();
+                let asked = streams
+                    .iter()
+                    .flatten()
+                    .filter(|id| sessions.reconnect(id))
+                    .count();
+                println!("[soak] Asked {} sessions to reconnect", asked);
+            }
+        }
+    }
+
+    // Stopped sessions still flush their results and end as usual
+    let sessions = app.state::<stream::StreamSessions>();
+    for id in streams.iter().flatten() {
+        let _ = stream::stop(&sessions, id);
+    }
+    let flushed = tokio::time::timeout(STOP_TIMEOUT, async {
+        while !counters.sessions.lock().unwrap().is_empty() {
+            tokio::time::sleep(Duration::from_millis(200)).await;
+        }
+    })
+    .await;
+    if flushed.is_err() {
+        println!("[soak] Gave up waiting for sessions to end");
+    }
+    for id in listeners {
+        app.unlisten(id);
+    }
+
+    report
+        .samples
+        .push(take_sample(app, started.elapsed(), &counters).await);
+    report.memory_growth_bytes_per_hour = memory_growth(&report.samples);
+    report.finished_at = Some(history::now_ms());
+    save(app, &mut report);
+    println!(
+        "[soak] Finished after {} minutes; memory growth {:?} bytes/hour; report: {:?}",
+        started.elapsed().as_secs() / 60,
+        report.memory_growth_bytes_per_hour,
+        report.path
+    );
+    let _ = schema::emit(app, "soak:finished", report.clone());
+    {
+        let soak = app.state::<SoakTest>();
+        let mut state = soak.0.lock().unwrap();
+        state.stop = None;
+        state.report = Some(report);
+    }
+    if run.exit_when_done {
+        app.exit(0);
+    }
+}
+
+/// Start a session in every slot whose session isn't running.
+async fn fill(app: &AppHandle, run: &Run, counters: &Counters, streams: &mut [Option<String>]) {
+    for (slot, stream_id) in streams.iter_mut().enumerate() {
+        let running = stream_id
+            .as_ref()
+            .is_some_and(|id| app.state::<stream::StreamSessions>().contains(id));
+        if running {
+            continue;
+        }
+        let source = format!("http://127.0.0.1:{}/audio/{}", run.port, slot);
+        let started = stream::start_with_endpoint(
+            app,
+            source,
+            &run.api_key,
+            true,
+            Priority::Interactive,
+            &run.endpoint,
+        )
+        .await;
+        match started {
+            Ok(id) => {
+                counters.sessions.lock().unwrap().insert(id.clone());
+                counters.started.fetch_add(1, Ordering::Relaxed);
+                *stream_id = Some(id);
+            }
+            Err(e) => {
+                // Tried again at the next check
+                println!("[soak] Session {} failed to start: {}", slot, e);
+                counters.failed.fetch_add(1, Ordering::Relaxed);
+                *stream_id = None;
+            }
+        }
+    }
+}
+
+/// Count the run's stream events, and delete its sessions from history as they end.
+fn listen(app: &AppHandle, counters: &Arc<Counters>) -> Vec<EventId> {
+    let mut listeners = Vec::new();
+    for name in [
+        "stream:connection",
+        "session:rolled_over",
+        "transcript:update",
+        "session:ended",
+    ] {
+        let counters = counters.clone();
+        let handle = app.clone();
+        listeners.push(app.listen_any(name, move |event| {
+            let Ok(event) = serde_json::from_str::<SessionEvent>(event.payload()) else {
+                return;
+            };
+            if !counters.tracks(&event.session_id) {
+                return;
+            }
+            match (name, event.state.as_deref()) {
+                ("stream:connection", Some("reconnecting")) => {
+                    counters.reconnects.fetch_add(1, Ordering::Relaxed);
+                }
+                ("stream:connection", Some("reconnect_failed")) => {
+                    counters.reconnects_failed.fetch_add(1, Ordering::Relaxed);
+                }
+                ("session:rolled_over", _) => {
+                    counters.rollovers.fetch_add(1, Ordering::Relaxed);
+                    if let Some(continuation) = event.continuation_id {
+                        counters.sessions.lock().unwrap().insert(continuation);
+                    }
+                }
+                ("transcript:update", _) => {
+                    counters.transcripts.fetch_add(1, Ordering::Relaxed);
+                }
+                ("session:ended", _) => {
+                    if event.error.is_some() {
+                        counters.failed.fetch_add(1, Ordering::Relaxed);
+                    }
+                    counters.sessions.lock().unwrap().remove(&event.session_id);
+                    // Sessions without a final result were never stored
+                    let _ = history::delete(&handle, &event.session_id);
+                }
+                _ => {}
+            }
+        }));
+    }
+    listeners
+}
+
+async fn take_sample(app: &AppHandle, elapsed: Duration, counters: &Counters) -> SoakSample {
+    let (requests_running, requests_waiting) = scheduler::occupancy(app);
+    SoakSample {
+        elapsed_seconds: elapsed.as_secs(),
+        resident_bytes: resident_bytes().await,
+        active_sessions: app.state::<stream::StreamSessions>().len(),
+        requests_running,
+        requests_waiting,
+        outbox_pending: outbox::pending(app).map_or(0, |queue| queue.len()),
+        sessions_started: counters.started.load(Ordering::Relaxed),
+        sessions_failed: counters.failed.load(Ordering::Relaxed),
+        reconnects: counters.reconnects.load(Ordering::Relaxed),
+        reconnects_failed: counters.reconnects_failed.load(Ordering::Relaxed),
+        rollovers: counters.rollovers.load(Ordering::Relaxed),
+        transcripts: counters.transcripts.load(Ordering::Relaxed),
+    }
+}
+
+async fn output(program: &str, args: &[&str]) -> Option<String> {
+    let output = Command::new(program).args(args).output().await.ok()?;
+    let text = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    (output.status.success() && !text.is_empty()).then_some(text)
+}
+
+/// Resident memory of this process in bytes.
+async fn resident_bytes() -> Option<u64> {
+    let pid = std::process::id().to_string();
+    if cfg!(target_os = "linux") {
+        let status = fs::read_to_string("/proc/self/status").ok()?;
+        let line = status.lines().find(|line| line.starts_with("VmRSS:"))?;
+        let kb: u64 = line.split_whitespace().nth(1)?.parse().ok()?;
+        Some(kb * 1024)
+    } else if cfg!(target_os = "windows") {
+        let script = format!("(Get-Process -Id {}).WorkingSet64", pid);
+        output("powershell", &["-NoProfile", "-Command", &script])
+            .await?
+            .parse()
+            .ok()
+    } else {
+        // ps reports kilobytes
+        let kb: u64 = output("ps", &["-o", "rss=", "-p", &pid])
+            .await?
+            .parse()
+            .ok()?;
+        Some(kb * 1024)
+    }
+}
+
+/// Least-squares slope of resident memory over the samples after the warm-up, per hour.
+fn memory_growth(samples: &[SoakSample]) -> Option<f64> {
+    let points: Vec<(f64, f64)> = samples
+        .iter()
+        .filter(|sample| sample.elapsed_seconds >= WARM_UP.as_secs())
+        .filter_map(|sample| {
+            let bytes = sample.resident_bytes? as f64;
+            Some((sample.elapsed_seconds as f64 / 3600.0, bytes))
+        })
+        .collect();
+    if points.len() < 2 {
+        return None;
+    }
+    let n = points.len() as f64;
+    let mean_hours = points.iter().map(|(hours, _)| hours).sum::<f64>() / n;
+    let mean_bytes = points.iter().map(|(_, bytes)| bytes).sum::<f64>() / n;
+    let (covariance, variance) =
+        points
+            .iter()
+            .fold((0.0, 0.0), |(covariance, variance), (hours, bytes)| {
+                let dx = hours - mean_hours;
+                (covariance + dx * (bytes - mean_bytes), variance + dx * dx)
+            });
+    (variance > 0.0).then(|| covariance / variance)
+}
+
+fn soak_dir(app: &AppHandle) -> Result<PathBuf, String> {
+    let dir = app
+        .path()
+        .app_data_dir()
+        .map_err(|e| format!("Failed to resolve app data directory: {}", e))?
+        .join("soak");
+    fs::create_dir_all(&dir).map_err(|e| format!("Failed to create soak directory: {}", e))?;
+    Ok(dir)
+}
+
+/// Write the report so far, and publish it to `get_soak_report`.
+fn save(app: &AppHandle, report: &mut SoakReport) {
+    let written = soak_dir(app).and_then(|dir| {
+        let path = dir.join(format!("{}.json", report.started_at));
+        report.path = Some(path.to_string_lossy().into_owned());
+        let bytes = REPORT_STORE.encode(report)?;
+        fs::write(&path, bytes).map_err(|e| format!("Failed to write soak report: {}", e))
+    });
+    if let Err(e) = written {
+        println!("[soak] {}", e);
+    }
+    app.state::<SoakTest>().0.lock().unwrap().report = Some(report.clone());
+}
+
+/// Serve synthetic audio at `/audio/<slot>` and the mock provider's WebSocket until
+/// `shutdown` resolves. Connections already open run until their session lets go.
+async fn serve(listener: TcpListener, mut shutdown: oneshot::Receiver<()>) {
+    loop {
+        tokio::select! {
+            _ = &mut shutdown => break,
+            accepted = listener.accept() => match accepted {
+                Ok((stream, _)) => {
+                    tauri::async_runtime::spawn(async move {
+                        if let Err(e) = handle_connection(stream).await {
+                            println!("[soak] {}", e);
+                        }
+                    });
+                }
+                Err(e) => println!("[soak] Accept failed: {}", e),
+            },
+        }
+    }
+}
+
+async fn handle_connection(stream: TcpStream) -> Result<(), String> {
+    // Peek at the request head to tell WebSocket upgrades from plain HTTP
+    let mut head = [0u8; 2048];
+    let n = stream
+        .peek(&mut head)
+        .await
+        .map_err(|e| format!("Failed to read request: {}", e))?;
+    let head = String::from_utf8_lossy(&head[..n]).to_ascii_lowercase();
+
+    if head.contains("upgrade: websocket") {
+        serve_mock_provider(stream).await
+    } else {
+        serve_audio(stream).await
+    }
+}
+
+/// An endless WAV of speech-like audio, sent in real time.
+async fn serve_audio(mut stream: TcpStream) -> Result<(), String> {
+    let mut buffer = vec![0u8; 4096];
+    let n = stream
+        .read(&mut buffer)
+        .await
+        .map_err(|e| format!("Failed to read request: {}", e))?;
+    let request = String::from_utf8_lossy(&buffer[..n]);
+    let target = request.split_whitespace().nth(1).unwrap_or("/");
+    let Some(slot) = target
+        .strip_prefix("/audio/")
+        .and_then(|slot| slot.parse::<u64>().ok())
+    else {
+        let response = "HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\nConnection: close\r\n\r\n";
+        return stream
+            .write_all(response.as_bytes())
+            .await
+            .map_err(|e| format!("Failed to write response: {}", e));
+    };
+
+    let mut synth = Synth::new(slot + 1);
+    let mut header = wav::encode(&Wav {
+        sample_rate: SAMPLE_RATE,
+        channels: 1,
+        samples: Vec::new(),
+    });
+    // Lengths of a stream that never ends
+    header[4..8].copy_from_slice(&u32::MAX.to_le_bytes());
+    header[40..44].copy_from_slice(&u32::MAX.to_le_bytes());
+    let head = "HTTP/1.1 200 OK\r\nContent-Type: audio/wav\r\nCache-Control: no-store\r\nConnection: close\r\n\r\n";
+    let mut response = head.as_bytes().to_vec();
+    response.extend_from_slice(&header);
+
+    let chunk_samples = (SAMPLE_RATE as u128 * CHUNK.as_millis() / 1000) as usize;
+    let mut ticks = tokio::time::interval(CHUNK);
+    loop {
+        ticks.tick().await;
+        let chunk = wav::encode(&Wav {
+            sample_rate: SAMPLE_RATE,
+            channels: 1,
+            samples: synth.take(chunk_samples),
+        });
+        response.extend_from_slice(&chunk[header.len()..]);
+        // Fails once the session has stopped reading
+        if stream.write_all(&response).await.is_err() {
+            return Ok(());
+        }
+        response.clear();
+    }
+}
+
+/// Answers like Deepgram's live API, with results made of `MOCK_WORDS` at a steady pace
+/// of the audio received.
+async fn serve_mock_provider(stream: TcpStream) -> Result<(), String> {
+    let seed = stream
+        .peer_addr()
+        .map_or(1, |address| address.port() as u64);
+    let socket = tokio_tungstenite::accept_async(stream)
+        .await
+        .map_err(|e| format!("Mock provider handshake failed: {}", e))?;
+    let (mut sink, mut incoming) = socket.split();
+    let mut transcript = MockTranscript::new(seed);
+
+    while let Some(message) = incoming.next().await {
+        let message = message.map_err(|e| format!("Mock provider connection failed: {}", e))?;
+        let (replies, closing) = match message {
+            Message::Binary(audio) => (transcript.hear(audio.len()), false),
+            Message::Text(text) if text.contains("Finalize") => (transcript.finalize(), false),
+            Message::Text(text) if text.contains("CloseStream") => {
+                let mut replies = transcript.finalize();
+                replies.push(r#"{"type":"Metadata"}"#.to_string());
+                (replies, true)
+            }
+            Message::Close(_) => break,
+            _ => (Vec::new(), false),
+        };
+        for reply in replies {
+            if sink.send(Message::Text(reply)).await.is_err() {
+                return Ok(());
+            }
+        }
+        if closing {
+            let _ = sink.close().await;
+            break;
+        }
+    }
+    Ok(())
+}
+
+/// A 64-bit xorshift; test audio and transcripts needn't be any more random than that.
+fn next_random(state: &mut u64) -> f32 {
+    *state ^= *state << 13;
+    *state ^= *state >> 7;
+    *state ^= *state << 17;
+    (*state >> 40) as f32 / (1u64 << 24) as f32
+}
+
+/// Speech-like test audio: gliding tone bursts the length of syllables with harmonics,
+/// short pauses between words and a longer one every sentence, over faint noise.
+struct Synth {
+    random: u64,
+    /// Pitch of the word being voiced, if any.
+    pitch: Option<f32>,
+    /// Samples left of the current word or pause.
+    remaining: u32,
+    phase: f32,
+    words: u32,
+}
+
+impl Synth {
+    fn new(seed: u64) -> Self {
+        Synth {
+            random: seed.wrapping_mul(0x9E37_79B9_7F4A_7C15) | 1,
+            pitch: None,
+            remaining: 0,
+            phase: 0.0,
+            words: 0,
+        }
+    }
+
+    fn take(&mut self, len: usize) -> Vec<f32> {
+        let mut samples = Vec::with_capacity(len);
+        for _ in 0..len {
+            if self.remaining == 0 {
+                self.advance();
+            }
+            self.remaining -= 1;
+            let noise = (next_random(&mut self.random) - 0.5) * 0.01;
+            let voice = match self.pitch.as_mut() {
+                Some(pitch) => {
+                    // A falling pitch, as at the end of a syllable
+                    *pitch *= 0.99998;
+                    self.phase = (self.phase + *pitch / SAMPLE_RATE as f32).fract();
+                    let x = self.phase * std::f32::consts::TAU;
+                    0.3 * (x.sin() + 0.5 * (2.0 * x).sin() + 0.25 * (3.0 * x).sin())
+                }
+                None => 0.0,
+            };
+            samples.push(voice + noise);
+        }
+        samples
+    }
+
+    fn advance(&mut self) {
+        let random = next_random(&mut self.random);
+        let seconds = if self.pitch.take().is_some() {
+            self.words += 1;
+            if self.words.is_multiple_of(8) {
+                1.2
+            } else {
+                0.15 + 0.1 * random
+            }
+        } else {
+            self.pitch = Some(120.0 + 140.0 * random);
+            0.2 + 0.4 * next_random(&mut self.random)
+        };
+        self.remaining = (seconds * SAMPLE_RATE as f32) as u32;
+    }
+}
+
+/// The words the mock provider has "heard" of the utterance in progress.
+struct MockTranscript {
+    random: u64,
+    /// Seconds of audio received, and where the utterance and the last interim began.
+    heard: f64,
+    utterance_start: f64,
+    last_interim: f64,
+    words: Vec<&'static str>,
+}
+
+impl MockTranscript {
+    fn new(seed: u64) -> Self {
+        MockTranscript {
+            random: seed.wrapping_mul(0x9E37_79B9_7F4A_7C15) | 1,
+            heard: 0.0,
+            utterance_start: 0.0,
+            last_interim: 0.0,
+            words: Vec::new(),
+        }
+    }
+
+    /// Take in `bytes` of 16-bit mono audio. The WAV header is counted as audio too,
+    /// which is close enough.
+    fn hear(&mut self, bytes: usize) -> Vec<String> {
+        self.heard += bytes as f64 / (SAMPLE_RATE as f64 * 2.0);
+        if self.heard - self.utterance_start >= MOCK_FINAL_SECONDS {
+            return vec![self.result(true)];
+        }
+        if self.heard - self.last_interim >= MOCK_INTERIM_SECONDS {
+            return vec![self.result(false)];
+        }
+        Vec::new()
+    }
+
+    fn finalize(&mut self) -> Vec<String> {
+        if self.words.is_empty() {
+            return Vec::new();
+        }
+        vec![self.result(true)]
+    }
+
+    fn result(&mut self, is_final: bool) -> String {
+        let index = (next_random(&mut self.random) * MOCK_WORDS.len() as f32) as usize;
+        self.words.push(MOCK_WORDS[index.min(MOCK_WORDS.len() - 1)]);
+        let result = serde_json::json!({
+            "type": "Results",
+            "is_final": is_final,
+            "start": self.utterance_start,
+            "duration": self.heard - self.utterance_start,
+            "channel": {
+                "alternatives": [{ "transcript": self.words.join(" "), "confidence": 0.9 }]
+            }
+        });
+        self.last_interim = self.heard;
+        if is_final {
+            self.words.clear();
+            self.utterance_start = self.heard;
+        }
+        result.to_string()
+    }
+}
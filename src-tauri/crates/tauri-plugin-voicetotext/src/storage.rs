@@ -0,0 +1,19 @@
+//! What is kept on disk and what leaves the machine: settings, encryption and the app lock,
+//! backups, retention, the disk quota, sync, webhooks and telemetry.
+
+pub mod app_lock;
+pub mod backup;
+pub mod bundle;
+pub mod crash;
+pub mod encryption;
+pub mod lan_sync;
+pub mod outbox;
+pub mod quota;
+pub mod retention;
+pub mod schema;
+pub mod settings;
+pub mod sync;
+pub mod telemetry;
+pub mod webhook;
+pub mod wipe;
+pub mod zip;
@@ -0,0 +1,171 @@
+//! Optional lock on the whole app for shared computers.
+//!
+//! While locked, commands that read transcripts or settings (which hold API keys) refuse
+//! to run until `unlock` is called with the passphrase. Settings keep only a salt and an
+//! Argon2id hash of the passphrase. Unlike [`encryption`](crate::encryption) nothing on
+//! disk changes; the lock starts engaged on every launch.
+
+use crate::encryption;
+use crate::settings::{self, AppLockSettings};
+use chacha20poly1305::aead::rand_core::RngCore;
+use chacha20poly1305::aead::OsRng;
+use serde::Serialize;
+use specta::Type;
+use std::sync::Mutex;
+use tauri::{AppHandle, Manager, State};
+
+const LOCKED: &str = "The app is locked; unlock it with your passphrase first";
+
+/// Whether the passphrase was entered since launch or the last `lock`.
+#[derive(Default)]
+pub struct AppLock(Mutex<bool>);
+
+#[derive(Serialize, Clone, Debug, Type)]
+pub struct AppLockStatus {
+    pub enabled: bool,
+    pub unlocked: bool,
+}
+
+fn hash(passphrase: &str, salt: &[u8]) -> Result<String, String> {
+    encryption::derive_key(passphrase, salt).map(hex::encode)
+}
+
+fn verify(config: &AppLockSettings, passphrase: &str) -> Result<(), String> {
+    let salt = hex::decode(&config.salt).map_err(|e| format!("Corrupt salt: {}", e))?;
+    let expected = config.hash.as_bytes();
+    let actual = hash(passphrase, &salt)?;
+    if encryption::constant_time_eq(actual.as_bytes(), expected) {
+        Ok(())
+    } else {
+        Err("Wrong passphrase".to_string())
+    }
+}
+
+fn lock_status(enabled: bool, lock: &AppLock) -> AppLockStatus {
+    AppLockStatus {
+        enabled,
+        unlocked: !enabled || *lock.0.lock().unwrap(),
+    }
+}
+
+fn status(app: &AppHandle, lock: &AppLock) -> AppLockStatus {
+    lock_status(settings::load(app).app_lock.is_some(), lock)
+}
+
+pub(crate) fn refuse_when_locked(status: &AppLockStatus) -> Result<(), String> {
+    if status.unlocked {
+        Ok(())
+    } else {
+        Err(LOCKED.to_string())
+    }
+}
+
+/// Errors while the app lock is enabled and engaged.
+pub fn ensure_unlocked(app: &AppHandle) -> Result<(), String> {
+    refuse_when_locked(&status(app, &app.state::<AppLock>()))
+}
+
+#[tauri::command]
+#[specta::specta]
+pub fn get_app_lock_status(app: AppHandle, lock: State<'_, AppLock>) -> AppLockStatus {
+    status(&app, &lock)
+}
+
+/// Protect the app with `passphrase`. The current run stays unlocked.
+#[tauri::command]
+#[specta::specta]
+pub fn enable_app_lock(
+    app: AppHandle,
+    lock: State<'_, AppLock>,
+    passphrase: String,
+) -> Result<AppLockStatus, String> {
+    let mut current = settings::load(&app);
+    if current.app_lock.is_some() {
+        return Err("The app lock is already enabled".to_string());
+    }
+    let mut salt = [0u8; 16];
+    OsRng.fill_bytes(&mut salt);
+    current.app_lock = Some(AppLockSettings {
+        salt: hex::encode(salt),
+        hash: hash(&passphrase, &salt)?,
+    });
+    settings::save(&app, &current)?;
+    *lock.0.lock().unwrap() = true;
+    Ok(status(&app, &lock))
+}
+
+#[tauri::command]
+#[specta::specta]
+pub fn disable_app_lock(
+    app: AppHandle,
+    lock: State<'_, AppLock>,
+    passphrase: String,
+) -> Result<AppLockStatus, String> {
+    let mut current = settings::load(&app);
+    let Some(config) = &current.app_lock else {
+        return Err("The app lock is not enabled".to_string());
+    };
+    verify(config, &passphrase)?;
+    current.app_lock = None;
+    settings::save(&app, &current)?;
+    Ok(status(&app, &lock))
+}
+
+#[tauri::command]
+#[specta::specta]
+pub fn unlock(
+    app: AppHandle,
+    lock: State<'_, AppLock>,
+    passphrase: String,
+) -> Result<AppLockStatus, String> {
+    let config = settings::load(&app)
+        .app_lock
+        .ok_or_else(|| "The app lock is not enabled".to_string())?;
+    verify(&config, &passphrase)?;
+    *lock.0.lock().unwrap() = true;
+    Ok(status(&app, &lock))
+}
+
+#[tauri::command]
+#[specta::specta]
+pub fn lock(app: AppHandle, lock: State<'_, AppLock>) -> AppLockStatus {
+    *lock.0.lock().unwrap() = false;
+    status(&app, &lock)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lock_settings(passphrase: &str) -> AppLockSettings {
+        let salt = [7u8; 16];
+        AppLockSettings {
+            salt: hex::encode(salt),
+            hash: hash(passphrase, &salt).unwrap(),
+        }
+    }
+
+    #[test]
+    fn refuses_while_locked() {
+        let lock = AppLock::default();
+        assert_eq!(
+            refuse_when_locked(&lock_status(true, &lock)),
+            Err(LOCKED.to_string())
+        );
+        *lock.0.lock().unwrap() = true;
+        assert!(refuse_when_locked(&lock_status(true, &lock)).is_ok());
+    }
+
+    #[test]
+    fn allows_everything_without_a_lock() {
+        assert!(refuse_when_locked(&lock_status(false, &AppLock::default())).is_ok());
+    }
+
+    #[test]
+    fn unlocks_only_with_the_passphrase() {
+        let config = lock_settings("correct horse");
+        assert!(verify(&config, "correct horse").is_ok());
+        assert!(verify(&config, "wrong horse").is_err());
+        assert!(verify(&config, "").is_err());
+    }
+}
@@ -0,0 +1,203 @@
+//! Single-file backup and restore of settings and history.
+//!
+//! A backup is one JSON document whose `contents` are covered by a SHA-256 checksum.
+//! While encryption is on, `contents` are sealed with the history key like every stored
+//! session, since they hold transcripts and API keys; such a backup restores wherever
+//! history is unlocked with that key. A plaintext backup, which restores on any machine,
+//! has to be asked for. Restoring into an encrypted history seals sessions again with the
+//! local key.
+
+use crate::history::{self, Session};
+use crate::settings::{self, Settings};
+use crate::{app_lock, encryption, folder_watch, hotkeys};
+use base64::Engine;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use specta::Type;
+use std::fs;
+use tauri::AppHandle;
+
+const FORMAT: &str = "voice-to-text-backup";
+/// Version 2 added sealed contents.
+const VERSION: u32 = 2;
+
+/// Settings and sessions, each stamped with its own schema version.
+#[derive(Serialize, Deserialize)]
+struct Contents {
+    settings: Value,
+    sessions: Vec<Value>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct Archive {
+    format: String,
+    version: u32,
+    created_at: u64,
+    /// Hex SHA-256 of the serialized `contents`.
+    checksum: String,
+    /// `contents` are the sealed JSON of [`Contents`], base64-encoded.
+    #[serde(default)]
+    sealed: bool,
+    contents: Value,
+}
+
+#[derive(Serialize, Clone, Debug, Type)]
+pub struct BackupSummary {
+    pub sessions: usize,
+    pub bytes: usize,
+}
+
+fn checksum(contents: &Value) -> Result<String, String> {
+    let bytes =
+        serde_json::to_vec(contents).map_err(|e| format!("Failed to serialize backup: {}", e))?;
+    Ok(hex::encode(Sha256::digest(bytes)))
+}
+
+/// Seal serialized contents with the history key, as a base64 string.
+fn seal_contents(app: &AppHandle, contents: &Value) -> Result<Value, String> {
+    let json =
+        serde_json::to_vec(contents).map_err(|e| format!("Failed to serialize backup: {}", e))?;
+    let sealed = encryption::seal(app, json)?;
+    Ok(Value::String(
+        base64::engine::general_purpose::STANDARD.encode(sealed),
+    ))
+}
+
+fn open_contents(app: &AppHandle, contents: &Value) -> Result<Value, String> {
+    let sealed = contents
+        .as_str()
+        .and_then(|encoded| {
+            base64::engine::general_purpose::STANDARD
+                .decode(encoded)
+                .ok()
+        })
+        .ok_or_else(|| "Backup is corrupt: sealed contents are not base64".to_string())?;
+    let json = encryption::open(app, sealed).map_err(|e| {
+        format!(
+            "Can't open the sealed backup ({}); it restores only where history is unlocked \
+             with the key that sealed it",
+            e
+        )
+    })?;
+    serde_json::from_slice(&json).map_err(|e| format!("Backup is corrupt: {}", e))
+}
+
+/// Write settings and every stored session to `path`. While encryption is on the backup
+/// is sealed with the history key unless `plaintext` asks for one that restores anywhere.
+#[tauri::command]
+#[specta::specta]
+pub fn backup_data(
+    app: AppHandle,
+    path: String,
+    plaintext: Option<bool>,
+) -> Result<BackupSummary, String> {
+    app_lock::ensure_unlocked(&app)?;
+    // Listing skips sealed sessions while locked, which would silently drop them
+    encryption::ensure_unlocked(&app)?;
+    let mut settings = settings::load(&app);
+    // The salt only means something next to the files it encrypted
+    settings.encryption = None;
+    settings.app_lock = None;
+    let sessions = history::load_all(&app)?;
+    let session_count = sessions.len();
+
+    let contents = Contents {
+        settings: settings::STORE.stamp(&settings)?,
+        sessions: sessions
+            .iter()
+            .map(|session| history::STORE.stamp(session))
+            .collect::<Result<_, _>>()?,
+    };
+    let mut contents =
+        serde_json::to_value(contents).map_err(|e| format!("Failed to serialize backup: {}", e))?;
+    let sealed = encryption::is_enabled(&app) && !plaintext.unwrap_or(false);
+    if sealed {
+        contents = seal_contents(&app, &contents)?;
+    }
+    let archive = Archive {
+        format: FORMAT.to_string(),
+        version: VERSION,
+        created_at: history::now_ms(),
+        checksum: checksum(&contents)?,
+        sealed,
+        contents,
+    };
+    let json = serde_json::to_vec_pretty(&archive)
+        .map_err(|e| format!("Failed to serialize backup: {}", e))?;
+    fs::write(&path, &json).map_err(|e| format!("Failed to write {}: {}", path, e))?;
+    println!(
+        "[backup] Wrote {} sessions to {}{}",
+        session_count,
+        path,
+        if sealed { ", sealed" } else { "" }
+    );
+    Ok(BackupSummary {
+        sessions: session_count,
+        bytes: json.len(),
+    })
+}
+
+/// Verify a backup and restore it: settings are replaced and sessions are added,
+/// overwriting stored sessions with the same id.
+#[tauri::command]
+#[specta::specta]
+pub fn restore_data(app: AppHandle, path: String) -> Result<BackupSummary, String> {
+    app_lock::ensure_unlocked(&app)?;
+    let bytes = fs::read(&path).map_err(|e| format!("Failed to read {}: {}", path, e))?;
+    let archive: Archive =
+        serde_json::from_slice(&bytes).map_err(|e| format!("Not a backup file: {}", e))?;
+    if archive.format != FORMAT {
+        return Err("Not a backup file".to_string());
+    }
+    if archive.version > VERSION {
+        return Err(format!(
+            "Backup version {} is newer than this app supports",
+            archive.version
+        ));
+    }
+    if checksum(&archive.contents)? != archive.checksum {
+        return Err("Backup is corrupt: checksum mismatch".to_string());
+    }
+    let contents = if archive.sealed {
+        open_contents(&app, &archive.contents)?
+    } else {
+        archive.contents
+    };
+    let contents: Contents =
+        serde_json::from_value(contents).map_err(|e| format!("Backup is corrupt: {}", e))?;
+    let sessions: Vec<Session> = contents
+        .sessions
+        .into_iter()
+        .map(|session| history::STORE.upgrade(session))
+        .collect::<Result<_, _>>()
+        .map_err(|e| format!("Backup is corrupt: {}", e))?;
+    let mut restored: Settings = settings::STORE
+        .upgrade(contents.settings)
+        .map_err(|e| format!("Backup is corrupt: {}", e))?;
+
+    encryption::ensure_unlocked(&app)?;
+    for session in &sessions {
+        history::save(&app, session)?;
+    }
+    let current = settings::load(&app);
+    restored.encryption = current.encryption;
+    restored.app_lock = current.app_lock;
+    settings::save(&app, &restored)?;
+
+    if let Err(e) = folder_watch::restart(&app) {
+        println!("[backup] {}", e);
+    }
+    if let Err(e) = hotkeys::register(&app) {
+        println!("[backup] {}", e);
+    }
+    println!(
+        "[backup] Restored {} sessions from {}",
+        sessions.len(),
+        path
+    );
+    Ok(BackupSummary {
+        sessions: sessions.len(),
+        bytes: bytes.len(),
+    })
+}
@@ -0,0 +1,146 @@
+//! One-file hand-off of a session: a ZIP holding the recording, the transcript as JSON,
+//! a subtitle or text rendering and a manifest describing them.
+//!
+//! Audio is stored as is (it is already compressed or compresses poorly); text entries
+//! are deflated. Archives are plain ZIP without ZIP64, so each entry and the whole file
+//! must stay under 4 GiB.
+
+use crate::export::{self, ExportFormat};
+use crate::history::{self, Session};
+use crate::zip::ZipWriter;
+use crate::{app_lock, recordings};
+use chrono::Utc;
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use specta::Type;
+use std::fs;
+use tauri::AppHandle;
+
+const FORMAT: &str = "voice-to-text-bundle";
+const VERSION: u32 = 1;
+
+#[derive(Serialize)]
+struct Manifest<'a> {
+    format: &'a str,
+    version: u32,
+    session_id: &'a str,
+    title: &'a str,
+    source: &'a str,
+    created_at: u64,
+    duration: f64,
+    exported_at: u64,
+    files: Vec<ManifestFile>,
+}
+
+#[derive(Serialize)]
+struct ManifestFile {
+    name: String,
+    /// `audio`, `transcript` or `subtitles`.
+    role: &'static str,
+    bytes: usize,
+    /// Hex SHA-256 of the uncompressed contents.
+    sha256: String,
+}
+
+#[derive(Serialize, Clone, Debug, Type)]
+pub struct BundleSummary {
+    pub files: Vec<String>,
+    pub bytes: usize,
+    /// False when the session keeps no local recording, e.g. live streams.
+    pub has_audio: bool,
+}
+
+/// The session's recording, when one is kept locally.
+fn recording(app: &AppHandle, session: &Session) -> Result<Option<(String, Vec<u8>)>, String> {
+    let Some((path, bytes)) = recordings::read(app, session)? else {
+        return Ok(None);
+    };
+    let extension = path
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .map(str::to_ascii_lowercase)
+        .unwrap_or_else(|| "audio".to_string());
+    Ok(Some((format!("audio.{}", extension), bytes)))
+}
+
+fn manifest_file(name: &str, role: &'static str, contents: &[u8]) -> ManifestFile {
+    ManifestFile {
+        name: name.to_string(),
+        role,
+        bytes: contents.len(),
+        sha256: hex::encode(Sha256::digest(contents)),
+    }
+}
+
+pub fn build(
+    app: &AppHandle,
+    session: &Session,
+    subtitles: ExportFormat,
+) -> Result<(Vec<u8>, BundleSummary), String> {
+    if subtitles == ExportFormat::Json {
+        return Err("The JSON transcript is always included; choose a subtitle format".to_string());
+    }
+    let transcript = export::render(session, ExportFormat::Json)?;
+    let rendered = export::render(session, subtitles)?;
+    let subtitles_name = format!("transcript.{}", subtitles.extension());
+    let audio = recording(app, session)?;
+
+    let mut files = vec![
+        manifest_file("transcript.json", "transcript", &transcript),
+        manifest_file(&subtitles_name, "subtitles", &rendered),
+    ];
+    if let Some((name, bytes)) = &audio {
+        files.insert(0, manifest_file(name, "audio", bytes));
+    }
+    let manifest = Manifest {
+        format: FORMAT,
+        version: VERSION,
+        session_id: &session.id,
+        title: &session.title,
+        source: &session.source,
+        created_at: session.created_at,
+        duration: session.duration(),
+        exported_at: history::now_ms(),
+        files,
+    };
+    let manifest = serde_json::to_vec_pretty(&manifest)
+        .map_err(|e| format!("Failed to serialize manifest: {}", e))?;
+
+    let mut zip = ZipWriter::new(Utc::now());
+    zip.add("manifest.json", &manifest, true)?;
+    if let Some((name, bytes)) = &audio {
+        zip.add(name, bytes, false)?;
+    }
+    zip.add("transcript.json", &transcript, true)?;
+    zip.add(&subtitles_name, &rendered, true)?;
+    let files = zip.names();
+    let bytes = zip.finish()?;
+    let summary = BundleSummary {
+        files,
+        bytes: bytes.len(),
+        has_audio: audio.is_some(),
+    };
+    Ok((bytes, summary))
+}
+
+/// Write a session's recording, transcript and subtitles to one ZIP file at `path`.
+#[tauri::command]
+#[specta::specta]
+pub fn export_bundle(
+    app: AppHandle,
+    session_id: String,
+    subtitle_format: ExportFormat,
+    path: String,
+) -> Result<BundleSummary, String> {
+    app_lock::ensure_unlocked(&app)?;
+    let session = history::load(&app, &session_id)?;
+    let (bytes, summary) = build(&app, &session, subtitle_format)?;
+    fs::write(&path, bytes).map_err(|e| format!("Failed to write {}: {}", path, e))?;
+    println!(
+        "[bundle] Wrote {} ({} files, {} bytes)",
+        path,
+        summary.files.len(),
+        summary.bytes
+    );
+    Ok(summary)
+}
@@ -0,0 +1,358 @@
+//! Local crash reports, uploaded only when the user opts in.
+//!
+//! A panic hook writes one JSON report per crash to `<app data dir>/crashes`: the panic
+//! message and location, a backtrace, and the most recent breadcrumbs recorded with
+//! [`breadcrumb`]. Everything free-form is scrubbed before it is written: quoted strings
+//! (which is where transcript text shows up in `Debug` output), long key-like tokens,
+//! credentials in query strings and headers, and every secret currently in settings.
+//! Pending reports are uploaded on the next launch when `crash_reports.upload` is set.
+
+use crate::schema::{self, Store};
+use crate::settings::{self, Settings};
+use crate::{app_lock, history};
+use serde::{Deserialize, Serialize};
+use specta::Type;
+use std::collections::VecDeque;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, OnceLock};
+use std::time::Duration;
+use tauri::{AppHandle, Manager};
+
+const MAX_BREADCRUMBS: usize = 100;
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
+const REDACTED: &str = "<redacted>";
+/// Bare tokens at least this long that mix letters and digits are treated as keys.
+const KEY_LIKE_LEN: usize = 24;
+const SENSITIVE_NAMES: [&str; 5] = ["key", "token", "secret", "password", "signature"];
+const STORE: Store = Store {
+    name: "crash report",
+    wrapped: false,
+    migrations: &[schema::unversioned],
+};
+
+static BREADCRUMBS: Mutex<VecDeque<String>> = Mutex::new(VecDeque::new());
+
+/// Where the hook writes, resolved once at startup since the hook has no `AppHandle`.
+struct Paths {
+    crash_dir: PathBuf,
+    settings: Option<PathBuf>,
+    app_version: String,
+}
+
+static PATHS: OnceLock<Paths> = OnceLock::new();
+
+#[derive(Serialize, Deserialize, Clone, Debug, Type)]
+pub struct CrashReport {
+    pub id: String,
+    pub created_at: u64,
+    pub app_version: String,
+    pub os: String,
+    pub arch: String,
+    pub thread: String,
+    pub message: String,
+    pub location: Option<String>,
+    pub backtrace: String,
+    /// Breadcrumbs leading up to the crash, oldest first.
+    pub recent: Vec<String>,
+    #[serde(default)]
+    pub uploaded: bool,
+}
+
+/// Record a line in the ring buffer attached to crash reports. Keep transcript text out
+/// of breadcrumbs; scrubbing is a safety net, not a license.
+pub fn breadcrumb(line: impl Into<String>) {
+    let Ok(mut breadcrumbs) = BREADCRUMBS.lock() else {
+        return;
+    };
+    if breadcrumbs.len() == MAX_BREADCRUMBS {
+        breadcrumbs.pop_front();
+    }
+    breadcrumbs.push_back(format!("{} {}", history::now_ms(), line.into()));
+}
+
+pub fn crash_dir(app: &AppHandle) -> Result<PathBuf, String> {
+    let dir = app
+        .path()
+        .app_data_dir()
+        .map_err(|e| format!("Failed to resolve app data directory: {}", e))?
+        .join("crashes");
+    fs::create_dir_all(&dir).map_err(|e| format!("Failed to create crash directory: {}", e))?;
+    Ok(dir)
+}
+
+/// Every secret the user has configured, so it can be removed verbatim.
+fn configured_secrets(settings: &Settings) -> Vec<String> {
+    let mut secrets: Vec<String> = Vec::new();
+    secrets.extend(settings.deepgram_api_key.clone());
+    secrets.extend(settings.deepgram_api_keys.iter().cloned());
+    secrets.extend(settings.webhook.as_ref().map(|w| w.secret.clone()));
+    secrets.extend(settings.caption_server.token.clone());
+    secrets.extend(settings.rest_api.token.clone());
+    if let Some(sync) = &settings.sync {
+        match &sync.target {
+            settings::SyncTarget::WebDav { password, .. } => secrets.extend(password.clone()),
+            settings::SyncTarget::S3 {
+                access_key_id,
+                secret_access_key,
+                ..
+            } => {
+                secrets.push(access_key_id.clone());
+                secrets.push(secret_access_key.clone());
+            }
+        }
+    }
+    // Short values would redact ordinary words
+    secrets.retain(|secret| secret.len() >= 8);
+    secrets
+}
+
+fn is_key_like(word: &str) -> bool {
+    let token = word.trim_matches(|c: char| !c.is_ascii_alphanumeric() && c != '-' && c != '_');
+    token.len() >= KEY_LIKE_LEN
+        && token
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+        && token.chars().any(|c| c.is_ascii_digit())
+        && token.chars().any(|c| c.is_ascii_alphabetic())
+}
+
+/// Replace the contents of every double-quoted string.
+fn scrub_quoted(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        out.push(c);
+        if c != '"' {
+            continue;
+        }
+        let mut escaped = false;
+        let mut empty = true;
+        let mut closed = false;
+        for inner in chars.by_ref() {
+            if !escaped && inner == '"' {
+                closed = true;
+                break;
+            }
+            escaped = !escaped && inner == '\\';
+            empty = false;
+        }
+        if !empty {
+            out.push_str("<text>");
+        }
+        if closed {
+            out.push('"');
+        }
+    }
+    out
+}
+
+/// Redact `name=value` pairs whose name looks like a credential, as in query strings.
+fn scrub_params(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(eq) = rest.find('=') {
+        let (before, after) = rest.split_at(eq);
+        out.push_str(before);
+        out.push('=');
+        let name_start = before
+            .rfind(|c: char| !c.is_ascii_alphanumeric() && c != '_')
+            .map(|i| i + 1)
+            .unwrap_or(0);
+        let name = before[name_start..].to_ascii_lowercase();
+        let after = &after[1..];
+        let value_len = after
+            .find(|c: char| c == '&' || c == '"' || c.is_whitespace())
+            .unwrap_or(after.len());
+        if value_len > 0 && SENSITIVE_NAMES.iter().any(|s| name.contains(s)) {
+            out.push_str(REDACTED);
+            rest = &after[value_len..];
+        } else {
+            rest = after;
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+/// Redact header-style credentials (`Token abc`, `Bearer abc`) and bare key-like words.
+fn scrub_words(text: &str) -> String {
+    let mut previous = "";
+    text.split(' ')
+        .map(|word| {
+            let scheme = previous.trim_start_matches(|c: char| !c.is_ascii_alphabetic());
+            let after_scheme =
+                scheme.eq_ignore_ascii_case("token") || scheme.eq_ignore_ascii_case("bearer");
+            previous = word;
+            if (after_scheme && !word.is_empty()) || is_key_like(word) {
+                REDACTED
+            } else {
+                word
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn scrub(text: &str, secrets: &[String]) -> String {
+    let mut text = text.to_string();
+    for secret in secrets {
+        text = text.replace(secret.as_str(), REDACTED);
+    }
+    scrub_words(&scrub_params(&scrub_quoted(&text)))
+}
+
+fn read_secrets(settings_path: Option<&Path>) -> Vec<String> {
+    settings_path
+        .and_then(|path| fs::read(path).ok())
+        .and_then(|bytes| settings::STORE.decode::<Settings>(&bytes).ok())
+        .map(|settings| configured_secrets(&settings))
+        .unwrap_or_default()
+}
+
+fn write_report(paths: &Paths, info: &std::panic::PanicHookInfo) -> Result<PathBuf, String> {
+    let secrets = read_secrets(paths.settings.as_deref());
+    let payload = info
+        .payload()
+        .downcast_ref::<&str>()
+        .map(|s| s.to_string())
+        .or_else(|| info.payload().downcast_ref::<String>().cloned())
+        .unwrap_or_else(|| "Box<dyn Any>".to_string());
+    // A panic while the buffer was locked leaves it poisoned; the contents are still fine
+    let recent = BREADCRUMBS
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+        .iter()
+        .map(|line| scrub(line, &secrets))
+        .collect();
+
+    let report = CrashReport {
+        id: uuid::Uuid::new_v4().to_string(),
+        created_at: history::now_ms(),
+        app_version: paths.app_version.clone(),
+        os: std::env::consts::OS.to_string(),
+        arch: std::env::consts::ARCH.to_string(),
+        thread: std::thread::current()
+            .name()
+            .unwrap_or("<unnamed>")
+            .to_string(),
+        message: scrub(&payload, &secrets),
+        location: info
+            .location()
+            .map(|l| format!("{}:{}:{}", l.file(), l.line(), l.column())),
+        backtrace: std::backtrace::Backtrace::force_capture().to_string(),
+        recent,
+        uploaded: false,
+    };
+    let path = paths.crash_dir.join(format!("{}.json", report.id));
+    STORE.write(&path, &report)?;
+    Ok(path)
+}
+
+/// Install the panic hook. Called once from `setup`; the default hook still runs after.
+pub fn install(app: &AppHandle) -> Result<(), String> {
+    let paths = Paths {
+        crash_dir: crash_dir(app)?,
+        settings: settings::settings_path(app).ok(),
+        app_version: app.package_info().version.to_string(),
+    };
+    if PATHS.set(paths).is_err() {
+        return Ok(());
+    }
+    let previous = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        if let Some(paths) = PATHS.get() {
+            match write_report(paths, info) {
+                Ok(path) => eprintln!("[crash] Report written to {}", path.display()),
+                Err(e) => eprintln!("[crash] {}", e),
+            }
+        }
+        previous(info);
+    }));
+    Ok(())
+}
+
+fn report_paths(app: &AppHandle) -> Result<Vec<PathBuf>, String> {
+    let entries = fs::read_dir(crash_dir(app)?)
+        .map_err(|e| format!("Failed to read crash directory: {}", e))?;
+    Ok(entries
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "json"))
+        .collect())
+}
+
+fn read_report(path: &Path) -> Option<CrashReport> {
+    fs::read(path)
+        .ok()
+        .and_then(|bytes| STORE.decode(&bytes).ok())
+}
+
+async fn upload_pending(app: &AppHandle, endpoint: &str) -> Result<usize, String> {
+    let client = reqwest::Client::new();
+    let mut uploaded = 0;
+    for path in report_paths(app)? {
+        let Some(mut report) = read_report(&path) else {
+            continue;
+        };
+        if report.uploaded {
+            continue;
+        }
+        let response = client
+            .post(endpoint)
+            .timeout(REQUEST_TIMEOUT)
+            .json(&report)
+            .send()
+            .await
+            .map_err(|e| format!("Crash report upload failed: {}", e))?;
+        if !response.status().is_success() {
+            return Err(format!(
+                "Crash report endpoint returned HTTP {}",
+                response.status()
+            ));
+        }
+        report.uploaded = true;
+        STORE.write(&path, &report)?;
+        uploaded += 1;
+    }
+    Ok(uploaded)
+}
+
+/// Upload reports left by earlier crashes, if the user opted in. Called once from `setup`.
+pub fn spawn_upload(app: AppHandle) {
+    let config = settings::load(&app).crash_reports;
+    let Some(endpoint) = config.endpoint.filter(|e| config.upload && !e.is_empty()) else {
+        return;
+    };
+    tauri::async_runtime::spawn(async move {
+        match upload_pending(&app, &endpoint).await {
+            Ok(0) => {}
+            Ok(count) => println!("[crash] Uploaded {} crash reports", count),
+            Err(e) => println!("[crash] {}", e),
+        }
+    });
+}
+
+/// Stored crash reports, newest first.
+#[tauri::command]
+#[specta::specta]
+pub fn list_crash_reports(app: AppHandle) -> Result<Vec<CrashReport>, String> {
+    app_lock::ensure_unlocked(&app)?;
+    let mut reports: Vec<CrashReport> = report_paths(&app)?
+        .iter()
+        .filter_map(|path| read_report(path))
+        .collect();
+    reports.sort_by_key(|report| std::cmp::Reverse(report.created_at));
+    Ok(reports)
+}
+
+/// Delete every stored crash report and return how many were removed.
+#[tauri::command]
+#[specta::specta]
+pub fn delete_crash_reports(app: AppHandle) -> Result<usize, String> {
+    let paths = report_paths(&app)?;
+    for path in &paths {
+        fs::remove_file(path).map_err(|e| format!("Failed to delete crash report: {}", e))?;
+    }
+    Ok(paths.len())
+}
@@ -0,0 +1,571 @@
+//! Optional at-rest encryption of stored sessions and archived recordings.
+//!
+//! Files are sealed to an X25519 public key kept in settings: each gets a fresh ephemeral
+//! key pair, and the XChaCha20-Poly1305 key comes from the Diffie-Hellman secret. Writing
+//! therefore never needs unlocking, so dictation keeps being stored while history is
+//! locked. Reading needs the private key. It is either sealed with a key derived from a
+//! user passphrase with Argon2id, or kept in the OS keychain and unlocked at startup; it is
+//! only ever held in memory. Settings keep a sealed check value so a wrong passphrase is
+//! detected before anything is decrypted.
+//!
+//! Sealed files start with a short magic prefix, so plaintext files from before
+//! encryption still load. Files sealed with the passphrase key directly, by versions from
+//! before public keys, are still opened with it.
+
+use crate::settings::{self, EncryptionSettings};
+use crate::{app_lock, history, outbox, recordings, schema};
+use argon2::Argon2;
+use chacha20poly1305::aead::rand_core::RngCore;
+use chacha20poly1305::aead::{Aead, AeadCore, KeyInit, OsRng};
+use chacha20poly1305::{XChaCha20Poly1305, XNonce};
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use specta::Type;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use tauri::{AppHandle, Manager, State};
+use x25519_dalek::{PublicKey, StaticSecret};
+
+const MAGIC: &[u8] = b"VTTENC1\n";
+/// Files sealed to the public key: ephemeral public key, nonce, then ciphertext.
+const SEALED_MAGIC: &[u8] = b"VTTENC2\n";
+const NONCE_LEN: usize = 24;
+const KEY_LEN: usize = 32;
+const CHECK_PLAINTEXT: &[u8] = b"voice-to-text";
+const LOCKED: &str = "History is locked; unlock it with your passphrase first";
+/// Where the private key is kept in the OS keychain.
+const KEYCHAIN_SERVICE: &str = "voice-to-text";
+const KEYCHAIN_ACCOUNT: &str = "history-key";
+
+/// The unlocked keys, if any.
+#[derive(Default)]
+pub struct Vault(Mutex<Option<Keys>>);
+
+#[derive(Clone)]
+struct Keys {
+    /// Opens files sealed to the public key.
+    secret: [u8; 32],
+    /// The passphrase key, for files it sealed directly.
+    passphrase: Option<[u8; 32]>,
+}
+
+#[derive(Serialize, Clone, Debug, Type)]
+pub struct EncryptionStatus {
+    pub enabled: bool,
+    pub unlocked: bool,
+    /// The key is kept in the OS keychain rather than behind a passphrase.
+    pub keychain: bool,
+}
+
+/// Compare secrets without stopping at the first difference, so response times don't
+/// reveal how much of a guess was right.
+pub fn constant_time_eq(actual: &[u8], expected: &[u8]) -> bool {
+    let differences = actual
+        .iter()
+        .zip(expected)
+        .fold(actual.len() ^ expected.len(), |acc, (a, b)| {
+            acc | (a ^ b) as usize
+        });
+    differences == 0
+}
+
+pub fn derive_key(passphrase: &str, salt: &[u8]) -> Result<[u8; 32], String> {
+    if passphrase.is_empty() {
+        return Err("Passphrase must not be empty".to_string());
+    }
+    let mut key = [0u8; 32];
+    Argon2::default()
+        .hash_password_into(passphrase.as_bytes(), salt, &mut key)
+        .map_err(|e| format!("Failed to derive key: {}", e))?;
+    Ok(key)
+}
+
+pub fn encrypt(key: &[u8; 32], plaintext: &[u8]) -> Result<Vec<u8>, String> {
+    let cipher = XChaCha20Poly1305::new(key.into());
+    let nonce = XChaCha20Poly1305::generate_nonce(&mut OsRng);
+    let ciphertext = cipher
+        .encrypt(&nonce, plaintext)
+        .map_err(|_| "Encryption failed".to_string())?;
+    let mut out = Vec::with_capacity(MAGIC.len() + NONCE_LEN + ciphertext.len());
+    out.extend_from_slice(MAGIC);
+    out.extend_from_slice(&nonce);
+    out.extend_from_slice(&ciphertext);
+    Ok(out)
+}
+
+pub fn decrypt(key: &[u8; 32], sealed: &[u8]) -> Result<Vec<u8>, String> {
+    let body = sealed
+        .strip_prefix(MAGIC)
+        .filter(|body| body.len() > NONCE_LEN)
+        .ok_or_else(|| "Not an encrypted file".to_string())?;
+    let (nonce, ciphertext) = body.split_at(NONCE_LEN);
+    XChaCha20Poly1305::new(key.into())
+        .decrypt(XNonce::from_slice(nonce), ciphertext)
+        .map_err(|_| "Decryption failed (wrong passphrase or corrupt file)".to_string())
+}
+
+/// The symmetric key shared by an ephemeral key pair and the recipient.
+fn shared_key(shared: &[u8; 32], ephemeral: &PublicKey, recipient: &PublicKey) -> [u8; 32] {
+    let mut hasher = Sha256::new();
+    hasher.update(b"voice-to-text sealed file");
+    hasher.update(shared);
+    hasher.update(ephemeral.as_bytes());
+    hasher.update(recipient.as_bytes());
+    hasher.finalize().into()
+}
+
+/// Seal `plaintext` so only the holder of the private key of `recipient` can open it.
+pub fn seal_to(recipient: &[u8; 32], plaintext: &[u8]) -> Result<Vec<u8>, String> {
+    let recipient = PublicKey::from(*recipient);
+    let ephemeral = StaticSecret::random_from_rng(OsRng);
+    let ephemeral_public = PublicKey::from(&ephemeral);
+    let shared = ephemeral.diffie_hellman(&recipient);
+    let key = shared_key(shared.as_bytes(), &ephemeral_public, &recipient);
+    let cipher = XChaCha20Poly1305::new((&key).into());
+    let nonce = XChaCha20Poly1305::generate_nonce(&mut OsRng);
+    let ciphertext = cipher
+        .encrypt(&nonce, plaintext)
+        .map_err(|_| "Encryption failed".to_string())?;
+    let mut out = Vec::with_capacity(SEALED_MAGIC.len() + KEY_LEN + NONCE_LEN + ciphertext.len());
+    out.extend_from_slice(SEALED_MAGIC);
+    out.extend_from_slice(ephemeral_public.as_bytes());
+    out.extend_from_slice(&nonce);
+    out.extend_from_slice(&ciphertext);
+    Ok(out)
+}
+
+/// Open what [`seal_to`] sealed to the public key of `secret`.
+pub fn open_sealed(secret: &[u8; 32], sealed: &[u8]) -> Result<Vec<u8>, String> {
+    let body = sealed
+        .strip_prefix(SEALED_MAGIC)
+        .filter(|body| body.len() > KEY_LEN + NONCE_LEN)
+        .ok_or_else(|| "Not a sealed file".to_string())?;
+    let (ephemeral, body) = body.split_at(KEY_LEN);
+    let (nonce, ciphertext) = body.split_at(NONCE_LEN);
+    let ephemeral = PublicKey::from(<[u8; 32]>::try_from(ephemeral).unwrap());
+    let secret = StaticSecret::from(*secret);
+    let shared = secret.diffie_hellman(&ephemeral);
+    let key = shared_key(shared.as_bytes(), &ephemeral, &PublicKey::from(&secret));
+    XChaCha20Poly1305::new((&key).into())
+        .decrypt(XNonce::from_slice(nonce), ciphertext)
+        .map_err(|_| "Decryption failed (wrong key or corrupt file)".to_string())
+}
+
+fn key_from_hex(hex: &str, what: &str) -> Result<[u8; 32], String> {
+    hex::decode(hex)
+        .ok()
+        .and_then(|bytes| bytes.try_into().ok())
+        .ok_or_else(|| format!("Corrupt {}", what))
+}
+
+/// A new key pair: the private key, and the public key in hex.
+fn generate_key_pair() -> ([u8; 32], String) {
+    let secret = StaticSecret::random_from_rng(OsRng);
+    let public = PublicKey::from(&secret);
+    (secret.to_bytes(), hex::encode(public.as_bytes()))
+}
+
+fn keychain_entry() -> Result<keyring::Entry, String> {
+    keyring::Entry::new(KEYCHAIN_SERVICE, KEYCHAIN_ACCOUNT)
+        .map_err(|e| format!("OS keychain unavailable: {}", e))
+}
+
+fn keychain_secret() -> Result<[u8; 32], String> {
+    let stored = keychain_entry()?
+        .get_password()
+        .map_err(|e| format!("Failed to read the history key from the OS keychain: {}", e))?;
+    key_from_hex(&stored, "history key in the OS keychain")
+}
+
+/// Remove the private key from the OS keychain, e.g. when all data is erased.
+pub fn forget_keychain_key() {
+    if let Ok(entry) = keychain_entry() {
+        let _ = entry.delete_credential();
+    }
+}
+
+/// Derive the passphrase key, check it against the stored check value, and open the
+/// private key with it.
+fn unlock_keys(config: &EncryptionSettings, passphrase: &str) -> Result<Keys, String> {
+    let salt = hex::decode(&config.salt).map_err(|e| format!("Corrupt salt: {}", e))?;
+    let check = hex::decode(&config.check).map_err(|e| format!("Corrupt check value: {}", e))?;
+    let key = derive_key(passphrase, &salt)?;
+    match decrypt(&key, &check) {
+        Ok(plaintext) if plaintext == CHECK_PLAINTEXT => {}
+        _ => return Err("Wrong passphrase".to_string()),
+    }
+    let secret = match &config.sealed_secret {
+        Some(sealed) => {
+            let sealed = hex::decode(sealed).map_err(|e| format!("Corrupt history key: {}", e))?;
+            let secret = decrypt(&key, &sealed)?;
+            <[u8; 32]>::try_from(secret.as_slice())
+                .map_err(|_| "Corrupt history key".to_string())?
+        }
+        // Settings from before public keys get their key pair on first unlock
+        None => [0u8; 32],
+    };
+    Ok(Keys {
+        secret,
+        passphrase: Some(key),
+    })
+}
+
+/// The keys for the current settings: from the keychain, or else from `passphrase`.
+fn keys_for(config: &EncryptionSettings, passphrase: Option<&str>) -> Result<Keys, String> {
+    if config.keychain {
+        return Ok(Keys {
+            secret: keychain_secret()?,
+            passphrase: None,
+        });
+    }
+    unlock_keys(
+        config,
+        passphrase.ok_or_else(|| "A passphrase is required".to_string())?,
+    )
+}
+
+pub fn is_encrypted(bytes: &[u8]) -> bool {
+    bytes.starts_with(MAGIC) || bytes.starts_with(SEALED_MAGIC)
+}
+
+/// Errors when encryption is on but no key is unlocked.
+pub fn ensure_unlocked(app: &AppHandle) -> Result<(), String> {
+    if settings::load(app).encryption.is_some() && app.state::<Vault>().0.lock().unwrap().is_none()
+    {
+        return Err(LOCKED.to_string());
+    }
+    Ok(())
+}
+
+/// Whether files written now are sealed.
+pub fn is_enabled(app: &AppHandle) -> bool {
+    settings::load(app).encryption.is_some()
+}
+
+/// Encrypt bytes about to be written when encryption is on. Needs no unlocking, except
+/// with settings from before public keys until history is first unlocked.
+pub fn seal(app: &AppHandle, plaintext: Vec<u8>) -> Result<Vec<u8>, String> {
+    let Some(config) = settings::load(app).encryption else {
+        return Ok(plaintext);
+    };
+    if let Some(public_key) = &config.public_key {
+        return seal_to(&key_from_hex(public_key, "public key")?, &plaintext);
+    }
+    match app.state::<Vault>().0.lock().unwrap().as_ref() {
+        Some(Keys {
+            passphrase: Some(key),
+            ..
+        }) => encrypt(key, &plaintext),
+        _ => Err(LOCKED.to_string()),
+    }
+}
+
+fn open_with(keys: &Keys, bytes: &[u8]) -> Result<Vec<u8>, String> {
+    if bytes.starts_with(SEALED_MAGIC) {
+        return open_sealed(&keys.secret, bytes);
+    }
+    match &keys.passphrase {
+        Some(key) => decrypt(key, bytes),
+        None => Err("File was sealed with a passphrase this key can't replace".to_string()),
+    }
+}
+
+/// Decrypt bytes read from disk if they are sealed; plaintext passes through.
+pub fn open(app: &AppHandle, bytes: Vec<u8>) -> Result<Vec<u8>, String> {
+    if !is_encrypted(&bytes) {
+        return Ok(bytes);
+    }
+    match app.state::<Vault>().0.lock().unwrap().as_ref() {
+        Some(keys) => open_with(keys, &bytes),
+        None => Err(LOCKED.to_string()),
+    }
+}
+
+fn stored_files(app: &AppHandle) -> Result<Vec<PathBuf>, String> {
+    let entries = fs::read_dir(history::history_dir(app)?)
+        .map_err(|e| format!("Failed to read history directory: {}", e))?;
+    let mut files: Vec<PathBuf> = entries
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "json"))
+        .collect();
+    files.extend(
+        recordings::list(app)?
+            .into_iter()
+            .map(|recording| recording.path),
+    );
+    Ok(files)
+}
+
+/// Rewrite every session and recording through `transform`, skipping files it leaves
+/// unchanged.
+fn rewrite_all(
+    app: &AppHandle,
+    transform: impl Fn(Vec<u8>) -> Result<Vec<u8>, String>,
+) -> Result<usize, String> {
+    let mut rewritten = 0;
+    for path in stored_files(app)? {
+        let bytes = fs::read(&path).map_err(|e| format!("Failed to read {:?}: {}", path, e))?;
+        let output = transform(bytes.clone())?;
+        if output != bytes {
+            // Replaced whole, so an interruption never leaves a file half rewritten
+            schema::write_atomic(&path, &output)
+                .map_err(|e| format!("Failed to write {:?}: {}", path, e))?;
+            rewritten += 1;
+        }
+    }
+    Ok(rewritten)
+}
+
+fn status(app: &AppHandle, vault: &Vault) -> EncryptionStatus {
+    let config = settings::load(app).encryption;
+    EncryptionStatus {
+        enabled: config.as_ref().is_some_and(|config| !config.sealing),
+        unlocked: vault.0.lock().unwrap().is_some(),
+        keychain: config.is_some_and(|config| config.keychain),
+    }
+}
+
+/// Unlock history at startup when its key is kept in the OS keychain.
+pub fn unlock_from_keychain(app: &AppHandle) {
+    let Some(config) = settings::load(app)
+        .encryption
+        .filter(|config| config.keychain)
+    else {
+        return;
+    };
+    match keys_for(&config, None) {
+        Ok(keys) => *app.state::<Vault>().0.lock().unwrap() = Some(keys),
+        Err(e) => println!("[encryption] {}", e),
+    }
+}
+
+#[tauri::command]
+#[specta::specta]
+pub fn get_encryption_status(app: AppHandle, vault: State<'_, Vault>) -> EncryptionStatus {
+    status(&app, &vault)
+}
+
+/// A new key pair, with its private key protected by `passphrase` or stored in the OS
+/// keychain when none is given.
+fn new_keys(passphrase: Option<String>) -> Result<(EncryptionSettings, Keys), String> {
+    let (secret, public_key) = generate_key_pair();
+    match passphrase {
+        Some(passphrase) => {
+            let mut salt = [0u8; 16];
+            OsRng.fill_bytes(&mut salt);
+            let key = derive_key(&passphrase, &salt)?;
+            let config = EncryptionSettings {
+                salt: hex::encode(salt),
+                check: hex::encode(encrypt(&key, CHECK_PLAINTEXT)?),
+                public_key: Some(public_key),
+                sealed_secret: Some(hex::encode(encrypt(&key, &secret)?)),
+                keychain: false,
+                sealing: true,
+            };
+            let keys = Keys {
+                secret,
+                passphrase: Some(key),
+            };
+            Ok((config, keys))
+        }
+        None => {
+            keychain_entry()?
+                .set_password(&hex::encode(secret))
+                .map_err(|e| {
+                    format!("Failed to store the history key in the OS keychain: {}", e)
+                })?;
+            let config = EncryptionSettings {
+                salt: String::new(),
+                check: String::new(),
+                public_key: Some(public_key),
+                sealed_secret: None,
+                keychain: true,
+                sealing: true,
+            };
+            let keys = Keys {
+                secret,
+                passphrase: None,
+            };
+            Ok((config, keys))
+        }
+    }
+}
+
+/// Turn encryption on and seal every stored session and recording. The private key is
+/// protected by `passphrase`, or kept in the OS keychain when none is given. After an
+/// interrupted attempt this finishes sealing with the keys that attempt saved.
+#[tauri::command]
+#[specta::specta]
+pub fn enable_encryption(
+    app: AppHandle,
+    vault: State<'_, Vault>,
+    passphrase: Option<String>,
+) -> Result<EncryptionStatus, String> {
+    app_lock::ensure_unlocked(&app)?;
+    let mut current = settings::load(&app);
+    let (mut config, keys) = match current.encryption.clone() {
+        Some(config) if !config.sealing => {
+            return Err("Encryption is already enabled".to_string());
+        }
+        Some(config) => {
+            let keys = keys_for(&config, passphrase.as_deref())?;
+            (config, keys)
+        }
+        None => {
+            let (config, keys) = new_keys(passphrase)?;
+            // Save the keys first: if rewriting stops halfway, whatever was already sealed
+            // still opens
+            current.encryption = Some(config.clone());
+            settings::save(&app, &current)?;
+            (config, keys)
+        }
+    };
+    *vault.0.lock().unwrap() = Some(keys);
+
+    let recipient = key_from_hex(
+        config.public_key.as_deref().unwrap_or_default(),
+        "public key",
+    )?;
+    let transform = |bytes: Vec<u8>| {
+        if is_encrypted(&bytes) {
+            Ok(bytes)
+        } else {
+            seal_to(&recipient, &bytes)
+        }
+    };
+    let count = rewrite_all(&app, transform)?;
+    let queued = outbox::rewrite_webhook_bodies(&app, transform)?;
+
+    // Only now does encryption count as enabled
+    config.sealing = false;
+    current.encryption = Some(config);
+    settings::save(&app, &current)?;
+    println!(
+        "[encryption] Enabled; sealed {} files and {} queued webhooks",
+        count, queued
+    );
+    Ok(status(&app, &vault))
+}
+
+/// Decrypt every stored session and recording and turn encryption off. `passphrase` is
+/// not needed when the key is kept in the OS keychain.
+#[tauri::command]
+#[specta::specta]
+pub fn disable_encryption(
+    app: AppHandle,
+    vault: State<'_, Vault>,
+    passphrase: Option<String>,
+) -> Result<EncryptionStatus, String> {
+    app_lock::ensure_unlocked(&app)?;
+    let mut current = settings::load(&app);
+    let Some(config) = current.encryption.clone() else {
+        return Err("Encryption is not enabled".to_string());
+    };
+    let keys = keys_for(&config, passphrase.as_deref())?;
+
+    let transform = |bytes: Vec<u8>| {
+        if is_encrypted(&bytes) {
+            open_with(&keys, &bytes)
+        } else {
+            Ok(bytes)
+        }
+    };
+    let count = rewrite_all(&app, transform)?;
+    outbox::rewrite_webhook_bodies(&app, transform)?;
+    current.encryption = None;
+    settings::save(&app, &current)?;
+    *vault.0.lock().unwrap() = None;
+    if config.keychain {
+        forget_keychain_key();
+    }
+    println!("[encryption] Disabled; decrypted {} files", count);
+    Ok(status(&app, &vault))
+}
+
+#[tauri::command]
+#[specta::specta]
+pub fn unlock_history(
+    app: AppHandle,
+    vault: State<'_, Vault>,
+    passphrase: Option<String>,
+) -> Result<EncryptionStatus, String> {
+    let mut current = settings::load(&app);
+    let mut config = current
+        .encryption
+        .clone()
+        .ok_or_else(|| "Encryption is not enabled".to_string())?;
+    let mut keys = keys_for(&config, passphrase.as_deref())?;
+    if config.public_key.is_none() {
+        // Settings from before public keys: from now on, saving needs no unlocking
+        if let Some(key) = keys.passphrase {
+            let (secret, public_key) = generate_key_pair();
+            config.public_key = Some(public_key);
+            config.sealed_secret = Some(hex::encode(encrypt(&key, &secret)?));
+            current.encryption = Some(config);
+            settings::save(&app, &current)?;
+            keys.secret = secret;
+        }
+    }
+    *vault.0.lock().unwrap() = Some(keys);
+    Ok(status(&app, &vault))
+}
+
+/// Forget the unlocked key. With the key in the OS keychain, history stays locked until
+/// `unlock_history` or the next launch.
+#[tauri::command]
+#[specta::specta]
+pub fn lock_history(app: AppHandle, vault: State<'_, Vault>) -> EncryptionStatus {
+    *vault.0.lock().unwrap() = None;
+    status(&app, &vault)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn seals_and_opens_with_the_key_pair() {
+        let (secret, public_key) = generate_key_pair();
+        let recipient = key_from_hex(&public_key, "public key").unwrap();
+        let sealed = seal_to(&recipient, b"dictated text").unwrap();
+        assert!(is_encrypted(&sealed));
+        assert_eq!(open_sealed(&secret, &sealed).unwrap(), b"dictated text");
+    }
+
+    #[test]
+    fn refuses_to_open_with_another_key() {
+        let (_, public_key) = generate_key_pair();
+        let (other, _) = generate_key_pair();
+        let sealed = seal_to(&key_from_hex(&public_key, "public key").unwrap(), b"x").unwrap();
+        assert!(open_sealed(&other, &sealed).is_err());
+    }
+
+    #[test]
+    fn refuses_a_tampered_sealed_file() {
+        let (secret, public_key) = generate_key_pair();
+        let mut sealed = seal_to(&key_from_hex(&public_key, "public key").unwrap(), b"x").unwrap();
+        *sealed.last_mut().unwrap() ^= 1;
+        assert!(open_sealed(&secret, &sealed).is_err());
+    }
+
+    #[test]
+    fn encrypts_and_decrypts_with_a_passphrase_key() {
+        let key = derive_key("correct horse", b"0123456789abcdef").unwrap();
+        let sealed = encrypt(&key, CHECK_PLAINTEXT).unwrap();
+        assert!(is_encrypted(&sealed));
+        assert_eq!(decrypt(&key, &sealed).unwrap(), CHECK_PLAINTEXT);
+
+        let wrong = derive_key("wrong horse", b"0123456789abcdef").unwrap();
+        assert!(decrypt(&wrong, &sealed).is_err());
+    }
+
+    #[test]
+    fn leaves_plaintext_alone() {
+        assert!(!is_encrypted(b"{\"id\": \"session\"}"));
+        assert!(open_sealed(&[0; 32], b"{}").is_err());
+        assert!(decrypt(&[0; 32], b"{}").is_err());
+        assert!(derive_key("", b"0123456789abcdef").is_err());
+    }
+}
@@ -0,0 +1,400 @@
+//! Moving sessions between two machines on the same network, without any cloud.
+//!
+//! The receiving machine advertises itself over mDNS (`_voicetotext._tcp`) and shows a
+//! one-time pairing code. The sender discovers it, then POSTs the selected sessions
+//! sealed with a key derived from that code and a salt the receiver advertises, so only
+//! someone who was shown the code can send to it, and nobody else on the network can read
+//! the transfer. The code is spent by the first transfer that opens with it.
+
+use crate::history::{self, Session};
+use crate::schema;
+use crate::{app_lock, encryption};
+use axum::body::Bytes;
+use axum::extract::State as AxumState;
+use axum::http::StatusCode;
+use axum::routing::post;
+use axum::{Json, Router};
+use chacha20poly1305::aead::rand_core::RngCore;
+use chacha20poly1305::aead::OsRng;
+use mdns_sd::{ServiceDaemon, ServiceEvent, ServiceInfo};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use specta::Type;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tauri::{AppHandle, Manager, State};
+use tokio::net::TcpListener;
+use tokio::sync::oneshot;
+
+const SERVICE_TYPE: &str = "_voicetotext._tcp.local.";
+/// TXT property carrying the receiver session's key salt.
+const SALT_PROPERTY: &str = "salt";
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(60);
+
+#[derive(Serialize, Deserialize)]
+struct Transfer {
+    from: String,
+    /// Sessions as stored, so the receiver can migrate ones from another version.
+    sessions: Vec<Value>,
+}
+
+/// Payload of the `lan_sync:received` event, and the receiver's answer to the sender.
+#[derive(Serialize, Deserialize, Clone, Debug, Type)]
+pub struct LanReceived {
+    pub from: String,
+    pub count: usize,
+}
+
+#[derive(Serialize, Clone, Debug, Type)]
+pub struct LanReceiverStatus {
+    pub running: bool,
+    pub name: Option<String>,
+    pub port: Option<u16>,
+    /// Code to enter on the sending machine, until a transfer has used it.
+    pub code: Option<String>,
+}
+
+#[derive(Serialize, Clone, Debug, Type)]
+pub struct LanPeer {
+    pub name: String,
+    pub host: String,
+    pub port: u16,
+    /// Salt advertised by the receiver, to pass back to `send_to_lan_peer`.
+    pub salt: String,
+}
+
+struct Pairing {
+    code: String,
+    key: [u8; 32],
+}
+
+struct Running {
+    name: String,
+    port: u16,
+    pairing: Arc<Mutex<Option<Pairing>>>,
+    mdns: ServiceDaemon,
+    stop: oneshot::Sender<()>,
+}
+
+#[derive(Default)]
+pub struct LanReceiver {
+    running: Mutex<Option<Running>>,
+}
+
+#[derive(Clone)]
+struct ReceiverState {
+    app: AppHandle,
+    pairing: Arc<Mutex<Option<Pairing>>>,
+}
+
+fn device_name() -> String {
+    std::env::var("HOSTNAME")
+        .or_else(|_| std::env::var("COMPUTERNAME"))
+        .ok()
+        .filter(|name| !name.is_empty())
+        .unwrap_or_else(|| "voice-to-text".to_string())
+}
+
+/// Codes are shown as `xxxx-xxxx-xxxx`; dashes and case are ignored when entered.
+fn normalize_code(code: &str) -> String {
+    code.chars()
+        .filter(|c| c.is_ascii_alphanumeric())
+        .collect::<String>()
+        .to_lowercase()
+}
+
+fn new_code() -> String {
+    let hex = uuid::Uuid::new_v4().simple().to_string();
+    format!("{}-{}-{}", &hex[0..4], &hex[4..8], &hex[8..12])
+}
+
+fn transfer_key(code: &str, salt: &str) -> Result<[u8; 32], String> {
+    let salt = hex::decode(salt).map_err(|e| format!("Invalid pairing salt: {}", e))?;
+    encryption::derive_key(&normalize_code(code), &salt)
+}
+
+/// Decrypt a transfer, spending the pairing code if it opens. A wrong code leaves it
+/// usable so the sender can retry.
+fn open(pairing: &Mutex<Option<Pairing>>, body: &[u8]) -> Result<Vec<u8>, (StatusCode, String)> {
+    let mut pairing = pairing.lock().unwrap();
+    let key = pairing
+        .as_ref()
+        .ok_or((StatusCode::GONE, "Pairing code already used".to_string()))?
+        .key;
+    let plaintext = encryption::decrypt(&key, body)
+        .map_err(|_| (StatusCode::UNAUTHORIZED, "Wrong pairing code".to_string()))?;
+    *pairing = None;
+    Ok(plaintext)
+}
+
+async fn receive(
+    AxumState(state): AxumState<ReceiverState>,
+    body: Bytes,
+) -> Result<Json<LanReceived>, (StatusCode, String)> {
+    let plaintext = open(&state.pairing, &body)?;
+    let transfer: Transfer = serde_json::from_slice(&plaintext)
+        .map_err(|e| (StatusCode::BAD_REQUEST, format!("Invalid transfer: {}", e)))?;
+    let sessions: Vec<Session> = transfer
+        .sessions
+        .into_iter()
+        .map(|session| history::STORE.upgrade(session))
+        .collect::<Result<_, _>>()
+        .map_err(|e| (StatusCode::BAD_REQUEST, format!("Invalid transfer: {}", e)))?;
+
+    for session in &sessions {
+        history::save(&state.app, session).map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e))?;
+    }
+    let received = LanReceived {
+        from: transfer.from,
+        count: sessions.len(),
+    };
+    println!(
+        "[lan_sync] Received {} sessions from {}",
+        received.count, received.from
+    );
+    let _ = schema::emit(&state.app, "lan_sync:received", received.clone());
+    Ok(Json(received))
+}
+
+fn status(receiver: &LanReceiver) -> LanReceiverStatus {
+    match receiver.running.lock().unwrap().as_ref() {
+        Some(running) => LanReceiverStatus {
+            running: true,
+            name: Some(running.name.clone()),
+            port: Some(running.port),
+            code: running
+                .pairing
+                .lock()
+                .unwrap()
+                .as_ref()
+                .map(|pairing| pairing.code.clone()),
+        },
+        None => LanReceiverStatus {
+            running: false,
+            name: None,
+            port: None,
+            code: None,
+        },
+    }
+}
+
+/// Start accepting sessions from the local network under a fresh pairing code.
+#[tauri::command]
+#[specta::specta]
+pub async fn start_lan_receiver(app: AppHandle) -> Result<LanReceiverStatus, String> {
+    encryption::ensure_unlocked(&app)?;
+    if app.state::<LanReceiver>().running.lock().unwrap().is_some() {
+        return Err("LAN receiver is already running".to_string());
+    }
+    let code = new_code();
+    let mut salt = [0u8; 16];
+    OsRng.fill_bytes(&mut salt);
+    let salt = hex::encode(salt);
+    let key = transfer_key(&code, &salt)?;
+    let pairing = Arc::new(Mutex::new(Some(Pairing { code, key })));
+
+    let listener = TcpListener::bind(("0.0.0.0", 0))
+        .await
+        .map_err(|e| format!("Failed to bind LAN receiver: {}", e))?;
+    let port = listener
+        .local_addr()
+        .map_err(|e| format!("Failed to read LAN receiver address: {}", e))?
+        .port();
+
+    let name = device_name();
+    let mdns = ServiceDaemon::new().map_err(|e| format!("Failed to start mDNS: {}", e))?;
+    let info = ServiceInfo::new(
+        SERVICE_TYPE,
+        &name,
+        &format!("{}.local.", name),
+        "",
+        port,
+        &[(SALT_PROPERTY, salt.as_str())][..],
+    )
+    .map_err(|e| format!("Failed to describe mDNS service: {}", e))?
+    .enable_addr_auto();
+    mdns.register(info)
+        .map_err(|e| format!("Failed to advertise over mDNS: {}", e))?;
+
+    let router = Router::new()
+        .route("/sessions", post(receive))
+        .with_state(ReceiverState {
+            app: app.clone(),
+            pairing: pairing.clone(),
+        });
+    let (stop_tx, stop_rx) = oneshot::channel::<()>();
+    *app.state::<LanReceiver>().running.lock().unwrap() = Some(Running {
+        name: name.clone(),
+        port,
+        pairing,
+        mdns,
+        stop: stop_tx,
+    });
+
+    println!("[lan_sync] Receiving as {} on port {}", name, port);
+    tauri::async_runtime::spawn(async move {
+        let shutdown = async {
+            let _ = stop_rx.await;
+        };
+        if let Err(e) = axum::serve(listener, router)
+            .with_graceful_shutdown(shutdown)
+            .await
+        {
+            println!("[lan_sync] Receiver error: {}", e);
+        }
+        println!("[lan_sync] Receiver stopped");
+    });
+
+    Ok(status(&app.state::<LanReceiver>()))
+}
+
+#[tauri::command]
+#[specta::specta]
+pub fn stop_lan_receiver(receiver: State<'_, LanReceiver>) -> LanReceiverStatus {
+    if let Some(running) = receiver.running.lock().unwrap().take() {
+        let _ = running.mdns.shutdown();
+        let _ = running.stop.send(());
+    }
+    status(&receiver)
+}
+
+/// Browse the network for receivers for `timeout_ms` (default 3 s).
+#[tauri::command]
+#[specta::specta]
+pub async fn discover_lan_peers(timeout_ms: Option<u64>) -> Result<Vec<LanPeer>, String> {
+    let mdns = ServiceDaemon::new().map_err(|e| format!("Failed to start mDNS: {}", e))?;
+    let events = mdns
+        .browse(SERVICE_TYPE)
+        .map_err(|e| format!("Failed to browse mDNS: {}", e))?;
+    let deadline = tokio::time::Instant::now() + Duration::from_millis(timeout_ms.unwrap_or(3000));
+
+    let mut peers: Vec<LanPeer> = Vec::new();
+    while let Ok(Ok(event)) = tokio::time::timeout_at(deadline, events.recv_async()).await {
+        let ServiceEvent::ServiceResolved(service) = event else {
+            continue;
+        };
+        // Prefer IPv4; link-local IPv6 needs a scope id that URLs can't carry portably
+        let mut addresses: Vec<_> = service
+            .addresses
+            .iter()
+            .map(|address| address.to_ip_addr())
+            .collect();
+        addresses.sort_by_key(|address| address.is_ipv6());
+        let Some(address) = addresses.first() else {
+            continue;
+        };
+        let Some(salt) = service.get_property_val_str(SALT_PROPERTY) else {
+            continue;
+        };
+        let name = service
+            .fullname
+            .strip_suffix(&format!(".{}", SERVICE_TYPE))
+            .unwrap_or(&service.fullname)
+            .to_string();
+        if !peers.iter().any(|peer| peer.name == name) {
+            peers.push(LanPeer {
+                name,
+                host: address.to_string(),
+                port: service.port,
+                salt: salt.to_string(),
+            });
+        }
+    }
+    let _ = mdns.shutdown();
+    Ok(peers)
+}
+
+/// Send stored sessions to a receiver using the code it displays and the salt it
+/// advertised.
+#[tauri::command]
+#[specta::specta]
+pub async fn send_to_lan_peer(
+    app: AppHandle,
+    host: String,
+    port: u16,
+    salt: String,
+    code: String,
+    session_ids: Vec<String>,
+) -> Result<LanReceived, String> {
+    app_lock::ensure_unlocked(&app)?;
+    let sessions = session_ids
+        .iter()
+        .map(|id| history::STORE.stamp(&history::load(&app, id)?))
+        .collect::<Result<Vec<_>, _>>()?;
+    let transfer = serde_json::to_vec(&Transfer {
+        from: device_name(),
+        sessions,
+    })
+    .map_err(|e| format!("Failed to serialize sessions: {}", e))?;
+    let body = encryption::encrypt(&transfer_key(&code, &salt)?, &transfer)?;
+
+    let host = match host.parse::<std::net::IpAddr>() {
+        Ok(std::net::IpAddr::V6(v6)) => format!("[{}]", v6),
+        _ => host,
+    };
+    let response = reqwest::Client::new()
+        .post(format!("http://{}:{}/sessions", host, port))
+        .timeout(REQUEST_TIMEOUT)
+        .body(body)
+        .send()
+        .await
+        .map_err(|e| format!("Failed to reach {}: {}", host, e))?;
+    match response.status() {
+        StatusCode::UNAUTHORIZED => Err("The receiver rejected the pairing code".to_string()),
+        StatusCode::GONE => Err("The pairing code was already used".to_string()),
+        status if !status.is_success() => {
+            let message = response.text().await.unwrap_or_default();
+            Err(format!("Receiver returned HTTP {}: {}", status, message))
+        }
+        _ => response
+            .json()
+            .await
+            .map_err(|e| format!("Invalid receiver response: {}", e)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pairing(code: &str, salt: &str) -> Mutex<Option<Pairing>> {
+        Mutex::new(Some(Pairing {
+            code: code.to_string(),
+            key: transfer_key(code, salt).unwrap(),
+        }))
+    }
+
+    #[test]
+    fn spends_the_code_on_the_first_transfer() {
+        let salt = hex::encode([7u8; 16]);
+        let pairing = pairing("abcd-ef01-2345", &salt);
+        let key = transfer_key("ABCD EF01 2345", &salt).unwrap();
+        let body = encryption::encrypt(&key, b"sessions").unwrap();
+
+        assert_eq!(open(&pairing, &body).unwrap(), b"sessions");
+        assert_eq!(open(&pairing, &body).unwrap_err().0, StatusCode::GONE);
+    }
+
+    #[test]
+    fn keeps_the_code_after_a_wrong_one() {
+        let salt = hex::encode([7u8; 16]);
+        let pairing = pairing("abcd-ef01-2345", &salt);
+        let wrong = transfer_key("abcd-ef01-2346", &salt).unwrap();
+        let wrong = encryption::encrypt(&wrong, b"sessions").unwrap();
+        let right = transfer_key("abcd-ef01-2345", &salt).unwrap();
+        let right = encryption::encrypt(&right, b"sessions").unwrap();
+
+        assert_eq!(
+            open(&pairing, &wrong).unwrap_err().0,
+            StatusCode::UNAUTHORIZED
+        );
+        assert!(open(&pairing, &right).is_ok());
+    }
+
+    #[test]
+    fn derives_a_different_key_per_receiver_salt() {
+        let first = transfer_key("abcd-ef01-2345", &hex::encode([1u8; 16])).unwrap();
+        let second = transfer_key("abcd-ef01-2345", &hex::encode([2u8; 16])).unwrap();
+        assert_ne!(first, second);
+    }
+}
@@ -0,0 +1,405 @@
+//! Durable queue of outbound deliveries: webhook calls and sync pushes.
+//!
+//! Every delivery is written to `<app data dir>/outbox.json` before it is first tried and
+//! only removed once it went through, so a crash or quit never loses one. Failures are
+//! retried in the background with exponential backoff, from `FIRST_RETRY` doubling up
+//! to `MAX_BACKOFF`, and given up after `MAX_ATTEMPTS`. Deliveries whose integration was
+//! turned off, or whose session was deleted, are dropped when they come up.
+//! `list_pending_deliveries` and `purge_pending_deliveries` inspect and clear the queue.
+//!
+//! Sync pushes only name their session. Webhook bodies carry transcripts, so while
+//! history is encrypted they are queued sealed like stored sessions, and wait for
+//! history to be unlocked before they are sent.
+
+use crate::app_lock;
+use crate::encryption;
+use crate::history;
+use crate::schema::{self, Store};
+use crate::settings;
+use crate::{sync, webhook};
+use base64::Engine;
+use serde::{Deserialize, Serialize};
+use specta::Type;
+use std::collections::HashSet;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::time::Duration;
+use tauri::{AppHandle, Manager};
+
+const POLL_INTERVAL: Duration = Duration::from_secs(15);
+const FIRST_RETRY: Duration = Duration::from_secs(30);
+const MAX_BACKOFF: Duration = Duration::from_secs(60 * 60);
+const MAX_ATTEMPTS: u32 = 20;
+
+#[derive(Default)]
+pub struct Outbox {
+    /// Serializes updates of the queue file.
+    file: Mutex<()>,
+    /// Deliveries being attempted right now, so none is sent twice at once.
+    in_flight: Mutex<HashSet<String>>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Type)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Delivery {
+    /// A webhook body, signed and sent to the webhook configured when it is attempted.
+    /// `sealed` bodies are encrypted (see [`encryption::seal`]) and base64-encoded.
+    Webhook {
+        body: String,
+        #[serde(default)]
+        sealed: bool,
+    },
+    /// Upload of a stored session to the sync target.
+    SyncPush { session_id: String },
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Type)]
+pub struct PendingDelivery {
+    pub id: String,
+    #[serde(flatten)]
+    pub delivery: Delivery,
+    /// Failed attempts so far.
+    pub attempts: u32,
+    pub created_at: u64,
+    /// Not attempted again before this time, in ms since the epoch.
+    pub next_attempt_at: u64,
+    pub last_error: Option<String>,
+}
+
+/// An entry of the webhook-only queue kept by earlier versions.
+#[derive(Deserialize)]
+struct LegacyWebhookDelivery {
+    id: String,
+    body: String,
+    attempts: u32,
+    created_at: u64,
+}
+
+enum Outcome {
+    Delivered,
+    Failed(String),
+    /// Nothing left to deliver to, or nothing left to deliver.
+    Obsolete(String),
+    /// Can't be sent while history is locked; tried again without counting an attempt.
+    Locked(String),
+}
+
+pub fn queue_path(app: &AppHandle) -> Result<PathBuf, String> {
+    let dir = app
+        .path()
+        .app_data_dir()
+        .map_err(|e| format!("Failed to resolve app data directory: {}", e))?;
+    fs::create_dir_all(&dir).map_err(|e| format!("Failed to create app data directory: {}", e))?;
+    Ok(dir.join("outbox.json"))
+}
+
+const STORE: Store = Store {
+    name: "outbox",
+    wrapped: true,
+    migrations: &[schema::wrap_records],
+};
+
+fn read_queue(app: &AppHandle) -> Result<Vec<PendingDelivery>, String> {
+    Ok(STORE.read(&queue_path(app)?)?.unwrap_or_default())
+}
+
+fn write_queue(app: &AppHandle, queue: &[PendingDelivery]) -> Result<(), String> {
+    STORE.write(&queue_path(app)?, &queue)
+}
+
+/// Change the queue on disk under the file lock.
+fn update<R>(app: &AppHandle, change: impl FnOnce(&mut Vec<PendingDelivery>) -> R) -> R {
+    let state = app.state::<Outbox>();
+    let _guard = state.file.lock().unwrap();
+    // An unreadable queue has been set aside; start a new one
+    let mut queue = read_queue(app).unwrap_or_else(|e| {
+        println!("[outbox] {}", e);
+        Vec::new()
+    });
+    let result = change(&mut queue);
+    if let Err(e) = write_queue(app, &queue) {
+        println!("[outbox] {}", e);
+    }
+    result
+}
+
+fn backoff(attempts: u32) -> Duration {
+    let doublings = attempts.saturating_sub(1).min(16);
+    (FIRST_RETRY * 2u32.pow(doublings)).min(MAX_BACKOFF)
+}
+
+/// A webhook body as queued: sealed ones as base64.
+fn store_body(bytes: Vec<u8>) -> Result<(String, bool), String> {
+    if encryption::is_encrypted(&bytes) {
+        return Ok((
+            base64::engine::general_purpose::STANDARD.encode(bytes),
+            true,
+        ));
+    }
+    String::from_utf8(bytes)
+        .map(|body| (body, false))
+        .map_err(|e| format!("Webhook body is not text: {}", e))
+}
+
+fn body_bytes(body: &str, sealed: bool) -> Result<Vec<u8>, String> {
+    if !sealed {
+        return Ok(body.as_bytes().to_vec());
+    }
+    base64::engine::general_purpose::STANDARD
+        .decode(body)
+        .map_err(|e| format!("Corrupt sealed webhook body: {}", e))
+}
+
+fn open_body(app: &AppHandle, body: &str, sealed: bool) -> Result<String, String> {
+    let bytes = encryption::open(app, body_bytes(body, sealed)?)?;
+    String::from_utf8(bytes).map_err(|e| format!("Webhook body is not text: {}", e))
+}
+
+/// A webhook delivery of `body`, sealed while history is encrypted.
+pub fn webhook(app: &AppHandle, body: String) -> Result<Delivery, String> {
+    let (body, sealed) = store_body(encryption::seal(app, body.into_bytes())?)?;
+    Ok(Delivery::Webhook { body, sealed })
+}
+
+/// Run every queued webhook body through `transform`, as encryption is turned on or off.
+pub fn rewrite_webhook_bodies(
+    app: &AppHandle,
+    transform: impl Fn(Vec<u8>) -> Result<Vec<u8>, String>,
+) -> Result<usize, String> {
+    let state = app.state::<Outbox>();
+    let _guard = state.file.lock().unwrap();
+    let mut queue = read_queue(app)?;
+    let mut rewritten = 0;
+    for pending in &mut queue {
+        if let Delivery::Webhook { body, sealed } = &mut pending.delivery {
+            (*body, *sealed) = store_body(transform(body_bytes(body, *sealed)?)?)?;
+            rewritten += 1;
+        }
+    }
+    write_queue(app, &queue)?;
+    Ok(rewritten)
+}
+
+async fn send(app: &AppHandle, delivery: &Delivery) -> Outcome {
+    let config = settings::load(app);
+    let result = match delivery {
+        Delivery::Webhook { body, sealed } => {
+            let Some(webhook) = config.webhook else {
+                return Outcome::Obsolete("webhook disabled".to_string());
+            };
+            if *sealed {
+                if let Err(e) = encryption::ensure_unlocked(app) {
+                    return Outcome::Locked(e);
+                }
+            }
+            match open_body(app, body, *sealed) {
+                Ok(body) => webhook::post(&webhook, &body).await,
+                Err(e) => Err(e),
+            }
+        }
+        Delivery::SyncPush { session_id } => {
+            let Some(sync) = config.sync else {
+                return Outcome::Obsolete("sync disabled".to_string());
+            };
+            if !history::exists(app, session_id) {
+                return Outcome::Obsolete(format!("session {} was deleted", session_id));
+            }
+            if let Err(e) = encryption::ensure_unlocked(app) {
+                return Outcome::Locked(e);
+            }
+            match history::load(app, session_id) {
+                Ok(session) => sync::push(app, sync.target, &session).await,
+                Err(e) => Err(e),
+            }
+        }
+    };
+    match result {
+        Ok(()) => Outcome::Delivered,
+        Err(e) => Outcome::Failed(e),
+    }
+}
+
+/// Try one queued delivery and record the outcome.
+async fn attempt(app: &AppHandle, pending: PendingDelivery) {
+    if !app
+        .state::<Outbox>()
+        .in_flight
+        .lock()
+        .unwrap()
+        .insert(pending.id.clone())
+    {
+        return;
+    }
+    let outcome = send(app, &pending.delivery).await;
+    update(app, |queue| {
+        let Some(index) = queue.iter().position(|entry| entry.id == pending.id) else {
+            // Purged while it was being sent
+            return;
+        };
+        match outcome {
+            Outcome::Delivered => {
+                if queue[index].attempts > 0 {
+                    println!("[outbox] Delivered {}", pending.id);
+                }
+                queue.remove(index);
+            }
+            Outcome::Obsolete(reason) => {
+                println!("[outbox] Dropping {}: {}", pending.id, reason);
+                queue.remove(index);
+            }
+            Outcome::Locked(reason) => {
+                queue[index].last_error = Some(reason);
+            }
+            Outcome::Failed(e) => {
+                let entry = &mut queue[index];
+                entry.attempts += 1;
+                if entry.attempts >= MAX_ATTEMPTS {
+                    println!(
+                        "[outbox] Giving up on {} after {} attempts: {}",
+                        pending.id, entry.attempts, e
+                    );
+                    queue.remove(index);
+                    return;
+                }
+                let delay = backoff(entry.attempts);
+                println!(
+                    "[outbox] {} failed ({}); retrying in {}s",
+                    pending.id,
+                    e,
+                    delay.as_secs()
+                );
+                entry.next_attempt_at = history::now_ms() + delay.as_millis() as u64;
+                entry.last_error = Some(e);
+            }
+        }
+    });
+    app.state::<Outbox>()
+        .in_flight
+        .lock()
+        .unwrap()
+        .remove(&pending.id);
+}
+
+/// Queue a delivery and try it right away in the background.
+pub fn submit(app: &AppHandle, delivery: Delivery) {
+    let now = history::now_ms();
+    let pending = PendingDelivery {
+        id: uuid::Uuid::new_v4().to_string(),
+        delivery,
+        attempts: 0,
+        created_at: now,
+        next_attempt_at: now,
+        last_error: None,
+    };
+    update(app, |queue| queue.push(pending.clone()));
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move { attempt(&app, pending).await });
+}
+
+/// Move deliveries from the old webhook queue into the outbox.
+fn migrate_legacy_queue(app: &AppHandle) {
+    let Some(path) = queue_path(app)
+        .ok()
+        .map(|path| path.with_file_name("webhook_queue.json"))
+        .filter(|path| path.is_file())
+    else {
+        return;
+    };
+    let legacy: Vec<LegacyWebhookDelivery> = fs::read(&path)
+        .ok()
+        .and_then(|bytes| serde_json::from_slice(&bytes).ok())
+        .unwrap_or_default();
+    let count = legacy.len();
+    update(app, |queue| {
+        queue.extend(legacy.into_iter().map(|old| PendingDelivery {
+            id: old.id,
+            delivery: Delivery::Webhook {
+                body: old.body,
+                sealed: false,
+            },
+            attempts: old.attempts,
+            created_at: old.created_at,
+            next_attempt_at: 0,
+            last_error: None,
+        }))
+    });
+    let _ = fs::remove_file(&path);
+    println!("[outbox] Moved {} queued webhook deliveries", count);
+}
+
+/// Start the background loop that retries due deliveries. Called once from `setup`.
+pub fn spawn(app: AppHandle) {
+    migrate_legacy_queue(&app);
+    tauri::async_runtime::spawn(async move {
+        let mut interval = tokio::time::interval(POLL_INTERVAL);
+        loop {
+            interval.tick().await;
+            let now = history::now_ms();
+            let due: Vec<PendingDelivery> = {
+                let state = app.state::<Outbox>();
+                let _guard = state.file.lock().unwrap();
+                read_queue(&app)
+                    .unwrap_or_else(|e| {
+                        println!("[outbox] {}", e);
+                        Vec::new()
+                    })
+                    .into_iter()
+                    .filter(|pending| pending.next_attempt_at <= now)
+                    .collect()
+            };
+            for pending in due {
+                attempt(&app, pending).await;
+            }
+        }
+    });
+}
+
+/// Deliveries waiting to be sent or retried, oldest first.
+pub fn pending(app: &AppHandle) -> Result<Vec<PendingDelivery>, String> {
+    let state = app.state::<Outbox>();
+    let _guard = state.file.lock().unwrap();
+    read_queue(app)
+}
+
+/// See [`pending`].
+#[tauri::command]
+#[specta::specta]
+pub fn list_pending_deliveries(app: AppHandle) -> Result<Vec<PendingDelivery>, String> {
+    app_lock::ensure_unlocked(&app)?;
+    pending(&app)
+}
+
+/// Drop the given deliveries, or every pending one when `ids` is not given. Returns how
+/// many were removed.
+#[tauri::command]
+#[specta::specta]
+pub fn purge_pending_deliveries(app: AppHandle, ids: Option<Vec<String>>) -> usize {
+    let removed = update(&app, |queue| {
+        let before = queue.len();
+        match &ids {
+            Some(ids) => queue.retain(|pending| !ids.contains(&pending.id)),
+            None => queue.clear(),
+        }
+        before - queue.len()
+    });
+    println!("[outbox] Purged {} deliveries", removed);
+    removed
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keeps_sealed_webhook_bodies_as_base64() {
+        let sealed = encryption::seal_to(&[7; 32], b"{\"event\":\"session.completed\"}").unwrap();
+        let (body, is_sealed) = store_body(sealed.clone()).unwrap();
+        assert!(is_sealed);
+        assert!(!body.contains("session.completed"));
+        assert_eq!(body_bytes(&body, is_sealed).unwrap(), sealed);
+
+        let (body, is_sealed) = store_body(b"{}".to_vec()).unwrap();
+        assert_eq!((body.as_str(), is_sealed), ("{}", false));
+    }
+}
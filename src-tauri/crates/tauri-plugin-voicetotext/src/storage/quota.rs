@@ -0,0 +1,146 @@
+//! Disk quota for the audio archive.
+//!
+//! When recordings use more than `storage.quota_mb`, the least recently used are deleted
+//! until they fit again; transcripts are kept. `storage:quota_warning` is sent once usage
+//! passes `storage.warn_percent` of the quota, so users can export or free space before
+//! anything is removed, and `storage:evicting` names the recordings about to be deleted.
+
+use crate::history;
+use crate::recordings::{self, Recording};
+use crate::schema;
+use crate::settings::{self, StorageSettings};
+use serde::Serialize;
+use specta::Type;
+use std::fs;
+use tauri::AppHandle;
+
+const MB: u64 = 1024 * 1024;
+
+#[derive(Serialize, Clone, Debug, Type)]
+pub struct StorageUsage {
+    pub recording_bytes: u64,
+    pub recording_count: usize,
+    /// Stored transcripts, which the quota doesn't cover.
+    pub history_bytes: u64,
+    pub quota_bytes: Option<u64>,
+    /// Share of the quota in use, 0–100 (or more while over it).
+    pub percent_used: Option<f64>,
+}
+
+/// Payload of `storage:quota_warning`.
+#[derive(Serialize, Clone, Debug, Type)]
+pub struct QuotaWarning {
+    pub recording_bytes: u64,
+    pub quota_bytes: u64,
+    pub percent_used: f64,
+}
+
+/// Payload of `storage:evicting`, sent before the recordings are deleted.
+#[derive(Serialize, Clone, Debug, Type)]
+pub struct Evicting {
+    pub session_ids: Vec<String>,
+    pub bytes: u64,
+    pub quota_bytes: u64,
+}
+
+fn quota_bytes(config: &StorageSettings) -> Option<u64> {
+    config.quota_mb.map(|mb| mb.saturating_mul(MB))
+}
+
+fn history_bytes(app: &AppHandle) -> u64 {
+    history::history_dir(app)
+        .and_then(|dir| fs::read_dir(dir).map_err(|e| e.to_string()))
+        .map(|entries| {
+            entries
+                .filter_map(|entry| entry.ok()?.metadata().ok())
+                .map(|metadata| metadata.len())
+                .sum()
+        })
+        .unwrap_or(0)
+}
+
+/// Recordings to delete, least recently used first, so the rest fit in `quota`. The
+/// recording of `keep` is never chosen.
+fn eviction_plan(mut recordings: Vec<Recording>, quota: u64, keep: Option<&str>) -> Vec<Recording> {
+    let mut used: u64 = recordings.iter().map(|r| r.bytes).sum();
+    recordings.sort_by_key(|r| r.last_used);
+    recordings
+        .into_iter()
+        .filter(|r| Some(r.session_id.as_str()) != keep)
+        .take_while(|r| {
+            let over = used > quota;
+            used = used.saturating_sub(r.bytes);
+            over
+        })
+        .collect()
+}
+
+/// Delete the least recently used recordings while the archive is over its quota.
+/// `keep` is the recording just stored, which must survive even on its own.
+pub fn enforce_quota(app: &AppHandle, keep: Option<&str>) {
+    let config = settings::load(app).storage;
+    let Some(quota) = quota_bytes(&config) else {
+        return;
+    };
+    let Ok(recordings) = recordings::list(app) else {
+        return;
+    };
+    let used: u64 = recordings.iter().map(|r| r.bytes).sum();
+    let percent_used = used as f64 * 100.0 / quota.max(1) as f64;
+    if percent_used >= config.warn_percent {
+        let _ = schema::emit(
+            app,
+            "storage:quota_warning",
+            QuotaWarning {
+                recording_bytes: used,
+                quota_bytes: quota,
+                percent_used,
+            },
+        );
+    }
+    if used <= quota {
+        return;
+    }
+
+    let plan = eviction_plan(recordings, quota, keep);
+    if plan.is_empty() {
+        return;
+    }
+    let _ = schema::emit(
+        app,
+        "storage:evicting",
+        Evicting {
+            session_ids: plan.iter().map(|r| r.session_id.clone()).collect(),
+            bytes: plan.iter().map(|r| r.bytes).sum(),
+            quota_bytes: quota,
+        },
+    );
+    for recording in plan {
+        match fs::remove_file(&recording.path) {
+            Ok(()) => println!(
+                "[storage] Evicted recording of {} ({} bytes)",
+                recording.session_id, recording.bytes
+            ),
+            Err(e) => println!(
+                "[storage] Failed to evict {}: {}",
+                recording.path.display(),
+                e
+            ),
+        }
+    }
+}
+
+#[tauri::command]
+#[specta::specta]
+pub fn get_storage_usage(app: AppHandle) -> Result<StorageUsage, String> {
+    let recordings = recordings::list(&app)?;
+    let recording_bytes = recordings.iter().map(|r| r.bytes).sum();
+    let quota_bytes = quota_bytes(&settings::load(&app).storage);
+    Ok(StorageUsage {
+        recording_bytes,
+        recording_count: recordings.len(),
+        history_bytes: history_bytes(&app),
+        quota_bytes,
+        percent_used: quota_bytes.map(|quota| recording_bytes as f64 * 100.0 / quota.max(1) as f64),
+    })
+}
@@ -0,0 +1,82 @@
+//! Automatic cleanup of old sessions per `settings.retention`: recordings and whole
+//! sessions are deleted once they are older than the configured number of days. Starred
+//! sessions are always kept. Rules are applied at startup and then every hour.
+
+use crate::history::{self, Session};
+use crate::recordings;
+use crate::settings::{self, RetentionSettings};
+use serde::Serialize;
+use specta::Type;
+use std::time::Duration;
+use tauri::AppHandle;
+
+const CHECK_INTERVAL: Duration = Duration::from_secs(60 * 60);
+const DAY_MS: u64 = 24 * 60 * 60 * 1000;
+
+#[derive(Serialize, Clone, Debug, Default, Type)]
+pub struct RetentionReport {
+    /// Sessions whose recording was deleted.
+    pub audio_deleted: Vec<String>,
+    /// Sessions deleted entirely.
+    pub sessions_deleted: Vec<String>,
+}
+
+fn older_than(session: &Session, days: Option<u32>, now: u64) -> bool {
+    days.is_some_and(|days| now.saturating_sub(session.created_at) > days as u64 * DAY_MS)
+}
+
+/// Apply the retention rules once. Sessions sealed while history is locked can't be read
+/// and are left for a later run.
+pub fn apply(app: &AppHandle, config: &RetentionSettings) -> Result<RetentionReport, String> {
+    let mut report = RetentionReport::default();
+    if config.delete_audio_after_days.is_none() && config.delete_transcripts_after_days.is_none() {
+        return Ok(report);
+    }
+
+    let now = history::now_ms();
+    for session in history::load_all(app)? {
+        if session.starred {
+            continue;
+        }
+        if older_than(&session, config.delete_transcripts_after_days, now) {
+            match history::delete(app, &session.id) {
+                Ok(()) => report.sessions_deleted.push(session.id),
+                Err(e) => println!("[retention] {}", e),
+            }
+        } else if older_than(&session, config.delete_audio_after_days, now)
+            && recordings::find(app, &session.id).is_some()
+        {
+            match recordings::delete(app, &session.id) {
+                Ok(()) => report.audio_deleted.push(session.id),
+                Err(e) => println!("[retention] {}", e),
+            }
+        }
+    }
+    if !report.audio_deleted.is_empty() || !report.sessions_deleted.is_empty() {
+        println!(
+            "[retention] Deleted {} recordings and {} sessions",
+            report.audio_deleted.len(),
+            report.sessions_deleted.len()
+        );
+    }
+    Ok(report)
+}
+
+pub fn spawn(app: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        let mut interval = tokio::time::interval(CHECK_INTERVAL);
+        loop {
+            interval.tick().await;
+            if let Err(e) = apply(&app, &settings::load(&app).retention) {
+                println!("[retention] {}", e);
+            }
+        }
+    });
+}
+
+/// Apply the retention rules now, e.g. right after changing them.
+#[tauri::command]
+#[specta::specta]
+pub fn apply_retention(app: AppHandle) -> Result<RetentionReport, String> {
+    apply(&app, &settings::load(&app).retention)
+}
@@ -0,0 +1,387 @@
+//! Schema versions of emitted events and stored records.
+//!
+//! Every event payload carries `schema_version`, so a frontend can tell a payload it was
+//! built for from one it predates. Every JSON record kept on disk is written with a
+//! `schema_version` too; lists and maps are wrapped as `{"schema_version", "records"}`.
+//! Reading runs the record through its store's migrations, one version at a time, before
+//! deserializing. Records from before versioning count as version 0, and records written
+//! by a newer build are refused rather than misread.
+//!
+//! A file that can't be read, newer or corrupt, is moved aside to `<file>.<time>.bak` and
+//! reported instead of being replaced by defaults on the next write. Files are written
+//! through a temporary file, so a crash mid-write never leaves one truncated.
+//!
+//! To change a stored shape, append a migration to its store; to change an event payload,
+//! bump `EVENT_SCHEMA_VERSION` and keep the old fields where frontends still read them.
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use serde_json::{Map, Value};
+use std::ffi::OsString;
+use std::fs;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+use tauri::{AppHandle, Emitter};
+
+/// Version of every event payload.
+pub const EVENT_SCHEMA_VERSION: u32 = 1;
+
+const VERSION_FIELD: &str = "schema_version";
+const RECORDS_FIELD: &str = "records";
+
+/// Upgrade of a stored record from the version it is listed at to the next.
+pub type Migration = fn(&mut Value) -> Result<(), String>;
+
+/// A kind of stored record and the migrations that bring it up to date.
+pub struct Store {
+    /// What the record is, for error messages.
+    pub name: &'static str,
+    /// The record is a list or map, kept under `records` next to the version.
+    pub wrapped: bool,
+    /// Migrations in order; the current version is how many there are.
+    pub migrations: &'static [Migration],
+}
+
+#[derive(Serialize, Clone)]
+struct Versioned<T> {
+    schema_version: u32,
+    #[serde(flatten)]
+    payload: T,
+}
+
+/// Emit an event with `schema_version` added to its payload.
+pub fn emit<T: Serialize + Clone>(app: &AppHandle, event: &str, payload: T) -> tauri::Result<()> {
+    app.emit(
+        event,
+        Versioned {
+            schema_version: EVENT_SCHEMA_VERSION,
+            payload,
+        },
+    )
+}
+
+/// First migration of a record stored as an object: it only gains its version.
+pub fn unversioned(_record: &mut Value) -> Result<(), String> {
+    Ok(())
+}
+
+/// First migration of a list or map: the bare value moves under `records`.
+pub fn wrap_records(record: &mut Value) -> Result<(), String> {
+    let records = record.take();
+    *record = Value::Object(Map::from_iter([(RECORDS_FIELD.to_string(), records)]));
+    Ok(())
+}
+
+/// `path` with `suffix` appended to its file name.
+fn with_suffix(path: &Path, suffix: &str) -> PathBuf {
+    let mut name = OsString::from(path.as_os_str());
+    name.push(suffix);
+    PathBuf::from(name)
+}
+
+/// Write `bytes` to `path` through a temporary file that replaces it once complete, so a
+/// crash mid-write leaves the previous contents rather than a truncated file.
+pub fn write_atomic(path: &Path, bytes: &[u8]) -> std::io::Result<()> {
+    let partial = with_suffix(path, ".tmp");
+    fs::write(&partial, bytes)?;
+    fs::rename(&partial, path)
+}
+
+/// A name next to `path` for setting it aside that no earlier backup has taken.
+fn backup_path(path: &Path) -> PathBuf {
+    let stamp = chrono::Utc::now().format("%Y%m%dT%H%M%S%3f");
+    let mut backup = with_suffix(path, &format!(".{}.bak", stamp));
+    let mut attempt = 1;
+    while backup.exists() {
+        attempt += 1;
+        backup = with_suffix(path, &format!(".{}-{}.bak", stamp, attempt));
+    }
+    backup
+}
+
+/// Files left next to a record file: unreadable copies [`Store::read`] set aside and
+/// temporary files of interrupted writes.
+pub fn leftovers(path: &Path) -> Vec<PathBuf> {
+    let (Some(dir), Some(name)) = (path.parent(), path.file_name()) else {
+        return Vec::new();
+    };
+    let prefix = format!("{}.", name.to_string_lossy());
+    let Ok(entries) = fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    entries
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|sibling| {
+            sibling
+                .file_name()
+                .map(|name| name.to_string_lossy())
+                .is_some_and(|name| {
+                    name.starts_with(&prefix) && (name.ends_with(".bak") || name.ends_with(".tmp"))
+                })
+        })
+        .collect()
+}
+
+impl Store {
+    pub fn version(&self) -> u32 {
+        self.migrations.len() as u32
+    }
+
+    /// The record as stored: stamped with the current version.
+    pub fn stamp<T: Serialize>(&self, record: &T) -> Result<Value, String> {
+        let value = serde_json::to_value(record)
+            .map_err(|e| format!("Failed to serialize {}: {}", self.name, e))?;
+        let mut object = if self.wrapped {
+            Map::from_iter([(RECORDS_FIELD.to_string(), value)])
+        } else {
+            match value {
+                Value::Object(object) => object,
+                _ => return Err(format!("{} is not stored as an object", self.name)),
+            }
+        };
+        object.insert(VERSION_FIELD.to_string(), self.version().into());
+        Ok(Value::Object(object))
+    }
+
+    /// Bring a stored record up to date and deserialize it.
+    pub fn upgrade<T: DeserializeOwned>(&self, mut value: Value) -> Result<T, String> {
+        // Bare lists and maps never had a version field
+        let versioned = !self.wrapped || value.get(RECORDS_FIELD).is_some();
+        let found = match value.get(VERSION_FIELD).and_then(Value::as_u64) {
+            Some(version) if versioned => version as u32,
+            _ => 0,
+        };
+        if found > self.version() {
+            return Err(format!(
+                "{} has schema version {}, newer than this app supports ({})",
+                self.name,
+                found,
+                self.version()
+            ));
+        }
+        for migration in &self.migrations[found as usize..] {
+            migration(&mut value).map_err(|e| format!("Failed to migrate {}: {}", self.name, e))?;
+        }
+        if let Value::Object(object) = &mut value {
+            object.remove(VERSION_FIELD);
+            if self.wrapped {
+                value = object.remove(RECORDS_FIELD).unwrap_or(Value::Null);
+            }
+        }
+        serde_json::from_value(value).map_err(|e| format!("Corrupt {}: {}", self.name, e))
+    }
+
+    /// Serialize a record for disk.
+    pub fn encode<T: Serialize>(&self, record: &T) -> Result<Vec<u8>, String> {
+        serde_json::to_vec_pretty(&self.stamp(record)?)
+            .map_err(|e| format!("Failed to serialize {}: {}", self.name, e))
+    }
+
+    /// Read a record from disk, migrating it as needed.
+    pub fn decode<T: DeserializeOwned>(&self, bytes: &[u8]) -> Result<T, String> {
+        let value =
+            serde_json::from_slice(bytes).map_err(|e| format!("Corrupt {}: {}", self.name, e))?;
+        self.upgrade(value)
+    }
+
+    /// Read a record file, migrating it as needed; `None` when there is no file yet.
+    ///
+    /// A file that exists but can't be decoded is renamed to `<file>.<time>.bak` before the
+    /// error is returned, so whatever is written next doesn't replace it, and earlier
+    /// backups are kept.
+    pub fn read<T: DeserializeOwned>(&self, path: &Path) -> Result<Option<T>, String> {
+        let bytes = match fs::read(path) {
+            Ok(bytes) => bytes,
+            Err(e) if e.kind() == ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(format!("Failed to read {}: {}", self.name, e)),
+        };
+        self.decode(&bytes).map(Some).map_err(|e| {
+            let backup = backup_path(path);
+            match fs::rename(path, &backup) {
+                Ok(()) => format!("{} (kept as {})", e, backup.display()),
+                Err(rename) => format!("{} (failed to set it aside: {})", e, rename),
+            }
+        })
+    }
+
+    /// [`read`](Self::read) for records that can start over: a missing or unreadable file
+    /// gives the default, and the error is logged.
+    pub fn read_or_default<T: DeserializeOwned + Default>(&self, path: &Path) -> T {
+        self.read(path)
+            .unwrap_or_else(|e| {
+                println!("[schema] {}", e);
+                None
+            })
+            .unwrap_or_default()
+    }
+
+    /// Write a record file through a temporary file that replaces it once complete.
+    pub fn write<T: Serialize>(&self, path: &Path, record: &T) -> Result<(), String> {
+        let json = self.encode(record)?;
+        write_atomic(path, &json).map_err(|e| format!("Failed to write {}: {}", self.name, e))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde::Deserialize;
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq, Default)]
+    struct Record {
+        name: String,
+        #[serde(default)]
+        count: u32,
+    }
+
+    fn title_to_name(record: &mut Value) -> Result<(), String> {
+        let object = record.as_object_mut().ok_or("not an object")?;
+        if let Some(title) = object.remove("title") {
+            object.insert("name".to_string(), title);
+        }
+        Ok(())
+    }
+
+    const RECORD: Store = Store {
+        name: "record",
+        wrapped: false,
+        migrations: &[unversioned, title_to_name],
+    };
+
+    const RECORDS: Store = Store {
+        name: "records",
+        wrapped: true,
+        migrations: &[wrap_records],
+    };
+
+    fn temp_file(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("schema-{}", uuid::Uuid::new_v4()));
+        fs::create_dir_all(&dir).unwrap();
+        dir.join(name)
+    }
+
+    #[test]
+    fn round_trips_at_the_current_version() {
+        let record = Record {
+            name: "a".into(),
+            count: 2,
+        };
+        let bytes = RECORD.encode(&record).unwrap();
+        let value: Value = serde_json::from_slice(&bytes).unwrap();
+        assert_eq!(value["schema_version"], 2);
+        assert_eq!(RECORD.decode::<Record>(&bytes).unwrap(), record);
+    }
+
+    #[test]
+    fn migrates_each_version() {
+        let cases = [
+            (r#"{"title": "old"}"#, "old"),
+            (r#"{"schema_version": 1, "title": "v1"}"#, "v1"),
+            (r#"{"schema_version": 2, "name": "v2"}"#, "v2"),
+        ];
+        for (stored, name) in cases {
+            let record: Record = RECORD.decode(stored.as_bytes()).unwrap();
+            assert_eq!(record.name, name, "{}", stored);
+        }
+    }
+
+    #[test]
+    fn wraps_bare_records() {
+        let stored = br#"[{"name": "a"}, {"name": "b", "count": 3}]"#;
+        let records: Vec<Record> = RECORDS.decode(stored).unwrap();
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[1].count, 3);
+
+        let bytes = RECORDS.encode(&records).unwrap();
+        let value: Value = serde_json::from_slice(&bytes).unwrap();
+        assert_eq!(value["schema_version"], 1);
+        assert!(value["records"].is_array());
+        assert_eq!(RECORDS.decode::<Vec<Record>>(&bytes).unwrap(), records);
+    }
+
+    #[test]
+    fn refuses_newer_versions() {
+        let err = RECORD
+            .decode::<Record>(br#"{"schema_version": 3, "name": "future"}"#)
+            .unwrap_err();
+        assert!(err.contains("newer"), "{}", err);
+        let err = RECORDS
+            .decode::<Vec<Record>>(br#"{"schema_version": 2, "records": []}"#)
+            .unwrap_err();
+        assert!(err.contains("newer"), "{}", err);
+    }
+
+    #[test]
+    fn missing_file_reads_as_none() {
+        let path = temp_file("missing.json");
+        assert_eq!(RECORD.read::<Record>(&path).unwrap(), None);
+    }
+
+    #[test]
+    fn unreadable_file_is_set_aside() {
+        let path = temp_file("future.json");
+        let stored = br#"{"schema_version": 9, "name": "future"}"#;
+        fs::write(&path, stored).unwrap();
+
+        assert!(RECORD.read::<Record>(&path).is_err());
+        assert!(!path.exists());
+        let backups = leftovers(&path);
+        assert_eq!(backups.len(), 1);
+        assert_eq!(fs::read(&backups[0]).unwrap(), stored);
+
+        // Writing afresh leaves the backup alone
+        RECORD.write(&path, &Record::default()).unwrap();
+        assert_eq!(fs::read(&backups[0]).unwrap(), stored);
+    }
+
+    #[test]
+    fn keeps_every_backup() {
+        let path = temp_file("corrupt.json");
+        for stored in [&b"{"[..], b"[", b"nope"] {
+            fs::write(&path, stored).unwrap();
+            assert!(RECORD.read::<Record>(&path).is_err());
+        }
+        let mut backups: Vec<Vec<u8>> = leftovers(&path)
+            .iter()
+            .map(|backup| fs::read(backup).unwrap())
+            .collect();
+        backups.sort();
+        assert_eq!(
+            backups,
+            vec![b"[".to_vec(), b"nope".to_vec(), b"{".to_vec()]
+        );
+    }
+
+    #[test]
+    fn writes_through_a_temporary_file() {
+        let path = temp_file("record.json");
+        let record = Record {
+            name: "saved".into(),
+            count: 1,
+        };
+        RECORD.write(&path, &record).unwrap();
+        assert!(!with_suffix(&path, ".tmp").exists());
+        assert_eq!(RECORD.read::<Record>(&path).unwrap(), Some(record));
+    }
+
+    #[test]
+    fn finds_leftovers_of_a_record_file() {
+        let path = temp_file("record.json");
+        let dir = path.parent().unwrap();
+        for name in [
+            "record.json",
+            "record.json.bak",
+            "record.json.tmp",
+            "other.json.bak",
+        ] {
+            fs::write(dir.join(name), b"{}").unwrap();
+        }
+        let mut found = leftovers(&path);
+        found.sort();
+        assert_eq!(
+            found,
+            vec![dir.join("record.json.bak"), dir.join("record.json.tmp")]
+        );
+    }
+}
@@ -0,0 +1,929 @@
+//! Persisted backend settings, stored as `<app config dir>/settings.json`.
+//!
+//! Every field has a default so older settings files keep loading as new options are added.
+
+use crate::api_keys;
+use crate::app_lock;
+use crate::export::ExportFormat;
+use crate::hotkeys::HotkeyAction;
+use crate::models::{ModelChoice, ModelSize, Quantization};
+use crate::schema::{self, Store};
+use crate::wake_word::WakeWordAction;
+use serde::{Deserialize, Serialize};
+use specta::Type;
+use std::fs;
+use std::path::PathBuf;
+use tauri::{AppHandle, Manager};
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default, Type)]
+#[serde(default)]
+pub struct Settings {
+    /// Key used by unattended work (scheduled jobs) that has no frontend to ask.
+    pub deepgram_api_key: Option<String>,
+    /// Further keys, switched to in order when the one in use is rate-limited or out of
+    /// quota.
+    pub deepgram_api_keys: Vec<String>,
+    /// Send the key as a `token` query parameter instead of an `Authorization` header
+    /// when opening WebSockets. Only for proxies that strip the header: the URL ends up
+    /// in their logs.
+    pub deepgram_key_in_url: bool,
+    /// Outbound webhook for final transcripts; disabled when unset.
+    pub webhook: Option<WebhookSettings>,
+    pub caption_server: CaptionServerSettings,
+    /// Folders whose new audio files are transcribed automatically.
+    pub watch_folders: Vec<WatchFolder>,
+    pub rest_api: RestApiSettings,
+    pub control: ControlSettings,
+    /// Global shortcuts; an action without an entry has no chord.
+    pub hotkeys: Vec<Hotkey>,
+    /// HID buttons, such as transcription foot pedals, bound to the same actions as
+    /// hotkeys.
+    pub hid_buttons: Vec<HidButton>,
+    /// Present while stored sessions are encrypted.
+    pub encryption: Option<EncryptionSettings>,
+    /// Remote store sessions are synced with; sync is off when unset.
+    pub sync: Option<SyncSettings>,
+    pub interim: InterimSettings,
+    /// Force a final result after this many seconds of continuous speech, so a long
+    /// monologue still reaches injection and webhooks; off when unset.
+    pub max_utterance_seconds: Option<f64>,
+    /// Split stream sessions into linked parts of at most this many minutes, keeping
+    /// provider connections and stored sessions bounded; unlimited when unset.
+    pub max_session_minutes: Option<u32>,
+    /// Anonymous usage counters; off unless the user opts in.
+    pub telemetry: Option<TelemetrySettings>,
+    pub crash_reports: CrashReportSettings,
+    /// Preprocessing applied, in order, to microphone audio and WAV files before
+    /// recognition. Empty means audio is sent untouched.
+    pub audio_filters: Vec<FilterStage>,
+    /// Shorten long silences in WAV files before upload; off when unset.
+    pub silence_trimming: Option<SilenceTrimSettings>,
+    /// Upload long WAV files in resumable, overlapping chunks; off when unset.
+    pub chunking: Option<ChunkingSettings>,
+    pub jobs: JobSettings,
+    /// Local model used for each kind of work.
+    pub models: ModelDefaults,
+    pub offline_fallback: OfflineFallback,
+    /// Start dictation hands-free by saying a trained phrase.
+    pub wake_word: WakeWordSettings,
+    pub commands: CommandSettings,
+    pub injection: InjectionSettings,
+    /// Write spoken numbers, amounts and units as figures; off when unset.
+    pub number_formatting: Option<NumberFormatting>,
+    /// Write spoken dates and times in the locale's format; off when unset.
+    pub date_formatting: Option<DateFormatting>,
+    /// Templates for structured dictation, in addition to the built-in ones.
+    pub templates: Vec<Template>,
+    /// Spoken phrases that expand to stored text.
+    pub snippets: Vec<Snippet>,
+    /// Dictation adjustments per foreground application; the first match applies.
+    pub profiles: Vec<AppProfile>,
+    pub recent: RecentSettings,
+    /// Turn spoken emoji names into emoji; off when unset.
+    pub emoji: Option<EmojiSettings>,
+    pub capitalization: CapitalizationSettings,
+    /// Reading transcripts aloud.
+    pub tts: TtsSettings,
+    /// Static host `publish_session` uploads pages to; pages are written locally when unset.
+    pub publishing: Option<PublishSettings>,
+    /// Re-encode archived recordings once their session ends; they stay WAV when unset.
+    pub recording_compression: Option<RecordingCompression>,
+    pub storage: StorageSettings,
+    pub retention: RetentionSettings,
+    pub network: NetworkSettings,
+    /// Present while the app lock is enabled.
+    pub app_lock: Option<AppLockSettings>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Type)]
+pub struct AppLockSettings {
+    /// Hex-encoded Argon2 salt.
+    pub salt: String,
+    /// Hex-encoded Argon2id hash of the passphrase.
+    pub hash: String,
+}
+
+/// Automatic cleanup of old sessions. Starred sessions are never touched.
+#[derive(Serialize, Deserialize, Clone, Debug, Default, Type)]
+#[serde(default)]
+pub struct RetentionSettings {
+    /// Delete a session's recording this many days after it was recorded; kept when unset.
+    pub delete_audio_after_days: Option<u32>,
+    /// Delete the whole session, transcript included; kept when unset.
+    pub delete_transcripts_after_days: Option<u32>,
+}
+
+/// How connections to Deepgram are made.
+#[derive(Serialize, Deserialize, Clone, Debug, Default, Type)]
+#[serde(default)]
+pub struct NetworkSettings {
+    pub timeouts: ConnectTimeouts,
+    pub resolver: Resolver,
+    pub ip_preference: IpPreference,
+    /// Ask Deepgram to compress the results it sends (permessage-deflate), which saves
+    /// most of the downstream traffic of verbose interim results on metered connections.
+    pub permessage_deflate: bool,
+}
+
+/// Which IP versions connections to the provider use, for networks with a broken route
+/// over one of them.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq, Type)]
+#[serde(rename_all = "snake_case")]
+pub enum IpPreference {
+    /// Whichever family the resolver lists first, falling back to the other quickly.
+    #[default]
+    Auto,
+    PreferIpv4,
+    PreferIpv6,
+    Ipv4Only,
+    Ipv6Only,
+}
+
+/// How the provider's host name is turned into addresses.
+#[derive(Serialize, Deserialize, Clone, Debug, Default, Type)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Resolver {
+    /// The operating system's resolver.
+    #[default]
+    System,
+    /// DNS over HTTPS, e.g. `https://1.1.1.1/dns-query`. Name the server by address when
+    /// the system resolver can't be relied on to find it either.
+    Doh { url: String },
+    /// A plain DNS server, as `9.9.9.9` or `9.9.9.9:53`.
+    Server { address: String },
+}
+
+/// Time limit of each stage of opening a WebSocket, in milliseconds.
+#[derive(Serialize, Deserialize, Clone, Debug, Type)]
+#[serde(default)]
+pub struct ConnectTimeouts {
+    pub dns_ms: u64,
+    pub tcp_ms: u64,
+    pub tls_ms: u64,
+    pub upgrade_ms: u64,
+    /// From the first audio sent (or, when probing, the request to close) to the first
+    /// message back.
+    pub first_message_ms: u64,
+}
+
+impl Default for ConnectTimeouts {
+    fn default() -> Self {
+        ConnectTimeouts {
+            dns_ms: 5_000,
+            tcp_ms: 5_000,
+            tls_ms: 5_000,
+            upgrade_ms: 5_000,
+            first_message_ms: 10_000,
+        }
+    }
+}
+
+/// Limits on the audio archive.
+#[derive(Serialize, Deserialize, Clone, Debug, Type)]
+#[serde(default)]
+pub struct StorageSettings {
+    /// Disk space recordings may use; the least recently used are deleted beyond it.
+    /// Unlimited when unset.
+    pub quota_mb: Option<u64>,
+    /// Share of the quota at which `storage:quota_warning` is sent, before anything is
+    /// deleted.
+    pub warn_percent: f64,
+}
+
+impl Default for StorageSettings {
+    fn default() -> Self {
+        StorageSettings {
+            quota_mb: None,
+            warn_percent: 90.0,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Type)]
+#[serde(default)]
+pub struct RecordingCompression {
+    pub codec: RecordingCodec,
+    /// Target bitrate for Opus; FLAC is lossless and ignores it.
+    pub bitrate_kbps: u32,
+}
+
+impl Default for RecordingCompression {
+    fn default() -> Self {
+        RecordingCompression {
+            codec: RecordingCodec::Flac,
+            bitrate_kbps: 32,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, Type)]
+#[serde(rename_all = "snake_case")]
+pub enum RecordingCodec {
+    /// Lossless, roughly half the size of WAV for speech.
+    Flac,
+    /// Lossy but far smaller; needs `opusenc` or `ffmpeg` on the PATH.
+    Opus,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Type)]
+pub struct PublishSettings {
+    pub target: SyncTarget,
+    /// Public address of the same location, e.g. `https://example.com/transcripts`; the
+    /// page's file name is appended to form the link.
+    pub public_url: String,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default, Type)]
+#[serde(default)]
+pub struct CapitalizationSettings {
+    pub mode: CapitalizationMode,
+    /// Words and names always written as listed, e.g. `GitHub` or `New York`.
+    pub proper_nouns: Vec<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq, Type)]
+#[serde(rename_all = "snake_case")]
+pub enum CapitalizationMode {
+    /// Keep the casing the provider returned.
+    #[default]
+    Provider,
+    /// Capitalize sentence starts and "I" only; other words are lowercased unless they
+    /// are acronyms or proper nouns.
+    Sentence,
+    /// Everything lowercase except acronyms and proper nouns, e.g. for code comments.
+    Lower,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Type)]
+#[serde(default)]
+pub struct EmojiSettings {
+    pub mappings: Vec<EmojiMapping>,
+}
+
+impl Default for EmojiSettings {
+    fn default() -> Self {
+        EmojiSettings {
+            mappings: crate::emoji::default_mappings(),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Type)]
+pub struct EmojiMapping {
+    /// Phrase to say, e.g. `thumbs up emoji`.
+    pub phrase: String,
+    pub emoji: String,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Type)]
+#[serde(default)]
+pub struct RecentSettings {
+    /// Final utterances kept for `get_recent_utterances`; 0 keeps none.
+    pub max_utterances: usize,
+}
+
+impl Default for RecentSettings {
+    fn default() -> Self {
+        RecentSettings { max_utterances: 20 }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Type)]
+pub struct AppProfile {
+    pub name: String,
+    /// Application or process names, e.g. `Slack`, `Code`, `Terminal`. Case and a
+    /// trailing `.exe` are ignored.
+    pub apps: Vec<String>,
+    /// Type dictation into these apps; turn off for terminals.
+    #[serde(default = "default_true")]
+    pub inject: bool,
+    /// Override whether numbers are formatted; unset follows `number_formatting`.
+    #[serde(default)]
+    pub format_numbers: Option<bool>,
+    /// Override whether dates and times are formatted; unset follows `date_formatting`.
+    #[serde(default)]
+    pub format_dates: Option<bool>,
+    /// Override whether snippets are expanded.
+    #[serde(default)]
+    pub expand_snippets: Option<bool>,
+    /// Override whether spoken emoji are converted; unset follows `emoji`.
+    #[serde(default)]
+    pub emoji: Option<bool>,
+    /// Override `capitalization.mode`, e.g. `lower` in an IDE.
+    #[serde(default)]
+    pub capitalization: Option<CapitalizationMode>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Type)]
+pub struct Snippet {
+    /// Phrase to say, e.g. `insert disclaimer`.
+    pub trigger: String,
+    pub text: String,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Type)]
+pub struct Template {
+    pub name: String,
+    /// Text with `{slot}` placeholders, filled in the order they appear.
+    pub body: String,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Type)]
+#[serde(default)]
+pub struct NumberFormatting {
+    /// BCP 47 tag deciding separators and currency placement, e.g. `en-US` or `de-DE`.
+    pub locale: String,
+    /// Group thousands ("2,025").
+    pub group_digits: bool,
+    /// Abbreviate units after numbers ("3.5 km").
+    pub units: bool,
+    /// Write currency amounts with their symbol ("$2,025").
+    pub currency: bool,
+}
+
+impl Default for NumberFormatting {
+    fn default() -> Self {
+        NumberFormatting {
+            locale: "en-US".to_string(),
+            group_digits: true,
+            units: true,
+            currency: true,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Type)]
+#[serde(default)]
+pub struct DateFormatting {
+    /// BCP 47 tag deciding the order of day, month and year and 12- or 24-hour time.
+    pub locale: String,
+    /// Write ISO 8601 ("2026-03-03T16:30") instead of the locale's format.
+    pub iso8601: bool,
+}
+
+impl Default for DateFormatting {
+    fn default() -> Self {
+        DateFormatting {
+            locale: "en-US".to_string(),
+            iso8601: false,
+        }
+    }
+}
+
+/// Typing dictation into the focused app.
+#[derive(Serialize, Deserialize, Clone, Debug, Type)]
+#[serde(default)]
+pub struct InjectionSettings {
+    /// Spoken phrases that delete the last injected utterance.
+    pub scratch_phrases: Vec<String>,
+    /// How the `insert_last` hotkey puts the last transcript into the focused app.
+    pub paste_method: PasteMethod,
+}
+
+impl Default for InjectionSettings {
+    fn default() -> Self {
+        InjectionSettings {
+            scratch_phrases: vec!["scratch that".to_string()],
+            paste_method: PasteMethod::Type,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, Type)]
+#[serde(rename_all = "snake_case")]
+pub enum PasteMethod {
+    /// Keystrokes; works everywhere but is slow for long text.
+    Type,
+    /// Copy to the clipboard and press the paste shortcut, replacing the clipboard.
+    Clipboard,
+}
+
+/// Command-and-control mode, where speech is matched against a grammar instead of
+/// being dictated.
+#[derive(Serialize, Deserialize, Clone, Debug, Default, Type)]
+#[serde(default)]
+pub struct CommandSettings {
+    pub enabled: bool,
+    /// Rules are tried in order; the first matching phrase wins.
+    pub grammar: Vec<CommandRule>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Type)]
+pub struct CommandRule {
+    /// Identifier sent with `command:recognized`, e.g. `open_history`.
+    pub intent: String,
+    /// Phrases such as `"switch to {language}"`; see `commands` for the syntax.
+    pub phrases: Vec<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Type)]
+#[serde(default)]
+pub struct WakeWordSettings {
+    pub enabled: bool,
+    /// 0 accepts only close matches to the training recordings; 1 is the most permissive.
+    pub sensitivity: f32,
+    /// Trained word to listen for; any trained word triggers when unset.
+    pub active: Option<String>,
+    /// Words that do something other than start dictation. Bound words are always
+    /// listened for.
+    pub actions: Vec<WakeWordBinding>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Type)]
+pub struct WakeWordBinding {
+    /// Name of a trained wake word.
+    pub word: String,
+    pub action: WakeWordAction,
+}
+
+impl Default for WakeWordSettings {
+    fn default() -> Self {
+        WakeWordSettings {
+            enabled: false,
+            sensitivity: 0.5,
+            active: None,
+            actions: Vec::new(),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Type)]
+#[serde(default)]
+pub struct ModelDefaults {
+    /// Dictation and live streams, where speed matters most.
+    pub live: ModelChoice,
+    /// File jobs and re-transcription, where accuracy matters most.
+    pub batch: ModelChoice,
+}
+
+impl Default for ModelDefaults {
+    fn default() -> Self {
+        ModelDefaults {
+            live: ModelChoice {
+                size: ModelSize::Base,
+                quantization: Quantization::Q5,
+                english_only: false,
+            },
+            batch: ModelChoice {
+                size: ModelSize::Large,
+                quantization: Quantization::Q5,
+                english_only: false,
+            },
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Type)]
+#[serde(default)]
+pub struct TtsSettings {
+    /// Speaking rate relative to the voice's normal pace, e.g. 1.5 for half again as fast.
+    pub rate: f32,
+    /// Voice name passed to the platform voice; its default voice when unset.
+    pub voice: Option<String>,
+    /// A piper voice model (`.onnx`) to speak with instead of the platform voice.
+    pub piper_model: Option<String>,
+    /// The piper program; `piper` is looked up on the PATH when unset.
+    pub piper_binary: Option<String>,
+}
+
+impl Default for TtsSettings {
+    fn default() -> Self {
+        TtsSettings {
+            rate: 1.0,
+            voice: None,
+            piper_model: None,
+            piper_binary: None,
+        }
+    }
+}
+
+/// Transcribing files with a downloaded model when Deepgram can't be reached.
+#[derive(Serialize, Deserialize, Clone, Debug, Type)]
+#[serde(default)]
+pub struct OfflineFallback {
+    pub enabled: bool,
+    /// The whisper.cpp program; `whisper-cli` or `whisper-cpp` is looked up on the PATH
+    /// when unset.
+    pub binary: Option<String>,
+    /// Let whisper.cpp use the GPU backend it was built with (CUDA, Metal or Vulkan).
+    pub gpu: bool,
+    /// Which GPU to use when there are several; whisper.cpp takes the first when unset.
+    pub gpu_device: Option<u32>,
+}
+
+impl Default for OfflineFallback {
+    fn default() -> Self {
+        OfflineFallback {
+            enabled: true,
+            binary: None,
+            gpu: true,
+            gpu_device: None,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Type)]
+#[serde(default)]
+pub struct JobSettings {
+    /// Provider requests allowed in flight at once; live sessions are always admitted.
+    pub max_concurrent_requests: usize,
+}
+
+impl Default for JobSettings {
+    fn default() -> Self {
+        JobSettings {
+            max_concurrent_requests: 2,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Type)]
+#[serde(default)]
+pub struct ChunkingSettings {
+    pub chunk_seconds: f64,
+    /// Audio shared by neighbouring chunks, so words at a cut aren't lost.
+    pub overlap_seconds: f64,
+    /// Tries per chunk before the job fails (and can be resumed).
+    pub max_attempts: u32,
+}
+
+impl Default for ChunkingSettings {
+    fn default() -> Self {
+        ChunkingSettings {
+            chunk_seconds: 600.0,
+            overlap_seconds: 5.0,
+            max_attempts: 3,
+        }
+    }
+}
+
+impl ChunkingSettings {
+    /// Chunks must advance by at least a second, or a long file turns into endless chunks.
+    pub fn validate(&self) -> Result<(), String> {
+        if !self.chunk_seconds.is_finite() || !self.overlap_seconds.is_finite() {
+            return Err("Chunk length and overlap must be numbers".to_string());
+        }
+        if self.overlap_seconds < 0.0 {
+            return Err("Chunk overlap must not be negative".to_string());
+        }
+        if self.chunk_seconds - self.overlap_seconds < 1.0 {
+            return Err(format!(
+                "Chunk overlap ({}s) must be at least a second shorter than the chunk ({}s)",
+                self.overlap_seconds, self.chunk_seconds
+            ));
+        }
+        Ok(())
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Type)]
+#[serde(default)]
+pub struct SilenceTrimSettings {
+    /// Silences at least this long are shortened.
+    pub min_silence_ms: u32,
+    /// Silence left in place of each cut, so words aren't run together.
+    pub keep_ms: u32,
+    /// Audio quieter than this (dBFS) counts as silence.
+    pub threshold_db: f32,
+}
+
+impl Default for SilenceTrimSettings {
+    fn default() -> Self {
+        SilenceTrimSettings {
+            min_silence_ms: 2000,
+            keep_ms: 500,
+            threshold_db: -50.0,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Type)]
+pub struct FilterStage {
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+    #[serde(flatten)]
+    pub filter: AudioFilter,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Type)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum AudioFilter {
+    HighPass(HighPassParams),
+    NoiseSuppression(NoiseSuppressionParams),
+    Agc(AgcParams),
+    Vad(VadParams),
+}
+
+// Every variant holds a struct, so the tagged enum flattens to an object as serde expects;
+// specta only assumes that for variants with named fields
+impl specta::Flatten for AudioFilter {}
+
+/// Second-order high-pass that removes rumble and desk thumps below the cutoff.
+#[derive(Serialize, Deserialize, Clone, Debug, Type)]
+#[serde(default)]
+pub struct HighPassParams {
+    pub cutoff_hz: f32,
+    /// Filter Q; the default gives a Butterworth response.
+    pub q: f32,
+}
+
+impl Default for HighPassParams {
+    fn default() -> Self {
+        HighPassParams {
+            cutoff_hz: 80.0,
+            q: std::f32::consts::FRAC_1_SQRT_2,
+        }
+    }
+}
+
+/// Attenuates audio that stays close to the tracked noise floor.
+#[derive(Serialize, Deserialize, Clone, Debug, Type)]
+#[serde(default)]
+pub struct NoiseSuppressionParams {
+    /// How far above the noise floor audio must be to pass untouched.
+    pub threshold_db: f32,
+    /// Attenuation applied to audio near the floor.
+    pub reduction_db: f32,
+}
+
+impl Default for NoiseSuppressionParams {
+    fn default() -> Self {
+        NoiseSuppressionParams {
+            threshold_db: 6.0,
+            reduction_db: 18.0,
+        }
+    }
+}
+
+/// Automatic gain control towards a target loudness.
+#[derive(Serialize, Deserialize, Clone, Debug, Type)]
+#[serde(default)]
+pub struct AgcParams {
+    /// Target RMS level in dBFS.
+    pub target_db: f32,
+    /// Upper bound on applied gain.
+    pub max_gain_db: f32,
+}
+
+impl Default for AgcParams {
+    fn default() -> Self {
+        AgcParams {
+            target_db: -20.0,
+            max_gain_db: 20.0,
+        }
+    }
+}
+
+/// Energy-based voice activity detection; non-speech is replaced by silence, so
+/// timestamps are unchanged.
+#[derive(Serialize, Deserialize, Clone, Debug, Type)]
+#[serde(default)]
+pub struct VadParams {
+    /// Frames louder than this (dBFS) count as speech.
+    pub threshold_db: f32,
+    /// How long audio keeps passing after speech drops below the threshold.
+    pub hang_ms: u32,
+}
+
+impl Default for VadParams {
+    fn default() -> Self {
+        VadParams {
+            threshold_db: -45.0,
+            hang_ms: 300,
+        }
+    }
+}
+
+/// Crash reports are always written locally; uploading them is opt-in.
+#[derive(Serialize, Deserialize, Clone, Debug, Default, Type)]
+#[serde(default)]
+pub struct CrashReportSettings {
+    /// Upload pending reports to `endpoint` on the next launch.
+    pub upload: bool,
+    pub endpoint: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default, Type)]
+#[serde(default)]
+pub struct TelemetrySettings {
+    pub enabled: bool,
+    /// Where daily reports are POSTed; counters are only kept locally when unset.
+    pub endpoint: Option<String>,
+}
+
+/// Limits on interim (non-final) results relayed from backend streams. Finals always pass.
+#[derive(Serialize, Deserialize, Clone, Debug, Default, Type)]
+#[serde(default)]
+pub struct InterimSettings {
+    /// At most this many interim updates per second per session.
+    pub max_per_second: Option<f64>,
+    /// Skip interim updates whose text length changed by fewer characters than this.
+    pub min_char_change: usize,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Type)]
+pub struct SyncSettings {
+    pub target: SyncTarget,
+    /// Upload each session as soon as it is stored.
+    #[serde(default = "default_true")]
+    pub push_on_complete: bool,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Type)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum SyncTarget {
+    /// A WebDAV collection URL; sessions are stored directly inside it.
+    WebDav {
+        url: String,
+        username: Option<String>,
+        password: Option<String>,
+    },
+    /// An S3-compatible bucket, addressed path-style (`<endpoint>/<bucket>/<key>`).
+    S3 {
+        endpoint: String,
+        region: String,
+        bucket: String,
+        access_key_id: String,
+        secret_access_key: String,
+        #[serde(default)]
+        prefix: String,
+    },
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Type)]
+pub struct EncryptionSettings {
+    /// Hex-encoded Argon2 salt; empty when the key is kept in the OS keychain.
+    pub salt: String,
+    /// Hex-encoded sealed known value, used to verify the passphrase.
+    pub check: String,
+    /// Hex-encoded X25519 public key files are sealed to. Missing in settings from before
+    /// public keys until history is next unlocked.
+    #[serde(default)]
+    pub public_key: Option<String>,
+    /// Hex-encoded private key, sealed with the passphrase key.
+    #[serde(default)]
+    pub sealed_secret: Option<String>,
+    /// The private key is kept in the OS keychain instead of behind a passphrase.
+    #[serde(default)]
+    pub keychain: bool,
+    /// Existing files are still being sealed. The keys are saved before anything is sealed
+    /// and this is cleared last, so enabling again after an interruption finishes the job.
+    #[serde(default)]
+    pub sealing: bool,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Type)]
+pub struct Hotkey {
+    pub action: HotkeyAction,
+    /// Accelerator string such as `CommandOrControl+Shift+Space`.
+    pub chord: String,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Type)]
+pub struct HidButton {
+    pub action: HotkeyAction,
+    pub vendor_id: u16,
+    pub product_id: u16,
+    /// Bit of the input report that is set while the button is held, counting from the
+    /// lowest bit of the first byte read.
+    pub bit: u32,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Type)]
+#[serde(default)]
+pub struct RestApiSettings {
+    /// Start the localhost REST API on launch.
+    pub enabled: bool,
+    pub port: u16,
+    /// Bearer token required on every request; generated on first start when unset.
+    pub token: Option<String>,
+}
+
+impl Default for RestApiSettings {
+    fn default() -> Self {
+        RestApiSettings {
+            enabled: false,
+            port: 7892,
+            token: None,
+        }
+    }
+}
+
+/// The control socket of headless mode.
+#[derive(Serialize, Deserialize, Clone, Debug, Default, Type)]
+#[serde(default)]
+pub struct ControlSettings {
+    /// Token clients authenticate with; generated on first start when unset.
+    pub token: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Type)]
+pub struct WatchFolder {
+    pub path: String,
+    /// Format written next to each transcribed file.
+    #[serde(default = "default_export_format")]
+    pub export_format: ExportFormat,
+}
+
+fn default_export_format() -> ExportFormat {
+    ExportFormat::Txt
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Type)]
+pub struct WebhookSettings {
+    pub url: String,
+    /// Shared secret used to sign each body (HMAC-SHA256).
+    pub secret: String,
+    /// POST every final segment of live sessions as it arrives.
+    #[serde(default)]
+    pub send_segments: bool,
+    /// POST each session once it is complete and stored.
+    #[serde(default = "default_true")]
+    pub send_sessions: bool,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Type)]
+#[serde(default)]
+pub struct CaptionServerSettings {
+    /// Start the localhost caption server on launch.
+    pub enabled: bool,
+    pub port: u16,
+    /// Required from every client; generated on first start when unset.
+    pub token: Option<String>,
+}
+
+impl Default for CaptionServerSettings {
+    fn default() -> Self {
+        CaptionServerSettings {
+            enabled: false,
+            port: 7890,
+            token: None,
+        }
+    }
+}
+
+fn default_true() -> bool {
+    true
+}
+
+pub fn settings_path(app: &AppHandle) -> Result<PathBuf, String> {
+    let dir = app
+        .path()
+        .app_config_dir()
+        .map_err(|e| format!("Failed to resolve app config directory: {}", e))?;
+    fs::create_dir_all(&dir).map_err(|e| format!("Failed to create config directory: {}", e))?;
+    Ok(dir.join("settings.json"))
+}
+
+pub const STORE: Store = Store {
+    name: "settings",
+    wrapped: false,
+    migrations: &[schema::unversioned],
+};
+
+/// Load settings; defaults when there is no settings file yet.
+///
+/// A settings file that can't be read is an error, and is kept as `settings.json.bak`.
+pub fn try_load(app: &AppHandle) -> Result<Settings, String> {
+    Ok(STORE.read(&settings_path(app)?)?.unwrap_or_default())
+}
+
+/// Load settings, falling back to defaults when they can't be read (see [`try_load`]).
+pub fn load(app: &AppHandle) -> Settings {
+    try_load(app).unwrap_or_else(|e| {
+        println!("[settings] {}", e);
+        Settings::default()
+    })
+}
+
+pub fn save(app: &AppHandle, settings: &Settings) -> Result<(), String> {
+    STORE.write(&settings_path(app)?, settings)
+}
+
+/// The Deepgram key in use (see [`api_keys::current`]), or an error explaining that one
+/// is needed.
+pub fn api_key(app: &AppHandle) -> Result<String, String> {
+    api_keys::current(app).ok_or_else(|| "No Deepgram API key configured in settings".to_string())
+}
+
+#[tauri::command]
+#[specta::specta]
+pub fn get_settings(app: AppHandle) -> Result<Settings, String> {
+    app_lock::ensure_unlocked(&app)?;
+    try_load(&app)
+}
+
+#[tauri::command]
+#[specta::specta]
+pub fn update_settings(app: AppHandle, mut settings: Settings) -> Result<(), String> {
+    app_lock::ensure_unlocked(&app)?;
+    let current = try_load(&app)?;
+    // Losing the salt would make encrypted history unreadable; only `encryption` commands
+    // may change it. Likewise the app lock can only be changed with its passphrase
+    settings.encryption = current.encryption;
+    settings.app_lock = current.app_lock;
+    if let Some(chunking) = &settings.chunking {
+        chunking.validate()?;
+    }
+    save(&app, &settings)
+}
@@ -0,0 +1,520 @@
+//! Opt-in sync of stored sessions with a WebDAV share or an S3 bucket.
+//!
+//! Each session is one object, `<id>.json`, holding the session in plain JSON. Conflicts
+//! are resolved against the content hash recorded at the last sync
+//! (`<app data dir>/sync_state.json`): a side that is unchanged since then takes the other
+//! side's version. If both changed, the local copy wins and the remote copy is kept as a
+//! new "(conflict)" session, so nothing is lost.
+
+use crate::history::{self, Session};
+use crate::outbox::{self, Delivery};
+use crate::schema::{self, Store};
+use crate::settings::{self, SyncTarget};
+use crate::{app_lock, encryption};
+use chrono::Utc;
+use hmac::{Hmac, Mac};
+use reqwest::{Client, Method, RequestBuilder, Response, Url};
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use specta::Type;
+use std::collections::{BTreeSet, HashMap};
+use std::fs;
+use std::path::PathBuf;
+use std::time::Duration;
+use tauri::{AppHandle, Manager};
+use tokio::sync::Mutex;
+
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Serializes sync runs and automatic pushes, which both update the state file.
+#[derive(Default)]
+pub struct SyncLock(Mutex<()>);
+
+#[derive(Serialize, Clone, Debug, Default, Type)]
+pub struct SyncReport {
+    pub pushed: usize,
+    pub pulled: usize,
+    /// Ids of sessions created from conflicting remote copies.
+    pub conflicts: Vec<String>,
+}
+
+fn sha256_hex(bytes: &[u8]) -> String {
+    hex::encode(Sha256::digest(bytes))
+}
+
+pub fn state_path(app: &AppHandle) -> Result<PathBuf, String> {
+    let dir = app
+        .path()
+        .app_data_dir()
+        .map_err(|e| format!("Failed to resolve app data directory: {}", e))?;
+    fs::create_dir_all(&dir).map_err(|e| format!("Failed to create app data directory: {}", e))?;
+    Ok(dir.join("sync_state.json"))
+}
+
+const STATE_STORE: Store = Store {
+    name: "sync state",
+    wrapped: true,
+    migrations: &[schema::wrap_records],
+};
+
+/// Content hash of each session as of its last successful sync.
+fn read_state(app: &AppHandle) -> HashMap<String, String> {
+    state_path(app)
+        .map(|path| STATE_STORE.read_or_default(&path))
+        .unwrap_or_default()
+}
+
+fn write_state(app: &AppHandle, state: &HashMap<String, String>) -> Result<(), String> {
+    STATE_STORE.write(&state_path(app)?, state)
+}
+
+fn encode(session: &Session) -> Result<Vec<u8>, String> {
+    history::STORE.encode(session)
+}
+
+fn decode(id: &str, body: &[u8]) -> Result<Session, String> {
+    history::STORE
+        .decode(body)
+        .map_err(|e| format!("Remote session {}: {}", id, e))
+}
+
+async fn check(response: Result<Response, reqwest::Error>) -> Result<Response, String> {
+    let response = response.map_err(|e| format!("Sync request failed: {}", e))?;
+    if !response.status().is_success() {
+        return Err(format!("Sync server returned HTTP {}", response.status()));
+    }
+    Ok(response)
+}
+
+/// Every `<tag>…</tag>` text in a (namespace-agnostic) XML document.
+fn xml_values(xml: &str, tag: &str) -> Vec<String> {
+    let mut values = Vec::new();
+    let open = format!("{}>", tag);
+    let mut rest = xml;
+    while let Some(position) = rest.find(&open) {
+        let after = &rest[position + open.len()..];
+        // Only opening tags, optionally namespaced (`<href>`, `<d:href>`); this skips
+        // closing tags and longer names that merely end in `tag`
+        let head = &rest[..position];
+        let is_open = head.rfind('<').is_some_and(|lt| {
+            let prefix = &head[lt + 1..];
+            prefix.is_empty()
+                || (prefix.ends_with(':')
+                    && prefix[..prefix.len() - 1]
+                        .chars()
+                        .all(char::is_alphanumeric))
+        });
+        if is_open {
+            if let Some(end) = after.find('<') {
+                values.push(after[..end].to_string());
+            }
+        }
+        rest = after;
+    }
+    values
+}
+
+fn id_from_name(name: &str) -> Option<String> {
+    let file = name.trim_end_matches('/').rsplit('/').next()?;
+    file.strip_suffix(".json")
+        .filter(|id| !id.is_empty())
+        .map(str::to_string)
+}
+
+struct Remote {
+    client: Client,
+    target: SyncTarget,
+}
+
+impl Remote {
+    fn new(target: SyncTarget) -> Self {
+        Remote {
+            client: Client::new(),
+            target,
+        }
+    }
+
+    fn webdav_url(url: &str, name: Option<&str>) -> String {
+        let base = url.trim_end_matches('/');
+        match name {
+            Some(name) => format!("{}/{}", base, name),
+            None => format!("{}/", base),
+        }
+    }
+
+    fn webdav_request(&self, method: Method, name: Option<&str>) -> RequestBuilder {
+        let SyncTarget::WebDav {
+            url,
+            username,
+            password,
+        } = &self.target
+        else {
+            unreachable!("WebDAV request on a non-WebDAV target")
+        };
+        let mut request = self
+            .client
+            .request(method, Self::webdav_url(url, name))
+            .timeout(REQUEST_TIMEOUT);
+        if let Some(username) = username {
+            request = request.basic_auth(username, password.as_ref());
+        }
+        request
+    }
+
+    /// Build an S3 request signed with AWS Signature Version 4 (path-style addressing).
+    fn s3_request(
+        &self,
+        method: Method,
+        key: &str,
+        query: &[(&str, String)],
+        body: Vec<u8>,
+    ) -> Result<RequestBuilder, String> {
+        let SyncTarget::S3 {
+            endpoint,
+            region,
+            bucket,
+            access_key_id,
+            secret_access_key,
+            ..
+        } = &self.target
+        else {
+            unreachable!("S3 request on a non-S3 target")
+        };
+
+        let path = format!("/{}/{}", bucket, key);
+        let canonical_uri = uri_encode(&path, false);
+        let mut query: Vec<(String, String)> = query
+            .iter()
+            .map(|(k, v)| (uri_encode(k, true), uri_encode(v, true)))
+            .collect();
+        query.sort();
+        let canonical_query = query
+            .iter()
+            .map(|(k, v)| format!("{}={}", k, v))
+            .collect::<Vec<_>>()
+            .join("&");
+
+        let base = Url::parse(endpoint).map_err(|e| format!("Invalid S3 endpoint: {}", e))?;
+        let host = match (base.host_str(), base.port()) {
+            (Some(host), Some(port)) => format!("{}:{}", host, port),
+            (Some(host), None) => host.to_string(),
+            _ => return Err("Invalid S3 endpoint: missing host".to_string()),
+        };
+
+        let now = Utc::now();
+        let amz_date = now.format("%Y%m%dT%H%M%SZ").to_string();
+        let date = now.format("%Y%m%d").to_string();
+        let payload_hash = sha256_hex(&body);
+        let signed_headers = "host;x-amz-content-sha256;x-amz-date";
+        let canonical_request = format!(
+            "{}\n{}\n{}\nhost:{}\nx-amz-content-sha256:{}\nx-amz-date:{}\n\n{}\n{}",
+            method.as_str(),
+            canonical_uri,
+            canonical_query,
+            host,
+            payload_hash,
+            amz_date,
+            signed_headers,
+            payload_hash
+        );
+        let scope = format!("{}/{}/s3/aws4_request", date, region);
+        let string_to_sign = format!(
+            "AWS4-HMAC-SHA256\n{}\n{}\n{}",
+            amz_date,
+            scope,
+            sha256_hex(canonical_request.as_bytes())
+        );
+
+        let mut key = format!("AWS4{}", secret_access_key).into_bytes();
+        for part in [date.as_str(), region.as_str(), "s3", "aws4_request"] {
+            key = hmac_sha256(&key, part.as_bytes());
+        }
+        let signature = hex::encode(hmac_sha256(&key, string_to_sign.as_bytes()));
+        let authorization = format!(
+            "AWS4-HMAC-SHA256 Credential={}/{}, SignedHeaders={}, Signature={}",
+            access_key_id, scope, signed_headers, signature
+        );
+
+        let mut url = format!("{}{}", endpoint.trim_end_matches('/'), canonical_uri);
+        if !canonical_query.is_empty() {
+            url = format!("{}?{}", url, canonical_query);
+        }
+        Ok(self
+            .client
+            .request(method, url)
+            .timeout(REQUEST_TIMEOUT)
+            .header("x-amz-date", amz_date)
+            .header("x-amz-content-sha256", payload_hash)
+            .header("Authorization", authorization)
+            .body(body))
+    }
+
+    fn s3_key(&self, name: &str) -> String {
+        match &self.target {
+            SyncTarget::S3 { prefix, .. } if !prefix.is_empty() => {
+                format!("{}/{}", prefix.trim_matches('/'), name)
+            }
+            _ => name.to_string(),
+        }
+    }
+
+    /// Ids of every session stored remotely.
+    async fn list(&self) -> Result<Vec<String>, String> {
+        match &self.target {
+            SyncTarget::WebDav { .. } => {
+                // Create the collection on first use; servers answer 405 when it exists
+                let _ = self
+                    .webdav_request(Method::from_bytes(b"MKCOL").unwrap(), None)
+                    .send()
+                    .await;
+                let response = check(
+                    self.webdav_request(Method::from_bytes(b"PROPFIND").unwrap(), None)
+                        .header("Depth", "1")
+                        .send()
+                        .await,
+                )
+                .await?;
+                let body = response
+                    .text()
+                    .await
+                    .map_err(|e| format!("Failed to read listing: {}", e))?;
+                Ok(xml_values(&body, "href")
+                    .iter()
+                    .filter_map(|href| id_from_name(href))
+                    .collect())
+            }
+            SyncTarget::S3 { prefix, .. } => {
+                let prefix = match prefix.trim_matches('/') {
+                    "" => String::new(),
+                    prefix => format!("{}/", prefix),
+                };
+                let mut ids = Vec::new();
+                let mut token: Option<String> = None;
+                loop {
+                    let mut query =
+                        vec![("list-type", "2".to_string()), ("prefix", prefix.clone())];
+                    if let Some(token) = &token {
+                        query.push(("continuation-token", token.clone()));
+                    }
+                    let request = self.s3_request(Method::GET, "", &query, Vec::new())?;
+                    let body = check(request.send().await)
+                        .await?
+                        .text()
+                        .await
+                        .map_err(|e| format!("Failed to read listing: {}", e))?;
+                    ids.extend(
+                        xml_values(&body, "Key")
+                            .iter()
+                            .filter_map(|key| id_from_name(key)),
+                    );
+                    token = xml_values(&body, "NextContinuationToken")
+                        .into_iter()
+                        .next();
+                    if token.is_none() {
+                        break;
+                    }
+                }
+                Ok(ids)
+            }
+        }
+    }
+
+    async fn get(&self, id: &str) -> Result<Vec<u8>, String> {
+        let name = format!("{}.json", id);
+        let request = match &self.target {
+            SyncTarget::WebDav { .. } => self.webdav_request(Method::GET, Some(&name)),
+            SyncTarget::S3 { .. } => {
+                self.s3_request(Method::GET, &self.s3_key(&name), &[], Vec::new())?
+            }
+        };
+        let bytes = check(request.send().await)
+            .await?
+            .bytes()
+            .await
+            .map_err(|e| format!("Failed to download {}: {}", id, e))?;
+        Ok(bytes.to_vec())
+    }
+
+    async fn put(&self, id: &str, body: Vec<u8>) -> Result<(), String> {
+        self.put_object(&format!("{}.json", id), "application/json", body)
+            .await
+    }
+
+    /// Store any file next to the sessions, e.g. a published page.
+    async fn put_object(
+        &self,
+        name: &str,
+        content_type: &str,
+        body: Vec<u8>,
+    ) -> Result<(), String> {
+        let request = match &self.target {
+            SyncTarget::WebDav { .. } => self.webdav_request(Method::PUT, Some(name)).body(body),
+            SyncTarget::S3 { .. } => self.s3_request(Method::PUT, &self.s3_key(name), &[], body)?,
+        };
+        check(request.header("Content-Type", content_type).send().await).await?;
+        Ok(())
+    }
+}
+
+fn hmac_sha256(key: &[u8], data: &[u8]) -> Vec<u8> {
+    let mut mac = Hmac::<Sha256>::new_from_slice(key).expect("HMAC accepts keys of any size");
+    mac.update(data);
+    mac.finalize().into_bytes().to_vec()
+}
+
+/// RFC 3986 encoding as SigV4 expects; `/` is kept in paths.
+fn uri_encode(value: &str, encode_slash: bool) -> String {
+    let mut out = String::new();
+    for byte in value.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => {
+                out.push(byte as char)
+            }
+            b'/' if !encode_slash => out.push('/'),
+            _ => out.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+    out
+}
+
+/// Store a remote copy that conflicts with a local edit as a separate session.
+fn save_conflict_copy(app: &AppHandle, mut remote: Session) -> Result<String, String> {
+    remote.id = uuid::Uuid::new_v4().to_string();
+    remote.title = format!("{} (conflict)", remote.title);
+    history::save(app, &remote)?;
+    Ok(remote.id)
+}
+
+async fn run(app: &AppHandle, target: SyncTarget) -> Result<SyncReport, String> {
+    // Listing skips sealed sessions while locked, which would look like remote-only ones
+    encryption::ensure_unlocked(app)?;
+    let remote = Remote::new(target);
+    let mut state = read_state(app);
+    let mut report = SyncReport::default();
+
+    let local: HashMap<String, Session> = history::load_all(app)?
+        .into_iter()
+        .map(|session| (session.id.clone(), session))
+        .collect();
+    let remote_ids: BTreeSet<String> = remote.list().await?.into_iter().collect();
+    let ids: BTreeSet<&String> = local.keys().chain(remote_ids.iter()).collect();
+
+    for id in ids {
+        let result = async {
+            match (local.get(id), remote_ids.contains(id)) {
+                (Some(session), false) => {
+                    let body = encode(session)?;
+                    let hash = sha256_hex(&body);
+                    remote.put(id, body).await?;
+                    report.pushed += 1;
+                    state.insert(id.clone(), hash);
+                }
+                (None, true) => {
+                    // Previously synced but gone locally: the user deleted it here
+                    if state.contains_key(id) {
+                        return Ok(());
+                    }
+                    let body = remote.get(id).await?;
+                    history::save(app, &decode(id, &body)?)?;
+                    report.pulled += 1;
+                    state.insert(id.clone(), sha256_hex(&body));
+                }
+                (Some(session), true) => {
+                    let local_body = encode(session)?;
+                    let local_hash = sha256_hex(&local_body);
+                    if state.get(id) == Some(&local_hash) {
+                        // Unchanged here since the last sync; take the remote version
+                        let body = remote.get(id).await?;
+                        let remote_hash = sha256_hex(&body);
+                        if remote_hash != local_hash {
+                            history::save(app, &decode(id, &body)?)?;
+                            report.pulled += 1;
+                        }
+                        state.insert(id.clone(), remote_hash);
+                        return Ok(());
+                    }
+
+                    let body = remote.get(id).await?;
+                    let remote_hash = sha256_hex(&body);
+                    if remote_hash != local_hash && state.get(id) != Some(&remote_hash) {
+                        // Both sides changed
+                        if let Ok(session) = decode(id, &body) {
+                            report.conflicts.push(save_conflict_copy(app, session)?);
+                        }
+                    }
+                    if remote_hash != local_hash {
+                        remote.put(id, local_body).await?;
+                        report.pushed += 1;
+                    }
+                    state.insert(id.clone(), local_hash);
+                }
+                (None, false) => {}
+            }
+            Ok::<(), String>(())
+        }
+        .await;
+        if let Err(e) = result {
+            println!("[sync] {}: {}", id, e);
+        }
+    }
+
+    write_state(app, &state)?;
+    Ok(report)
+}
+
+/// Upload one stored session and record it in the sync state.
+pub async fn push(app: &AppHandle, target: SyncTarget, session: &Session) -> Result<(), String> {
+    let lock = app.state::<SyncLock>();
+    let _guard = lock.0.lock().await;
+    let body = encode(session)?;
+    let hash = sha256_hex(&body);
+    Remote::new(target).put(&session.id, body).await?;
+    let mut state = read_state(app);
+    state.insert(session.id.clone(), hash);
+    write_state(app, &state)
+}
+
+/// Called once a session has been stored in history; queues it for upload when
+/// configured to. See [`outbox`].
+pub fn session_completed(app: &AppHandle, session: &Session) {
+    if settings::load(app).sync.is_some_and(|s| s.push_on_complete) {
+        outbox::submit(
+            app,
+            Delivery::SyncPush {
+                session_id: session.id.clone(),
+            },
+        );
+    }
+}
+
+/// Upload one file to `target` under `name`, outside the sync state.
+pub async fn upload(
+    target: SyncTarget,
+    name: &str,
+    content_type: &str,
+    body: Vec<u8>,
+) -> Result<(), String> {
+    Remote::new(target)
+        .put_object(name, content_type, body)
+        .await
+}
+
+/// Push local changes and pull remote ones now.
+#[tauri::command]
+#[specta::specta]
+pub async fn sync_now(app: AppHandle) -> Result<SyncReport, String> {
+    app_lock::ensure_unlocked(&app)?;
+    let config = settings::load(&app)
+        .sync
+        .ok_or_else(|| "Sync is not configured".to_string())?;
+    let lock = app.state::<SyncLock>();
+    let _guard = lock.0.lock().await;
+    let report = run(&app, config.target).await?;
+    println!(
+        "[sync] Pushed {}, pulled {}, {} conflicts",
+        report.pushed,
+        report.pulled,
+        report.conflicts.len()
+    );
+    Ok(report)
+}
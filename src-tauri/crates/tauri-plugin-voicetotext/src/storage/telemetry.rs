@@ -0,0 +1,217 @@
+//! Strictly opt-in, anonymous usage counters.
+//!
+//! Nothing is collected until the user turns telemetry on. What is collected is only
+//! counters (sessions by source kind, errors by category) and result latencies, tied to a
+//! random install id — never transcript text, audio, URLs, paths or keys. The pending
+//! report can be inspected with `get_telemetry_report` and is sent at most once a day to
+//! the configured endpoint. Turning telemetry off deletes everything collected.
+
+use crate::history::{self, LatencyStats, Session};
+use crate::schema::{self, Store};
+use crate::settings::{self, TelemetrySettings};
+use serde::{Deserialize, Serialize};
+use specta::Type;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::time::Duration;
+use tauri::{AppHandle, Manager};
+
+const CHECK_INTERVAL: Duration = Duration::from_secs(60 * 60);
+const REPORT_INTERVAL_MS: u64 = 24 * 60 * 60 * 1000;
+/// Latency samples kept between reports.
+const MAX_LATENCY_SAMPLES: usize = 200;
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(15);
+
+/// Serializes updates of the telemetry file.
+#[derive(Default)]
+pub struct Telemetry(Mutex<()>);
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default, Type)]
+#[serde(default)]
+pub struct TelemetryReport {
+    pub install_id: String,
+    pub app_version: String,
+    pub os: String,
+    pub counters: BTreeMap<String, u64>,
+    /// Per-session p50 result latency in milliseconds.
+    pub latency_p50_ms: Vec<f64>,
+    pub collected_since: u64,
+}
+
+/// The report as kept on disk, with bookkeeping that isn't uploaded.
+#[derive(Serialize, Deserialize, Default)]
+#[serde(default)]
+struct Stored {
+    last_sent: u64,
+    #[serde(flatten)]
+    report: TelemetryReport,
+}
+
+pub fn report_path(app: &AppHandle) -> Result<PathBuf, String> {
+    let dir = app
+        .path()
+        .app_data_dir()
+        .map_err(|e| format!("Failed to resolve app data directory: {}", e))?;
+    fs::create_dir_all(&dir).map_err(|e| format!("Failed to create app data directory: {}", e))?;
+    Ok(dir.join("telemetry.json"))
+}
+
+const STORE: Store = Store {
+    name: "telemetry",
+    wrapped: false,
+    migrations: &[schema::unversioned],
+};
+
+fn read_stored(app: &AppHandle) -> Stored {
+    let mut stored: Stored = report_path(app)
+        .map(|path| STORE.read_or_default(&path))
+        .unwrap_or_default();
+    if stored.report.install_id.is_empty() {
+        stored.report.install_id = uuid::Uuid::new_v4().to_string();
+        stored.report.collected_since = history::now_ms();
+        stored.last_sent = history::now_ms();
+    }
+    stored.report.app_version = app.package_info().version.to_string();
+    stored.report.os = std::env::consts::OS.to_string();
+    stored
+}
+
+fn write_stored(app: &AppHandle, stored: &Stored) -> Result<(), String> {
+    STORE.write(&report_path(app)?, stored)
+}
+
+fn enabled(app: &AppHandle) -> Option<TelemetrySettings> {
+    settings::load(app).telemetry.filter(|t| t.enabled)
+}
+
+fn update(app: &AppHandle, change: impl FnOnce(&mut TelemetryReport)) {
+    if enabled(app).is_none() {
+        return;
+    }
+    let state = app.state::<Telemetry>();
+    let _guard = state.0.lock().unwrap();
+    let mut stored = read_stored(app);
+    change(&mut stored.report);
+    if let Err(e) = write_stored(app, &stored) {
+        println!("[telemetry] {}", e);
+    }
+}
+
+/// Count one occurrence of `name`, e.g. `session.stream`.
+pub fn count(app: &AppHandle, name: &str) {
+    update(app, |report| {
+        *report.counters.entry(name.to_string()).or_default() += 1;
+    });
+}
+
+/// Count an error by category only; messages may contain URLs or text and are not kept.
+pub fn error(app: &AppHandle, category: &str) {
+    count(app, &format!("error.{}", category));
+}
+
+/// Called once a session has been stored in history.
+pub fn session_completed(app: &AppHandle, session: &Session) {
+    // Only the source kind (`stream`, `url`, `file`...), never the source itself
+    let kind = session.source.split(':').next().unwrap_or("unknown");
+    count(app, &format!("session.{}", kind));
+    if let Some(LatencyStats { p50_ms, .. }) = session.latency {
+        update(app, |report| {
+            if report.latency_p50_ms.len() < MAX_LATENCY_SAMPLES {
+                report.latency_p50_ms.push(p50_ms.round());
+            }
+        });
+    }
+}
+
+async fn send_if_due(app: &AppHandle) -> Result<(), String> {
+    let Some(config) = enabled(app) else {
+        return Ok(());
+    };
+    let Some(endpoint) = config.endpoint.filter(|e| !e.is_empty()) else {
+        return Ok(());
+    };
+    let stored = {
+        let state = app.state::<Telemetry>();
+        let _guard = state.0.lock().unwrap();
+        read_stored(app)
+    };
+    if history::now_ms().saturating_sub(stored.last_sent) < REPORT_INTERVAL_MS
+        || stored.report.counters.is_empty()
+    {
+        return Ok(());
+    }
+
+    let response = reqwest::Client::new()
+        .post(&endpoint)
+        .timeout(REQUEST_TIMEOUT)
+        .json(&stored.report)
+        .send()
+        .await
+        .map_err(|e| format!("Telemetry upload failed: {}", e))?;
+    if !response.status().is_success() {
+        return Err(format!(
+            "Telemetry endpoint returned HTTP {}",
+            response.status()
+        ));
+    }
+
+    // Start a new collection period, keeping the install id
+    let state = app.state::<Telemetry>();
+    let _guard = state.0.lock().unwrap();
+    let mut next = read_stored(app);
+    next.report.counters.clear();
+    next.report.latency_p50_ms.clear();
+    next.report.collected_since = history::now_ms();
+    next.last_sent = history::now_ms();
+    write_stored(app, &next)
+}
+
+/// Start the background loop that sends the daily report. Called once from `setup`.
+pub fn spawn(app: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        let mut interval = tokio::time::interval(CHECK_INTERVAL);
+        loop {
+            interval.tick().await;
+            if let Err(e) = send_if_due(&app).await {
+                println!("[telemetry] {}", e);
+            }
+        }
+    });
+}
+
+/// Turn telemetry on or off. Turning it off deletes everything collected so far.
+#[tauri::command]
+#[specta::specta]
+pub fn set_telemetry(app: AppHandle, enabled: bool) -> Result<(), String> {
+    let mut current = settings::load(&app);
+    let mut telemetry = current.telemetry.unwrap_or_default();
+    telemetry.enabled = enabled;
+    current.telemetry = Some(telemetry);
+    settings::save(&app, &current)?;
+
+    if !enabled {
+        let state = app.state::<Telemetry>();
+        let _guard = state.0.lock().unwrap();
+        let path = report_path(&app)?;
+        if path.exists() {
+            fs::remove_file(&path).map_err(|e| format!("Failed to delete telemetry: {}", e))?;
+        }
+    }
+    println!(
+        "[telemetry] {}",
+        if enabled { "Enabled" } else { "Disabled" }
+    );
+    Ok(())
+}
+
+/// Exactly what the next report would contain, or nothing while telemetry is off.
+#[tauri::command]
+#[specta::specta]
+pub fn get_telemetry_report(app: AppHandle) -> Option<TelemetryReport> {
+    enabled(&app)?;
+    let state = app.state::<Telemetry>();
+    let _guard = state.0.lock().unwrap();
+    Some(read_stored(&app).report)
+}
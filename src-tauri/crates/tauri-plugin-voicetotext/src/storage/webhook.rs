@@ -0,0 +1,82 @@
+//! Outbound webhook delivery of final transcripts.
+//!
+//! Bodies are JSON signed with HMAC-SHA256 over the raw body using the configured shared
+//! secret, sent as `X-VoiceToText-Signature: sha256=<hex>`. Deliveries go through the
+//! outbox, which keeps them on disk until they are through; see [`outbox`].
+
+use crate::history::{Segment, Session};
+use crate::outbox;
+use crate::settings::{self, WebhookSettings};
+use hmac::{Hmac, Mac};
+use serde::Serialize;
+use sha2::Sha256;
+use std::time::Duration;
+use tauri::AppHandle;
+
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(15);
+
+#[derive(Serialize)]
+#[serde(tag = "event")]
+enum Payload<'a> {
+    #[serde(rename = "segment.final")]
+    SegmentFinal {
+        session_id: &'a str,
+        segment: &'a Segment,
+    },
+    #[serde(rename = "session.completed")]
+    SessionCompleted { session: &'a Session },
+}
+
+fn sign(secret: &str, body: &str) -> String {
+    let mut mac =
+        Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC accepts keys of any size");
+    mac.update(body.as_bytes());
+    format!("sha256={}", hex::encode(mac.finalize().into_bytes()))
+}
+
+pub async fn post(config: &WebhookSettings, body: &str) -> Result<(), String> {
+    let response = reqwest::Client::new()
+        .post(&config.url)
+        .timeout(REQUEST_TIMEOUT)
+        .header("Content-Type", "application/json")
+        .header("X-VoiceToText-Signature", sign(&config.secret, body))
+        .body(body.to_string())
+        .send()
+        .await
+        .map_err(|e| format!("Webhook request failed: {}", e))?;
+    if !response.status().is_success() {
+        return Err(format!("Webhook returned HTTP {}", response.status()));
+    }
+    Ok(())
+}
+
+/// Queue the payload in the outbox, which sends it right away and retries on failure.
+fn deliver(app: &AppHandle, payload: Payload) {
+    let delivery = serde_json::to_string(&payload)
+        .map_err(|e| format!("Failed to serialize payload: {}", e))
+        .and_then(|body| outbox::webhook(app, body));
+    match delivery {
+        Ok(delivery) => outbox::submit(app, delivery),
+        Err(e) => println!("[webhook] {}", e),
+    }
+}
+
+/// Called for each final segment of a live session.
+pub fn segment_final(app: &AppHandle, session_id: &str, segment: &Segment) {
+    if settings::load(app).webhook.is_some_and(|w| w.send_segments) {
+        deliver(
+            app,
+            Payload::SegmentFinal {
+                session_id,
+                segment,
+            },
+        );
+    }
+}
+
+/// Called once a session has been stored in history.
+pub fn session_completed(app: &AppHandle, session: &Session) {
+    if settings::load(app).webhook.is_some_and(|w| w.send_sessions) {
+        deliver(app, Payload::SessionCompleted { session });
+    }
+}
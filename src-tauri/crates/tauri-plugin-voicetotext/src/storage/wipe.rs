@@ -0,0 +1,145 @@
+//! Erasing everything the app stores, for shared machines and privacy requests.
+
+use crate::{
+    app_lock, bandwidth, calibration, caption_server, chunked, crash, encryption, folder_watch,
+    history, hotkeys, outbox, publish, recent, recordings, rest_api, scheduler, schema, settings,
+    stream, sync, telemetry, wake_word,
+};
+use serde::Serialize;
+use specta::Type;
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use tauri::{AppHandle, Manager};
+
+#[derive(Serialize, Clone, Debug, Default, Type)]
+pub struct EraseReport {
+    /// Every file that was overwritten and deleted.
+    pub removed: Vec<String>,
+    pub bytes_removed: u64,
+    /// Files that could not be removed, with the reason.
+    pub failed: Vec<String>,
+    /// Whether webview storage (including the API key saved by the UI) was cleared.
+    pub browsing_data_cleared: bool,
+}
+
+/// Overwrite a file with zeros before unlinking it. On SSDs and copy-on-write
+/// filesystems the old blocks may survive anyway; this is best effort.
+fn shred(path: &Path) -> std::io::Result<u64> {
+    let len = fs::metadata(path)?.len();
+    let mut file = OpenOptions::new().write(true).open(path)?;
+    let zeros = [0u8; 8192];
+    let mut remaining = len;
+    while remaining > 0 {
+        let chunk = remaining.min(zeros.len() as u64) as usize;
+        file.write_all(&zeros[..chunk])?;
+        remaining -= chunk as u64;
+    }
+    file.sync_all()?;
+    drop(file);
+    fs::remove_file(path)?;
+    Ok(len)
+}
+
+fn shred_into(report: &mut EraseReport, path: &Path) {
+    if !path.is_file() {
+        return;
+    }
+    match shred(path) {
+        Ok(bytes) => {
+            report.bytes_removed += bytes;
+            report.removed.push(path.display().to_string());
+        }
+        Err(e) => report.failed.push(format!("{}: {}", path.display(), e)),
+    }
+}
+
+fn shred_dir(report: &mut EraseReport, dir: &Path) {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return;
+    };
+    for entry in entries.filter_map(|entry| entry.ok()) {
+        let path = entry.path();
+        if path.is_dir() {
+            shred_dir(report, &path);
+        } else {
+            shred_into(report, &path);
+        }
+    }
+    let _ = fs::remove_dir(dir);
+}
+
+/// Every file the backend writes: history, recordings, settings (keys and tokens), jobs,
+/// the outbox, resumable upload progress, telemetry, crash reports, sync state, published
+/// pages and logs, with any copies of them the schema layer set aside.
+fn owned_paths(app: &AppHandle) -> Vec<PathBuf> {
+    let mut paths = Vec::new();
+    paths.extend(history::history_dir(app));
+    paths.extend(recordings::recordings_dir(app));
+    paths.extend(settings::settings_path(app));
+    paths.extend(scheduler::jobs_path(app));
+    paths.extend(outbox::queue_path(app));
+    paths.extend(chunked::uploads_dir(app));
+    paths.extend(telemetry::report_path(app));
+    paths.extend(bandwidth::log_path(app));
+    paths.extend(crash::crash_dir(app));
+    paths.extend(wake_word::wake_words_path(app));
+    paths.extend(calibration::store_path(app));
+    paths.extend(sync::state_path(app));
+    paths.extend(publish::published_dir(app));
+    paths.extend(app.path().app_log_dir());
+    let set_aside: Vec<PathBuf> = paths
+        .iter()
+        .filter(|path| !path.is_dir())
+        .flat_map(|path| schema::leftovers(path))
+        .collect();
+    paths.extend(set_aside);
+    paths
+}
+
+/// Securely delete all stored data and reset the running app to a clean state.
+#[tauri::command]
+#[specta::specta]
+pub fn erase_all_data(app: AppHandle) -> Result<EraseReport, String> {
+    app_lock::ensure_unlocked(&app)?;
+    // A live stream would store its session after the wipe
+    if !app.state::<stream::StreamSessions>().is_empty() {
+        return Err("Stop active streams before erasing data".to_string());
+    }
+    rest_api::stop(&app.state::<rest_api::RestApi>());
+    caption_server::stop(&app.state::<caption_server::CaptionServer>());
+
+    let mut report = EraseReport::default();
+    for path in owned_paths(&app) {
+        if path.is_dir() {
+            shred_dir(&mut report, &path);
+        } else {
+            shred_into(&mut report, &path);
+        }
+    }
+
+    report.browsing_data_cleared = app
+        .webview_windows()
+        .values()
+        .all(|window| window.clear_all_browsing_data().is_ok());
+
+    // Drop in-memory copies of what was just erased, and the key it was sealed with
+    let _ = encryption::lock_history(app.clone(), app.state());
+    encryption::forget_keychain_key();
+    scheduler::clear(&app);
+    recent::clear(&app);
+    if let Err(e) = folder_watch::restart(&app) {
+        println!("[wipe] {}", e);
+    }
+    if let Err(e) = hotkeys::register(&app) {
+        println!("[wipe] {}", e);
+    }
+
+    println!(
+        "[wipe] Removed {} files ({} bytes), {} failures",
+        report.removed.len(),
+        report.bytes_removed,
+        report.failed.len()
+    );
+    Ok(report)
+}
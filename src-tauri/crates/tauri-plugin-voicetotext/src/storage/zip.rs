@@ -0,0 +1,130 @@
+//! Minimal ZIP writing, for session bundles and DOCX exports.
+//!
+//! Archives are plain ZIP without ZIP64, so each entry and the whole file must stay under
+//! 4 GiB.
+
+use chrono::{DateTime, Datelike, Timelike, Utc};
+use flate2::write::DeflateEncoder;
+use flate2::Compression;
+use std::io::Write;
+
+const METHOD_STORED: u16 = 0;
+const METHOD_DEFLATED: u16 = 8;
+/// Names are UTF-8 (general purpose flag bit 11).
+const FLAG_UTF8: u16 = 1 << 11;
+
+struct Entry {
+    name: String,
+    crc: u32,
+    method: u16,
+    compressed_size: u32,
+    size: u32,
+    offset: u32,
+}
+
+/// Minimal ZIP writer for archives built in memory.
+pub struct ZipWriter {
+    out: Vec<u8>,
+    entries: Vec<Entry>,
+    time: u16,
+    date: u16,
+}
+
+impl ZipWriter {
+    pub fn new(modified: DateTime<Utc>) -> Self {
+        // MS-DOS timestamps start in 1980 and have two-second resolution
+        let year = modified.year().clamp(1980, 2107) as u16;
+        ZipWriter {
+            out: Vec::new(),
+            entries: Vec::new(),
+            time: ((modified.hour() as u16) << 11)
+                | ((modified.minute() as u16) << 5)
+                | (modified.second() as u16 / 2),
+            date: ((year - 1980) << 9) | ((modified.month() as u16) << 5) | modified.day() as u16,
+        }
+    }
+
+    pub fn add(&mut self, name: &str, contents: &[u8], compress: bool) -> Result<(), String> {
+        let too_large = || format!("{} is too large for a ZIP archive", name);
+        let (method, data) = if compress {
+            let mut encoder = DeflateEncoder::new(Vec::new(), Compression::default());
+            encoder
+                .write_all(contents)
+                .and_then(|_| encoder.finish())
+                .map(|data| (METHOD_DEFLATED, data))
+                .map_err(|e| format!("Failed to compress {}: {}", name, e))?
+        } else {
+            (METHOD_STORED, contents.to_vec())
+        };
+        let entry = Entry {
+            name: name.to_string(),
+            crc: crc32fast::hash(contents),
+            method,
+            compressed_size: u32::try_from(data.len()).map_err(|_| too_large())?,
+            size: u32::try_from(contents.len()).map_err(|_| too_large())?,
+            offset: u32::try_from(self.out.len()).map_err(|_| too_large())?,
+        };
+
+        self.out.extend_from_slice(&0x04034b50u32.to_le_bytes());
+        self.out.extend_from_slice(&20u16.to_le_bytes());
+        self.out.extend_from_slice(&FLAG_UTF8.to_le_bytes());
+        self.out.extend_from_slice(&entry.method.to_le_bytes());
+        self.out.extend_from_slice(&self.time.to_le_bytes());
+        self.out.extend_from_slice(&self.date.to_le_bytes());
+        self.out.extend_from_slice(&entry.crc.to_le_bytes());
+        self.out
+            .extend_from_slice(&entry.compressed_size.to_le_bytes());
+        self.out.extend_from_slice(&entry.size.to_le_bytes());
+        self.out
+            .extend_from_slice(&(name.len() as u16).to_le_bytes());
+        self.out.extend_from_slice(&0u16.to_le_bytes());
+        self.out.extend_from_slice(name.as_bytes());
+        self.out.extend_from_slice(&data);
+        self.entries.push(entry);
+        Ok(())
+    }
+
+    /// Names of the entries added so far, in order.
+    pub fn names(&self) -> Vec<String> {
+        self.entries
+            .iter()
+            .map(|entry| entry.name.clone())
+            .collect()
+    }
+
+    pub fn finish(mut self) -> Result<Vec<u8>, String> {
+        let too_large = || "The ZIP archive is too large".to_string();
+        let directory_start = u32::try_from(self.out.len()).map_err(|_| too_large())?;
+        for entry in &self.entries {
+            self.out.extend_from_slice(&0x02014b50u32.to_le_bytes());
+            self.out.extend_from_slice(&20u16.to_le_bytes());
+            self.out.extend_from_slice(&20u16.to_le_bytes());
+            self.out.extend_from_slice(&FLAG_UTF8.to_le_bytes());
+            self.out.extend_from_slice(&entry.method.to_le_bytes());
+            self.out.extend_from_slice(&self.time.to_le_bytes());
+            self.out.extend_from_slice(&self.date.to_le_bytes());
+            self.out.extend_from_slice(&entry.crc.to_le_bytes());
+            self.out
+                .extend_from_slice(&entry.compressed_size.to_le_bytes());
+            self.out.extend_from_slice(&entry.size.to_le_bytes());
+            self.out
+                .extend_from_slice(&(entry.name.len() as u16).to_le_bytes());
+            // Extra field, comment, disk number, internal and external attributes
+            self.out.extend_from_slice(&[0; 12]);
+            self.out.extend_from_slice(&entry.offset.to_le_bytes());
+            self.out.extend_from_slice(entry.name.as_bytes());
+        }
+        let directory_size =
+            u32::try_from(self.out.len()).map_err(|_| too_large())? - directory_start;
+
+        let count = self.entries.len() as u16;
+        self.out.extend_from_slice(&0x06054b50u32.to_le_bytes());
+        self.out.extend_from_slice(&[0; 4]);
+        self.out.extend_from_slice(&count.to_le_bytes());
+        self.out.extend_from_slice(&count.to_le_bytes());
+        self.out.extend_from_slice(&directory_size.to_le_bytes());
+        self.out.extend_from_slice(&directory_start.to_le_bytes());
+        self.out.extend_from_slice(&0u16.to_le_bytes());
+        Ok(self.out)
+    }
+}
@@ -0,0 +1,2365 @@
+// @ts-nocheck
+// Generated by tauri-plugin-voicetotext; do not edit.
+
+export const BINDINGS_VERSION = "0.1.0" as const;
+export const EVENT_SCHEMA_VERSION = 1 as const;
+
+// This file was generated by [tauri-specta](https://github.com/oscartbeaumont/tauri-specta). Do not edit this file manually.
+
+/** user-defined commands **/
+
+
+export const commands = {
+/**
+ * Probe Deepgram WebSocket endpoint to validate API key and network connectivity.
+ * Runs outside the browser context, avoiding browser-level TLS/proxy restrictions.
+ * Each stage of the connection has its own time limit, from `timeouts` or else
+ * `network.timeouts`; a failure reports the stage it stopped at.
+ */
+async probeDeepgram(apiKey: string, timeouts: ConnectTimeouts | null) : Promise<ProbeResult> {
+    return await TAURI_INVOKE("plugin:voicetotext|probe_deepgram", { apiKey, timeouts });
+},
+/**
+ * Check that `key` is well-formed and accepted by `provider` without opening a stream.
+ */
+async validateApiKey(provider: string, key: string) : Promise<Result<KeyValidation, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("plugin:voicetotext|validate_api_key", { provider, key }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * Daily traffic of the last `days` days (default 30), today included.
+ */
+async getBandwidthStats(days: number | null) : Promise<BandwidthStats> {
+    return await TAURI_INVOKE("plugin:voicetotext|get_bandwidth_stats", { days });
+},
+/**
+ * Start transcribing a network audio stream. Returns the new session id; results are
+ * delivered as `transcript:update` events and the end of the session as `session:ended`.
+ * Final segments are stored in history when the session ends. With `interim_results`
+ * off (default on) only final results are requested and emitted.
+ */
+async startStreamTranscription(url: string, apiKey: string, interimResults: boolean | null) : Promise<Result<string, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("plugin:voicetotext|start_stream_transcription", { url, apiKey, interimResults }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * Stop a running stream session. Pending results are still flushed before `session:ended`.
+ */
+async stopStreamTranscription(sessionId: string) : Promise<Result<null, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("plugin:voicetotext|stop_stream_transcription", { sessionId }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * Transcribe a remote audio file. Deepgram fetches the URL itself, so nothing is
+ * downloaded locally unless Deepgram can't be reached and a local model is used. The
+ * result is stored in history and returned.
+ */
+async transcribeUrl(url: string, apiKey: string) : Promise<Result<Session, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("plugin:voicetotext|transcribe_url", { url, apiKey }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * Run `file` through every candidate model (or just `models`) and report each result.
+ * A failing model is reported with `error` rather than failing the whole benchmark.
+ */
+async benchmarkProviders(file: string, apiKey: string, models: string[] | null) : Promise<Result<BenchmarkResult[], string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("plugin:voicetotext|benchmark_providers", { file, apiKey, models }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * Compare a stored session's transcript with `reference_text`.
+ */
+async evaluateAccuracy(sessionId: string, referenceText: string) : Promise<Result<AccuracyReport, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("plugin:voicetotext|evaluate_accuracy", { sessionId, referenceText }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * Report the GPU runtimes found on this machine and the one local transcription uses.
+ */
+async getAccelerationInfo() : Promise<AccelerationInfo> {
+    return await TAURI_INVOKE("plugin:voicetotext|get_acceleration_info");
+},
+/**
+ * Models offered upstream, marked with what is already downloaded. Offline, only the
+ * models on disk are listed.
+ */
+async listAvailableModels() : Promise<Result<ModelInfo[], string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("plugin:voicetotext|list_available_models") };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * Download (or resume downloading) a model and verify its checksum.
+ */
+async downloadModel(name: string) : Promise<Result<ModelInfo, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("plugin:voicetotext|download_model", { name }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * Delete a downloaded model and any partial download of it.
+ */
+async deleteModel(name: string) : Promise<Result<null, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("plugin:voicetotext|delete_model", { name }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * Estimated download size and memory for a model choice.
+ */
+async estimateModelRequirements(choice: ModelChoice) : Promise<ModelRequirements> {
+    return await TAURI_INVOKE("plugin:voicetotext|estimate_model_requirements", { choice });
+},
+/**
+ * Every size and quantization with its estimate, smallest first.
+ */
+async listModelOptions(englishOnly: boolean | null) : Promise<ModelOption[]> {
+    return await TAURI_INVOKE("plugin:voicetotext|list_model_options", { englishOnly });
+},
+/**
+ * The engine that handled the latest work.
+ */
+async getActiveEngine() : Promise<Engine> {
+    return await TAURI_INVOKE("plugin:voicetotext|get_active_engine");
+},
+/**
+ * Read text aloud, sentence by sentence. Returns the readback id used in `tts:state`.
+ */
+async speakText(text: string) : Promise<Result<string, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("plugin:voicetotext|speak_text", { text }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * Read a stored session aloud, one segment at a time.
+ */
+async speakSession(sessionId: string) : Promise<Result<string, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("plugin:voicetotext|speak_session", { sessionId }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * Pause, resume, stop or skip within the current readback.
+ */
+async controlReadback(action: ReadbackControl) : Promise<Result<null, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("plugin:voicetotext|control_readback", { action }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * Train (or retrain) a wake word from at least three recordings of the phrase.
+ */
+async trainWakeWord(name: string, recordings: number[][], sampleRate: number) : Promise<Result<WakeWordInfo, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("plugin:voicetotext|train_wake_word", { name, recordings, sampleRate }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async listWakeWords() : Promise<WakeWordInfo[]> {
+    return await TAURI_INVOKE("plugin:voicetotext|list_wake_words");
+},
+async deleteWakeWord(name: string) : Promise<Result<null, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("plugin:voicetotext|delete_wake_word", { name }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * Feed the next chunk of microphone audio while listening for wake words. Returns the
+ * detection, if any; it is also emitted as `wake_word:detected`.
+ * 
+ * Keep feeding while dictating so phrases bound to actions like `stop_and_copy` are heard;
+ * matching happens here, before the frontend decides what audio to stream.
+ */
+async feedWakeWord(samples: number[], sampleRate: number) : Promise<WakeWordDetected | null> {
+    return await TAURI_INVOKE("plugin:voicetotext|feed_wake_word", { samples, sampleRate });
+},
+/**
+ * Forget buffered audio, e.g. when the microphone stops listening.
+ */
+async resetWakeWord() : Promise<void> {
+    await TAURI_INVOKE("plugin:voicetotext|reset_wake_word");
+},
+/**
+ * Match text recognized by the frontend against the grammar. Returns `None` when
+ * command mode is off or nothing matched; a match is also emitted as `command:recognized`.
+ */
+async recognizeCommand(text: string) : Promise<RecognizedCommand | null> {
+    return await TAURI_INVOKE("plugin:voicetotext|recognize_command", { text });
+},
+/**
+ * Validate and save the command grammar.
+ */
+async setCommandGrammar(grammar: CommandRule[]) : Promise<Result<null, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("plugin:voicetotext|set_command_grammar", { grammar }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * Type one final utterance into the focused app, unless its profile turns injection
+ * off. An utterance that is (or ends with) a scratch phrase is not typed: on its own it
+ * deletes the previous utterance, and after other words it cancels just those words.
+ */
+async injectText(text: string) : Promise<Result<InjectResult, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("plugin:voicetotext|inject_text", { text }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * Delete the last injected utterance, as the spoken scratch phrase does.
+ */
+async scratchThat() : Promise<Result<number, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("plugin:voicetotext|scratch_that") };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * Forget injected utterances, e.g. when focus moves to another app and backspacing
+ * would delete the wrong text.
+ */
+async resetInjection() : Promise<Result<null, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("plugin:voicetotext|reset_injection") };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async pasteLastTranscript() : Promise<Result<string, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("plugin:voicetotext|paste_last_transcript") };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async setSpellingMode(enabled: boolean) : Promise<void> {
+    await TAURI_INVOKE("plugin:voicetotext|set_spelling_mode", { enabled });
+},
+async getSpellingMode() : Promise<boolean> {
+    return await TAURI_INVOKE("plugin:voicetotext|get_spelling_mode");
+},
+/**
+ * Apply the dictation passes to a final result recognized by the frontend. While a
+ * template document is open the text goes into it instead, and `None` is returned.
+ */
+async finalizeDictation(text: string) : Promise<Result<string | null, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("plugin:voicetotext|finalize_dictation", { text }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * Validate and save the snippets table.
+ */
+async setSnippets(snippets: Snippet[]) : Promise<Result<null, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("plugin:voicetotext|set_snippets", { snippets }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * Report the foreground application and its profile, for setting up profiles.
+ */
+async getForegroundApp() : Promise<ForegroundApp> {
+    return await TAURI_INVOKE("plugin:voicetotext|get_foreground_app");
+},
+/**
+ * Recent utterances, newest first.
+ */
+async getRecentUtterances() : Promise<Result<Utterance[], string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("plugin:voicetotext|get_recent_utterances") };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * Copy the `n`th most recent utterance (0 is the last) to the clipboard.
+ */
+async copyUtterance(n: number) : Promise<Result<Utterance, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("plugin:voicetotext|copy_utterance", { n }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async listTemplates() : Promise<Template[]> {
+    return await TAURI_INVOKE("plugin:voicetotext|list_templates");
+},
+/**
+ * Open a document from the named template; dictation fills it until it is finished.
+ */
+async startTemplate(name: string) : Promise<Result<DocumentState, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("plugin:voicetotext|start_template", { name }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * Move to another slot, by name or by `"next"` / `"previous"`.
+ */
+async selectTemplateSlot(slot: string) : Promise<Result<DocumentState, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("plugin:voicetotext|select_template_slot", { slot }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async getTemplateDocument() : Promise<Result<DocumentState | null, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("plugin:voicetotext|get_template_document") };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * Close the open document and return its rendered text.
+ */
+async finishTemplate() : Promise<Result<DocumentState, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("plugin:voicetotext|finish_template") };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async listSessions() : Promise<Result<SessionSummary[], string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("plugin:voicetotext|list_sessions") };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async getSession(sessionId: string) : Promise<Result<Session, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("plugin:voicetotext|get_session", { sessionId }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async deleteSession(sessionId: string) : Promise<Result<null, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("plugin:voicetotext|delete_session", { sessionId }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async setSessionStarred(sessionId: string, starred: boolean) : Promise<Result<null, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("plugin:voicetotext|set_session_starred", { sessionId, starred }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async searchSessions(query: string) : Promise<Result<SearchMatch[], string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("plugin:voicetotext|search_sessions", { query }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async findInSession(sessionId: string, query: string) : Promise<Result<SessionMatch[], string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("plugin:voicetotext|find_in_session", { sessionId, query }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async getSegmentAt(sessionId: string, timestamp: number) : Promise<Result<SegmentAt | null, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("plugin:voicetotext|get_segment_at", { sessionId, timestamp }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async getSettings() : Promise<Result<Settings, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("plugin:voicetotext|get_settings") };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async updateSettings(settings: Settings) : Promise<Result<null, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("plugin:voicetotext|update_settings", { settings }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async listJobs() : Promise<Job[]> {
+    return await TAURI_INVOKE("plugin:voicetotext|list_jobs");
+},
+async addJob(name: string, schedule: Schedule, action: JobAction, priority: Priority | null) : Promise<Result<Job, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("plugin:voicetotext|add_job", { name, schedule, action, priority }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async removeJob(jobId: string) : Promise<Result<null, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("plugin:voicetotext|remove_job", { jobId }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async setJobEnabled(jobId: string, enabled: boolean) : Promise<Result<null, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("plugin:voicetotext|set_job_enabled", { jobId, enabled }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * Hold an interactive slot while the frontend dictates, so batch jobs stay out of the
+ * way. Returns an id for `end_dictation`; the slot is also released by the next
+ * `begin_dictation` or after [`DICTATION_TIMEOUT`].
+ */
+async beginDictation() : Promise<string> {
+    return await TAURI_INVOKE("plugin:voicetotext|begin_dictation");
+},
+async endDictation(dictationId: string) : Promise<void> {
+    await TAURI_INVOKE("plugin:voicetotext|end_dictation", { dictationId });
+},
+async startCaptionServer() : Promise<Result<CaptionServerStatus, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("plugin:voicetotext|start_caption_server") };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async stopCaptionServer() : Promise<CaptionServerStatus> {
+    return await TAURI_INVOKE("plugin:voicetotext|stop_caption_server");
+},
+async getCaptionServerStatus() : Promise<CaptionServerStatus> {
+    return await TAURI_INVOKE("plugin:voicetotext|get_caption_server_status");
+},
+/**
+ * Lets the webview forward captions from its own (microphone) sessions to the caption
+ * server and the overlay window.
+ */
+async publishCaption(caption: Caption) : Promise<void> {
+    await TAURI_INVOKE("plugin:voicetotext|publish_caption", { caption });
+},
+async showCaptionOverlay() : Promise<Result<null, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("plugin:voicetotext|show_caption_overlay") };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async hideCaptionOverlay() : Promise<Result<null, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("plugin:voicetotext|hide_caption_overlay") };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * Move/resize the overlay, in logical pixels. Omitted size values are left unchanged.
+ */
+async setCaptionOverlayPosition(x: number, y: number, width: number | null, height: number | null) : Promise<Result<null, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("plugin:voicetotext|set_caption_overlay_position", { x, y, width, height }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * Render a stored session and write it to `path`.
+ */
+async exportSession(sessionId: string, format: ExportFormat, path: string) : Promise<Result<null, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("plugin:voicetotext|export_session", { sessionId, format, path }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * Write a session's recording, transcript and subtitles to one ZIP file at `path`.
+ */
+async exportBundle(sessionId: string, subtitleFormat: ExportFormat, path: string) : Promise<Result<BundleSummary, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("plugin:voicetotext|export_bundle", { sessionId, subtitleFormat, path }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * Store the finished recording of a session. `samples` are interleaved and scaled to
+ * [-1, 1]; any earlier recording of the session is replaced.
+ */
+async archiveRecording(sessionId: string, samples: number[], sampleRate: number, channels: number | null) : Promise<Result<null, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("plugin:voicetotext|archive_recording", { sessionId, samples, sampleRate, channels }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async getStorageUsage() : Promise<Result<StorageUsage, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("plugin:voicetotext|get_storage_usage") };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * Apply the retention rules now, e.g. right after changing them.
+ */
+async applyRetention() : Promise<Result<RetentionReport, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("plugin:voicetotext|apply_retention") };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * Render a session as a shareable page and return where it can be opened.
+ * 
+ * With `path` the page is written there. Otherwise it is uploaded to the configured
+ * publishing host and its public URL returned, or written to `<app data dir>/published`
+ * when none is configured.
+ */
+async publishSession(sessionId: string, includeAudio: boolean, path: string | null) : Promise<Result<string, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("plugin:voicetotext|publish_session", { sessionId, includeAudio, path }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * Import a transcript file as a new session. The format is detected from the extension
+ * (or a `WEBVTT` header) unless given explicitly.
+ */
+async importTranscript(path: string, format: ImportFormat | null) : Promise<Result<Session, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("plugin:voicetotext|import_transcript", { path, format }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * Merge stored sessions into a new one, optionally deleting the originals afterwards.
+ */
+async mergeSessions(sessionIds: string[], deleteOriginals: boolean) : Promise<Result<Session, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("plugin:voicetotext|merge_sessions", { sessionIds, deleteOriginals }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * Correct the text of one segment. The previous segments are kept as a version.
+ */
+async editSegment(sessionId: string, segmentIndex: number, text: string) : Promise<Result<Session, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("plugin:voicetotext|edit_segment", { sessionId, segmentIndex, text }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * Transcribe a session's audio again with `model`, keeping the previous segments as a
+ * version so the results can be compared.
+ */
+async retranscribeSession(sessionId: string, apiKey: string, model: string) : Promise<Result<Session, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("plugin:voicetotext|retranscribe_session", { sessionId, apiKey, model }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * Undo the latest edit or re-transcription of a session. The undone segments can be
+ * restored with `redo_edit` until the session is edited again.
+ */
+async undoEdit(sessionId: string) : Promise<Result<Session, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("plugin:voicetotext|undo_edit", { sessionId }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async redoEdit(sessionId: string) : Promise<Result<Session, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("plugin:voicetotext|redo_edit", { sessionId }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * Every version of a session, oldest first; the last one is the current transcript.
+ */
+async getSessionHistory(sessionId: string) : Promise<Result<VersionInfo[], string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("plugin:voicetotext|get_session_history", { sessionId }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async getSessionVersion(sessionId: string, version: number) : Promise<Result<Segment[], string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("plugin:voicetotext|get_session_version", { sessionId, version }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * Compare two versions. `to` defaults to the current transcript, so `from: 0` shows
+ * every correction made to the original recognition.
+ */
+async diffSessionVersions(sessionId: string, from: number, to: number | null) : Promise<Result<DiffChunk[], string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("plugin:voicetotext|diff_session_versions", { sessionId, from, to }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async generateChapters(sessionId: string, mode: ChapterMode) : Promise<Result<Chapter[], string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("plugin:voicetotext|generate_chapters", { sessionId, mode }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async addChapter(sessionId: string, start: number, title: string) : Promise<Result<Chapter[], string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("plugin:voicetotext|add_chapter", { sessionId, start, title }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async removeChapter(sessionId: string, index: number) : Promise<Result<Chapter[], string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("plugin:voicetotext|remove_chapter", { sessionId, index }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * Replace the watched folders and restart watching.
+ */
+async setWatchFolders(folders: WatchFolder[]) : Promise<Result<null, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("plugin:voicetotext|set_watch_folders", { folders }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * Validate, save and re-register the shortcut map.
+ */
+async setHotkeys(hotkeys: Hotkey[]) : Promise<Result<null, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("plugin:voicetotext|set_hotkeys", { hotkeys }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * HID devices connected at the last scan, to pick vendor and product ids from.
+ */
+async listHidDevices() : Promise<HidDeviceInfo[]> {
+    return await TAURI_INVOKE("plugin:voicetotext|list_hid_devices");
+},
+async getEncryptionStatus() : Promise<EncryptionStatus> {
+    return await TAURI_INVOKE("plugin:voicetotext|get_encryption_status");
+},
+/**
+ * Turn encryption on and seal every stored session and recording. The private key is
+ * protected by `passphrase`, or kept in the OS keychain when none is given. After an
+ * interrupted attempt this finishes sealing with the keys that attempt saved.
+ */
+async enableEncryption(passphrase: string | null) : Promise<Result<EncryptionStatus, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("plugin:voicetotext|enable_encryption", { passphrase }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * Decrypt every stored session and recording and turn encryption off. `passphrase` is
+ * not needed when the key is kept in the OS keychain.
+ */
+async disableEncryption(passphrase: string | null) : Promise<Result<EncryptionStatus, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("plugin:voicetotext|disable_encryption", { passphrase }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async unlockHistory(passphrase: string | null) : Promise<Result<EncryptionStatus, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("plugin:voicetotext|unlock_history", { passphrase }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * Forget the unlocked key. With the key in the OS keychain, history stays locked until
+ * `unlock_history` or the next launch.
+ */
+async lockHistory() : Promise<EncryptionStatus> {
+    return await TAURI_INVOKE("plugin:voicetotext|lock_history");
+},
+async getAppLockStatus() : Promise<AppLockStatus> {
+    return await TAURI_INVOKE("plugin:voicetotext|get_app_lock_status");
+},
+/**
+ * Protect the app with `passphrase`. The current run stays unlocked.
+ */
+async enableAppLock(passphrase: string) : Promise<Result<AppLockStatus, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("plugin:voicetotext|enable_app_lock", { passphrase }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async disableAppLock(passphrase: string) : Promise<Result<AppLockStatus, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("plugin:voicetotext|disable_app_lock", { passphrase }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async unlock(passphrase: string) : Promise<Result<AppLockStatus, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("plugin:voicetotext|unlock", { passphrase }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async lock() : Promise<AppLockStatus> {
+    return await TAURI_INVOKE("plugin:voicetotext|lock");
+},
+/**
+ * Securely delete all stored data and reset the running app to a clean state.
+ */
+async eraseAllData() : Promise<Result<EraseReport, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("plugin:voicetotext|erase_all_data") };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * Write settings and every stored session to `path`. While encryption is on the backup
+ * is sealed with the history key unless `plaintext` asks for one that restores anywhere.
+ */
+async backupData(path: string, plaintext: boolean | null) : Promise<Result<BackupSummary, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("plugin:voicetotext|backup_data", { path, plaintext }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * Verify a backup and restore it: settings are replaced and sessions are added,
+ * overwriting stored sessions with the same id.
+ */
+async restoreData(path: string) : Promise<Result<BackupSummary, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("plugin:voicetotext|restore_data", { path }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * Push local changes and pull remote ones now.
+ */
+async syncNow() : Promise<Result<SyncReport, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("plugin:voicetotext|sync_now") };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * See [`pending`].
+ */
+async listPendingDeliveries() : Promise<Result<PendingDelivery[], string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("plugin:voicetotext|list_pending_deliveries") };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * Drop the given deliveries, or every pending one when `ids` is not given. Returns how
+ * many were removed.
+ */
+async purgePendingDeliveries(ids: string[] | null) : Promise<number> {
+    return await TAURI_INVOKE("plugin:voicetotext|purge_pending_deliveries", { ids });
+},
+/**
+ * Start accepting sessions from the local network under a fresh pairing code.
+ */
+async startLanReceiver() : Promise<Result<LanReceiverStatus, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("plugin:voicetotext|start_lan_receiver") };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async stopLanReceiver() : Promise<LanReceiverStatus> {
+    return await TAURI_INVOKE("plugin:voicetotext|stop_lan_receiver");
+},
+/**
+ * Browse the network for receivers for `timeout_ms` (default 3 s).
+ */
+async discoverLanPeers(timeoutMs: number | null) : Promise<Result<LanPeer[], string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("plugin:voicetotext|discover_lan_peers", { timeoutMs }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * Send stored sessions to a receiver using the code it displays and the salt it
+ * advertised.
+ */
+async sendToLanPeer(host: string, port: number, salt: string, code: string, sessionIds: string[]) : Promise<Result<LanReceived, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("plugin:voicetotext|send_to_lan_peer", { host, port, salt, code, sessionIds }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * Turn telemetry on or off. Turning it off deletes everything collected so far.
+ */
+async setTelemetry(enabled: boolean) : Promise<Result<null, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("plugin:voicetotext|set_telemetry", { enabled }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * Exactly what the next report would contain, or nothing while telemetry is off.
+ */
+async getTelemetryReport() : Promise<TelemetryReport | null> {
+    return await TAURI_INVOKE("plugin:voicetotext|get_telemetry_report");
+},
+/**
+ * Stored crash reports, newest first.
+ */
+async listCrashReports() : Promise<Result<CrashReport[], string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("plugin:voicetotext|list_crash_reports") };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * Delete every stored crash report and return how many were removed.
+ */
+async deleteCrashReports() : Promise<Result<number, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("plugin:voicetotext|delete_crash_reports") };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * Open a chain built from the current settings, tuned to the input device when it was
+ * calibrated. Returns `None` when no stage is enabled, in which case the caller should
+ * send audio untouched.
+ */
+async createFilterChain(sampleRate: number, deviceId: string | null) : Promise<string | null> {
+    return await TAURI_INVOKE("plugin:voicetotext|create_filter_chain", { sampleRate, deviceId });
+},
+/**
+ * Filter the next chunk of mono samples for a chain. Chunks must be sent in order.
+ */
+async filterAudio(chainId: string, samples: number[]) : Promise<Result<number[], string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("plugin:voicetotext|filter_audio", { chainId, samples }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async closeFilterChain(chainId: string) : Promise<void> {
+    await TAURI_INVOKE("plugin:voicetotext|close_filter_chain", { chainId });
+},
+/**
+ * Measure the noise floor of a recording of room tone and store it for the device.
+ */
+async calibrateDevice(deviceId: string, label: string, samples: number[], sampleRate: number) : Promise<Result<DeviceCalibration, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("plugin:voicetotext|calibrate_device", { deviceId, label, samples, sampleRate }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * Stored calibrations, by device id.
+ */
+async listDeviceCalibrations() : Promise<DeviceCalibration[]> {
+    return await TAURI_INVOKE("plugin:voicetotext|list_device_calibrations");
+},
+async startRestApi() : Promise<Result<RestApiStatus, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("plugin:voicetotext|start_rest_api") };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async stopRestApi() : Promise<RestApiStatus> {
+    return await TAURI_INVOKE("plugin:voicetotext|stop_rest_api");
+},
+async getRestApiStatus() : Promise<RestApiStatus> {
+    return await TAURI_INVOKE("plugin:voicetotext|get_rest_api_status");
+},
+/**
+ * Start a soak test in the background. Returns its id; progress is reported as
+ * `soak:sample` events and the end as `soak:finished`.
+ */
+async startSoakTest(config: SoakConfig) : Promise<Result<string, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("plugin:voicetotext|start_soak_test", { config }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * Stop the running soak test. Its sessions are stopped and the report finished as if
+ * its time were up.
+ */
+async stopSoakTest() : Promise<Result<null, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("plugin:voicetotext|stop_soak_test") };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * The report of the running soak test, or else of the latest one since the app started.
+ */
+async getSoakReport() : Promise<SoakReport | null> {
+    return await TAURI_INVOKE("plugin:voicetotext|get_soak_report");
+}
+}
+
+/** user-defined events **/
+
+
+
+/** user-defined constants **/
+
+
+
+/** user-defined types **/
+
+export type AccelerationInfo = { accelerators: Accelerator[]; 
+/**
+ * Whether local transcription is set up: whisper.cpp is installed and a model is
+ * downloaded.
+ */
+local_backend: boolean; 
+/**
+ * The accelerator local transcription runs on; the CPU when unset.
+ */
+active: AcceleratorKind | null }
+export type Accelerator = { kind: AcceleratorKind; detected: boolean; 
+/**
+ * What the detection was based on, e.g. the driver library that was found.
+ */
+detail: string | null; 
+/**
+ * Whether the installed whisper.cpp program was built with this backend.
+ */
+supported: boolean }
+export type AcceleratorKind = "cuda" | "metal" | "vulkan"
+export type AccuracyReport = { wer: number; cer: number; words: EditCounts; chars: EditCounts }
+/**
+ * Automatic gain control towards a target loudness.
+ */
+export type AgcParams = { 
+/**
+ * Target RMS level in dBFS.
+ */
+target_db: number; 
+/**
+ * Upper bound on applied gain.
+ */
+max_gain_db: number }
+export type AppLockSettings = { 
+/**
+ * Hex-encoded Argon2 salt.
+ */
+salt: string; 
+/**
+ * Hex-encoded Argon2id hash of the passphrase.
+ */
+hash: string }
+export type AppLockStatus = { enabled: boolean; unlocked: boolean }
+export type AppProfile = { name: string; 
+/**
+ * Application or process names, e.g. `Slack`, `Code`, `Terminal`. Case and a
+ * trailing `.exe` are ignored.
+ */
+apps: string[]; 
+/**
+ * Type dictation into these apps; turn off for terminals.
+ */
+inject?: boolean; 
+/**
+ * Override whether numbers are formatted; unset follows `number_formatting`.
+ */
+format_numbers?: boolean | null; 
+/**
+ * Override whether dates and times are formatted; unset follows `date_formatting`.
+ */
+format_dates?: boolean | null; 
+/**
+ * Override whether snippets are expanded.
+ */
+expand_snippets?: boolean | null; 
+/**
+ * Override whether spoken emoji are converted; unset follows `emoji`.
+ */
+emoji?: boolean | null; 
+/**
+ * Override `capitalization.mode`, e.g. `lower` in an IDE.
+ */
+capitalization?: CapitalizationMode | null }
+export type BackupSummary = { sessions: number; bytes: number }
+export type BandwidthStats = { today: Usage; 
+/**
+ * Sum of `days`.
+ */
+total: Usage; 
+/**
+ * Days with traffic, oldest first.
+ */
+days: DailyUsage[] }
+export type BenchmarkResult = { provider: string; model: string; 
+/**
+ * Wall-clock time from starting the upload to the parsed response.
+ */
+latency_ms: number | null; 
+/**
+ * Audio length as reported by the provider.
+ */
+audio_seconds: number | null; 
+/**
+ * Estimated price of this request in USD, when the model's rate is known.
+ */
+cost_estimate_usd: number | null; text: string; error: string | null }
+export type BundleSummary = { files: string[]; bytes: number; 
+/**
+ * False when the session keeps no local recording, e.g. live streams.
+ */
+has_audio: boolean }
+export type CapitalizationMode = 
+/**
+ * Keep the casing the provider returned.
+ */
+"provider" | 
+/**
+ * Capitalize sentence starts and "I" only; other words are lowercased unless they
+ * are acronyms or proper nouns.
+ */
+"sentence" | 
+/**
+ * Everything lowercase except acronyms and proper nouns, e.g. for code comments.
+ */
+"lower"
+export type CapitalizationSettings = { mode: CapitalizationMode; 
+/**
+ * Words and names always written as listed, e.g. `GitHub` or `New York`.
+ */
+proper_nouns: string[] }
+export type Caption = { text: string; is_final: boolean; session_id: string | null }
+export type CaptionServerSettings = { 
+/**
+ * Start the localhost caption server on launch.
+ */
+enabled: boolean; port: number; 
+/**
+ * Required from every client; generated on first start when unset.
+ */
+token: string | null }
+export type CaptionServerStatus = { running: boolean; port: number | null; token: string | null; clients: number }
+/**
+ * Payload of the `scheduler:capture` event.
+ */
+export type CaptureRequest = { job_id: string; duration_minutes: number }
+export type Chapter = { 
+/**
+ * Offset in seconds where the chapter begins.
+ */
+start: number; title: string; 
+/**
+ * User-placed markers survive automatic re-chaptering.
+ */
+manual?: boolean }
+export type ChapterMode = 
+/**
+ * Start a chapter wherever nobody spoke for at least `min_gap_seconds`.
+ */
+{ kind: "silence"; min_gap_seconds: number } | 
+/**
+ * Start a chapter every `seconds`.
+ */
+{ kind: "interval"; seconds: number }
+export type ChunkingSettings = { chunk_seconds: number; 
+/**
+ * Audio shared by neighbouring chunks, so words at a cut aren't lost.
+ */
+overlap_seconds: number; 
+/**
+ * Tries per chunk before the job fails (and can be resumed).
+ */
+max_attempts: number }
+export type CommandRule = { 
+/**
+ * Identifier sent with `command:recognized`, e.g. `open_history`.
+ */
+intent: string; 
+/**
+ * Phrases such as `"switch to {language}"`; see `commands` for the syntax.
+ */
+phrases: string[] }
+/**
+ * Command-and-control mode, where speech is matched against a grammar instead of
+ * being dictated.
+ */
+export type CommandSettings = { enabled: boolean; 
+/**
+ * Rules are tried in order; the first matching phrase wins.
+ */
+grammar: CommandRule[] }
+/**
+ * Time limit of each stage of opening a WebSocket, in milliseconds.
+ */
+export type ConnectTimeouts = { dns_ms: number; tcp_ms: number; tls_ms: number; upgrade_ms: number; 
+/**
+ * From the first audio sent (or, when probing, the request to close) to the first
+ * message back.
+ */
+first_message_ms: number }
+/**
+ * Payload of the `stream:connection` event, sent whenever the Deepgram connection of a
+ * session is opened or replaced.
+ */
+export type ConnectionEvent = { session_id: string; state: ConnectionState; 
+/**
+ * Why reconnecting failed; the session carries on over the old connection.
+ */
+error: string | null }
+export type ConnectionState = "connected" | "reconnecting" | "reconnect_failed"
+/**
+ * The control socket of headless mode.
+ */
+export type ControlSettings = { 
+/**
+ * Token clients authenticate with; generated on first start when unset.
+ */
+token: string | null }
+export type CrashReport = { id: string; created_at: number; app_version: string; os: string; arch: string; thread: string; message: string; location: string | null; backtrace: string; 
+/**
+ * Breadcrumbs leading up to the crash, oldest first.
+ */
+recent: string[]; uploaded?: boolean }
+/**
+ * Crash reports are always written locally; uploading them is opt-in.
+ */
+export type CrashReportSettings = { 
+/**
+ * Upload pending reports to `endpoint` on the next launch.
+ */
+upload: boolean; endpoint: string | null }
+export type DailyUsage = ({ bytes_sent: number; bytes_received: number }) & { 
+/**
+ * Local date, `YYYY-MM-DD`.
+ */
+date: string }
+export type DateFormatting = { 
+/**
+ * BCP 47 tag deciding the order of day, month and year and 12- or 24-hour time.
+ */
+locale: string; 
+/**
+ * Write ISO 8601 ("2026-03-03T16:30") instead of the locale's format.
+ */
+iso8601: boolean }
+export type DeviceCalibration = { 
+/**
+ * The browser's id of the input device.
+ */
+device_id: string; label: string; 
+/**
+ * Median level of the room tone, in dBFS.
+ */
+noise_floor_db: number; 
+/**
+ * Gap between the loudest 5% and the median, in dB.
+ */
+spread_db: number; 
+/**
+ * Unix time in milliseconds.
+ */
+measured_at: number }
+/**
+ * A run of words that is the same in both versions, only in the newer one, or only in
+ * the older one.
+ */
+export type DiffChunk = { kind: DiffKind; text: string }
+export type DiffKind = "equal" | "insert" | "delete"
+/**
+ * Payload of `template:updated` and `template:finished`, and what the commands return.
+ */
+export type DocumentState = { template: string; slots: string[]; 
+/**
+ * The slot dictation goes into.
+ */
+current: string; values: Partial<{ [key in string]: string }>; text: string }
+/**
+ * Payload of the `model:download_progress` event.
+ */
+export type DownloadProgress = { name: string; downloaded: number; total: number }
+export type EditCounts = { substitutions: number; deletions: number; insertions: number; 
+/**
+ * Length of the reference, in words or characters.
+ */
+reference_len: number }
+export type EmojiMapping = { 
+/**
+ * Phrase to say, e.g. `thumbs up emoji`.
+ */
+phrase: string; emoji: string }
+export type EmojiSettings = { mappings: EmojiMapping[] }
+export type EncryptionSettings = { 
+/**
+ * Hex-encoded Argon2 salt; empty when the key is kept in the OS keychain.
+ */
+salt: string; 
+/**
+ * Hex-encoded sealed known value, used to verify the passphrase.
+ */
+check: string; 
+/**
+ * Hex-encoded X25519 public key files are sealed to. Missing in settings from before
+ * public keys until history is next unlocked.
+ */
+public_key?: string | null; 
+/**
+ * Hex-encoded private key, sealed with the passphrase key.
+ */
+sealed_secret?: string | null; 
+/**
+ * The private key is kept in the OS keychain instead of behind a passphrase.
+ */
+keychain?: boolean; 
+/**
+ * Existing files are still being sealed. The keys are saved before anything is sealed
+ * and this is cleared last, so enabling again after an interruption finishes the job.
+ */
+sealing?: boolean }
+export type EncryptionStatus = { enabled: boolean; unlocked: boolean; 
+/**
+ * The key is kept in the OS keychain rather than behind a passphrase.
+ */
+keychain: boolean }
+export type Engine = "deepgram" | "local"
+/**
+ * Payload of the `engine:active` event.
+ */
+export type EngineChanged = { engine: Engine; 
+/**
+ * The local model in use.
+ */
+model: string | null; 
+/**
+ * Why Deepgram was left, e.g. the failed connection stage.
+ */
+reason: string | null }
+export type EraseReport = { 
+/**
+ * Every file that was overwritten and deleted.
+ */
+removed: string[]; bytes_removed: number; 
+/**
+ * Files that could not be removed, with the reason.
+ */
+failed: string[]; 
+/**
+ * Whether webview storage (including the API key saved by the UI) was cleared.
+ */
+browsing_data_cleared: boolean }
+/**
+ * Payload of `storage:evicting`, sent before the recordings are deleted.
+ */
+export type Evicting = { session_ids: string[]; bytes: number; quota_bytes: number }
+export type ExportFormat = "txt" | "srt" | "vtt" | "json" | "docx"
+export type FilterStage = (({ kind: "high_pass" } & HighPassParams) | ({ kind: "noise_suppression" } & NoiseSuppressionParams) | ({ kind: "agc" } & AgcParams) | ({ kind: "vad" } & VadParams)) & { enabled?: boolean }
+export type ForegroundApp = { name: string | null; 
+/**
+ * Profile that applies to it, if any.
+ */
+profile: string | null }
+export type HidButton = { action: HotkeyAction; vendor_id: number; product_id: number; 
+/**
+ * Bit of the input report that is set while the button is held, counting from the
+ * lowest bit of the first byte read.
+ */
+bit: number }
+export type HidDeviceInfo = { vendor_id: number; product_id: number; manufacturer: string | null; product: string | null }
+/**
+ * Second-order high-pass that removes rumble and desk thumps below the cutoff.
+ */
+export type HighPassParams = { cutoff_hz: number; 
+/**
+ * Filter Q; the default gives a Butterworth response.
+ */
+q: number }
+export type Hotkey = { action: HotkeyAction; 
+/**
+ * Accelerator string such as `CommandOrControl+Shift+Space`.
+ */
+chord: string }
+export type HotkeyAction = 
+/**
+ * Record while the chord is held.
+ */
+"push_to_talk" | 
+/**
+ * Start or stop recording.
+ */
+"toggle" | 
+/**
+ * Copy the last transcript to the clipboard.
+ */
+"copy_last" | 
+/**
+ * Put the last transcript into the focused app; handled in the backend.
+ */
+"insert_last" | 
+/**
+ * Switch spelling mode on or off; handled in the backend.
+ */
+"toggle_spelling"
+/**
+ * Payload of the `hotkey:triggered` event.
+ */
+export type HotkeyEvent = { action: HotkeyAction; 
+/**
+ * False when the chord is released, which ends push-to-talk.
+ */
+pressed: boolean }
+export type ImportFormat = "srt" | "vtt" | "txt"
+export type InjectResult = { 
+/**
+ * What was typed, including the separating space.
+ */
+typed: string; 
+/**
+ * Characters deleted by a scratch phrase.
+ */
+deleted: number }
+/**
+ * Typing dictation into the focused app.
+ */
+export type InjectionSettings = { 
+/**
+ * Spoken phrases that delete the last injected utterance.
+ */
+scratch_phrases: string[]; 
+/**
+ * How the `insert_last` hotkey puts the last transcript into the focused app.
+ */
+paste_method: PasteMethod }
+/**
+ * Limits on interim (non-final) results relayed from backend streams. Finals always pass.
+ */
+export type InterimSettings = { 
+/**
+ * At most this many interim updates per second per session.
+ */
+max_per_second: number | null; 
+/**
+ * Skip interim updates whose text length changed by fewer characters than this.
+ */
+min_char_change: number }
+/**
+ * Which IP versions connections to the provider use, for networks with a broken route
+ * over one of them.
+ */
+export type IpPreference = 
+/**
+ * Whichever family the resolver lists first, falling back to the other quickly.
+ */
+"auto" | "prefer_ipv_4" | "prefer_ipv_6" | "ipv_4_only" | "ipv_6_only"
+export type Job = { id: string; name: string; schedule: Schedule; action: JobAction; enabled: boolean; created_at: number; last_run: number | null; priority?: Priority }
+export type JobAction = 
+/**
+ * Ask the frontend to record from the microphone for a while.
+ */
+{ kind: "capture"; duration_minutes: number } | 
+/**
+ * Transcribe a network stream for a while.
+ */
+{ kind: "stream_url"; url: string; duration_minutes: number } | 
+/**
+ * Transcribe every audio file in a folder that isn't in history yet.
+ */
+{ kind: "transcribe_folder"; path: string }
+/**
+ * Payload of the `scheduler:job_finished` event.
+ */
+export type JobFinished = { job_id: string; error: string | null }
+export type JobSettings = { 
+/**
+ * Provider requests allowed in flight at once; live sessions are always admitted.
+ */
+max_concurrent_requests: number }
+/**
+ * Payload of `api_key:exhausted`. Keys are identified by their last characters only.
+ */
+export type KeyExhausted = { provider: string; key_hint: string; 
+/**
+ * The key used from now on; `None` when every configured key is exhausted.
+ */
+next_key_hint: string | null }
+export type KeyProject = { project_id: string; name: string }
+export type KeyValidation = { valid: boolean; message: string; 
+/**
+ * Projects the key can access.
+ */
+projects: KeyProject[]; 
+/**
+ * Permissions of the key, when the provider reports them.
+ */
+scopes: string[] }
+export type LanPeer = { name: string; host: string; port: number; 
+/**
+ * Salt advertised by the receiver, to pass back to `send_to_lan_peer`.
+ */
+salt: string }
+/**
+ * Payload of the `lan_sync:received` event, and the receiver's answer to the sender.
+ */
+export type LanReceived = { from: string; count: number }
+export type LanReceiverStatus = { running: boolean; name: string | null; port: number | null; 
+/**
+ * Code to enter on the sending machine, until a transfer has used it.
+ */
+code: string | null }
+/**
+ * Aggregated delay between audio being sent and its transcript arriving.
+ */
+export type LatencyStats = { samples: number; p50_ms: number; p95_ms: number; max_ms: number }
+export type ModelChoice = { size: ModelSize; quantization: Quantization; 
+/**
+ * English-only variants are a little more accurate for English; there is none for
+ * `large`.
+ */
+english_only?: boolean }
+export type ModelDefaults = { 
+/**
+ * Dictation and live streams, where speed matters most.
+ */
+live: ModelChoice; 
+/**
+ * File jobs and re-transcription, where accuracy matters most.
+ */
+batch: ModelChoice }
+export type ModelInfo = { 
+/**
+ * e.g. `base.en` or `large-v3-q5_0`.
+ */
+name: string; file: string; size: number; 
+/**
+ * Unknown for local files when the catalog can't be reached.
+ */
+sha256: string | null; downloaded: boolean; 
+/**
+ * Bytes of an interrupted download that will be resumed.
+ */
+partial_bytes: number }
+export type ModelOption = { choice: ModelChoice; requirements: ModelRequirements }
+export type ModelRequirements = { 
+/**
+ * Name to pass to `download_model`.
+ */
+name: string; download_bytes: number; 
+/**
+ * Estimated memory while transcribing: RAM on CPU, VRAM when offloaded to a GPU.
+ */
+memory_bytes: number }
+export type ModelSize = "tiny" | "base" | "small" | "medium" | "large"
+/**
+ * Payload of the `network:changed` event.
+ */
+export type NetworkChanged = { 
+/**
+ * Interfaces with an address after the change.
+ */
+interfaces: string[]; 
+/**
+ * Stream sessions asked to reconnect.
+ */
+reconnecting: number }
+/**
+ * How connections to Deepgram are made.
+ */
+export type NetworkSettings = { timeouts: ConnectTimeouts; resolver: Resolver; ip_preference: IpPreference; 
+/**
+ * Ask Deepgram to compress the results it sends (permessage-deflate), which saves
+ * most of the downstream traffic of verbose interim results on metered connections.
+ */
+permessage_deflate: boolean }
+/**
+ * Attenuates audio that stays close to the tracked noise floor.
+ */
+export type NoiseSuppressionParams = { 
+/**
+ * How far above the noise floor audio must be to pass untouched.
+ */
+threshold_db: number; 
+/**
+ * Attenuation applied to audio near the floor.
+ */
+reduction_db: number }
+export type NumberFormatting = { 
+/**
+ * BCP 47 tag deciding separators and currency placement, e.g. `en-US` or `de-DE`.
+ */
+locale: string; 
+/**
+ * Group thousands ("2,025").
+ */
+group_digits: boolean; 
+/**
+ * Abbreviate units after numbers ("3.5 km").
+ */
+units: boolean; 
+/**
+ * Write currency amounts with their symbol ("$2,025").
+ */
+currency: boolean }
+/**
+ * Transcribing files with a downloaded model when Deepgram can't be reached.
+ */
+export type OfflineFallback = { enabled: boolean; 
+/**
+ * The whisper.cpp program; `whisper-cli` or `whisper-cpp` is looked up on the PATH
+ * when unset.
+ */
+binary: string | null; 
+/**
+ * Let whisper.cpp use the GPU backend it was built with (CUDA, Metal or Vulkan).
+ */
+gpu: boolean; 
+/**
+ * Which GPU to use when there are several; whisper.cpp takes the first when unset.
+ */
+gpu_device: number | null }
+export type PasteMethod = 
+/**
+ * Keystrokes; works everywhere but is slow for long text.
+ */
+"type" | 
+/**
+ * Copy to the clipboard and press the paste shortcut, replacing the clipboard.
+ */
+"clipboard"
+export type PendingDelivery = (
+/**
+ * A webhook body, signed and sent to the webhook configured when it is attempted.
+ * `sealed` bodies are encrypted (see [`encryption::seal`]) and base64-encoded.
+ */
+{ kind: "webhook"; body: string; sealed?: boolean } | 
+/**
+ * Upload of a stored session to the sync target.
+ */
+{ kind: "sync_push"; session_id: string }) & { id: string; 
+/**
+ * Failed attempts so far.
+ */
+attempts: number; created_at: number; 
+/**
+ * Not attempted again before this time, in ms since the epoch.
+ */
+next_attempt_at: number; last_error: string | null }
+export type PlaybackState = "playing" | "paused" | "finished" | "stopped" | "failed"
+/**
+ * Order in which waiting provider requests are admitted.
+ */
+export type Priority = "low" | "normal" | "high" | 
+/**
+ * Live dictation and streams. Never queued, and batch work yields to it.
+ */
+"interactive"
+export type ProbeResult = { success: boolean; message: string; code: number | null; reason: string | null; 
+/**
+ * Connection stage that failed.
+ */
+stage: Stage | null }
+export type PublishSettings = { target: SyncTarget; 
+/**
+ * Public address of the same location, e.g. `https://example.com/transcripts`; the
+ * page's file name is appended to form the link.
+ */
+public_url: string }
+export type Quantization = "q5" | "q8" | "f16"
+/**
+ * Payload of `storage:quota_warning`.
+ */
+export type QuotaWarning = { recording_bytes: number; quota_bytes: number; percent_used: number }
+export type ReadbackControl = "pause" | "resume" | "stop" | 
+/**
+ * Skip to the next sentence or segment.
+ */
+"next" | 
+/**
+ * Go back to the previous sentence or segment.
+ */
+"previous"
+/**
+ * Payload of the `tts:state` event.
+ */
+export type ReadbackEvent = { readback_id: string; state: PlaybackState; 
+/**
+ * The sentence or segment being read, counted from zero.
+ */
+index: number; total: number; text: string | null; error: string | null }
+export type RecentSettings = { 
+/**
+ * Final utterances kept for `get_recent_utterances`; 0 keeps none.
+ */
+max_utterances: number }
+/**
+ * Payload of the `command:recognized` event.
+ */
+export type RecognizedCommand = { intent: string; 
+/**
+ * Slot values by name, as spoken.
+ */
+slots: Partial<{ [key in string]: string }>; 
+/**
+ * The recognized text the command was matched in.
+ */
+text: string; 
+/**
+ * Backend stream the text came from; `None` for text passed in by the frontend.
+ */
+session_id: string | null }
+export type RecordingCodec = 
+/**
+ * Lossless, roughly half the size of WAV for speech.
+ */
+"flac" | 
+/**
+ * Lossy but far smaller; needs `opusenc` or `ffmpeg` on the PATH.
+ */
+"opus"
+export type RecordingCompression = { codec: RecordingCodec; 
+/**
+ * Target bitrate for Opus; FLAC is lossless and ignores it.
+ */
+bitrate_kbps: number }
+/**
+ * How the provider's host name is turned into addresses.
+ */
+export type Resolver = 
+/**
+ * The operating system's resolver.
+ */
+{ kind: "system" } | 
+/**
+ * DNS over HTTPS, e.g. `https://1.1.1.1/dns-query`. Name the server by address when
+ * the system resolver can't be relied on to find it either.
+ */
+{ kind: "doh"; url: string } | 
+/**
+ * A plain DNS server, as `9.9.9.9` or `9.9.9.9:53`.
+ */
+{ kind: "server"; address: string }
+export type RestApiSettings = { 
+/**
+ * Start the localhost REST API on launch.
+ */
+enabled: boolean; port: number; 
+/**
+ * Bearer token required on every request; generated on first start when unset.
+ */
+token: string | null }
+export type RestApiStatus = { running: boolean; port: number | null; token: string | null }
+export type RetentionReport = { 
+/**
+ * Sessions whose recording was deleted.
+ */
+audio_deleted: string[]; 
+/**
+ * Sessions deleted entirely.
+ */
+sessions_deleted: string[] }
+/**
+ * Automatic cleanup of old sessions. Starred sessions are never touched.
+ */
+export type RetentionSettings = { 
+/**
+ * Delete a session's recording this many days after it was recorded; kept when unset.
+ */
+delete_audio_after_days: number | null; 
+/**
+ * Delete the whole session, transcript included; kept when unset.
+ */
+delete_transcripts_after_days: number | null }
+/**
+ * Payload of the `session:rolled_over` event.
+ */
+export type RolledOver = { session_id: string; 
+/**
+ * The session results go to from now on.
+ */
+continuation_id: string }
+/**
+ * Payload of the `session:save_failed` event: a finished session could not be stored
+ * and its transcript is only in the events already sent.
+ */
+export type SaveFailed = { session_id: string; error: string }
+export type Schedule = 
+/**
+ * Run once at a unix time in milliseconds.
+ */
+{ kind: "once"; at: number } | 
+/**
+ * Run at a local wall-clock time. `weekdays` uses 0 = Monday … 6 = Sunday; empty means every day.
+ */
+{ kind: "daily"; hour: number; minute: number; weekdays?: number[] }
+/**
+ * A segment matching a history search.
+ */
+export type SearchMatch = { session_id: string; title: string; segment_index: number; start: number; end: number; text: string }
+export type Segment = { 
+/**
+ * Offsets in seconds from the start of the recording.
+ */
+start: number; end: number; text: string; speaker?: number | null; confidence?: number }
+/**
+ * The segment playing at a given timestamp.
+ */
+export type SegmentAt = { segment_index: number; segment: Segment }
+export type Session = { id: string; title: string; 
+/**
+ * Where the audio came from, e.g. `stream:<url>` or `url:<url>`.
+ */
+source: string; 
+/**
+ * Unix time in milliseconds.
+ */
+created_at: number; segments: Segment[]; chapters?: Chapter[]; 
+/**
+ * Result latency of live sessions.
+ */
+latency?: LatencyStats | null; 
+/**
+ * Earlier segments, oldest first, kept when they are edited or re-transcribed.
+ */
+versions?: SessionVersion[]; 
+/**
+ * Undone changes, most recently undone last, until the next edit.
+ */
+redo?: SessionVersion[]; 
+/**
+ * Starred sessions are exempt from retention cleanup.
+ */
+starred?: boolean; 
+/**
+ * Traffic with the provider, for sessions the backend transcribed.
+ */
+bandwidth?: Usage | null; 
+/**
+ * The session this one continues after a stream rolled over.
+ */
+continues?: string | null }
+/**
+ * Payload of the `session:ended` event. `error` is set when the session ended abnormally.
+ */
+export type SessionEnded = { session_id: string; error: string | null; latency: LatencyStats | null }
+/**
+ * A query hit inside one session. `offset` is the estimated audio position of the hit,
+ * interpolated across the segment since word timings aren't stored.
+ */
+export type SessionMatch = { segment_index: number; 
+/**
+ * Character range of the hit within the segment text.
+ */
+char_start: number; char_end: number; offset: number }
+/**
+ * Lightweight listing entry returned by `list_sessions`.
+ */
+export type SessionSummary = { id: string; title: string; source: string; created_at: number; duration: number; segment_count: number; starred: boolean }
+/**
+ * Segments as they were before a change.
+ */
+export type SessionVersion = { segments: Segment[]; 
+/**
+ * Unix time in milliseconds when they were replaced.
+ */
+replaced_at: number; 
+/**
+ * What replaced them, e.g. `edit` or `retranscribe:nova-3`.
+ */
+replaced_by: string }
+export type Settings = { 
+/**
+ * Key used by unattended work (scheduled jobs) that has no frontend to ask.
+ */
+deepgram_api_key: string | null; 
+/**
+ * Further keys, switched to in order when the one in use is rate-limited or out of
+ * quota.
+ */
+deepgram_api_keys: string[]; 
+/**
+ * Send the key as a `token` query parameter instead of an `Authorization` header
+ * when opening WebSockets. Only for proxies that strip the header: the URL ends up
+ * in their logs.
+ */
+deepgram_key_in_url: boolean; 
+/**
+ * Outbound webhook for final transcripts; disabled when unset.
+ */
+webhook: WebhookSettings | null; caption_server: CaptionServerSettings; 
+/**
+ * Folders whose new audio files are transcribed automatically.
+ */
+watch_folders: WatchFolder[]; rest_api: RestApiSettings; control: ControlSettings; 
+/**
+ * Global shortcuts; an action without an entry has no chord.
+ */
+hotkeys: Hotkey[]; 
+/**
+ * HID buttons, such as transcription foot pedals, bound to the same actions as
+ * hotkeys.
+ */
+hid_buttons: HidButton[]; 
+/**
+ * Present while stored sessions are encrypted.
+ */
+encryption: EncryptionSettings | null; 
+/**
+ * Remote store sessions are synced with; sync is off when unset.
+ */
+sync: SyncSettings | null; interim: InterimSettings; 
+/**
+ * Force a final result after this many seconds of continuous speech, so a long
+ * monologue still reaches injection and webhooks; off when unset.
+ */
+max_utterance_seconds: number | null; 
+/**
+ * Split stream sessions into linked parts of at most this many minutes, keeping
+ * provider connections and stored sessions bounded; unlimited when unset.
+ */
+max_session_minutes: number | null; 
+/**
+ * Anonymous usage counters; off unless the user opts in.
+ */
+telemetry: TelemetrySettings | null; crash_reports: CrashReportSettings; 
+/**
+ * Preprocessing applied, in order, to microphone audio and WAV files before
+ * recognition. Empty means audio is sent untouched.
+ */
+audio_filters: FilterStage[]; 
+/**
+ * Shorten long silences in WAV files before upload; off when unset.
+ */
+silence_trimming: SilenceTrimSettings | null; 
+/**
+ * Upload long WAV files in resumable, overlapping chunks; off when unset.
+ */
+chunking: ChunkingSettings | null; jobs: JobSettings; 
+/**
+ * Local model used for each kind of work.
+ */
+models: ModelDefaults; offline_fallback: OfflineFallback; 
+/**
+ * Start dictation hands-free by saying a trained phrase.
+ */
+wake_word: WakeWordSettings; commands: CommandSettings; injection: InjectionSettings; 
+/**
+ * Write spoken numbers, amounts and units as figures; off when unset.
+ */
+number_formatting: NumberFormatting | null; 
+/**
+ * Write spoken dates and times in the locale's format; off when unset.
+ */
+date_formatting: DateFormatting | null; 
+/**
+ * Templates for structured dictation, in addition to the built-in ones.
+ */
+templates: Template[]; 
+/**
+ * Spoken phrases that expand to stored text.
+ */
+snippets: Snippet[]; 
+/**
+ * Dictation adjustments per foreground application; the first match applies.
+ */
+profiles: AppProfile[]; recent: RecentSettings; 
+/**
+ * Turn spoken emoji names into emoji; off when unset.
+ */
+emoji: EmojiSettings | null; capitalization: CapitalizationSettings; 
+/**
+ * Reading transcripts aloud.
+ */
+tts: TtsSettings; 
+/**
+ * Static host `publish_session` uploads pages to; pages are written locally when unset.
+ */
+publishing: PublishSettings | null; 
+/**
+ * Re-encode archived recordings once their session ends; they stay WAV when unset.
+ */
+recording_compression: RecordingCompression | null; storage: StorageSettings; retention: RetentionSettings; network: NetworkSettings; 
+/**
+ * Present while the app lock is enabled.
+ */
+app_lock: AppLockSettings | null }
+export type SilenceTrimSettings = { 
+/**
+ * Silences at least this long are shortened.
+ */
+min_silence_ms: number; 
+/**
+ * Silence left in place of each cut, so words aren't run together.
+ */
+keep_ms: number; 
+/**
+ * Audio quieter than this (dBFS) counts as silence.
+ */
+threshold_db: number }
+export type Snippet = { 
+/**
+ * Phrase to say, e.g. `insert disclaimer`.
+ */
+trigger: string; text: string }
+export type SoakConfig = { 
+/**
+ * Sessions kept running at once.
+ */
+sessions: number; duration_minutes: number; provider: SoakProvider; 
+/**
+ * Key for Deepgram; the configured one when not given. Unused by the mock provider.
+ */
+api_key: string | null; 
+/**
+ * Every session is asked to reconnect this often; never when unset or 0.
+ */
+reconnect_every_minutes: number | null; sample_every_seconds: number }
+/**
+ * Where the sessions of a soak test send their audio.
+ */
+export type SoakProvider = 
+/**
+ * A local imitation of Deepgram's live API; free, and needs no network.
+ */
+"mock" | "deepgram"
+/**
+ * A soak test and what it measured; also the payload of the `soak:finished` event.
+ */
+export type SoakReport = { id: string; config: SoakConfig; started_at: number; 
+/**
+ * Set once the run is over.
+ */
+finished_at: number | null; samples: SoakSample[]; 
+/**
+ * Resident memory growth fitted over the samples after the first five minutes. A
+ * steady rise over a long run points to a leak.
+ */
+memory_growth_bytes_per_hour: number | null; 
+/**
+ * Where the report is written.
+ */
+path: string | null }
+/**
+ * Payload of the `soak:sample` event. Counts are totals since the run started.
+ */
+export type SoakSample = { elapsed_seconds: number; 
+/**
+ * Resident memory of the app, when the platform tells.
+ */
+resident_bytes: number | null; 
+/**
+ * Stream sessions running, the run's own and any others.
+ */
+active_sessions: number; 
+/**
+ * Provider requests holding a slot of the request limiter, and waiting for one.
+ */
+requests_running: number; requests_waiting: number; 
+/**
+ * Webhook and sync deliveries waiting in the outbox.
+ */
+outbox_pending: number; sessions_started: number; 
+/**
+ * Sessions that failed to start or ended with an error.
+ */
+sessions_failed: number; reconnects: number; reconnects_failed: number; rollovers: number; 
+/**
+ * Transcript updates received, interim and final.
+ */
+transcripts: number }
+/**
+ * Payload of the `spelling:changed` event.
+ */
+export type SpellingChanged = { enabled: boolean }
+export type Stage = "dns" | "tcp" | "tls" | "upgrade" | "first_message"
+/**
+ * Limits on the audio archive.
+ */
+export type StorageSettings = { 
+/**
+ * Disk space recordings may use; the least recently used are deleted beyond it.
+ * Unlimited when unset.
+ */
+quota_mb: number | null; 
+/**
+ * Share of the quota at which `storage:quota_warning` is sent, before anything is
+ * deleted.
+ */
+warn_percent: number }
+export type StorageUsage = { recording_bytes: number; recording_count: number; 
+/**
+ * Stored transcripts, which the quota doesn't cover.
+ */
+history_bytes: number; quota_bytes: number | null; 
+/**
+ * Share of the quota in use, 0–100 (or more while over it).
+ */
+percent_used: number | null }
+export type SyncReport = { pushed: number; pulled: number; 
+/**
+ * Ids of sessions created from conflicting remote copies.
+ */
+conflicts: string[] }
+export type SyncSettings = { target: SyncTarget; 
+/**
+ * Upload each session as soon as it is stored.
+ */
+push_on_complete?: boolean }
+export type SyncTarget = 
+/**
+ * A WebDAV collection URL; sessions are stored directly inside it.
+ */
+{ kind: "web_dav"; url: string; username: string | null; password: string | null } | 
+/**
+ * An S3-compatible bucket, addressed path-style (`<endpoint>/<bucket>/<key>`).
+ */
+{ kind: "s3"; endpoint: string; region: string; bucket: string; access_key_id: string; secret_access_key: string; prefix?: string }
+export type TelemetryReport = { install_id: string; app_version: string; os: string; counters: Partial<{ [key in string]: number }>; 
+/**
+ * Per-session p50 result latency in milliseconds.
+ */
+latency_p50_ms: number[]; collected_since: number }
+export type TelemetrySettings = { enabled: boolean; 
+/**
+ * Where daily reports are POSTed; counters are only kept locally when unset.
+ */
+endpoint: string | null }
+export type Template = { name: string; 
+/**
+ * Text with `{slot}` placeholders, filled in the order they appear.
+ */
+body: string }
+/**
+ * Payload of the `transcript:update` event.
+ */
+export type TranscriptEvent = { session_id: string; transcript: string; is_final: boolean; confidence: number; start: number; duration: number; 
+/**
+ * Time from the audio being relayed to this result arriving, when it can be told.
+ */
+latency_ms: number | null }
+export type TtsSettings = { 
+/**
+ * Speaking rate relative to the voice's normal pace, e.g. 1.5 for half again as fast.
+ */
+rate: number; 
+/**
+ * Voice name passed to the platform voice; its default voice when unset.
+ */
+voice: string | null; 
+/**
+ * A piper voice model (`.onnx`) to speak with instead of the platform voice.
+ */
+piper_model: string | null; 
+/**
+ * The piper program; `piper` is looked up on the PATH when unset.
+ */
+piper_binary: string | null }
+export type Usage = { bytes_sent: number; bytes_received: number }
+export type Utterance = { text: string; created_at: number }
+/**
+ * Energy-based voice activity detection; non-speech is replaced by silence, so
+ * timestamps are unchanged.
+ */
+export type VadParams = { 
+/**
+ * Frames louder than this (dBFS) count as speech.
+ */
+threshold_db: number; 
+/**
+ * How long audio keeps passing after speech drops below the threshold.
+ */
+hang_ms: number }
+export type VersionInfo = { version: number; 
+/**
+ * Unix time in milliseconds when this version was created.
+ */
+created_at: number; 
+/**
+ * `transcribed` for the original, otherwise what produced it, e.g. `edit`.
+ */
+origin: string; segment_count: number; current: boolean }
+/**
+ * What hearing a wake word does. Words without a configured action start dictation.
+ */
+export type WakeWordAction = { kind: "start_dictation" } | 
+/**
+ * Start capturing a meeting recording.
+ */
+{ kind: "start_meeting_capture" } | 
+/**
+ * Insert the named template into the focused app.
+ */
+{ kind: "insert_template"; template: string } | 
+/**
+ * End dictation and copy the transcript to the clipboard.
+ */
+{ kind: "stop_and_copy" }
+export type WakeWordBinding = { 
+/**
+ * Name of a trained wake word.
+ */
+word: string; action: WakeWordAction }
+/**
+ * Payload of the `wake_word:detected` event.
+ */
+export type WakeWordDetected = { name: string; 
+/**
+ * 0 for a borderline match, approaching 1 for a close one.
+ */
+confidence: number; action: WakeWordAction }
+export type WakeWordInfo = { name: string; recordings: number; created_at: number }
+export type WakeWordSettings = { enabled: boolean; 
+/**
+ * 0 accepts only close matches to the training recordings; 1 is the most permissive.
+ */
+sensitivity: number; 
+/**
+ * Trained word to listen for; any trained word triggers when unset.
+ */
+active: string | null; 
+/**
+ * Words that do something other than start dictation. Bound words are always
+ * listened for.
+ */
+actions: WakeWordBinding[] }
+export type WatchFolder = { path: string; 
+/**
+ * Format written next to each transcribed file.
+ */
+export_format?: ExportFormat }
+export type WebhookSettings = { url: string; 
+/**
+ * Shared secret used to sign each body (HMAC-SHA256).
+ */
+secret: string; 
+/**
+ * POST every final segment of live sessions as it arrives.
+ */
+send_segments?: boolean; 
+/**
+ * POST each session once it is complete and stored.
+ */
+send_sessions?: boolean }
+
+/** tauri-specta globals **/
+
+import {
+	invoke as TAURI_INVOKE,
+	Channel as TAURI_CHANNEL,
+} from "@tauri-apps/api/core";
+import * as TAURI_API_EVENT from "@tauri-apps/api/event";
+import { type WebviewWindow as __WebviewWindow__ } from "@tauri-apps/api/webviewWindow";
+
+type __EventObj__<T> = {
+	listen: (
+		cb: TAURI_API_EVENT.EventCallback<T>,
+	) => ReturnType<typeof TAURI_API_EVENT.listen<T>>;
+	once: (
+		cb: TAURI_API_EVENT.EventCallback<T>,
+	) => ReturnType<typeof TAURI_API_EVENT.once<T>>;
+	emit: null extends T
+		? (payload?: T) => ReturnType<typeof TAURI_API_EVENT.emit>
+		: (payload: T) => ReturnType<typeof TAURI_API_EVENT.emit>;
+};
+
+export type Result<T, E> =
+	| { status: "ok"; data: T }
+	| { status: "error"; error: E };
+
+function __makeEvents__<T extends Record<string, any>>(
+	mappings: Record<keyof T, string>,
+) {
+	return new Proxy(
+		{} as unknown as {
+			[K in keyof T]: __EventObj__<T[K]> & {
+				(handle: __WebviewWindow__): __EventObj__<T[K]>;
+			};
+		},
+		{
+			get: (_, event) => {
+				const name = mappings[event as keyof T];
+
+				return new Proxy((() => {}) as any, {
+					apply: (_, __, [window]: [__WebviewWindow__]) => ({
+						listen: (arg: any) => window.listen(name, arg),
+						once: (arg: any) => window.once(name, arg),
+						emit: (arg: any) => window.emit(name, arg),
+					}),
+					get: (_, command: keyof __EventObj__<any>) => {
+						switch (command) {
+							case "listen":
+								return (arg: any) => TAURI_API_EVENT.listen(name, arg);
+							case "once":
+								return (arg: any) => TAURI_API_EVENT.once(name, arg);
+							case "emit":
+								return (arg: any) => TAURI_API_EVENT.emit(name, arg);
+						}
+					},
+				});
+			},
+		},
+	);
+}
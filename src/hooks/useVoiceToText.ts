@@ -4,15 +4,50 @@
  */
 
 import { useState, useCallback, useRef, useEffect } from 'react';
+import { commands, unwrap } from '../services/backend';
+import { DocumentState, WakeWordDetected } from '../bindings';
 import { AudioCaptureService } from '../services/audioCapture';
-import { TranscriptionService, TranscriptionResult } from '../services/transcription';
+import { TranscriptionService, TranscriptionResult, LatencyStats, SAMPLE_RATE } from '../services/transcription';
 
 export interface VoiceToTextConfig {
     deepgramApiKey: string;
     language?: string;
     model?: string;
+    /** Request and show interim results while speaking (default true). */
+    interimResults?: boolean;
+    /**
+     * Match final results against the command grammar (emitted as `command:recognized`)
+     * instead of adding them to the transcript. Needs `commands.enabled` in settings.
+     */
+    commandMode?: boolean;
+    /** Type each final result into the focused app; "scratch that" deletes the last one. */
+    injectText?: boolean;
+    /** Force a final result after this many seconds of continuous speech. */
+    maxUtteranceSeconds?: number;
+    /**
+     * Capture from the built-in (or any other) microphone rather than a Bluetooth
+     * headset's, so the headset stays in high-quality A2DP playback. Either way a headset
+     * input is reported as `capture:bluetooth_hfp`.
+     */
+    preferBuiltInMic?: boolean;
+}
+
+export interface RecordingOptions {
+    /**
+     * Interview mode: names of the speakers on the left and right channel of a stereo
+     * input. Each final is attributed to its channel's speaker, with no diarization.
+     */
+    interviewSpeakers?: [string, string];
 }
 
+export type { DeviceCalibration, WakeWordAction } from '../bindings';
+
+/** A trained wake word heard on-device; see the backend's `wake_word` module. */
+export type WakeWordDetection = WakeWordDetected;
+
+/** Structured document being dictated into; see the backend's `templates` module. */
+export type TemplateDocument = DocumentState;
+
 export interface VoiceToTextState {
     isRecording: boolean;
     isProcessing: boolean;
@@ -34,6 +69,14 @@ export function useVoiceToText(config: VoiceToTextConfig) {
 
     const audioServiceRef = useRef<AudioCaptureService | null>(null);
     const transcriptionServiceRef = useRef<TranscriptionService | null>(null);
+    const filterChainRef = useRef<string | null>(null);
+    const dictationRef = useRef<string | null>(null);
+    const wakeServiceRef = useRef<AudioCaptureService | null>(null);
+    // Read by wake-word actions, which outlive the render they were created in
+    const recordingRef = useRef(false);
+    const transcriptRef = useRef('');
+    recordingRef.current = state.isRecording;
+    transcriptRef.current = state.transcript;
 
     /**
      * Initialize services
@@ -44,8 +87,12 @@ export function useVoiceToText(config: VoiceToTextConfig) {
 
             // Initialize audio capture service
             if (!audioServiceRef.current) {
-                audioServiceRef.current = new AudioCaptureService();
+                audioServiceRef.current = new AudioCaptureService({ preferBuiltInMic: config.preferBuiltInMic });
                 await audioServiceRef.current.requestMicrophoneAccess();
+                // Stalls are emitted as `capture:stalled` and restarted; this only fires once that stops working
+                audioServiceRef.current.onError((error: Error) => {
+                    setState(prev => ({ ...prev, error: error.message }));
+                });
             }
 
             // Initialize transcription service
@@ -54,22 +101,60 @@ export function useVoiceToText(config: VoiceToTextConfig) {
                     apiKey: config.deepgramApiKey,
                     language: config.language,
                     model: config.model,
+                    interimResults: config.interimResults,
+                    maxUtteranceSeconds: config.maxUtteranceSeconds,
                 });
 
                 // Set up transcription callback
+                let finals = Promise.resolve();
                 transcriptionServiceRef.current.onTranscript((result: TranscriptionResult) => {
-                    if (result.isFinal) {
+                    if (config.commandMode) {
+                        if (result.isFinal) {
+                            commands.recognizeCommand(result.transcript).catch(() => {});
+                        }
+                        return;
+                    }
+
+                    if (!result.isFinal) {
+                        // Forward to the local caption server (no-op in the backend when it's stopped)
+                        commands.publishCaption({
+                            text: result.transcript,
+                            is_final: false,
+                            session_id: null,
+                        }).catch(() => {});
                         setState(prev => ({
                             ...prev,
-                            transcript: prev.transcript + ' ' + result.transcript,
-                            interimTranscript: '',
+                            interimTranscript: result.transcript,
                         }));
-                    } else {
+                        return;
+                    }
+
+                    // Finals go through the backend's dictation passes in order; voice commands come back as null
+                    finals = finals.then(async () => {
+                        const text = await unwrap(commands.finalizeDictation(result.transcript))
+                            .catch(() => result.transcript);
+                        if (text === null) {
+                            setState(prev => ({ ...prev, interimTranscript: '' }));
+                            return;
+                        }
+
+                        commands.publishCaption({ text, is_final: true, session_id: null }).catch(() => {});
+
+                        if (config.injectText) {
+                            unwrap(commands.injectText(text)).catch(err => {
+                                console.error('Failed to inject text:', err);
+                            });
+                        }
+
                         setState(prev => ({
                             ...prev,
-                            interimTranscript: result.transcript,
+                            // Interview transcripts get a line per turn
+                            transcript: result.speaker
+                                ? `${prev.transcript}\n${result.speaker}: ${text}`
+                                : prev.transcript + ' ' + text,
+                            interimTranscript: '',
                         }));
-                    }
+                    });
                 });
 
                 // Set up error callback
@@ -96,7 +181,7 @@ export function useVoiceToText(config: VoiceToTextConfig) {
                 isReady: false,
             }));
         }
-    }, [config.deepgramApiKey, config.language, config.model]);
+    }, [config.deepgramApiKey, config.language, config.model, config.commandMode, config.injectText, config.preferBuiltInMic]);
 
     /**
      * Validate API key on-demand
@@ -156,11 +241,12 @@ export function useVoiceToText(config: VoiceToTextConfig) {
     /**
      * Start recording and transcription
      */
-    const startRecording = useCallback(async () => {
+    const startRecording = useCallback(async (options: RecordingOptions = {}) => {
         if (!audioServiceRef.current || !transcriptionServiceRef.current) {
             setState(prev => ({ ...prev, error: 'Services not initialized' }));
             return;
         }
+        const interviewSpeakers = options.interviewSpeakers ?? null;
 
         try {
             setState(prev => ({
@@ -171,16 +257,37 @@ export function useVoiceToText(config: VoiceToTextConfig) {
                 interimTranscript: '',
             }));
 
+            // Stereo capture for interview mode; reopens the microphone when that changes
+            await audioServiceRef.current.setChannels(interviewSpeakers ? 2 : 1);
+            transcriptionServiceRef.current.setInterviewSpeakers(interviewSpeakers);
+
             // Connect to Deepgram
             await transcriptionServiceRef.current.connect();
+            // Tells the backend's job queue to hold batch work while we dictate
+            dictationRef.current = await commands.beginDictation().catch(() => null);
+
+            // Preprocessing configured in settings; null when no filter stage is enabled.
+            // Chains filter a mono signal, so interleaved interview audio goes out as it is.
+            filterChainRef.current = interviewSpeakers ? null : await commands.createFilterChain(
+                SAMPLE_RATE,
+                audioServiceRef.current.getCurrentDevice()?.deviceId ?? null,
+            ).catch(() => null);
+            let pending = Promise.resolve();
 
             // Start recording with callback to send audio to Deepgram
             audioServiceRef.current.startRecording((audioChunk: Float32Array) => {
-                if (transcriptionServiceRef.current) {
-                    transcriptionServiceRef.current.sendAudio(audioChunk).catch(err => {
-                        console.error('Failed to send audio:', err);
-                    });
-                }
+                const chainId = filterChainRef.current;
+                // Chained so filtered chunks keep their order
+                pending = pending.then(async () => {
+                    let samples = audioChunk;
+                    if (chainId) {
+                        const filtered = await unwrap(commands.filterAudio(chainId, Array.from(audioChunk)));
+                        samples = Float32Array.from(filtered);
+                    }
+                    await transcriptionServiceRef.current?.sendAudio(samples);
+                }).catch(err => {
+                    console.error('Failed to send audio:', err);
+                });
             });
 
             setState(prev => ({
@@ -212,6 +319,14 @@ export function useVoiceToText(config: VoiceToTextConfig) {
 
             // Stop recording
             audioServiceRef.current.stopRecording();
+            if (filterChainRef.current) {
+                commands.closeFilterChain(filterChainRef.current).catch(() => {});
+                filterChainRef.current = null;
+            }
+            if (dictationRef.current) {
+                commands.endDictation(dictationRef.current).catch(() => {});
+                dictationRef.current = null;
+            }
 
             // Finish transcription and get final results
             await transcriptionServiceRef.current.finish();
@@ -236,6 +351,94 @@ export function useVoiceToText(config: VoiceToTextConfig) {
         }
     }, []);
 
+    /**
+     * Stop listening for wake words
+     */
+    const stopWakeWordListening = useCallback(() => {
+        if (wakeServiceRef.current) {
+            wakeServiceRef.current.cleanup();
+            wakeServiceRef.current = null;
+            commands.resetWakeWord().catch(() => {});
+        }
+    }, []);
+
+    /**
+     * Listen on-device for trained wake words, including while recording, and carry out
+     * the action bound to each. Actions the hook can't perform itself (meeting capture,
+     * templates) are passed to `onAction`. Requires `wake_word.enabled` in settings.
+     */
+    const startWakeWordListening = useCallback(async (onAction?: (detection: WakeWordDetection) => void) => {
+        if (wakeServiceRef.current) return;
+
+        const service = new AudioCaptureService({ preferBuiltInMic: config.preferBuiltInMic });
+        await service.requestMicrophoneAccess();
+        wakeServiceRef.current = service;
+        let pending = Promise.resolve();
+
+        service.startRecording((audioChunk: Float32Array) => {
+            pending = pending.then(async () => {
+                if (wakeServiceRef.current !== service) return;
+                const detection = await commands.feedWakeWord(Array.from(audioChunk), SAMPLE_RATE);
+                if (!detection) return;
+
+                switch (detection.action.kind) {
+                    case 'start_dictation':
+                        if (!recordingRef.current) await startRecording();
+                        break;
+                    case 'stop_and_copy':
+                        if (recordingRef.current) await stopRecording();
+                        await navigator.clipboard.writeText(transcriptRef.current.trim());
+                        break;
+                    default:
+                        onAction?.(detection);
+                }
+            }).catch(err => {
+                console.error('Wake word handling failed:', err);
+            });
+        });
+    }, [startRecording, stopRecording, config.preferBuiltInMic]);
+
+    /**
+     * Dictate into a template until it is finished (by voice or with finishTemplate).
+     * Progress is emitted as `template:updated`.
+     */
+    const startTemplate = useCallback((name: string) => {
+        return unwrap(commands.startTemplate(name));
+    }, []);
+
+    /**
+     * Close the template document and get its rendered text
+     */
+    const finishTemplate = useCallback(() => {
+        return unwrap(commands.finishTemplate());
+    }, []);
+
+    /**
+     * Record a few seconds of room tone from the microphone capture uses and store its noise
+     * floor, which tunes the VAD and AGC filters for that device. Stay quiet meanwhile.
+     */
+    const calibrateDevice = useCallback(async (seconds = 3) => {
+        const service = new AudioCaptureService({ preferBuiltInMic: config.preferBuiltInMic });
+        await service.requestMicrophoneAccess();
+        const chunks: Float32Array[] = [];
+        try {
+            service.startRecording((audioChunk: Float32Array) => {
+                chunks.push(audioChunk);
+            });
+            await new Promise(resolve => setTimeout(resolve, seconds * 1000));
+            const device = service.getCurrentDevice();
+            const samples = chunks.flatMap(chunk => Array.from(chunk));
+            return await unwrap(commands.calibrateDevice(
+                device?.deviceId ?? '',
+                device?.label ?? '',
+                samples,
+                SAMPLE_RATE,
+            ));
+        } finally {
+            service.cleanup();
+        }
+    }, [config.preferBuiltInMic]);
+
     /**
      * Clear transcript
      */
@@ -265,11 +468,19 @@ export function useVoiceToText(config: VoiceToTextConfig) {
         }
     }, [state.transcript]);
 
+    /**
+     * Result latency (p50/p95) for the current connection
+     */
+    const getLatencyStats = useCallback((): LatencyStats | null => {
+        return transcriptionServiceRef.current?.getLatencyStats() ?? null;
+    }, []);
+
     /**
      * Cleanup on unmount
      */
     useEffect(() => {
         return () => {
+            wakeServiceRef.current?.cleanup();
             if (audioServiceRef.current) {
                 audioServiceRef.current.cleanup();
             }
@@ -289,8 +500,14 @@ export function useVoiceToText(config: VoiceToTextConfig) {
         stopRecording,
         clearTranscript,
         copyToClipboard,
+        startWakeWordListening,
+        stopWakeWordListening,
+        startTemplate,
+        finishTemplate,
+        calibrateDevice,
         // diagnostics helpers
         validateApiKey,
         runDiagnostics,
+        getLatencyStats,
     };
 }
@@ -2,32 +2,239 @@
  * Audio Capture Service
  * Handles microphone access and streams raw PCM audio (linear16, 16kHz) to callbacks
  * Uses Web Audio API for real-time processing instead of MediaRecorder (which encodes to webm/opus).
+ *
+ * A watchdog notices a microphone that went dead (typically after a USB suspend): one that
+ * stops delivering audio, or delivers nothing but digital silence. It emits `capture:stalled`
+ * and reopens the microphone, giving up after a few attempts without audio in between.
+ *
+ * Capture follows the system's default input: when the device in use is unplugged or the
+ * default changes (a headset plugged in mid-dictation), the stream moves to the new device
+ * without the session ending, and `capture:device_changed` describes the switch.
+ *
+ * With two channels (interview mode) chunks are interleaved stereo, left first.
+ *
+ * Opening the microphone of a Bluetooth headset switches it from A2DP to the hands-free
+ * profile (HFP), whose narrowband audio ruins accuracy. When the input would be such a
+ * headset, `capture:bluetooth_hfp` warns about it, and with `preferBuiltInMic` another
+ * input is used so the headset keeps playing in A2DP quality.
  */
 
+import { emit } from '@tauri-apps/api/event';
+
+export type StallReason = 'no_audio' | 'digital_silence' | 'track_ended';
+
+/** Payload of the `capture:stalled` event */
+export interface CaptureStall {
+  reason: StallReason;
+  /** Milliseconds since the microphone last delivered real audio */
+  stalledForMs: number;
+  /** Restart attempt this stall triggers; null once attempts are used up */
+  restartAttempt: number | null;
+}
+
+// How often the watchdog looks at the stream
+const WATCHDOG_INTERVAL_MS = 1000;
+// Buffers arrive every ~256ms, so this many ms without one means the device stopped
+const NO_AUDIO_TIMEOUT_MS = 3000;
+// Even a quiet room never reads as exact zeros from a working microphone
+const SILENCE_TIMEOUT_MS = 10000;
+const SILENCE_PEAK = 1e-6;
+// Restarts without real audio in between before giving up
+const MAX_RESTARTS = 3;
+
+export interface CaptureDevice {
+  deviceId: string;
+  groupId: string;
+  label: string;
+}
+
+export type DeviceChangeReason = 'removed' | 'default_changed';
+
+/** Payload of the `capture:device_changed` event */
+export interface CaptureDeviceChange {
+  reason: DeviceChangeReason;
+  previous: CaptureDevice | null;
+  /** Null when no input device is left; the stall watchdog takes over from there */
+  current: CaptureDevice | null;
+}
+
+// Browsers only see labels, e.g. "Headset (WH-1000XM4 Hands-Free AG Audio)" on Windows
+const BLUETOOTH_LABEL = /bluetooth|hands-?free|airpods|\bbuds\b|\bhfp\b|\bhsp\b|bluez/i;
+const BUILT_IN_LABEL = /built-?in|internal|integrated|macbook/i;
+// Chromium aliases of the default devices
+const ALIAS_IDS = ['default', 'communications'];
+
+export interface CaptureOptions {
+  /** Capture from another input rather than a Bluetooth headset's hands-free microphone */
+  preferBuiltInMic?: boolean;
+}
+
+/** Payload of the `capture:bluetooth_hfp` event */
+export interface BluetoothWarning {
+  /** The Bluetooth input that would be (or is being) captured */
+  device: CaptureDevice;
+  /** The input used instead with preferBuiltInMic; null while capturing from the headset */
+  usingInstead: CaptureDevice | null;
+}
+
+function isBluetooth(label: string): boolean {
+  return BLUETOOTH_LABEL.test(label);
+}
+
+function describe(device: MediaDeviceInfo): CaptureDevice {
+  return { deviceId: device.deviceId, groupId: device.groupId, label: device.label };
+}
+
+/** Version of the payloads of capture events, the same as the backend's event schema version */
+export const EVENT_SCHEMA_VERSION = 1;
+
+function emitEvent(event: string, payload: object): void {
+  emit(event, { ...payload, schema_version: EVENT_SCHEMA_VERSION }).catch(() => {});
+}
+
 export class AudioCaptureService {
   private mediaStream: MediaStream | null = null;
   private audioContext: AudioContext | null = null;
+  private sourceNode: MediaStreamAudioSourceNode | null = null;
   private scriptProcessor: ScriptProcessorNode | null = null;
   private isRecording: boolean = false;
   private onDataAvailableCallback: ((audioData: Float32Array) => void) | null = null;
+  private onErrorCallback: ((error: Error) => void) | null = null;
+  // Watchdog state: when the last buffer and the last non-silent buffer arrived
+  private watchdog: ReturnType<typeof setInterval> | null = null;
+  private lastBufferAt = 0;
+  private lastAudioAt = 0;
+  private restarts = 0;
+  private restarting = false;
+  private onDeviceChange = () => { this.handleDeviceChange(); };
+  private channels = 1;
+
+  constructor(private options: CaptureOptions = {}) {}
+
+  private async listInputs(): Promise<MediaDeviceInfo[]> {
+    const devices = await navigator.mediaDevices.enumerateDevices();
+    return devices.filter(device => device.kind === 'audioinput');
+  }
+
+  /**
+   * An input to use instead of a Bluetooth headset: built-in first, then any other
+   */
+  private alternativeInput(inputs: MediaDeviceInfo[]): MediaDeviceInfo | undefined {
+    const candidates = inputs.filter(device =>
+      !ALIAS_IDS.includes(device.deviceId) && device.label && !isBluetooth(device.label));
+    return candidates.find(device => BUILT_IN_LABEL.test(device.label)) ?? candidates[0];
+  }
 
   /**
    * Request microphone permission and initialize media stream
    */
   async requestMicrophoneAccess(): Promise<boolean> {
+    // Labels are listed once permission was granted, so a headset can usually be avoided
+    // before it is ever opened; otherwise it is noticed right after
+    let avoided: CaptureDevice | null = null;
+    let deviceId: string | undefined;
+    try {
+      const inputs = await this.listInputs();
+      const defaultInput = inputs.find(device => device.deviceId === 'default') ?? inputs[0];
+      if (this.options.preferBuiltInMic && defaultInput && isBluetooth(defaultInput.label)) {
+        deviceId = this.alternativeInput(inputs)?.deviceId;
+        if (deviceId) avoided = describe(defaultInput);
+      }
+    } catch {}
+
     try {
       this.mediaStream = await navigator.mediaDevices.getUserMedia({
-        audio: {
-          echoCancellation: true,
-          noiseSuppression: true,
-          // Sample rate is controlled by AudioContext, not the device
-        },
+        audio: this.constraints(deviceId),
       });
-      return true;
     } catch (error) {
       console.error('Microphone access denied:', error);
       throw new Error('Microphone access denied. Please grant permission to use the microphone.');
     }
+
+    const channelCount = this.mediaStream.getAudioTracks()[0]?.getSettings().channelCount ?? 1;
+    if (channelCount < this.channels) {
+      // A mono source would be copied to both channels and transcribed twice
+      this.mediaStream.getTracks().forEach(track => track.stop());
+      this.mediaStream = null;
+      throw new Error(`The microphone has ${channelCount} channel(s); interview mode needs a stereo input.`);
+    }
+
+    if (avoided) {
+      this.warnBluetooth(avoided, this.getCurrentDevice());
+    } else {
+      await this.checkBluetooth();
+    }
+    return true;
+  }
+
+  private constraints(deviceId?: string): MediaTrackConstraints {
+    const device = deviceId ? { deviceId: { exact: deviceId } } : {};
+    return this.channels > 1
+      ? {
+        ...device,
+        // Browser processing mixes the channels down to mono
+        channelCount: { ideal: this.channels },
+        echoCancellation: false,
+        noiseSuppression: false,
+        autoGainControl: false,
+      }
+      : {
+        ...device,
+        echoCancellation: true,
+        noiseSuppression: true,
+        // Sample rate is controlled by AudioContext, not the device
+      };
+  }
+
+  /**
+   * Warn when the open input is a Bluetooth headset, moving off it with preferBuiltInMic
+   */
+  private async checkBluetooth(): Promise<void> {
+    const current = this.getCurrentDevice();
+    if (!current || !isBluetooth(current.label)) return;
+
+    const alternative = this.options.preferBuiltInMic
+      ? this.alternativeInput(await this.listInputs())
+      : undefined;
+    if (!alternative) {
+      this.warnBluetooth(current, null);
+      return;
+    }
+    try {
+      const stream = await navigator.mediaDevices.getUserMedia({
+        audio: this.constraints(alternative.deviceId),
+      });
+      this.mediaStream?.getTracks().forEach(track => track.stop());
+      this.mediaStream = stream;
+      this.warnBluetooth(current, this.getCurrentDevice());
+    } catch (error) {
+      console.error('Failed to switch away from the Bluetooth headset:', error);
+      this.warnBluetooth(current, null);
+    }
+  }
+
+  private warnBluetooth(device: CaptureDevice, usingInstead: CaptureDevice | null): void {
+    console.warn(usingInstead
+      ? `Capturing from ${usingInstead.label} to keep ${device.label} out of hands-free mode`
+      : `Capturing from ${device.label} switches it to low-quality hands-free mode`);
+    const warning: BluetoothWarning = { device, usingInstead };
+    emitEvent('capture:bluetooth_hfp', warning);
+  }
+
+  /**
+   * Capture one (mono) or two (interleaved stereo) channels, reopening the microphone if needed
+   */
+  async setChannels(channels: 1 | 2): Promise<void> {
+    if (channels === this.channels) return;
+    if (this.isRecording) {
+      throw new Error('Change channels before recording starts');
+    }
+    this.channels = channels;
+    if (this.mediaStream) {
+      try { this.mediaStream.getTracks().forEach(track => track.stop()); } catch {}
+      this.mediaStream = null;
+      await this.requestMicrophoneAccess();
+    }
   }
 
   /**
@@ -51,6 +258,27 @@ export class AudioCaptureService {
       this.audioContext = new (window.AudioContext || (window as any).webkitAudioContext)({ sampleRate: 16000 });
     }
 
+    this.connectNodes();
+    this.isRecording = true;
+    this.restarts = 0;
+    this.startWatchdog();
+    navigator.mediaDevices.addEventListener('devicechange', this.onDeviceChange);
+    console.log(`Audio recording started (AudioContext sampleRate: ${this.audioContext.sampleRate}Hz)`);
+  }
+
+  /**
+   * Register a callback for when the microphone stays dead after the restart attempts
+   */
+  onError(callback: (error: Error) => void): void {
+    this.onErrorCallback = callback;
+  }
+
+  /**
+   * Build the graph mediaStream -> scriptProcessor -> destination
+   */
+  private connectNodes(): void {
+    if (!this.audioContext || !this.mediaStream) return;
+
     // Ensure AudioContext is resumed (required after user interaction in modern browsers)
     if (this.audioContext.state === 'suspended') {
       this.audioContext.resume();
@@ -58,19 +286,32 @@ export class AudioCaptureService {
 
     // Create source from media stream
     const mediaStreamAudioSourceNode = this.audioContext.createMediaStreamSource(this.mediaStream);
+    this.sourceNode = mediaStreamAudioSourceNode;
 
     // Create ScriptProcessorNode to access raw audio samples
     // Use 4096 sample buffer (at 16kHz = ~256ms), process at ~250ms intervals
     const bufferSize = 4096;
-    this.scriptProcessor = this.audioContext.createScriptProcessor(bufferSize, 1, 1);
+    const channels = this.channels;
+    this.scriptProcessor = this.audioContext.createScriptProcessor(bufferSize, channels, channels);
 
     // Process audio on every audio frame
     this.scriptProcessor.onaudioprocess = (audioEvent: AudioProcessingEvent) => {
       if (this.isRecording && this.onDataAvailableCallback) {
-        // Get the mono audio input (channel 0)
-        const inputData = audioEvent.inputBuffer.getChannelData(0);
-        // Send a copy to the callback (Float32Array)
-        this.onDataAvailableCallback(new Float32Array(inputData));
+        let chunk: Float32Array;
+        if (channels === 1) {
+          // Copy the mono audio input (channel 0)
+          chunk = new Float32Array(audioEvent.inputBuffer.getChannelData(0));
+        } else {
+          const inputs = Array.from({ length: channels }, (_, c) => audioEvent.inputBuffer.getChannelData(c));
+          chunk = new Float32Array(inputs[0].length * channels);
+          for (let i = 0; i < inputs[0].length; i++) {
+            for (let c = 0; c < channels; c++) {
+              chunk[i * channels + c] = inputs[c][i];
+            }
+          }
+        }
+        this.noteBuffer(chunk);
+        this.onDataAvailableCallback(chunk);
       }
     };
 
@@ -78,8 +319,150 @@ export class AudioCaptureService {
     mediaStreamAudioSourceNode.connect(this.scriptProcessor);
     this.scriptProcessor.connect(this.audioContext.destination);
 
-    this.isRecording = true;
-    console.log(`Audio recording started (AudioContext sampleRate: ${this.audioContext.sampleRate}Hz)`);
+    const now = Date.now();
+    this.lastBufferAt = now;
+    this.lastAudioAt = now;
+  }
+
+  private disconnectNodes(): void {
+    try { this.sourceNode?.disconnect(); } catch {}
+    try { this.scriptProcessor?.disconnect(); } catch {}
+    this.sourceNode = null;
+    this.scriptProcessor = null;
+  }
+
+  private noteBuffer(samples: Float32Array): void {
+    const now = Date.now();
+    this.lastBufferAt = now;
+    let peak = 0;
+    for (let i = 0; i < samples.length; i++) {
+      peak = Math.max(peak, Math.abs(samples[i]));
+    }
+    if (peak > SILENCE_PEAK) {
+      this.lastAudioAt = now;
+      this.restarts = 0;
+    }
+  }
+
+  private startWatchdog(): void {
+    this.stopWatchdog();
+    this.watchdog = setInterval(() => {
+      if (!this.isRecording || this.restarting) return;
+      const reason = this.stallReason();
+      if (reason) this.handleStall(reason);
+    }, WATCHDOG_INTERVAL_MS);
+  }
+
+  private stopWatchdog(): void {
+    if (this.watchdog) {
+      clearInterval(this.watchdog);
+      this.watchdog = null;
+    }
+  }
+
+  private stallReason(): StallReason | null {
+    const now = Date.now();
+    if (this.mediaStream?.getAudioTracks().every(track => track.readyState === 'ended')) {
+      return 'track_ended';
+    }
+    if (now - this.lastBufferAt > NO_AUDIO_TIMEOUT_MS) return 'no_audio';
+    if (now - this.lastAudioAt > SILENCE_TIMEOUT_MS) return 'digital_silence';
+    return null;
+  }
+
+  private async handleStall(reason: StallReason): Promise<void> {
+    const stalledForMs = Date.now() - this.lastAudioAt;
+    const restartAttempt = this.restarts < MAX_RESTARTS ? this.restarts + 1 : null;
+    console.warn(`Microphone stalled (${reason}, ${stalledForMs}ms)`);
+    const stall: CaptureStall = { reason, stalledForMs, restartAttempt };
+    emitEvent('capture:stalled', stall);
+
+    if (restartAttempt === null) {
+      this.stopWatchdog();
+      this.onErrorCallback?.(new Error('The microphone stopped delivering audio. Check the input device.'));
+      return;
+    }
+
+    this.restarts = restartAttempt;
+    this.restarting = true;
+    try {
+      await this.restart();
+      console.log(`Audio capture restarted (attempt ${restartAttempt})`);
+    } catch (error) {
+      console.error('Failed to restart audio capture:', error);
+      // Checked again on the next tick; the attempt still counts
+      this.lastBufferAt = Date.now();
+      this.lastAudioAt = Date.now();
+    } finally {
+      this.restarting = false;
+    }
+  }
+
+  /**
+   * The input device the stream currently captures from
+   */
+  getCurrentDevice(): CaptureDevice | null {
+    const track = this.mediaStream?.getAudioTracks()[0];
+    if (!track || track.readyState === 'ended') return null;
+    const settings = track.getSettings();
+    return {
+      deviceId: settings.deviceId ?? '',
+      groupId: settings.groupId ?? '',
+      label: track.label,
+    };
+  }
+
+  /**
+   * Move to the default input when the device in use went away or the default changed
+   */
+  private async handleDeviceChange(): Promise<void> {
+    if (!this.isRecording || this.restarting) return;
+    const previous = this.getCurrentDevice();
+    const inputs = (await navigator.mediaDevices.enumerateDevices())
+      .filter(device => device.kind === 'audioinput');
+    // Chromium lists the default input a second time under the id "default"
+    const defaultInput = inputs.find(device => device.deviceId === 'default');
+
+    let reason: DeviceChangeReason;
+    if (!previous || !inputs.some(device => device.deviceId === previous.deviceId)) {
+      reason = 'removed';
+    } else if (
+      defaultInput && previous.groupId && defaultInput.groupId !== previous.groupId
+      // Not a change when the default is the headset we deliberately don't capture from
+      && !(this.options.preferBuiltInMic && isBluetooth(defaultInput.label))
+    ) {
+      reason = 'default_changed';
+    } else {
+      return;
+    }
+
+    this.restarting = true;
+    try {
+      await this.restart();
+      const current = this.getCurrentDevice();
+      console.log(`Audio capture moved to ${current?.label || 'the default input'} (${reason})`);
+      const change: CaptureDeviceChange = { reason, previous, current };
+      emitEvent('capture:device_changed', change);
+    } catch (error) {
+      // No input left; the watchdog reports the stall and keeps retrying
+      console.error('Failed to move audio capture:', error);
+      const change: CaptureDeviceChange = { reason, previous, current: null };
+      emitEvent('capture:device_changed', change);
+    } finally {
+      this.restarting = false;
+    }
+  }
+
+  /**
+   * Reopen the microphone and rebuild the graph, keeping the data callback
+   */
+  private async restart(): Promise<void> {
+    this.disconnectNodes();
+    try { this.mediaStream?.getTracks().forEach(track => track.stop()); } catch {}
+    this.mediaStream = null;
+    await this.requestMicrophoneAccess();
+    if (!this.isRecording) return;
+    this.connectNodes();
   }
 
   /**
@@ -92,11 +475,11 @@ export class AudioCaptureService {
     }
 
     this.isRecording = false;
+    this.stopWatchdog();
+    navigator.mediaDevices.removeEventListener('devicechange', this.onDeviceChange);
 
     // Disconnect nodes
-    if (this.scriptProcessor) {
-      this.scriptProcessor.disconnect();
-    }
+    this.disconnectNodes();
 
     console.log('Audio recording stopped');
     return null; // We return null because we're streaming PCM, not buffering
@@ -117,10 +500,8 @@ export class AudioCaptureService {
       this.stopRecording();
     }
 
-    if (this.scriptProcessor) {
-      try { this.scriptProcessor.disconnect(); } catch {}
-      this.scriptProcessor = null;
-    }
+    this.stopWatchdog();
+    this.disconnectNodes();
 
     if (this.audioContext) {
       try { this.audioContext.close(); } catch {}
@@ -133,6 +514,7 @@ export class AudioCaptureService {
     }
 
     this.onDataAvailableCallback = null;
+    this.onErrorCallback = null;
   }
 }
 
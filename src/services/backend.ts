@@ -0,0 +1,18 @@
+/**
+ * Backend Commands
+ * The Rust backend is the `voicetotext` plugin; its commands and types are generated into
+ * `src/bindings.ts` from the plugin's source
+ */
+
+import { commands, Result } from '../bindings';
+
+export { commands };
+
+/** The data of a command that returns a Result, throwing its error instead. */
+export async function unwrap<T>(result: Promise<Result<T, string>>): Promise<T> {
+  const outcome = await result;
+  if (outcome.status === 'error') {
+    throw new Error(outcome.error);
+  }
+  return outcome.data;
+}
@@ -3,32 +3,25 @@
  * Calls the Rust backend to probe Deepgram without browser TLS/proxy restrictions
  */
 
-import { invoke } from '@tauri-apps/api/core';
-
-export interface NativeProbeResult {
-  success: boolean;
-  message: string;
-  code?: number;
-  reason?: string;
-}
+import { commands } from './backend';
+import { ConnectTimeouts, ProbeResult } from '../bindings';
 
 /**
  * Invoke the native Tauri probe_deepgram command
  */
 export async function probeDeepgramNative(
   apiKey: string,
-  timeoutMs: number = 5000
-): Promise<NativeProbeResult> {
+  timeouts?: ConnectTimeouts
+): Promise<ProbeResult> {
   try {
-    const result = await invoke<NativeProbeResult>('probe_deepgram', {
-      api_key: apiKey,
-      timeout_ms: timeoutMs,
-    });
-    return result;
+    return await commands.probeDeepgram(apiKey, timeouts ?? null);
   } catch (error: any) {
     return {
       success: false,
       message: `Native probe failed: ${error?.message || String(error)}`,
+      code: null,
+      reason: null,
+      stage: null,
     };
   }
 }
@@ -5,6 +5,7 @@
 
 import { createClient, LiveTranscriptionEvents } from '@deepgram/sdk';
 import { probeDeepgramNative } from './nativeProbe';
+import { ConnectTimeouts } from '../bindings';
 
 export interface TranscriptionConfig {
     apiKey: string;
@@ -12,20 +13,48 @@ export interface TranscriptionConfig {
     model?: string;
     punctuate?: boolean;
     interimResults?: boolean;
+    /** Force a final result after this many seconds of continuous speech (off when unset) */
+    maxUtteranceSeconds?: number;
+    /**
+     * Interview mode: names of the speakers on the left and right channel. Audio is sent as
+     * interleaved stereo and Deepgram transcribes each channel on its own, so every result
+     * is attributed by channel instead of by diarization.
+     */
+    interviewSpeakers?: [string, string];
 }
 
 export interface TranscriptionResult {
     transcript: string;
     isFinal: boolean;
     confidence: number;
+    /** Milliseconds from the result's audio being sent to the result arriving */
+    latencyMs?: number;
+    /** Name of the speaker whose channel the result came from, in interview mode */
+    speaker?: string;
+}
+
+export interface LatencyStats {
+    samples: number;
+    p50Ms: number;
+    p95Ms: number;
+    maxMs: number;
 }
 
+/** Sample rate of the linear16 audio we send */
+export const SAMPLE_RATE = 16000;
+
 export class TranscriptionService {
     private deepgram: any;
     private connection: any = null;
     private isConnected: boolean = false;
     private onTranscriptCallback: ((result: TranscriptionResult) => void) | null = null;
     private onErrorCallback: ((error: Error) => void) | null = null;
+    // When each stretch of audio went out, to measure result latency
+    private audioSentAt: { end: number; at: number }[] = [];
+    private audioSecondsSent = 0;
+    private latencies: number[] = [];
+    // When audio was first sent after the last final, for maxUtteranceSeconds
+    private utteranceStartedAt: number | null = null;
 
     constructor(private config: TranscriptionConfig) {
         if (!config.apiKey) {
@@ -35,6 +64,18 @@ export class TranscriptionService {
         this.deepgram = createClient(config.apiKey);
     }
 
+    /**
+     * Turn interview mode on (with the left and right speaker names) or off for the next connection
+     */
+    setInterviewSpeakers(speakers: [string, string] | null): void {
+        this.config = { ...this.config, interviewSpeakers: speakers ?? undefined };
+    }
+
+    /** Interleaved channels in the audio we send */
+    private get channels(): number {
+        return this.config.interviewSpeakers ? 2 : 1;
+    }
+
     /**
      * Initialize live transcription connection
      */
@@ -59,7 +100,8 @@ export class TranscriptionService {
                     smart_format: true,
                     encoding: 'linear16',
                     sample_rate: 16000,
-                    channels: 1,
+                    channels: this.channels,
+                    multichannel: this.channels > 1,
                 });
 
                 // Set up event listeners
@@ -74,6 +116,10 @@ export class TranscriptionService {
                     this.connection.on(LiveTranscriptionEvents.Open, () => {
                         clearTimeout(timeout);
                         this.isConnected = true;
+                        this.audioSentAt = [];
+                        this.audioSecondsSent = 0;
+                        this.latencies = [];
+                        this.utteranceStartedAt = null;
                         console.log('Connected to Deepgram');
                         resolve();
                     });
@@ -117,12 +163,21 @@ export class TranscriptionService {
             const transcript = data.channel?.alternatives?.[0]?.transcript;
             const isFinal = data.is_final;
             const confidence = data.channel?.alternatives?.[0]?.confidence || 0;
+            const latencyMs = transcript ? this.measureLatency((data.start || 0) + (data.duration || 0)) : undefined;
+            // Multichannel results carry [channel, channel count]
+            const speaker = this.config.interviewSpeakers?.[data.channel_index?.[0] ?? 0];
+            // Even an empty final closes the utterance; the next audio sent opens another
+            if (isFinal) {
+                this.utteranceStartedAt = null;
+            }
 
             if (transcript && this.onTranscriptCallback) {
                 this.onTranscriptCallback({
                     transcript,
                     isFinal,
                     confidence,
+                    latencyMs,
+                    speaker,
                 });
             }
         });
@@ -194,6 +249,8 @@ export class TranscriptionService {
             // If input is Float32Array (raw PCM), convert to linear16
             if (audioData instanceof Float32Array) {
                 audioBuffer = this.float32ToLinear16(audioData);
+                this.audioSecondsSent += audioData.length / SAMPLE_RATE / this.channels;
+                this.audioSentAt.push({ end: this.audioSecondsSent, at: performance.now() });
             } else {
                 // If input is Blob, convert to ArrayBuffer
                 audioBuffer = await audioData.arrayBuffer();
@@ -201,12 +258,64 @@ export class TranscriptionService {
 
             // Send audio data to Deepgram
             this.connection.send(audioBuffer);
+            this.trackUtterance();
         } catch (error) {
             console.error('Failed to send audio:', error);
             throw new Error('Failed to send audio for transcription');
         }
     }
 
+    /**
+     * Ask Deepgram to finalize once speech has gone on without a final for longer than
+     * maxUtteranceSeconds, so injection and webhooks aren't starved during a monologue.
+     * Checked as audio is sent, since interim results may be turned off
+     */
+    private trackUtterance(): void {
+        const limit = this.config.maxUtteranceSeconds;
+        if (!limit || limit <= 0) {
+            return;
+        }
+        const now = performance.now();
+        if (this.utteranceStartedAt === null) {
+            this.utteranceStartedAt = now;
+        } else if (now - this.utteranceStartedAt >= limit * 1000) {
+            // The next audio sent starts the clock again
+            this.utteranceStartedAt = null;
+            this.connection?.finalize();
+        }
+    }
+
+    /**
+     * Latency of a result covering audio up to `audioEnd` seconds, or undefined when the
+     * audio's send time isn't known (e.g. Blob input)
+     */
+    private measureLatency(audioEnd: number): number | undefined {
+        // Chunks wholly before this result can't be needed again
+        while (this.audioSentAt.length > 1 && this.audioSentAt[1].end <= audioEnd) {
+            this.audioSentAt.shift();
+        }
+        const chunk = this.audioSentAt.find(c => c.end >= audioEnd);
+        if (!chunk) return undefined;
+        const latency = performance.now() - chunk.at;
+        this.latencies.push(latency);
+        return latency;
+    }
+
+    /**
+     * p50/p95 result latency since the connection opened, or null before any result
+     */
+    getLatencyStats(): LatencyStats | null {
+        if (this.latencies.length === 0) return null;
+        const sorted = [...this.latencies].sort((a, b) => a - b);
+        const rank = (p: number) => sorted[Math.min(sorted.length, Math.max(1, Math.ceil(p * sorted.length))) - 1];
+        return {
+            samples: sorted.length,
+            p50Ms: rank(0.5),
+            p95Ms: rank(0.95),
+            maxMs: sorted[sorted.length - 1],
+        };
+    }
+
     /**
      * Convert Float32Array (raw PCM samples from Web Audio API) to linear16 (Int16Array) bytes
      * Deepgram expects signed 16-bit PCM at 16kHz
@@ -245,15 +354,24 @@ export class TranscriptionService {
      *
      * Note: REST requests from the browser are blocked by CORS for Deepgram API.
      * We perform a short-lived WebSocket probe instead to surface auth vs network errors.
+     * The native probe gives each connection stage `timeoutMs` unless `timeouts` says
+     * otherwise.
      */
-    async validateApiKey(timeoutMs = 4000): Promise<void> {
+    async validateApiKey(timeoutMs = 4000, timeouts?: Partial<ConnectTimeouts>): Promise<void> {
         // Try browser-based probe first
         const probe = await this.probeConnection(timeoutMs);
         if (probe.success) return; // probe succeeded, key is valid for WS
 
         // If browser probe failed (likely due to TLS/proxy), try native Tauri probe
         console.warn('Browser-based probe failed; attempting native Tauri probe...');
-        const nativeProbe = await probeDeepgramNative(this.config.apiKey, timeoutMs);
+        const nativeProbe = await probeDeepgramNative(this.config.apiKey, {
+            dns_ms: timeoutMs,
+            tcp_ms: timeoutMs,
+            tls_ms: timeoutMs,
+            upgrade_ms: timeoutMs,
+            first_message_ms: timeoutMs,
+            ...timeouts,
+        });
         if (nativeProbe.success) return; // Native probe succeeded
 
         // Both probes failed — map error to user-friendly message